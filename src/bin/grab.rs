@@ -1,10 +1,12 @@
 //! GrabNet CLI
 
+mod config_file;
+
 use std::path::PathBuf;
 use std::time::Duration;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use grabnet::{Grab, PublishOptions, SiteIdExt};
+use grabnet::{Grab, PublishOptions, ChunkingMode, Compression, HashMethod, SiteIdExt, MemberState};
 use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
 use tracing_subscriber::{fmt, EnvFilter};
 
@@ -18,6 +20,10 @@ struct Cli {
     #[arg(long, env = "GRAB_DATA_DIR")]
     data_dir: Option<PathBuf>,
 
+    /// Path to `grab.toml` (defaults to `<data_dir>/grab.toml` if present)
+    #[arg(long, env = "GRAB_CONFIG")]
+    config: Option<PathBuf>,
+
     /// Enable verbose logging
     #[arg(short, long)]
     verbose: bool,
@@ -53,6 +59,27 @@ enum Commands {
         #[arg(long)]
         no_compress: bool,
 
+        /// Use content-defined (FastCDC) chunking instead of fixed-size,
+        /// so edits don't shift every later chunk boundary and dedup
+        /// savings hold up across revisions
+        #[arg(long)]
+        cdc: bool,
+
+        /// Digest for file and chunk hashes: blake3 (default), blake2b,
+        /// or sha256
+        #[arg(long)]
+        hash_method: Option<String>,
+
+        /// Comma-separated compression codecs to try per file, smallest
+        /// result wins (default: gzip,zstd,brotli). Example: --codecs zstd
+        #[arg(long)]
+        codecs: Option<String>,
+
+        /// Zstd compression level (1-22, higher is smaller but slower).
+        /// Defaults to the node's configured `publisher.zstd_level`.
+        #[arg(long)]
+        zstd_level: Option<i32>,
+
         /// Watch for changes and auto-republish
         #[arg(short, long)]
         watch: bool,
@@ -81,6 +108,16 @@ enum Commands {
         site: String,
     },
 
+    /// Collapse concurrent sibling revisions left by a causal-context fork
+    Resolve {
+        /// Site name or ID
+        site: String,
+
+        /// Revision number whose content to keep
+        #[arg(long)]
+        keep: u64,
+    },
+
     /// Node management
     Node {
         #[command(subcommand)]
@@ -115,15 +152,42 @@ enum Commands {
         action: KeysAction,
     },
 
+    /// Human-readable name management
+    Name {
+        #[command(subcommand)]
+        action: NameAction,
+    },
+
     /// Start the HTTP gateway
     Gateway {
-        /// Port to listen on
-        #[arg(short, long, default_value = "8080")]
-        port: u16,
+        /// Port to listen on (falls back to `grab.toml`, then 8080)
+        #[arg(short, long)]
+        port: Option<u16>,
 
         /// Default site to serve at root (name or ID)
         #[arg(long)]
         default_site: Option<String>,
+
+        /// Trust the `X-Forwarded-For` header to recover the client IP.
+        /// Only enable this behind a reverse proxy you control.
+        #[arg(long)]
+        trusted_proxy: bool,
+
+        /// IP or CIDR range allowed to reach the gateway (repeatable).
+        /// Empty means "allow everyone not explicitly denied".
+        #[arg(long = "allow")]
+        allow: Vec<String>,
+
+        /// IP or CIDR range denied access, checked before `--allow` (repeatable)
+        #[arg(long = "deny")]
+        deny: Vec<String>,
+    },
+
+    /// Start an S3-compatible endpoint for publishing/serving sites
+    S3 {
+        /// Port to listen on (falls back to `grab.toml`, then 9000)
+        #[arg(short, long)]
+        port: Option<u16>,
     },
 
     /// Show storage statistics
@@ -187,6 +251,64 @@ enum KeysAction {
     },
 }
 
+#[derive(Subcommand)]
+enum NameAction {
+    /// Claim (or renew) a human-readable name for a site
+    Claim {
+        /// The name to claim
+        name: String,
+
+        /// Site to point the name at
+        site_id: String,
+
+        /// Key to sign the claim with
+        #[arg(short, long, default_value = "default")]
+        key: String,
+    },
+
+    /// Resolve a name to its current site
+    Resolve {
+        /// The name to resolve
+        name: String,
+    },
+}
+
+/// Mirrors the default `Grab::new` uses when no `--data-dir` is given, so
+/// `grab.toml` can be located before the `Grab` instance exists.
+fn default_data_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".grab")
+}
+
+/// Parse `--hash-method`, defaulting to `HashMethod::default()` (BLAKE3)
+/// when unset.
+fn parse_hash_method(name: Option<&str>) -> Result<HashMethod> {
+    match name {
+        None => Ok(HashMethod::default()),
+        Some("blake3") => Ok(HashMethod::Blake3),
+        Some("blake2b") => Ok(HashMethod::Blake2b),
+        Some("sha256") => Ok(HashMethod::Sha256),
+        Some(other) => anyhow::bail!("unknown hash method '{other}' (expected blake3, blake2b, or sha256)"),
+    }
+}
+
+/// Parse `--codecs`, defaulting to the empty list (the default candidate
+/// set) when unset.
+fn parse_codecs(names: Option<&str>) -> Result<Vec<Compression>> {
+    let Some(names) = names else { return Ok(Vec::new()) };
+    names
+        .split(',')
+        .map(|name| match name.trim() {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            "brotli" => Ok(Compression::Brotli),
+            other => anyhow::bail!("unknown codec '{other}' (expected none, gzip, zstd, or brotli)"),
+        })
+        .collect()
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -199,8 +321,14 @@ async fn main() -> Result<()> {
     };
     fmt().with_env_filter(filter).init();
 
+    // Resolve grab.toml before creating the GrabNet instance, since it may
+    // itself specify the data dir to use.
+    let probe_dir = cli.data_dir.clone().unwrap_or_else(default_data_dir);
+    let operator_config = config_file::OperatorConfig::load(cli.config.as_deref(), &probe_dir)?;
+    let data_dir = cli.data_dir.clone().or_else(|| operator_config.data_dir.clone());
+
     // Create GrabNet instance
-    let grab = Grab::new(cli.data_dir).await?;
+    let grab = Grab::new(data_dir).await?;
 
     match cli.command {
         Commands::Publish {
@@ -210,6 +338,10 @@ async fn main() -> Result<()> {
             spa,
             clean_urls,
             no_compress,
+            cdc,
+            hash_method,
+            codecs,
+            zstd_level,
             watch,
             pre_hook,
             post_hook,
@@ -225,11 +357,15 @@ async fn main() -> Result<()> {
             let options = PublishOptions {
                 name: name.clone(),
                 entry: entry.clone(),
-                compress: !no_compress,
+                compress: !no_compress && operator_config.compress.unwrap_or(true),
                 spa_fallback: spa.clone(),
-                clean_urls,
+                clean_urls: clean_urls || operator_config.clean_urls.unwrap_or(false),
                 pre_hook: pre_hook.clone(),
                 post_hook: post_hook.clone(),
+                chunking_mode: if cdc { ChunkingMode::ContentDefined } else { ChunkingMode::Fixed },
+                hash_method: parse_hash_method(hash_method.as_deref())?,
+                compression_codecs: parse_codecs(codecs.as_deref())?,
+                zstd_level,
                 ..Default::default()
             };
 
@@ -332,11 +468,31 @@ async fn main() -> Result<()> {
                         println!("  ❌ Error loading manifest: {}", e);
                     }
                 }
+
+                let siblings = grab.bundle_store().get_siblings(&published.site_id)?;
+                if !siblings.is_empty() {
+                    println!();
+                    println!("  ⚠️  {} concurrent sibling revision(s) (forked across devices):", siblings.len());
+                    for sibling in &siblings {
+                        println!("    - revision {}", sibling.revision);
+                    }
+                    println!("  Run `grab resolve {} --keep <revision>` to collapse them", site);
+                }
             } else {
                 println!("❌ Site not found: {}", site);
             }
         }
 
+        Commands::Resolve { site, keep } => {
+            let site_id = grab.bundle_store()
+                .resolve_site_id(&site)?
+                .ok_or_else(|| anyhow::anyhow!("Site not found: {}", site))?;
+
+            let resolved = grab.bundle_store().resolve_siblings(&site_id, keep)?;
+            println!("✓ Resolved {} to revision {}", site, resolved.revision);
+            println!("  Causal context: {:?}", resolved.causal_context.0);
+        }
+
         Commands::Node { action } => {
             match action {
                 NodeAction::Start { port: _, light: _, bootstrap } => {
@@ -350,13 +506,17 @@ async fn main() -> Result<()> {
                         println!("  Peer ID: {}", peer_id);
                     }
                     
-                    // Connect to additional bootstrap peers
-                    if !bootstrap.is_empty() {
-                        for addr in bootstrap {
-                            println!("  Connecting to {}...", addr);
-                            if let Err(e) = grab.dial_peer(&addr).await {
-                                println!("  ⚠️  Failed: {}", e);
-                            }
+                    // Connect to additional bootstrap peers, plus any peer
+                    // marked required/pinned in grab.toml
+                    let required: Vec<String> = operator_config
+                        .required_peers()
+                        .into_iter()
+                        .filter_map(|(_, p)| p.multiaddr.clone())
+                        .collect();
+                    for addr in bootstrap.into_iter().chain(required) {
+                        println!("  Connecting to {}...", addr);
+                        if let Err(e) = grab.dial_peer(&addr).await {
+                            println!("  ⚠️  Failed: {}", e);
                         }
                     }
 
@@ -380,8 +540,11 @@ async fn main() -> Result<()> {
                                         Ok(grabnet::network::NetworkEvent::PeerDisconnected(peer)) => {
                                             println!("  🔴 Peer disconnected: {}", peer);
                                         }
-                                        Ok(grabnet::network::NetworkEvent::SiteAnnounced { site_id, peer_id, revision }) => {
-                                            println!("  📢 Site announced: {} rev {} from {}", site_id.to_base58(), revision, peer_id);
+                                        Ok(grabnet::network::NetworkEvent::SiteAnnounced { site_id, peer_id, revision, zone }) => {
+                                            match zone {
+                                                Some(zone) => println!("  📢 Site announced: {} rev {} from {} ({})", site_id.to_base58(), revision, peer_id, zone),
+                                                None => println!("  📢 Site announced: {} rev {} from {}", site_id.to_base58(), revision, peer_id),
+                                            }
                                         }
                                         Ok(grabnet::network::NetworkEvent::BootstrapComplete { peers }) => {
                                             println!("  ✓ Bootstrap complete, {} peers", peers);
@@ -404,6 +567,12 @@ async fn main() -> Result<()> {
                             println!("  Peer ID: {}", peer_id);
                         }
                         println!("  Peers:   {}", status.peers);
+                        if !status.members.is_empty() {
+                            let alive = status.members.iter().filter(|m| m.state == MemberState::Alive).count();
+                            let suspect = status.members.iter().filter(|m| m.state == MemberState::Suspect).count();
+                            let dead = status.members.iter().filter(|m| m.state == MemberState::Dead).count();
+                            println!("  Members: {} alive, {} suspect, {} dead", alive, suspect, dead);
+                        }
                     } else {
                         println!("🔴 Node is not running");
                     }
@@ -451,7 +620,9 @@ async fn main() -> Result<()> {
             // Give it a moment to initialize
             tokio::time::sleep(std::time::Duration::from_secs(1)).await;
 
-            // Connect to peer if provided
+            // Connect to peer if provided, falling back to a required/pinned
+            // peer's multiaddr from grab.toml
+            let peer = peer.or_else(|| operator_config.preferred_peer_multiaddr());
             if let Some(peer_addr) = peer {
                 println!("  Connecting to peer {}...", peer_addr);
                 grab.dial_peer(&peer_addr).await?;
@@ -518,9 +689,35 @@ async fn main() -> Result<()> {
             }
         }
 
-        Commands::Gateway { port, default_site } => {
+        Commands::Name { action } => {
+            match action {
+                NameAction::Claim { name, site_id, key } => {
+                    let site_id = match grabnet::SiteId::from_base58(&site_id) {
+                        Some(id) => id,
+                        None => {
+                            println!("❌ Invalid site ID: {}", site_id);
+                            return Ok(());
+                        }
+                    };
+
+                    let record = grab.claim_name(&name, &site_id, &key).await?;
+                    println!("✓ Claimed '{}' -> {} (revision {})", record.name, record.site_id.to_base58(), record.revision);
+                }
+
+                NameAction::Resolve { name } => {
+                    match grab.resolve_name(&name).await? {
+                        Some(record) => println!("{} -> {} (revision {})", record.name, record.site_id.to_base58(), record.revision),
+                        None => println!("❌ No site found for name '{}'", name),
+                    }
+                }
+            }
+        }
+
+        Commands::Gateway { port, default_site, trusted_proxy, allow, deny } => {
+            let port = port.or(operator_config.gateway_port).unwrap_or(8080);
+            let default_site = default_site.or_else(|| operator_config.default_site.clone());
             println!("🌐 Starting HTTP gateway on port {}...", port);
-            
+
             // Resolve default site if provided
             let default_site_id = if let Some(site_ref) = default_site {
                 // Try to find by name first
@@ -539,9 +736,10 @@ async fn main() -> Result<()> {
             };
 
             if let Some(site_id) = default_site_id {
-                grab.start_gateway_with_default_site(port, site_id).await?;
+                grab.start_gateway_with_default_site_and_access(port, site_id, trusted_proxy, allow, deny)
+                    .await?;
             } else {
-                grab.start_gateway_on_port(port).await?;
+                grab.start_gateway_on_port_with_access(port, trusted_proxy, allow, deny).await?;
             }
 
             let stats = grab.storage_stats();
@@ -558,6 +756,23 @@ async fn main() -> Result<()> {
             grab.stop_gateway().await?;
         }
 
+        Commands::S3 { port } => {
+            let port = port.or(operator_config.s3_port).unwrap_or(9000);
+            println!("🪣 Starting S3-compatible endpoint on port {}...", port);
+
+            grab.start_s3_on_port(port).await?;
+
+            println!();
+            println!("✓ S3 endpoint running at http://127.0.0.1:{}", port);
+            println!("  Point S3-targeting tools at it with the access key set to a");
+            println!("  key name from `grab keys list` and the secret from `grab keys export <name>`.");
+            println!();
+            println!("Press Ctrl+C to stop");
+
+            tokio::signal::ctrl_c().await?;
+            grab.stop_s3().await?;
+        }
+
         Commands::Stats => {
             let stats = grab.storage_stats();
             println!("📊 Storage Statistics:");
@@ -566,6 +781,8 @@ async fn main() -> Result<()> {
             println!("  Total size:      {} bytes", stats.total_size);
             println!("  Published sites: {}", stats.published_sites);
             println!("  Hosted sites:    {}", stats.hosted_sites);
+            println!("  Resync queued:   {}", stats.resync_queued);
+            println!("  Resync running:  {}", stats.resync_in_flight);
         }
     }
 
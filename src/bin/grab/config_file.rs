@@ -0,0 +1,85 @@
+//! Operator configuration file (`grab.toml`)
+//!
+//! Every invocation of the CLI used to re-specify the data dir, bootstrap
+//! peers, gateway port, and so on from scratch. This loads a persistent
+//! `grab.toml` (next to the data dir, or an explicit `--config` path) that
+//! holds global defaults plus a `[peer.<id>]` table for per-peer overrides,
+//! so an operator can declare a stable set of bootstrap/host peers once.
+//! Values are layered CLI flag > file > built-in default; callers are
+//! responsible for doing the actual merge since clap has already parsed
+//! the flags by the time this loads.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Global defaults and per-peer overrides loaded from `grab.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OperatorConfig {
+    pub data_dir: Option<PathBuf>,
+    pub gateway_port: Option<u16>,
+    pub s3_port: Option<u16>,
+    pub default_site: Option<String>,
+    pub compress: Option<bool>,
+    pub clean_urls: Option<bool>,
+    #[serde(default, rename = "peer")]
+    pub peers: HashMap<String, PeerOverride>,
+}
+
+/// Per-peer override, keyed by peer ID in the `[peer.<id>]` table.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PeerOverride {
+    /// Multiaddr to dial instead of relying on discovery.
+    pub multiaddr: Option<String>,
+    /// Pre-shared connection secret. It is parsed from the file, but the
+    /// CLI does not pass it on when dialing the peer.
+    pub secret: Option<String>,
+    /// Dial this peer when `node start` runs and use it as the default
+    /// peer for `pin`. A dropped connection is not re-established.
+    #[serde(default)]
+    pub required: bool,
+    /// Treated the same as `required`; nothing is pinned locally because
+    /// of this flag.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl OperatorConfig {
+    /// Load `grab.toml` from `explicit_path` if given, otherwise from
+    /// `<data_dir>/grab.toml`. Missing files yield the all-`None` default
+    /// rather than an error, since the file is optional.
+    pub fn load(explicit_path: Option<&Path>, data_dir: &Path) -> Result<Self> {
+        let path = match explicit_path {
+            Some(path) => path.to_path_buf(),
+            None => data_dir.join("grab.toml"),
+        };
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Peer IDs marked `required` or `pinned`, i.e. the ones `node start`
+    /// dials after the network comes up.
+    pub fn required_peers(&self) -> Vec<(&str, &PeerOverride)> {
+        self.peers
+            .iter()
+            .filter(|(_, p)| p.required || p.pinned)
+            .map(|(id, p)| (id.as_str(), p))
+            .collect()
+    }
+
+    /// The multiaddr of the first required/pinned peer, for commands like
+    /// `pin` that want a default peer to connect to when none is given.
+    pub fn preferred_peer_multiaddr(&self) -> Option<String> {
+        self.required_peers()
+            .into_iter()
+            .find_map(|(_, p)| p.multiaddr.clone())
+    }
+}
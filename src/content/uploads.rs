@@ -1,8 +1,8 @@
 //! User upload management for GrabNet sites
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use anyhow::Result;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -26,6 +26,14 @@ pub struct UploadPolicy {
     pub moderation: ModerationMode,
     /// Rate limit (uploads per hour)
     pub rate_limit: usize,
+    /// How long an upload stays retrievable after it's made, in seconds.
+    /// `None` means uploads never expire on their own.
+    pub lifetime_secs: Option<u64>,
+    /// Stricter limits applied instead of this policy whenever
+    /// `uploader_id` is `None`, so a site can be generous to logged-in
+    /// users while tightly bounding drive-by anonymous uploads. `None`
+    /// means anonymous uploaders get the same policy as everyone else.
+    pub anonymous: Option<Box<UploadPolicy>>,
 }
 
 impl Default for UploadPolicy {
@@ -37,6 +45,8 @@ impl Default for UploadPolicy {
             require_auth: false,
             moderation: ModerationMode::None,
             rate_limit: 60,
+            lifetime_secs: None,
+            anonymous: None,
         }
     }
 }
@@ -62,6 +72,22 @@ pub enum UploadStatus {
     Rejected,
 }
 
+/// Why [`UserContentManager::get_upload_content`] or
+/// [`UserContentManager::get_upload_content_with_password`] couldn't return
+/// an upload's bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadAccessError {
+    /// No upload exists with that ID, or it isn't currently readable for a
+    /// reason unrelated to its password: it's pending/rejected moderation,
+    /// its lifetime has elapsed, or its uploader is suspended.
+    NotFound,
+    /// The upload is password-protected; call
+    /// [`UserContentManager::get_upload_content_with_password`] instead.
+    PasswordRequired,
+    /// A password was supplied but didn't match the upload's.
+    IncorrectPassword,
+}
+
 /// A user upload
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserUpload {
@@ -83,10 +109,57 @@ pub struct UserUpload {
     pub chunks: Vec<ChunkId>,
     /// Upload timestamp
     pub uploaded_at: u64,
+    /// When this upload stops being retrievable (unix ms), per the
+    /// site's `UploadPolicy::lifetime_secs` at the time it was made.
+    /// `None` if that policy had no lifetime set.
+    pub expires_at: Option<u64>,
+    /// Random per-upload salt for `password_hash`. Never serialized: it's
+    /// only useful alongside the hash it was computed with.
+    #[serde(skip_serializing, default)]
+    password_salt: Option<[u8; 16]>,
+    /// BLAKE3(salt || password), checked in constant time by
+    /// `UserContentManager::verify_password`. `None` means the upload has
+    /// no password and is retrievable by anyone who can reach its ID.
+    #[serde(skip_serializing, default)]
+    password_hash: Option<[u8; 32]>,
     /// Status
     pub status: UploadStatus,
 }
 
+impl UserUpload {
+    /// Whether this upload's lifetime has elapsed as of `now` (unix ms).
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// Whether a password is required to read this upload's content.
+    pub fn requires_password(&self) -> bool {
+        self.password_hash.is_some()
+    }
+}
+
+/// Running upload statistics for one site, updated as each upload lands.
+/// Tracks chunk-level deduplication savings alongside the raw counts so a
+/// site owner can see how much physical storage their uploads are
+/// actually costing versus their logical size.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SiteUploadStats {
+    /// Number of uploads made to this site (including later-deleted ones).
+    pub upload_count: u64,
+    /// Sum of every upload's `size`, before dedup.
+    pub logical_bytes: u64,
+    /// Sum of bytes actually written to the chunk store, i.e. bytes
+    /// belonging to chunks that didn't already exist there.
+    pub stored_bytes: u64,
+}
+
+impl SiteUploadStats {
+    /// Bytes saved by chunk-level dedup: `logical_bytes - stored_bytes`.
+    pub fn dedup_savings(&self) -> u64 {
+        self.logical_bytes.saturating_sub(self.stored_bytes)
+    }
+}
+
 /// Manages user-uploaded content
 pub struct UserContentManager {
     chunk_store: Arc<ChunkStore>,
@@ -94,7 +167,39 @@ pub struct UserContentManager {
     uploads: RwLock<HashMap<String, UserUpload>>,
     uploads_by_site: RwLock<HashMap<SiteId, Vec<String>>>,
     uploads_by_user: RwLock<HashMap<String, Vec<String>>>,
-    rate_limits: RwLock<HashMap<String, Vec<u64>>>,
+    rate_limits: RwLock<HashMap<String, TokenBucket>>,
+    site_stats: RwLock<HashMap<SiteId, SiteUploadStats>>,
+    /// Uploaders whose rights have been revoked: they can't make new
+    /// uploads, and content they already uploaded stops being readable
+    /// until they're unsuspended. Anonymous uploaders (no `uploader_id`)
+    /// can't be suspended — there's no stable identity to key on.
+    suspended_users: RwLock<HashSet<String>>,
+}
+
+/// A per-uploader token-bucket allowance: starts full at `policy.rate_limit`
+/// tokens, drains one per upload, and continuously refills back up to that
+/// cap at a rate of `rate_limit` tokens/hour. Smooths out the old
+/// timestamp-vector limiter's burst-then-silence pattern — a user who's
+/// been quiet can burst back up to the full limit instead of waiting for
+/// a fixed hourly window to roll over.
+struct TokenBucket {
+    tokens: f64,
+    last_refill_ms: u64,
+}
+
+impl TokenBucket {
+    fn full(capacity: usize, now_ms: u64) -> Self {
+        Self { tokens: capacity as f64, last_refill_ms: now_ms }
+    }
+
+    /// Top up tokens for time elapsed since the last refill, capped at
+    /// `capacity`, assuming a refill rate of `capacity` tokens/hour.
+    fn refill(&mut self, now_ms: u64, capacity: usize) {
+        let elapsed_ms = now_ms.saturating_sub(self.last_refill_ms) as f64;
+        let tokens_per_ms = capacity as f64 / 3_600_000.0;
+        self.tokens = (self.tokens + elapsed_ms * tokens_per_ms).min(capacity as f64);
+        self.last_refill_ms = now_ms;
+    }
 }
 
 impl UserContentManager {
@@ -107,6 +212,8 @@ impl UserContentManager {
             uploads_by_site: RwLock::new(HashMap::new()),
             uploads_by_user: RwLock::new(HashMap::new()),
             rate_limits: RwLock::new(HashMap::new()),
+            site_stats: RwLock::new(HashMap::new()),
+            suspended_users: RwLock::new(HashSet::new()),
         }
     }
 
@@ -125,7 +232,21 @@ impl UserContentManager {
         self.policies.read().get(site_id).cloned()
     }
 
-    /// Upload content
+    /// Set the stricter policy override applied to anonymous uploaders for
+    /// a site, on top of whatever main policy is already set via
+    /// [`Self::set_policy`].
+    pub fn set_anonymous_policy(&self, site_id: &SiteId, policy: UploadPolicy) {
+        if let Some(main) = self.policies.write().get_mut(site_id) {
+            main.anonymous = Some(Box::new(policy));
+        }
+    }
+
+    /// Upload content, optionally requiring `password` to read it back
+    /// via `get_upload_content_with_password`. `anon_key` is only
+    /// consulted when `uploader_id` is `None`: it should be something
+    /// stable about the caller (e.g. their IP address) so the same caller
+    /// lands in the same rate-limit/quota bucket across requests instead
+    /// of a fresh one every time — see `anon_key`'s use below.
     pub fn upload(
         &self,
         site_id: &SiteId,
@@ -133,23 +254,46 @@ impl UserContentManager {
         mime_type: &str,
         data: &[u8],
         uploader_id: Option<&str>,
+        anon_key: Option<&str>,
+        password: Option<&str>,
     ) -> Result<Option<UserUpload>> {
-        // Get policy
+        // Get policy. Anonymous uploaders (no `uploader_id`) are bound by
+        // the site's `anonymous` override instead of its main policy, if
+        // one is set.
         let policy = match self.policies.read().get(site_id) {
+            Some(p) if uploader_id.is_none() => {
+                p.anonymous.as_deref().unwrap_or(p).clone()
+            }
             Some(p) => p.clone(),
             None => return Ok(None), // No policy = uploads disabled
         };
 
-        // Generate uploader ID
+        // Generate uploader ID. An anonymous uploader is keyed off
+        // `anon_key` (hashed, same as everything else derived from caller
+        // data in this file) so `check_rate_limit`/`get_user_storage` see
+        // the same bucket across repeated requests from the same caller
+        // instead of a brand-new random one every time. Callers with
+        // nothing stable to offer (no `anon_key`) fall back to the old
+        // random ID, which at least can't be impersonated.
         let uploader = uploader_id
             .map(String::from)
-            .unwrap_or_else(|| format!("anon_{}", encode_base58(&hash(&rand::random::<[u8; 8]>())[..8])));
+            .unwrap_or_else(|| match anon_key {
+                Some(key) => format!("anon_{}", encode_base58(&hash(key.as_bytes())[..8])),
+                None => format!("anon_{}", encode_base58(&hash(&rand::random::<[u8; 8]>())[..8])),
+            });
 
         // Check authentication requirement
         if policy.require_auth && uploader_id.is_none() {
             anyhow::bail!("Authentication required");
         }
 
+        // Check suspension
+        if let Some(id) = uploader_id {
+            if self.is_suspended(id) {
+                anyhow::bail!("User suspended");
+            }
+        }
+
         // Check file size
         if data.len() > policy.max_file_size {
             anyhow::bail!("File too large (max {} bytes)", policy.max_file_size);
@@ -176,11 +320,19 @@ impl UserContentManager {
             anyhow::bail!("Storage quota exceeded");
         }
 
-        // Chunk and store
+        // Chunk and store. `ChunkStore` is already content-addressed, so
+        // a chunk identical to one from an earlier upload (this site's or
+        // any other's) is never written twice — `stored_new_bytes` below
+        // only counts the chunks this call actually had to add.
         let chunk_size = 256 * 1024;
         let mut chunks = Vec::new();
+        let mut stored_new_bytes = 0u64;
 
         for chunk in data.chunks(chunk_size) {
+            let prospective_id = self.chunk_store.hash_method().hash(chunk);
+            if !self.chunk_store.contains(&prospective_id)? {
+                stored_new_bytes += chunk.len() as u64;
+            }
             let chunk_id = self.chunk_store.put(chunk)?;
             chunks.push(chunk_id);
         }
@@ -197,6 +349,15 @@ impl UserContentManager {
             ModerationMode::Pre => UploadStatus::Pending,
             _ => UploadStatus::Approved,
         };
+        let expires_at = policy.lifetime_secs.map(|secs| now + secs * 1000);
+
+        let (password_salt, password_hash) = match password {
+            Some(password) => {
+                let salt = rand::random::<[u8; 16]>();
+                (Some(salt), Some(hash_password(&salt, password)))
+            }
+            None => (None, None),
+        };
 
         let upload = UserUpload {
             id: upload_id.clone(),
@@ -208,6 +369,9 @@ impl UserContentManager {
             content_hash,
             chunks,
             uploaded_at: now,
+            expires_at,
+            password_salt,
+            password_hash,
             status,
         };
 
@@ -222,8 +386,15 @@ impl UserContentManager {
             .or_default()
             .push(upload_id);
 
+        let mut stats = self.site_stats.write();
+        let site_stats = stats.entry(*site_id).or_default();
+        site_stats.upload_count += 1;
+        site_stats.logical_bytes += data.len() as u64;
+        site_stats.stored_bytes += stored_new_bytes;
+        drop(stats);
+
         // Record rate limit
-        self.record_upload(&uploader);
+        self.record_upload(&uploader, policy.rate_limit);
 
         Ok(Some(upload))
     }
@@ -233,21 +404,70 @@ impl UserContentManager {
         self.uploads.read().get(upload_id).cloned()
     }
 
-    /// Get upload content
-    pub fn get_upload_content(&self, upload_id: &str) -> Option<Vec<u8>> {
-        let upload = self.uploads.read().get(upload_id)?.clone();
+    /// Get the content of an upload that isn't password-protected. Uploads
+    /// that require a password are refused outright -- use
+    /// [`Self::get_upload_content_with_password`] for those.
+    pub fn get_upload_content(&self, upload_id: &str) -> Result<Vec<u8>, UploadAccessError> {
+        let upload = self.readable_upload(upload_id)?;
+        if upload.requires_password() {
+            return Err(UploadAccessError::PasswordRequired);
+        }
+        self.read_chunks(&upload)
+    }
+
+    /// Get the content of a password-protected (or unprotected) upload,
+    /// checking `password` against the upload's `password_hash` (if any)
+    /// in constant time first.
+    pub fn get_upload_content_with_password(
+        &self,
+        upload_id: &str,
+        password: &str,
+    ) -> Result<Vec<u8>, UploadAccessError> {
+        let upload = self.readable_upload(upload_id)?;
+        if upload.requires_password() && !Self::verify_password(&upload, Some(password)) {
+            return Err(UploadAccessError::IncorrectPassword);
+        }
+        self.read_chunks(&upload)
+    }
+
+    /// Look up `upload_id`, applying every check that's independent of a
+    /// password: it must exist, be approved, not have expired, and its
+    /// uploader must not be suspended.
+    fn readable_upload(&self, upload_id: &str) -> Result<UserUpload, UploadAccessError> {
+        let upload = self.uploads.read().get(upload_id).cloned().ok_or(UploadAccessError::NotFound)?;
 
         if upload.status != UploadStatus::Approved {
-            return None;
+            return Err(UploadAccessError::NotFound);
         }
 
+        if self.is_suspended(&upload.uploader) {
+            return Err(UploadAccessError::NotFound);
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        if upload.is_expired(now) {
+            return Err(UploadAccessError::NotFound);
+        }
+
+        Ok(upload)
+    }
+
+    /// Reassemble an upload's bytes from the chunk store. A missing or
+    /// unreadable chunk is treated the same as the upload not being found
+    /// at all, since it's equally unreadable to the caller.
+    fn read_chunks(&self, upload: &UserUpload) -> Result<Vec<u8>, UploadAccessError> {
         let mut content = Vec::with_capacity(upload.size);
         for chunk_id in &upload.chunks {
-            let data = self.chunk_store.get(chunk_id).ok()??;
+            let data = self.chunk_store.get(chunk_id)
+                .ok()
+                .flatten()
+                .ok_or(UploadAccessError::NotFound)?;
             content.extend_from_slice(&data);
         }
-
-        Some(content)
+        Ok(content)
     }
 
     /// List uploads for a site
@@ -263,6 +483,32 @@ impl UserContentManager {
             .collect()
     }
 
+    /// Cumulative upload statistics for a site, including chunk-level
+    /// dedup savings. Counts every upload ever made, even ones since
+    /// deleted or expired — use `list_site_uploads().len()` instead if
+    /// you want only what's currently live.
+    pub fn get_site_stats(&self, site_id: &SiteId) -> SiteUploadStats {
+        self.site_stats.read().get(site_id).copied().unwrap_or_default()
+    }
+
+    /// Revoke a user's upload rights: blocks new uploads from them and
+    /// makes their existing approved uploads unreadable until they're
+    /// unsuspended.
+    pub fn suspend_user(&self, user_id: &str) {
+        self.suspended_users.write().insert(user_id.to_string());
+    }
+
+    /// Restore a suspended user's upload rights. Returns `false` if they
+    /// weren't suspended.
+    pub fn unsuspend_user(&self, user_id: &str) -> bool {
+        self.suspended_users.write().remove(user_id)
+    }
+
+    /// Whether `user_id` currently has their upload rights revoked.
+    pub fn is_suspended(&self, user_id: &str) -> bool {
+        self.suspended_users.read().contains(user_id)
+    }
+
     /// Approve an upload
     pub fn approve(&self, upload_id: &str) -> bool {
         if let Some(upload) = self.uploads.write().get_mut(upload_id) {
@@ -301,38 +547,70 @@ impl UserContentManager {
         true
     }
 
+    /// Delete every upload whose `lifetime_secs` has elapsed, returning
+    /// how many were removed. Intended to be called periodically (see
+    /// [`spawn_sweeper`]), but callers needing it on demand (e.g. before
+    /// reporting storage usage) can call it directly too.
+    pub fn sweep_expired(&self) -> usize {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        let expired: Vec<String> = self.uploads.read()
+            .values()
+            .filter(|u| u.is_expired(now))
+            .map(|u| u.id.clone())
+            .collect();
+
+        let mut removed = 0;
+        for upload_id in expired {
+            if self.delete(&upload_id) {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
     // =========================================================================
     // Helpers
     // =========================================================================
 
+    /// Check `password` against `upload`'s stored hash in constant time,
+    /// so a timing side-channel can't be used to guess it byte-by-byte.
+    /// An upload with no password rejects any supplied password, just as
+    /// one with a password rejects a missing one.
+    fn verify_password(upload: &UserUpload, password: Option<&str>) -> bool {
+        match (upload.password_salt, upload.password_hash, password) {
+            (Some(salt), Some(expected), Some(candidate)) => {
+                constant_time_eq(&hash_password(&salt, candidate), &expected)
+            }
+            _ => false,
+        }
+    }
+
     fn check_rate_limit(&self, user_id: &str, limit: usize) -> bool {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_millis() as u64;
-        let hour_ago = now - 3600 * 1000;
-
-        let rates = self.rate_limits.read();
-        let recent = rates.get(user_id)
-            .map(|times| times.iter().filter(|&&t| t > hour_ago).count())
-            .unwrap_or(0);
 
-        recent < limit
+        let mut buckets = self.rate_limits.write();
+        let bucket = buckets.entry(user_id.to_string()).or_insert_with(|| TokenBucket::full(limit, now));
+        bucket.refill(now, limit);
+        bucket.tokens >= 1.0
     }
 
-    fn record_upload(&self, user_id: &str) {
+    fn record_upload(&self, user_id: &str, limit: usize) {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_millis() as u64;
 
-        let mut rates = self.rate_limits.write();
-        let times = rates.entry(user_id.to_string()).or_default();
-        times.push(now);
-
-        // Clean old entries
-        let hour_ago = now - 3600 * 1000;
-        times.retain(|&t| t > hour_ago);
+        let mut buckets = self.rate_limits.write();
+        let bucket = buckets.entry(user_id.to_string()).or_insert_with(|| TokenBucket::full(limit, now));
+        bucket.refill(now, limit);
+        bucket.tokens = (bucket.tokens - 1.0).max(0.0);
     }
 
     fn get_user_storage(&self, user_id: &str) -> usize {
@@ -349,6 +627,38 @@ impl UserContentManager {
     }
 }
 
+/// Salt and hash an upload password with BLAKE3 via `crate::crypto::hash`.
+fn hash_password(salt: &[u8; 16], password: &str) -> [u8; 32] {
+    hash(&[salt.as_slice(), password.as_bytes()].concat())
+}
+
+/// Compare two byte slices in time independent of where they first
+/// differ, so a wrong password guess can't be narrowed down by timing
+/// individual bytes. Both sides are fixed-size 32-byte hashes, so there
+/// is no length to compare.
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Spawn a background task that calls [`UserContentManager::sweep_expired`]
+/// on a fixed cadence for as long as `manager` has other owners. Dropping
+/// every other `Arc` to it stops the task the next time it wakes.
+pub fn spawn_sweeper(manager: Arc<UserContentManager>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if Arc::strong_count(&manager) == 1 {
+                break;
+            }
+            let removed = manager.sweep_expired();
+            if removed > 0 {
+                tracing::debug!("upload sweeper removed {} expired upload(s)", removed);
+            }
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -369,6 +679,8 @@ mod tests {
             "text/plain",
             b"hello world",
             Some("user1"),
+            None,
+            None,
         )?.unwrap();
 
         assert_eq!(upload.filename, "test.txt");
@@ -399,12 +711,14 @@ mod tests {
             "text/plain",
             b"needs review",
             None,
+            None,
+            None,
         )?.unwrap();
 
         assert_eq!(upload.status, UploadStatus::Pending);
 
         // Content not accessible while pending
-        assert!(manager.get_upload_content(&upload.id).is_none());
+        assert_eq!(manager.get_upload_content(&upload.id), Err(UploadAccessError::NotFound));
 
         // Approve
         manager.approve(&upload.id);
@@ -412,7 +726,224 @@ mod tests {
         assert_eq!(upload.status, UploadStatus::Approved);
 
         // Now content is accessible
-        assert!(manager.get_upload_content(&upload.id).is_some());
+        assert!(manager.get_upload_content(&upload.id).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expired_upload_is_inaccessible_and_swept() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy {
+            lifetime_secs: Some(0),
+            ..Default::default()
+        });
+
+        let upload = manager.upload(
+            &site_id,
+            "ephemeral.txt",
+            "text/plain",
+            b"gone soon",
+            Some("user1"),
+            None,
+            None,
+        )?.unwrap();
+
+        // A zero-second lifetime has already elapsed.
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(manager.get_upload_content(&upload.id), Err(UploadAccessError::NotFound));
+
+        let removed = manager.sweep_expired();
+        assert_eq!(removed, 1);
+        assert!(manager.get_upload(&upload.id).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unexpiring_upload_survives_sweep() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy::default());
+
+        let upload = manager.upload(
+            &site_id,
+            "keeper.txt",
+            "text/plain",
+            b"sticks around",
+            Some("user1"),
+            None,
+            None,
+        )?.unwrap();
+        assert_eq!(upload.expires_at, None);
+
+        assert_eq!(manager.sweep_expired(), 0);
+        assert!(manager.get_upload(&upload.id).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_password_protected_upload_requires_correct_password() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy::default());
+
+        let upload = manager.upload(
+            &site_id,
+            "secret.txt",
+            "text/plain",
+            b"for your eyes only",
+            Some("user1"),
+            None,
+            Some("hunter2"),
+        )?.unwrap();
+
+        assert!(upload.requires_password());
+        assert_eq!(manager.get_upload_content(&upload.id), Err(UploadAccessError::PasswordRequired));
+        assert_eq!(
+            manager.get_upload_content_with_password(&upload.id, "wrong"),
+            Err(UploadAccessError::IncorrectPassword)
+        );
+        assert_eq!(
+            manager.get_upload_content_with_password(&upload.id, "hunter2").unwrap(),
+            b"for your eyes only"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_site_stats_reflect_chunk_dedup() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy::default());
+
+        let data = b"the exact same bytes every time";
+        manager.upload(&site_id, "a.txt", "text/plain", data, Some("user1"), None, None)?;
+        manager.upload(&site_id, "b.txt", "text/plain", data, Some("user1"), None, None)?;
+
+        let stats = manager.get_site_stats(&site_id);
+        assert_eq!(stats.upload_count, 2);
+        assert_eq!(stats.logical_bytes, data.len() as u64 * 2);
+        // The second upload's chunk is an exact duplicate of the first's,
+        // so only one copy was ever actually stored.
+        assert_eq!(stats.stored_bytes, data.len() as u64);
+        assert_eq!(stats.dedup_savings(), data.len() as u64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_bucket_rate_limit_blocks_when_empty_per_uploader() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy { rate_limit: 2, ..Default::default() });
+
+        manager.upload(&site_id, "a.txt", "text/plain", b"one", Some("user1"), None, None)?;
+        manager.upload(&site_id, "b.txt", "text/plain", b"two", Some("user1"), None, None)?;
+
+        // Bucket is now empty.
+        let result = manager.upload(&site_id, "c.txt", "text/plain", b"three", Some("user1"), None, None);
+        assert!(result.is_err());
+
+        // A different uploader has their own bucket and isn't affected.
+        assert!(manager.upload(&site_id, "d.txt", "text/plain", b"four", Some("user2"), None, None)?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_suspended_user_loses_upload_and_read_rights() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy::default());
+
+        let upload = manager.upload(
+            &site_id,
+            "before.txt",
+            "text/plain",
+            b"made before suspension",
+            Some("bad-actor"),
+            None,
+            None,
+        )?.unwrap();
+        assert!(manager.get_upload_content(&upload.id).is_ok());
+
+        manager.suspend_user("bad-actor");
+        assert!(manager.is_suspended("bad-actor"));
+
+        // Existing content is now unreadable...
+        assert_eq!(manager.get_upload_content(&upload.id), Err(UploadAccessError::NotFound));
+        // ...and new uploads are rejected.
+        let result = manager.upload(&site_id, "after.txt", "text/plain", b"too late", Some("bad-actor"), None, None);
+        assert!(result.is_err());
+
+        // Unsuspending restores both.
+        assert!(manager.unsuspend_user("bad-actor"));
+        assert!(manager.get_upload_content(&upload.id).is_ok());
+        assert!(manager.upload(&site_id, "after.txt", "text/plain", b"now ok", Some("bad-actor"), None, None)?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_anonymous_policy_override_is_stricter() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy { max_file_size: 1024, ..Default::default() });
+        manager.set_anonymous_policy(&site_id, UploadPolicy { max_file_size: 4, ..Default::default() });
+
+        // Authenticated uploaders still get the main policy's larger limit.
+        assert!(manager.upload(&site_id, "a.txt", "text/plain", b"twelve bytes", Some("user1"), None, None)?.is_some());
+
+        // Anonymous uploaders are bound by the stricter override.
+        let result = manager.upload(&site_id, "b.txt", "text/plain", b"twelve bytes", None, None, None);
+        assert!(result.is_err());
+        assert!(manager.upload(&site_id, "c.txt", "text/plain", b"ok", None, None, None)?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_anonymous_rate_limit_keys_on_anon_key_not_per_call() -> Result<()> {
+        let dir = tempdir()?;
+        let chunk_store = Arc::new(ChunkStore::new(dir.path())?);
+        let manager = UserContentManager::new(chunk_store);
+
+        let site_id = [1u8; 32];
+        manager.set_policy(&site_id, UploadPolicy { rate_limit: 2, ..Default::default() });
+
+        // Same anon_key (e.g. the same client IP) shares one bucket...
+        manager.upload(&site_id, "a.txt", "text/plain", b"one", None, Some("203.0.113.1"), None)?;
+        manager.upload(&site_id, "b.txt", "text/plain", b"two", None, Some("203.0.113.1"), None)?;
+        let result = manager.upload(&site_id, "c.txt", "text/plain", b"three", None, Some("203.0.113.1"), None);
+        assert!(result.is_err());
+
+        // ...but a different anon_key gets its own.
+        assert!(manager.upload(&site_id, "d.txt", "text/plain", b"four", None, Some("198.51.100.1"), None)?.is_some());
 
         Ok(())
     }
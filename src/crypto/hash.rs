@@ -1,13 +1,59 @@
 //! BLAKE3 hashing utilities
 
+use blake2::Blake2b;
+use blake2::digest::consts::U32;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
 use crate::types::{ChunkId, SiteId, PublicKey};
 
+/// BLAKE2b parameterized to a 32-byte digest, matching `ChunkId`'s width.
+type Blake2b256 = Blake2b<U32>;
+
 /// Hash data using BLAKE3
 #[inline]
 pub fn hash(data: &[u8]) -> [u8; 32] {
     *blake3::hash(data).as_bytes()
 }
 
+/// Which digest produced a `ChunkId` or content hash. Chunk stores and
+/// published manifests record this so a store or bundle is
+/// self-describing: switching to a faster or stronger digest for new
+/// content doesn't require rehashing (or misinterpreting) anything
+/// hashed under a previous method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashMethod {
+    Blake3,
+    Blake2b,
+    Sha256,
+}
+
+impl Default for HashMethod {
+    fn default() -> Self {
+        HashMethod::Blake3
+    }
+}
+
+impl HashMethod {
+    /// Hash `data` with this method.
+    pub fn hash(self, data: &[u8]) -> [u8; 32] {
+        match self {
+            HashMethod::Blake3 => hash(data),
+            HashMethod::Blake2b => {
+                let mut hasher = Blake2b256::new();
+                hasher.update(data);
+                hasher.finalize().into()
+            }
+            HashMethod::Sha256 => {
+                let mut hasher = Sha256::new();
+                hasher.update(data);
+                hasher.finalize().into()
+            }
+        }
+    }
+}
+
 /// Hash multiple byte slices
 pub fn hash_multi(parts: &[&[u8]]) -> [u8; 32] {
     let mut hasher = blake3::Hasher::new();
@@ -65,6 +111,36 @@ pub fn chunk_id(data: &[u8]) -> ChunkId {
     hash(data)
 }
 
+/// Bytes of trailing checksum `append_frame_checksum` adds. A truncated
+/// BLAKE3 digest is enough to catch a corrupted or truncated frame
+/// without doubling the size of small compressed payloads.
+const FRAME_CHECKSUM_LEN: usize = 8;
+
+/// Append a truncated BLAKE3 checksum of `data` after it, so a later
+/// `verify_frame_checksum` call can detect corruption (e.g. a damaged
+/// chunk) before the frame is decoded and served.
+pub fn append_frame_checksum(mut data: Vec<u8>) -> Vec<u8> {
+    let checksum = hash(&data);
+    data.extend_from_slice(&checksum[..FRAME_CHECKSUM_LEN]);
+    data
+}
+
+/// Split off and verify a trailing checksum written by
+/// `append_frame_checksum`. Returns the original data with the checksum
+/// stripped, or `None` if the frame is too short or the checksum doesn't
+/// match.
+pub fn verify_frame_checksum(framed: &[u8]) -> Option<&[u8]> {
+    if framed.len() < FRAME_CHECKSUM_LEN {
+        return None;
+    }
+    let (data, checksum) = framed.split_at(framed.len() - FRAME_CHECKSUM_LEN);
+    if hash(data)[..FRAME_CHECKSUM_LEN] == *checksum {
+        Some(data)
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,4 +183,40 @@ mod tests {
         let decoded = SiteId::from_base58(&encoded).unwrap();
         assert_eq!(data, decoded);
     }
+
+    #[test]
+    fn test_hash_method_agrees_with_default_blake3() {
+        let data = b"hello world";
+        assert_eq!(HashMethod::Blake3.hash(data), hash(data));
+    }
+
+    #[test]
+    fn test_hash_methods_differ() {
+        let data = b"hello world";
+        let blake3 = HashMethod::Blake3.hash(data);
+        let blake2b = HashMethod::Blake2b.hash(data);
+        let sha256 = HashMethod::Sha256.hash(data);
+        assert_ne!(blake3, blake2b);
+        assert_ne!(blake3, sha256);
+        assert_ne!(blake2b, sha256);
+    }
+
+    #[test]
+    fn test_frame_checksum_round_trip() {
+        let framed = append_frame_checksum(b"compressed bytes".to_vec());
+        assert_eq!(verify_frame_checksum(&framed), Some(&b"compressed bytes"[..]));
+    }
+
+    #[test]
+    fn test_frame_checksum_detects_corruption() {
+        let mut framed = append_frame_checksum(b"compressed bytes".to_vec());
+        let last = framed.len() - 1;
+        framed[last] ^= 0xff;
+        assert_eq!(verify_frame_checksum(&framed), None);
+    }
+
+    #[test]
+    fn test_frame_checksum_rejects_too_short_frame() {
+        assert_eq!(verify_frame_checksum(b"short"), None);
+    }
 }
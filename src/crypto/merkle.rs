@@ -1,6 +1,7 @@
 //! Merkle tree for content verification and delta sync
 
-use crate::types::MerkleProof;
+use crate::types::{MerkleProof, NodeId};
+use super::hash_multi;
 use super::hash;
 
 /// A Merkle tree for content verification
@@ -90,6 +91,7 @@ impl MerkleTree {
             leaf_hash: self.leaves[index],
             siblings,
             root: self.root(),
+            mmr_size: 0,
         })
     }
 
@@ -156,6 +158,404 @@ pub fn compute_content_hash(chunks: &[[u8; 32]]) -> [u8; 32] {
     tree.root()
 }
 
+/// One "mountain": a perfect binary subtree covering a contiguous,
+/// power-of-two-sized run of leaves.
+#[derive(Debug, Clone)]
+struct Peak {
+    /// Global index of this peak's first leaf.
+    leaf_start: usize,
+    leaves: Vec<[u8; 32]>,
+}
+
+impl Peak {
+    fn root(&self) -> [u8; 32] {
+        MerkleTree::new(self.leaves.clone()).root()
+    }
+}
+
+/// The ordered sizes (in descending order) of the perfect subtrees an MMR
+/// with `leaf_count` leaves is made of: one per set bit of `leaf_count`,
+/// most significant first. This is also the peak layout a `MerkleProof`
+/// with `mmr_size == leaf_count` was built against.
+fn peak_sizes(leaf_count: usize) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    let mut bit = if leaf_count == 0 {
+        0
+    } else {
+        1usize << (usize::BITS - 1 - leaf_count.leading_zeros())
+    };
+    while bit > 0 {
+        if leaf_count & bit != 0 {
+            sizes.push(bit);
+        }
+        bit >>= 1;
+    }
+    sizes
+}
+
+/// An append-only Merkle Mountain Range.
+///
+/// Leaves (typically a `FileEntry.hash` or chunk hash) are pushed one at a
+/// time. Rather than a single tree rebuilt from scratch, the range keeps a
+/// list of "peaks" — one perfect binary subtree per set bit of the leaf
+/// count — so appending touches only the peaks that merge, not the whole
+/// history. The root is the BLAKE3 bagging of every peak, folded
+/// right-to-left (newest peak first). Proofs walk the authentication path
+/// within a leaf's own peak and then carry the sibling peak hashes needed
+/// to redo the bagging, so `proof`/`verify` stay cheap even as the range
+/// grows.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleMountainRange {
+    peaks: Vec<Peak>,
+    leaf_count: usize,
+}
+
+impl MerkleMountainRange {
+    /// Create an empty range.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a range by appending each leaf in order.
+    pub fn from_leaves(leaves: Vec<[u8; 32]>) -> Self {
+        let mut mmr = Self::new();
+        for leaf in leaves {
+            mmr.append(leaf);
+        }
+        mmr
+    }
+
+    /// Append a leaf hash, merging peaks of equal height as needed.
+    /// Returns the leaf's index.
+    pub fn append(&mut self, leaf_hash: [u8; 32]) -> usize {
+        let index = self.leaf_count;
+        self.peaks.push(Peak {
+            leaf_start: index,
+            leaves: vec![leaf_hash],
+        });
+        self.leaf_count += 1;
+
+        while self.peaks.len() >= 2 {
+            let right_len = self.peaks[self.peaks.len() - 1].leaves.len();
+            let left_len = self.peaks[self.peaks.len() - 2].leaves.len();
+            if left_len != right_len {
+                break;
+            }
+            let right = self.peaks.pop().unwrap();
+            let mut left = self.peaks.pop().unwrap();
+            left.leaves.extend(right.leaves);
+            self.peaks.push(left);
+        }
+
+        index
+    }
+
+    /// Number of leaves appended so far.
+    pub fn len(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Whether no leaves have been appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// The root: every peak's hash, bagged right-to-left with BLAKE3.
+    pub fn root(&self) -> [u8; 32] {
+        let mut iter = self.peaks.iter().rev();
+        let Some(first) = iter.next() else {
+            return [0u8; 32];
+        };
+        let mut acc = first.root();
+        for peak in iter {
+            acc = hash_multi(&[&peak.root(), &acc]);
+        }
+        acc
+    }
+
+    /// Produce a proof for the leaf at `leaf_index`, cheap to compute since
+    /// only the owning peak is walked and the other peaks contribute just
+    /// their already-known roots.
+    pub fn proof(&self, leaf_index: usize) -> Option<MerkleProof> {
+        if leaf_index >= self.leaf_count {
+            return None;
+        }
+
+        let peak_idx = self
+            .peaks
+            .iter()
+            .position(|p| leaf_index >= p.leaf_start && leaf_index < p.leaf_start + p.leaves.len())?;
+        let peak = &self.peaks[peak_idx];
+        let local_index = leaf_index - peak.leaf_start;
+
+        let local_tree = MerkleTree::new(peak.leaves.clone());
+        let local_proof = local_tree.get_proof(local_index)?;
+
+        // Other peaks' roots, right-to-left, excluding our own peak —
+        // the order `verify` folds them back in to reconstruct `root`.
+        let bag_siblings: Vec<[u8; 32]> = (0..self.peaks.len())
+            .rev()
+            .filter(|&i| i != peak_idx)
+            .map(|i| self.peaks[i].root())
+            .collect();
+
+        let mut siblings = local_proof.siblings;
+        siblings.extend(bag_siblings);
+
+        Some(MerkleProof {
+            leaf_index,
+            leaf_hash: local_proof.leaf_hash,
+            siblings,
+            root: self.root(),
+            mmr_size: self.leaf_count,
+        })
+    }
+
+    /// Verify a proof produced by `proof`, recomputing `root` from
+    /// `leaf_hash`, `leaf_index`, and `siblings` alone.
+    pub fn verify(proof: &MerkleProof) -> bool {
+        let sizes = peak_sizes(proof.mmr_size);
+
+        let mut offset = 0usize;
+        let mut found = None;
+        for (i, &size) in sizes.iter().enumerate() {
+            if proof.leaf_index >= offset && proof.leaf_index < offset + size {
+                found = Some((i, size, offset));
+                break;
+            }
+            offset += size;
+        }
+        let Some((peak_idx, peak_size, peak_start)) = found else {
+            return false;
+        };
+        let local_index = proof.leaf_index - peak_start;
+        let height = peak_size.trailing_zeros() as usize;
+
+        if proof.siblings.len() < height {
+            return false;
+        }
+        let (local_siblings, bag_siblings) = proof.siblings.split_at(height);
+        if bag_siblings.len() != sizes.len() - 1 {
+            return false;
+        }
+
+        // Walk the within-peak authentication path.
+        let mut current = proof.leaf_hash;
+        let mut idx = local_index;
+        for sibling in local_siblings {
+            current = if idx % 2 == 0 {
+                hash_multi(&[&current, sibling])
+            } else {
+                hash_multi(&[sibling, &current])
+            };
+            idx /= 2;
+        }
+        let peak_root = current;
+
+        // Re-fold every peak root right-to-left, substituting ours in at
+        // its position, exactly mirroring `MerkleMountainRange::root`.
+        let mut bag_iter = bag_siblings.iter();
+        let mut vals = Vec::with_capacity(sizes.len());
+        for i in (0..sizes.len()).rev() {
+            if i == peak_idx {
+                vals.push(peak_root);
+            } else {
+                match bag_iter.next() {
+                    Some(s) => vals.push(*s),
+                    None => return false,
+                }
+            }
+        }
+
+        let mut acc = vals[0];
+        for v in &vals[1..] {
+            acc = hash_multi(&[v, &acc]);
+        }
+
+        acc == proof.root
+    }
+}
+
+/// An append-only Merkle tree that caches every intermediate node so
+/// pushing a leaf costs amortized O(log n) instead of `MerkleTree::new`'s
+/// full rebuild-and-pad.
+///
+/// `layers[0]` holds the leaves and each higher layer holds the parents of
+/// the layer below. A push writes the new leaf to `layers[0]` and then
+/// walks upward: whenever a layer's length becomes even, the last pair is
+/// hashed into the layer above; an odd trailing node is left in place
+/// rather than padded with a zero sibling. That trailing node is exactly a
+/// [`MerkleMountainRange`] peak root, so the set of odd-length layers
+/// (one per set bit of the leaf count) forms the same right spine, and
+/// `root`/`get_proof`/`verify` reuse that bagging.
+#[derive(Debug, Clone, Default)]
+pub struct AppendMerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl AppendMerkleTree {
+    /// Create an empty tree.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a tree by pushing each leaf in order.
+    pub fn from_leaves(leaves: Vec<[u8; 32]>) -> Self {
+        let mut tree = Self::new();
+        tree.append(&leaves);
+        tree
+    }
+
+    /// Push a single leaf, merging completed pairs up the layers. Returns
+    /// the leaf's index.
+    pub fn push(&mut self, leaf_hash: [u8; 32]) -> usize {
+        if self.layers.is_empty() {
+            self.layers.push(Vec::new());
+        }
+        self.layers[0].push(leaf_hash);
+        let index = self.layers[0].len() - 1;
+
+        let mut i = 0;
+        while self.layers[i].len() % 2 == 0 {
+            let len = self.layers[i].len();
+            let parent = hash_pair(&self.layers[i][len - 2], &self.layers[i][len - 1]);
+            if self.layers.len() == i + 1 {
+                self.layers.push(Vec::new());
+            }
+            self.layers[i + 1].push(parent);
+            i += 1;
+        }
+
+        index
+    }
+
+    /// Push a batch of leaves in order.
+    pub fn append(&mut self, leaves: &[[u8; 32]]) {
+        for &leaf in leaves {
+            self.push(leaf);
+        }
+    }
+
+    /// Merge in a whole batch of leaves delivered as a unit (for example a
+    /// bundle of chunks a peer streamed in one delta-sync message). Leaves
+    /// are still folded in one at a time, so interleaving `push` calls with
+    /// `append_subtree` calls produces the same tree as pushing everything
+    /// in sequence.
+    pub fn append_subtree(&mut self, leaves: &[[u8; 32]]) {
+        self.append(leaves);
+    }
+
+    /// Number of leaves pushed so far.
+    pub fn len(&self) -> usize {
+        self.layers.first().map_or(0, |leaves| leaves.len())
+    }
+
+    /// Whether no leaves have been pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The root: the same right-spine bagging as [`MerkleMountainRange::root`],
+    /// read directly off the trailing node of each odd-length layer.
+    pub fn root(&self) -> [u8; 32] {
+        if self.is_empty() {
+            return [0u8; 32];
+        }
+
+        let mut iter = peak_sizes(self.len()).into_iter().rev();
+        let mut acc = *self.layers[iter.next().unwrap().trailing_zeros() as usize].last().unwrap();
+        for size in iter {
+            let peak_root = *self.layers[size.trailing_zeros() as usize].last().unwrap();
+            acc = hash_multi(&[&peak_root, &acc]);
+        }
+        acc
+    }
+
+    /// Produce a proof for the leaf at `leaf_index`, reusing [`MerkleTree`]
+    /// for the within-peak authentication path and the cached layer nodes
+    /// for the cross-peak bagging siblings.
+    pub fn get_proof(&self, leaf_index: usize) -> Option<MerkleProof> {
+        let leaf_count = self.len();
+        if leaf_index >= leaf_count {
+            return None;
+        }
+
+        let sizes = peak_sizes(leaf_count);
+        let mut offset = 0usize;
+        let mut peak_start = 0usize;
+        let mut peak_size = 0usize;
+        for &size in &sizes {
+            if leaf_index >= offset && leaf_index < offset + size {
+                peak_start = offset;
+                peak_size = size;
+                break;
+            }
+            offset += size;
+        }
+
+        let local_index = leaf_index - peak_start;
+        let local_tree = MerkleTree::new(self.layers[0][peak_start..peak_start + peak_size].to_vec());
+        let local_proof = local_tree.get_proof(local_index)?;
+
+        // Other peaks' roots, right-to-left and excluding our own, matching
+        // the order `MerkleMountainRange::verify` folds them back in.
+        let mut offset = 0usize;
+        let mut bag_siblings = Vec::new();
+        for &size in &sizes {
+            if offset != peak_start {
+                bag_siblings.push(*self.layers[size.trailing_zeros() as usize].last().unwrap());
+            }
+            offset += size;
+        }
+        bag_siblings.reverse();
+
+        let mut siblings = local_proof.siblings;
+        siblings.extend(bag_siblings);
+
+        Some(MerkleProof {
+            leaf_index,
+            leaf_hash: local_proof.leaf_hash,
+            siblings,
+            root: self.root(),
+            mmr_size: leaf_count,
+        })
+    }
+
+    /// Verify a proof produced by `get_proof`. The peak layout this tree's
+    /// odd-length layers form is identical to a [`MerkleMountainRange`]'s,
+    /// so verification is the same bagging walk.
+    pub fn verify(proof: &MerkleProof) -> bool {
+        MerkleMountainRange::verify(proof)
+    }
+
+    /// The hash cached at `(height, index)`, or `None` if that position
+    /// isn't materialized yet. Since we never pad, `layers[height][index]`
+    /// always holds exactly the hash a classic padded binary tree would
+    /// have at that position (the hash of leaves `[index * 2^height, (index
+    /// + 1) * 2^height)`) whenever that range is fully covered by pushed
+    /// leaves, so this doubles as the node-addressing scheme for
+    /// [`crate::network::MerkleDiffSession`]'s anti-entropy diff.
+    pub fn node_hash(&self, height: u32, index: usize) -> Option<[u8; 32]> {
+        self.layers.get(height as usize)?.get(index).copied()
+    }
+
+    /// The `(height, index)` of every current peak — the nodes that
+    /// `root()` bags together. This is the natural starting frontier for a
+    /// diff: two trees with the same leaf count always have peaks at the
+    /// same positions, so a mismatch can jump straight to them instead of
+    /// walking down from a single (nonexistent) top node.
+    pub fn peak_node_ids(&self) -> Vec<NodeId> {
+        peak_sizes(self.len())
+            .into_iter()
+            .map(|size| {
+                let height = size.trailing_zeros();
+                let index = self.layers[height as usize].len() - 1;
+                NodeId { height, index }
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,4 +605,119 @@ mod tests {
         let diff = tree1.diff(&tree2);
         assert_eq!(diff, vec![1]); // Only index 1 differs
     }
+
+    #[test]
+    fn test_mmr_proofs_verify_at_every_size() {
+        // Exercise every peak layout from 1 to 11 leaves (non-power-of-two
+        // counts produce more than one peak).
+        for n in 1..=11 {
+            let leaves: Vec<[u8; 32]> = (0..n).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+            let mmr = MerkleMountainRange::from_leaves(leaves.clone());
+            assert_eq!(mmr.len(), n);
+
+            for i in 0..n {
+                let proof = mmr.proof(i).unwrap();
+                assert_eq!(proof.leaf_hash, leaves[i]);
+                assert_eq!(proof.root, mmr.root());
+                assert!(MerkleMountainRange::verify(&proof), "leaf {i} of {n} failed to verify");
+            }
+        }
+    }
+
+    #[test]
+    fn test_mmr_root_is_deterministic_and_order_sensitive() {
+        let mmr_a = MerkleMountainRange::from_leaves(vec![hash(b"a"), hash(b"b"), hash(b"c")]);
+        let mmr_b = MerkleMountainRange::from_leaves(vec![hash(b"a"), hash(b"b"), hash(b"c")]);
+        assert_eq!(mmr_a.root(), mmr_b.root());
+
+        let mmr_c = MerkleMountainRange::from_leaves(vec![hash(b"c"), hash(b"b"), hash(b"a")]);
+        assert_ne!(mmr_a.root(), mmr_c.root());
+    }
+
+    #[test]
+    fn test_mmr_tampered_proof_is_rejected() {
+        let leaves: Vec<[u8; 32]> = (0..5).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+        let mmr = MerkleMountainRange::from_leaves(leaves);
+
+        let mut proof = mmr.proof(2).unwrap();
+        proof.leaf_hash = hash(b"forged");
+        assert!(!MerkleMountainRange::verify(&proof));
+    }
+
+    #[test]
+    fn test_mmr_append_matches_from_leaves() {
+        let leaves: Vec<[u8; 32]> = (0..7).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+
+        let mut incremental = MerkleMountainRange::new();
+        for leaf in &leaves {
+            incremental.append(*leaf);
+        }
+
+        let bulk = MerkleMountainRange::from_leaves(leaves);
+        assert_eq!(incremental.root(), bulk.root());
+    }
+
+    #[test]
+    fn test_append_tree_proofs_verify_at_every_size() {
+        for n in 1..=11 {
+            let leaves: Vec<[u8; 32]> = (0..n).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+            let tree = AppendMerkleTree::from_leaves(leaves.clone());
+            assert_eq!(tree.len(), n);
+
+            for i in 0..n {
+                let proof = tree.get_proof(i).unwrap();
+                assert_eq!(proof.leaf_hash, leaves[i]);
+                assert_eq!(proof.root, tree.root());
+                assert!(AppendMerkleTree::verify(&proof), "leaf {i} of {n} failed to verify");
+            }
+        }
+    }
+
+    #[test]
+    fn test_append_tree_push_is_incremental_and_order_sensitive() {
+        let leaves: Vec<[u8; 32]> = (0..7).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+
+        let mut incremental = AppendMerkleTree::new();
+        for &leaf in &leaves {
+            incremental.push(leaf);
+        }
+        let bulk = AppendMerkleTree::from_leaves(leaves.clone());
+        assert_eq!(incremental.root(), bulk.root());
+
+        let reordered = AppendMerkleTree::from_leaves(leaves.into_iter().rev().collect());
+        assert_ne!(incremental.root(), reordered.root());
+    }
+
+    #[test]
+    fn test_append_tree_root_matches_mmr_root() {
+        // The two types cache nodes differently (layers vs. peak leaf
+        // lists) but decompose the same leaf count into the same peaks, so
+        // their roots must agree.
+        let leaves: Vec<[u8; 32]> = (0..13).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+        let append_tree = AppendMerkleTree::from_leaves(leaves.clone());
+        let mmr = MerkleMountainRange::from_leaves(leaves);
+        assert_eq!(append_tree.root(), mmr.root());
+    }
+
+    #[test]
+    fn test_append_tree_tampered_proof_is_rejected() {
+        let leaves: Vec<[u8; 32]> = (0..5).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+        let tree = AppendMerkleTree::from_leaves(leaves);
+
+        let mut proof = tree.get_proof(2).unwrap();
+        proof.leaf_hash = hash(b"forged");
+        assert!(!AppendMerkleTree::verify(&proof));
+    }
+
+    #[test]
+    fn test_append_subtree_matches_individual_pushes() {
+        let leaves: Vec<[u8; 32]> = (0..9).map(|i| hash(format!("leaf{i}").as_bytes())).collect();
+
+        let mut batched = AppendMerkleTree::new();
+        batched.append_subtree(&leaves[..4]);
+        batched.append_subtree(&leaves[4..]);
+
+        let sequential = AppendMerkleTree::from_leaves(leaves);
+        assert_eq!(batched.root(), sequential.root());
+    }
 }
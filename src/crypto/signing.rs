@@ -4,11 +4,36 @@ use ed25519_dalek::{SigningKey, VerifyingKey, Signer, Verifier};
 use crate::types::{PublicKey, Signature};
 use rand::rngs::OsRng;
 
+/// Domain-separation salt for deterministic keypair derivation. Fixed so the
+/// same secret always yields the same keypair across versions and machines.
+const DERIVE_KEYPAIR_SALT: &[u8] = b"grabnet-derive-keypair-v1";
+
 /// Generate a new Ed25519 keypair
 pub fn generate_keypair() -> (PublicKey, [u8; 32]) {
     let signing_key = SigningKey::generate(&mut OsRng);
     let verifying_key = signing_key.verifying_key();
-    
+
+    (verifying_key.to_bytes(), signing_key.to_bytes())
+}
+
+/// Deterministically derive an Ed25519 keypair from a shared secret, so
+/// multiple nodes (or the same node across reinstalls) can agree on an
+/// identity without exchanging key material out of band.
+///
+/// Uses Argon2id to stretch the secret before it is used as an Ed25519
+/// signing key seed, which resists brute-force guessing of low-entropy
+/// secrets far better than a plain hash.
+pub fn derive_keypair(secret: &str) -> (PublicKey, [u8; 32]) {
+    use argon2::Argon2;
+
+    let mut seed = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(secret.as_bytes(), DERIVE_KEYPAIR_SALT, &mut seed)
+        .expect("Argon2id derivation with a fixed-size output cannot fail");
+
+    let signing_key = SigningKey::from_bytes(&seed);
+    let verifying_key = signing_key.verifying_key();
+
     (verifying_key.to_bytes(), signing_key.to_bytes())
 }
 
@@ -68,6 +93,156 @@ pub fn verify_bundle(
     verify(&message, signature, public_key)
 }
 
+/// Sign a bootstrap node entry for authenticity (name + addresses),
+/// mirroring [`sign_bundle`] so maintainers can ship a signed default
+/// bootstrap list that nodes can verify before trusting it.
+pub fn sign_bootstrap(name: &str, addresses: &[String], private_key: &[u8; 32]) -> Signature {
+    sign(&bootstrap_signing_message(name, addresses), private_key)
+}
+
+/// Verify a bootstrap node entry's signature, mirroring [`verify_bundle`].
+pub fn verify_bootstrap(
+    name: &str,
+    addresses: &[String],
+    signature: &Signature,
+    public_key: &PublicKey,
+) -> bool {
+    verify(&bootstrap_signing_message(name, addresses), signature, public_key)
+}
+
+fn bootstrap_signing_message(name: &str, addresses: &[String]) -> Vec<u8> {
+    let mut message = Vec::new();
+    message.extend_from_slice(name.as_bytes());
+    for addr in addresses {
+        message.push(0); // separator, so adjacent fields can't be confused
+        message.extend_from_slice(addr.as_bytes());
+    }
+    message
+}
+
+/// Sign a name -> site binding for the naming registry, mirroring
+/// [`sign_bundle`].
+pub fn sign_name_record(
+    name: &str,
+    site_id: &[u8; 32],
+    revision: u64,
+    updated_at: u64,
+    private_key: &[u8; 32],
+) -> Signature {
+    sign(&name_record_signing_message(name, site_id, revision, updated_at), private_key)
+}
+
+/// Verify a name -> site binding's signature, mirroring [`verify_bundle`].
+pub fn verify_name_record(
+    name: &str,
+    site_id: &[u8; 32],
+    revision: u64,
+    updated_at: u64,
+    signature: &Signature,
+    public_key: &PublicKey,
+) -> bool {
+    verify(
+        &name_record_signing_message(name, site_id, revision, updated_at),
+        signature,
+        public_key,
+    )
+}
+
+fn name_record_signing_message(name: &str, site_id: &[u8; 32], revision: u64, updated_at: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(name.len() + 48);
+    message.extend_from_slice(name.as_bytes());
+    message.push(0); // separator, so the name can't bleed into site_id
+    message.extend_from_slice(site_id);
+    message.extend_from_slice(&revision.to_le_bytes());
+    message.extend_from_slice(&updated_at.to_le_bytes());
+    message
+}
+
+/// Sign a name-chain claim (see `crate::storage::name_chain`), mirroring
+/// [`sign_name_record`]. `prev_hash` is bound into the signature so a
+/// claim can't be replayed onto a different point in the chain than the
+/// owner actually built it against.
+pub fn sign_name_claim(
+    name: &str,
+    site_id: &[u8; 32],
+    prev_hash: &[u8; 32],
+    nonce: u64,
+    renewal: bool,
+    private_key: &[u8; 32],
+) -> Signature {
+    sign(&name_claim_signing_message(name, site_id, prev_hash, nonce, renewal), private_key)
+}
+
+/// Verify a name-chain claim's signature, mirroring [`verify_name_record`].
+pub fn verify_name_claim(
+    name: &str,
+    site_id: &[u8; 32],
+    prev_hash: &[u8; 32],
+    nonce: u64,
+    renewal: bool,
+    signature: &Signature,
+    public_key: &PublicKey,
+) -> bool {
+    verify(
+        &name_claim_signing_message(name, site_id, prev_hash, nonce, renewal),
+        signature,
+        public_key,
+    )
+}
+
+fn name_claim_signing_message(name: &str, site_id: &[u8; 32], prev_hash: &[u8; 32], nonce: u64, renewal: bool) -> Vec<u8> {
+    let mut message = Vec::with_capacity(name.len() + 74);
+    message.extend_from_slice(name.as_bytes());
+    message.push(0); // separator, so the name can't bleed into site_id
+    message.extend_from_slice(site_id);
+    message.extend_from_slice(prev_hash);
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message.push(renewal as u8);
+    message
+}
+
+/// Sign a [`crate::types::NodeInformation`] record exchanged after
+/// [`crate::network::pairing`]'s out-of-band code is confirmed, mirroring
+/// [`sign_name_claim`].
+pub fn sign_node_information(
+    peer_id: &str,
+    name: &str,
+    site_ids: &[[u8; 32]],
+    pubkey: &PublicKey,
+    private_key: &[u8; 32],
+) -> Signature {
+    sign(&node_information_signing_message(peer_id, name, site_ids, pubkey), private_key)
+}
+
+/// Verify a [`crate::types::NodeInformation`] record's signature, mirroring
+/// [`verify_name_claim`].
+pub fn verify_node_information(
+    peer_id: &str,
+    name: &str,
+    site_ids: &[[u8; 32]],
+    pubkey: &PublicKey,
+    signature: &Signature,
+) -> bool {
+    verify(
+        &node_information_signing_message(peer_id, name, site_ids, pubkey),
+        signature,
+        pubkey,
+    )
+}
+
+fn node_information_signing_message(peer_id: &str, name: &str, site_ids: &[[u8; 32]], pubkey: &PublicKey) -> Vec<u8> {
+    let mut message = Vec::with_capacity(peer_id.len() + name.len() + site_ids.len() * 32 + 34);
+    message.extend_from_slice(peer_id.as_bytes());
+    message.push(0); // separator, so peer_id can't bleed into name
+    message.extend_from_slice(name.as_bytes());
+    message.push(0); // separator, so name can't bleed into site_ids
+    for site_id in site_ids {
+        message.extend_from_slice(site_id);
+    }
+    message.extend_from_slice(pubkey);
+    message
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,6 +274,17 @@ mod tests {
         assert!(!verify(message, &signature, &other_public));
     }
 
+    #[test]
+    fn test_derive_keypair_deterministic() {
+        let (public1, private1) = derive_keypair("shared secret");
+        let (public2, private2) = derive_keypair("shared secret");
+        assert_eq!(public1, public2);
+        assert_eq!(private1, private2);
+
+        let (other_public, _) = derive_keypair("different secret");
+        assert_ne!(public1, other_public);
+    }
+
     #[test]
     fn test_bundle_signature() {
         let (public, private) = generate_keypair();
@@ -112,4 +298,69 @@ mod tests {
         // Wrong revision fails
         assert!(!verify_bundle(&site_id, 43, &root_hash, &signature, &public));
     }
+
+    #[test]
+    fn test_bootstrap_signature() {
+        let (public, private) = generate_keypair();
+        let name = "grabnet-us-east".to_string();
+        let addresses = vec!["/dns4/bootstrap-us.grabnet.io/tcp/4001".to_string()];
+
+        let signature = sign_bootstrap(&name, &addresses, &private);
+        assert!(verify_bootstrap(&name, &addresses, &signature, &public));
+
+        // Tampered address fails
+        let tampered = vec!["/dns4/evil.example.com/tcp/4001".to_string()];
+        assert!(!verify_bootstrap(&name, &tampered, &signature, &public));
+    }
+
+    #[test]
+    fn test_name_record_signature() {
+        let (public, private) = generate_keypair();
+        let site_id = [3u8; 32];
+
+        let signature = sign_name_record("example", &site_id, 1, 1_000, &private);
+        assert!(verify_name_record("example", &site_id, 1, 1_000, &signature, &public));
+
+        // A squatter claiming the same name for a different site fails
+        let other_site = [4u8; 32];
+        assert!(!verify_name_record("example", &other_site, 1, 1_000, &signature, &public));
+
+        // Wrong publisher fails
+        let (other_public, _) = generate_keypair();
+        assert!(!verify_name_record("example", &site_id, 1, 1_000, &signature, &other_public));
+    }
+
+    #[test]
+    fn test_name_claim_signature() {
+        let (public, private) = generate_keypair();
+        let site_id = [5u8; 32];
+        let prev_hash = [0u8; 32];
+
+        let signature = sign_name_claim("example", &site_id, &prev_hash, 7, false, &private);
+        assert!(verify_name_claim("example", &site_id, &prev_hash, 7, false, &signature, &public));
+
+        // A renewal flag flip invalidates the signature
+        assert!(!verify_name_claim("example", &site_id, &prev_hash, 7, true, &signature, &public));
+
+        // A different prev_hash invalidates the signature
+        let other_prev = [1u8; 32];
+        assert!(!verify_name_claim("example", &site_id, &other_prev, 7, false, &signature, &public));
+    }
+
+    #[test]
+    fn test_node_information_signature() {
+        let (public, private) = generate_keypair();
+        let site_ids = vec![[6u8; 32], [7u8; 32]];
+
+        let signature = sign_node_information("peer-1", "laptop", &site_ids, &public, &private);
+        assert!(verify_node_information("peer-1", "laptop", &site_ids, &public, &signature));
+
+        // A different site list invalidates the signature
+        let other_sites = vec![[8u8; 32]];
+        assert!(!verify_node_information("peer-1", "laptop", &other_sites, &public, &signature));
+
+        // Wrong signer key fails
+        let (other_public, _) = generate_keypair();
+        assert!(!verify_node_information("peer-1", "laptop", &site_ids, &other_public, &signature));
+    }
 }
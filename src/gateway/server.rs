@@ -1,25 +1,29 @@
 //! HTTP Gateway server using axum
 
+use std::convert::Infallible;
 use std::sync::Arc;
-use std::net::SocketAddr;
-use std::time::Instant;
+use std::io::Read;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use anyhow::Result;
 use axum::{
     Router,
     routing::get,
-    extract::{Path, State, Query},
-    response::{IntoResponse, Response, Html},
-    http::{StatusCode, header, HeaderMap, HeaderValue},
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, ConnectInfo, Path, Request, State, Query},
+    response::{IntoResponse, Response, Html, sse::{Event, KeepAlive, Sse}},
+    http::{StatusCode, header, HeaderMap, HeaderName, HeaderValue},
+    middleware::{self, Next},
     body::Body,
     Json,
 };
+use futures::stream::{self, Stream};
 use tower_http::cors::{CorsLayer, Any};
 use serde::{Deserialize, Serialize};
 use tokio::sync::oneshot;
 use parking_lot::RwLock;
 
-use crate::types::{Config, SiteId, FileEntry, Compression};
-use crate::storage::{ChunkStore, BundleStore};
+use crate::types::{Config, SiteId, FileEntry, Compression, GatewayConfig, HeaderPolicyConfig, ChunkId};
+use crate::storage::{ChunkStore, BundleStore, NameStore, NameChain};
 use crate::content::UserContentManager;
 use crate::crypto::SiteIdExt;
 use crate::network::GrabNetwork;
@@ -32,7 +36,9 @@ pub struct Gateway {
     content_manager: Option<UserContentManager>,
     shutdown_tx: Option<oneshot::Sender<()>>,
     default_site: Option<SiteId>,
-    network: Option<Arc<RwLock<Option<GrabNetwork>>>>,
+    network: Option<Arc<RwLock<Option<Arc<GrabNetwork>>>>>,
+    name_store: Option<Arc<NameStore>>,
+    name_chain: Option<Arc<NameChain>>,
     start_time: Instant,
 }
 
@@ -43,8 +49,12 @@ struct AppState {
     bundle_store: Arc<BundleStore>,
     content_manager: Option<Arc<UserContentManager>>,
     default_site: Option<SiteId>,
-    network: Option<Arc<RwLock<Option<GrabNetwork>>>>,
+    network: Option<Arc<RwLock<Option<Arc<GrabNetwork>>>>>,
+    name_store: Option<Arc<NameStore>>,
+    name_chain: Option<Arc<NameChain>>,
     start_time: Instant,
+    access: Arc<AccessControl>,
+    headers: Arc<HeaderPolicyConfig>,
 }
 
 impl Gateway {
@@ -63,6 +73,8 @@ impl Gateway {
             shutdown_tx: None,
             default_site: None,
             network: None,
+            name_store: None,
+            name_chain: None,
             start_time: Instant::now(),
         }
     }
@@ -83,28 +95,51 @@ impl Gateway {
             shutdown_tx: None,
             default_site: Some(default_site),
             network: None,
+            name_store: None,
+            name_chain: None,
             start_time: Instant::now(),
         }
     }
 
     /// Set the network reference for peer info endpoints
-    pub fn with_network(mut self, network: Arc<RwLock<Option<GrabNetwork>>>) -> Self {
+    pub fn with_network(mut self, network: Arc<RwLock<Option<Arc<GrabNetwork>>>>) -> Self {
         self.network = Some(network);
         self
     }
 
+    /// Set the naming registry so sites can be resolved by name as well as
+    /// by base58 `SiteId` (`/site/<name>/...`).
+    pub fn with_name_store(mut self, name_store: Arc<NameStore>) -> Self {
+        self.name_store = Some(name_store);
+        self
+    }
+
+    /// Set the name-claim chain so the dashboard can show chain-resolved
+    /// names next to site IDs and list pending/confirmed claims.
+    pub fn with_name_chain(mut self, name_chain: Arc<NameChain>) -> Self {
+        self.name_chain = Some(name_chain);
+        self
+    }
+
     /// Start the gateway
     pub async fn start(&self) -> Result<()> {
         let addr: SocketAddr = format!("{}:{}", self.config.gateway.host, self.config.gateway.port)
             .parse()?;
 
+        let access = Arc::new(AccessControl::from_config(&self.config.gateway));
+        let headers = Arc::new(self.config.gateway.headers.clone());
+
         let state = AppState {
             chunk_store: self.chunk_store.clone(),
             bundle_store: self.bundle_store.clone(),
             content_manager: self.content_manager.as_ref().map(|m| Arc::new(m.clone())),
             default_site: self.default_site.clone(),
             network: self.network.clone(),
+            name_store: self.name_store.clone(),
+            name_chain: self.name_chain.clone(),
             start_time: self.start_time,
+            access,
+            headers,
         };
 
         // Build router with standard routes
@@ -115,6 +150,8 @@ impl Gateway {
             .route("/api/network", get(network_status_handler))
             .route("/api/network/peers", get(peers_handler))
             .route("/api/network/stats", get(network_stats_handler))
+            .route("/api/status", get(status_handler))
+            .route("/api/events", get(events_handler))
             .route("/peers", get(peer_viewer_handler))
             // API routes
             .route("/api/sites", get(list_sites_handler))
@@ -126,7 +163,12 @@ impl Gateway {
             // Site content
             .route("/site/:site_id", get(redirect_to_index))
             .route("/site/:site_id/", get(serve_site_index))
-            .route("/site/:site_id/*path", get(serve_site_handler));
+            .route("/site/:site_id/*path", get(serve_site_handler))
+            // Reserved path: upgrades to a framed channel for P2P messages
+            // (chunk exchange, manifest lookups, live announcements), so a
+            // browser or firewalled peer can reach both the gateway and the
+            // node over one port.
+            .route("/_grab/ws", get(ws_upgrade_handler));
 
         // Add root routes if default site is set
         if self.default_site.is_some() {
@@ -142,13 +184,19 @@ impl Gateway {
                 .allow_origin(Any)
                 .allow_methods(Any)
                 .allow_headers(Any))
+            // IP allow/deny, checked ahead of everything else
+            .layer(middleware::from_fn_with_state(state.clone(), access_control_middleware))
+            // Security/cache headers, outermost so they land on denied and
+            // error responses too, but skipped for upgrade handshakes
+            .layer(middleware::from_fn_with_state(state.clone(), security_headers_middleware))
             .with_state(state);
 
         tracing::info!("Gateway listening on http://{}", addr);
 
-        // Start server
+        // Start server. `with_connect_info` is what makes the real peer
+        // address available to the access-control middleware above.
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
         Ok(())
     }
@@ -168,6 +216,157 @@ impl Clone for UserContentManager {
     }
 }
 
+// ============================================================================
+// Access control (trusted-proxy IP recovery + allow/deny lists)
+// ============================================================================
+
+/// A single IP or CIDR range from `--allow`/`--deny`.
+#[derive(Debug, Clone, Copy)]
+struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    fn parse(spec: &str) -> Option<Self> {
+        match spec.split_once('/') {
+            Some((addr_str, prefix_str)) => {
+                let addr: IpAddr = addr_str.trim().parse().ok()?;
+                let prefix_len: u8 = prefix_str.trim().parse().ok()?;
+                Some(Self { addr, prefix_len })
+            }
+            None => {
+                let addr: IpAddr = spec.trim().parse().ok()?;
+                let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+                Some(Self { addr, prefix_len })
+            }
+        }
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let bits = self.prefix_len.min(32);
+                let mask = if bits == 0 { 0 } else { u32::MAX << (32 - bits) };
+                (u32::from(net) & mask) == (u32::from(*ip) & mask)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let bits = self.prefix_len.min(128);
+                let mask = if bits == 0 { 0 } else { u128::MAX << (128 - bits) };
+                (u128::from(net) & mask) == (u128::from(*ip) & mask)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Built once from [`GatewayConfig`] at gateway startup.
+struct AccessControl {
+    trusted_proxy: bool,
+    allow: Vec<IpRange>,
+    deny: Vec<IpRange>,
+}
+
+impl AccessControl {
+    fn from_config(config: &GatewayConfig) -> Self {
+        Self {
+            trusted_proxy: config.trusted_proxy,
+            allow: config.allow.iter().filter_map(|s| IpRange::parse(s)).collect(),
+            deny: config.deny.iter().filter_map(|s| IpRange::parse(s)).collect(),
+        }
+    }
+
+    /// Recover the client IP. `X-Forwarded-For` is only honored in
+    /// trusted-proxy mode, since otherwise any client could spoof it.
+    /// Each hop *appends* the address it observed the connection from, so
+    /// the entry we actually trust is the *last* one -- the address our
+    /// own trusted proxy reported seeing -- not the first, which is
+    /// whatever the original client claimed and can freely forge (e.g.
+    /// `X-Forwarded-For: 127.0.0.1` to impersonate an allow-listed IP).
+    fn client_ip(&self, headers: &HeaderMap, remote_addr: IpAddr) -> IpAddr {
+        if self.trusted_proxy {
+            if let Some(forwarded) = headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+            {
+                if let Some(last) = forwarded.split(',').last() {
+                    if let Ok(ip) = last.trim().parse::<IpAddr>() {
+                        return ip;
+                    }
+                }
+            }
+        }
+        remote_addr
+    }
+
+    /// `deny` takes precedence; an empty `allow` list means "allow anyone
+    /// not explicitly denied".
+    fn is_allowed(&self, ip: &IpAddr) -> bool {
+        if self.deny.iter().any(|range| range.contains(ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|range| range.contains(ip))
+    }
+}
+
+async fn access_control_middleware(
+    State(state): State<AppState>,
+    ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client_ip = state.access.client_ip(request.headers(), remote_addr.ip());
+    if !state.access.is_allowed(&client_ip) {
+        tracing::warn!("Denied gateway access to {}", client_ip);
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+    next.run(request).await
+}
+
+// ============================================================================
+// Security headers (nosniff, frame options, CSP, permissions policy)
+// ============================================================================
+
+/// A `Connection: Upgrade` request (our own `/_grab/ws`, or anything else a
+/// reverse proxy forwards through) is a handshake, not an ordinary HTTP
+/// response -- injecting headers meant for served pages onto it risks
+/// breaking the upgrade, so it's left untouched.
+fn is_upgrade_request(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONNECTION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.to_ascii_lowercase().contains("upgrade"))
+}
+
+async fn security_headers_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if is_upgrade_request(request.headers()) {
+        return next.run(request).await;
+    }
+
+    let mut response = next.run(request).await;
+    let policy = &state.headers;
+    let out = response.headers_mut();
+
+    if policy.nosniff {
+        out.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    }
+    if let Some(value) = policy.frame_options.as_ref().and_then(|v| HeaderValue::from_str(v).ok()) {
+        out.insert(header::X_FRAME_OPTIONS, value);
+    }
+    if let Some(value) = policy.content_security_policy.as_ref().and_then(|v| HeaderValue::from_str(v).ok()) {
+        out.insert(header::CONTENT_SECURITY_POLICY, value);
+    }
+    if let Some(value) = policy.permissions_policy.as_ref().and_then(|v| HeaderValue::from_str(v).ok()) {
+        out.insert(HeaderName::from_static("permissions-policy"), value);
+    }
+
+    response
+}
+
 // ============================================================================
 // Handlers
 // ============================================================================
@@ -185,7 +384,7 @@ struct SitesResponse {
     hosted: Vec<SiteInfo>,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, PartialEq, Serialize)]
 struct SiteInfo {
     site_id: String,
     name: String,
@@ -315,12 +514,36 @@ async fn serve_site_path(
     state: AppState,
 ) -> Response {
     tracing::debug!("serve_site_path: site_id={}, path={}", site_id, path);
-    
+
+    // Accept either a base58 `SiteId` or, if a naming registry is
+    // configured, a human-readable name resolved through it.
     let site_id = match SiteId::from_base58(&site_id) {
         Some(id) => id,
-        None => return (StatusCode::BAD_REQUEST, "Invalid site ID").into_response(),
+        None => {
+            let resolved = state.name_store.as_ref().and_then(|names| names.resolve(&site_id).ok().flatten());
+            match resolved {
+                Some(record) => record.site_id,
+                None => return (StatusCode::BAD_REQUEST, "Invalid site ID or unknown name").into_response(),
+            }
+        }
     };
 
+    // `If-Modified-Since` is a weaker validator than our strong per-file
+    // ETag, so we only pay for the full-bundle fetch (needed for its
+    // `created_at`) when a client actually sends the header.
+    if let Some(since) = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok())
+    {
+        if let Ok(Some(bundle)) = state.bundle_store.get_bundle(&site_id) {
+            let last_modified = UNIX_EPOCH + Duration::from_millis(bundle.created_at);
+            if last_modified <= since {
+                return StatusCode::NOT_MODIFIED.into_response();
+            }
+        }
+    }
+
     // Get manifest
     let manifest = match state.bundle_store.get_manifest(&site_id) {
         Ok(Some(m)) => m,
@@ -341,9 +564,9 @@ async fn serve_site_path(
     let file = match file {
         Some(f) => f,
         None => {
-            // Try 404.html
+            // Try 404.html. It's never the entry and isn't worth caching.
             if let Some(f) = manifest.files.iter().find(|f| f.path == "404.html") {
-                return serve_file(f, &state.chunk_store, &headers, StatusCode::NOT_FOUND).await;
+                return serve_file(f, &state.chunk_store, &headers, StatusCode::NOT_FOUND, "no-cache").await;
             }
             return (StatusCode::NOT_FOUND, "File not found").into_response();
         }
@@ -352,7 +575,17 @@ async fn serve_site_path(
     // Record access
     let _ = state.bundle_store.record_access(&site_id);
 
-    serve_file(file, &state.chunk_store, &headers, StatusCode::OK).await
+    // The entry file shares its path across site revisions, so it must be
+    // revalidated (via the ETag check in `serve_file`) rather than cached
+    // outright; every other file is content-addressed and safe to cache for
+    // as long as the operator configures.
+    let cache_control = if file.path == manifest.entry {
+        "no-cache".to_string()
+    } else {
+        format!("public, max-age={}, immutable", state.headers.asset_max_age_secs)
+    };
+
+    serve_file(file, &state.chunk_store, &headers, StatusCode::OK, &cache_control).await
 }
 
 fn find_file<'a>(files: &'a [FileEntry], path: &str, routes: Option<&crate::types::RouteConfig>) -> Option<&'a FileEntry> {
@@ -387,11 +620,166 @@ fn find_file<'a>(files: &'a [FileEntry], path: &str, routes: Option<&crate::type
     None
 }
 
+/// A validated, resolved `Range: bytes=...` header against a known content length.
+enum RangeResult {
+    /// No usable range header; serve the whole body.
+    Full,
+    /// Inclusive byte offsets into the body.
+    Partial { start: usize, end: usize },
+    Unsatisfiable,
+}
+
+/// Supports `start-end`, open-ended `start-`, and suffix `-N` forms. A
+/// multipart `bytes=a-b,c-d` request (rare for our use case) falls back to
+/// a full response rather than a `multipart/byteranges` body.
+fn resolve_range(request_headers: &HeaderMap, len: usize) -> RangeResult {
+    let Some(raw) = request_headers.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return RangeResult::Full;
+    };
+    let Some(spec) = raw.strip_prefix("bytes=") else {
+        return RangeResult::Full;
+    };
+    if spec.contains(',') {
+        return RangeResult::Full;
+    }
+    let Some((start_str, end_str)) = spec.trim().split_once('-') else {
+        return RangeResult::Full;
+    };
+
+    if len == 0 {
+        return RangeResult::Unsatisfiable;
+    }
+
+    if start_str.is_empty() {
+        let Ok(suffix_len) = end_str.parse::<usize>() else {
+            return RangeResult::Unsatisfiable;
+        };
+        if suffix_len == 0 {
+            return RangeResult::Unsatisfiable;
+        }
+        let start = len.saturating_sub(suffix_len);
+        return RangeResult::Partial { start, end: len - 1 };
+    }
+
+    let Ok(start) = start_str.parse::<usize>() else {
+        return RangeResult::Unsatisfiable;
+    };
+    if start >= len {
+        return RangeResult::Unsatisfiable;
+    }
+    let end = if end_str.is_empty() {
+        len - 1
+    } else {
+        match end_str.parse::<usize>() {
+            Ok(end) => end.min(len - 1),
+            Err(_) => return RangeResult::Unsatisfiable,
+        }
+    };
+    if start > end {
+        return RangeResult::Unsatisfiable;
+    }
+    RangeResult::Partial { start, end }
+}
+
+/// Finish a response builder by honoring any `Range` header on `status ==
+/// 200` bodies, attaching `Accept-Ranges`/`Content-Range` as needed.
+/// Error bodies (404, 500, ...) are always served in full.
+fn apply_range(
+    builder: axum::http::response::Builder,
+    body: Vec<u8>,
+    status: StatusCode,
+    request_headers: &HeaderMap,
+) -> Response {
+    let builder = builder.header(header::ACCEPT_RANGES, "bytes");
+
+    if status != StatusCode::OK {
+        return builder
+            .status(status)
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(Body::from(body))
+            .unwrap();
+    }
+
+    match resolve_range(request_headers, body.len()) {
+        RangeResult::Full => builder
+            .status(status)
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(Body::from(body))
+            .unwrap(),
+        RangeResult::Partial { start, end } => {
+            let total = body.len();
+            let slice = body[start..=end].to_vec();
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_LENGTH, slice.len())
+                .header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{total}"))
+                .body(Body::from(slice))
+                .unwrap()
+        }
+        RangeResult::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", body.len()))
+            .body(Body::empty())
+            .unwrap(),
+    }
+}
+
+/// `Content-Encoding` token a client can ask for to receive `codec`'s
+/// output as-is, or `None` if it's not a real codec.
+fn encoding_name(codec: Compression) -> Option<&'static str> {
+    match codec {
+        Compression::None => None,
+        Compression::Gzip => Some("gzip"),
+        Compression::Zstd => Some("zstd"),
+        Compression::Brotli => Some("br"),
+    }
+}
+
+/// Verify and strip the trailing checksum a zstd-compressed `FileEntry`
+/// was stored with (see `publisher::bundle::compress_with`), so a
+/// damaged chunk is caught here rather than handed to the browser as
+/// either garbled content or, worse, a checksum byte baked into it.
+/// Other codecs are returned unchanged.
+fn verify_stored_content(compression: Option<Compression>, data: Vec<u8>) -> Result<Vec<u8>, &'static str> {
+    match compression {
+        Some(Compression::Zstd) => crate::crypto::verify_frame_checksum(&data)
+            .map(|payload| payload.to_vec())
+            .ok_or("Corrupted content (checksum mismatch)"),
+        _ => Ok(data),
+    }
+}
+
+/// Decompress `data` if it was stored under `compression` and the client
+/// didn't ask to receive it pre-encoded. Falls back to the original
+/// bytes if decoding fails.
+fn decompress(compression: Option<Compression>, data: Vec<u8>) -> Vec<u8> {
+    let codec = match compression {
+        Some(codec) => codec,
+        None => return data,
+    };
+    match codec {
+        Compression::None => data,
+        Compression::Gzip => {
+            use flate2::read::GzDecoder;
+            let mut decoder = GzDecoder::new(&data[..]);
+            let mut out = Vec::new();
+            if decoder.read_to_end(&mut out).is_ok() { out } else { data }
+        }
+        Compression::Zstd => zstd::stream::decode_all(&data[..]).unwrap_or(data),
+        Compression::Brotli => {
+            let mut reader = brotli::Decompressor::new(&data[..], 4096);
+            let mut out = Vec::new();
+            if reader.read_to_end(&mut out).is_ok() { out } else { data }
+        }
+    }
+}
+
 async fn serve_file(
     file: &FileEntry,
     chunk_store: &ChunkStore,
     request_headers: &HeaderMap,
     status: StatusCode,
+    cache_control: &str,
 ) -> Response {
     // Check ETag
     let etag = format!("\"{}\"", crate::crypto::encode_base58(&file.hash[..8]));
@@ -410,44 +798,33 @@ async fn serve_file(
         }
     }
 
+    let content = match verify_stored_content(file.compression, content) {
+        Ok(content) => content,
+        Err(message) => return (StatusCode::INTERNAL_SERVER_ERROR, message).into_response(),
+    };
+
     // Handle compression
     let accept_encoding = request_headers
         .get(header::ACCEPT_ENCODING)
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    let (body, content_encoding) = match file.compression {
-        Some(Compression::Gzip) if accept_encoding.contains("gzip") => {
-            (content, Some("gzip"))
-        }
-        Some(Compression::Gzip) => {
-            // Decompress for client
-            use flate2::read::GzDecoder;
-            use std::io::Read;
-            let mut decoder = GzDecoder::new(&content[..]);
-            let mut decompressed = Vec::new();
-            if decoder.read_to_end(&mut decompressed).is_ok() {
-                (decompressed, None)
-            } else {
-                (content, None)
-            }
-        }
-        _ => (content, None),
+    let (body, content_encoding) = match file.compression.and_then(encoding_name) {
+        Some(name) if accept_encoding.contains(name) => (content, Some(name)),
+        _ => (decompress(file.compression, content), None),
     };
 
     // Build response
-    let mut response = Response::builder()
-        .status(status)
+    let mut builder = Response::builder()
         .header(header::CONTENT_TYPE, &file.mime_type)
-        .header(header::CONTENT_LENGTH, body.len())
         .header(header::ETAG, &etag)
-        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable");
+        .header(header::CACHE_CONTROL, cache_control);
 
     if let Some(encoding) = content_encoding {
-        response = response.header(header::CONTENT_ENCODING, encoding);
+        builder = builder.header(header::CONTENT_ENCODING, encoding);
     }
 
-    response.body(Body::from(body)).unwrap()
+    apply_range(builder, body, status, request_headers)
 }
 
 // ============================================================================
@@ -468,13 +845,15 @@ async fn list_uploads_handler(
     };
 
     let uploads = manager.list_site_uploads(&site_id);
-    Json(serde_json::json!({ "uploads": uploads })).into_response()
+    let stats = manager.get_site_stats(&site_id);
+    Json(serde_json::json!({ "uploads": uploads, "stats": stats })).into_response()
 }
 
 async fn upload_handler(
     Path(site_id): Path<String>,
     headers: HeaderMap,
     State(state): State<AppState>,
+    ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
     body: axum::body::Bytes,
 ) -> impl IntoResponse {
     let Some(manager) = &state.content_manager else {
@@ -498,7 +877,17 @@ async fn upload_handler(
         .unwrap_or("application/octet-stream")
         .to_string();
 
-    match manager.upload(&site_id, &filename, &mime_type, &body, None) {
+    let password = headers
+        .get("x-upload-password")
+        .and_then(|v| v.to_str().ok());
+
+    // Anonymous uploads have no `uploader_id`, so the client's address --
+    // recovered the same way `access_control_middleware` does, honoring
+    // `--trusted-proxy` -- is what keys their rate-limit/quota bucket
+    // instead, so the same client actually hits those limits across
+    // requests rather than minting a fresh bucket every time.
+    let client_ip = state.access.client_ip(&headers, remote_addr.ip());
+    match manager.upload(&site_id, &filename, &mime_type, &body, None, Some(&client_ip.to_string()), password) {
         Ok(Some(upload)) => {
             Json(serde_json::json!({
                 "upload": upload,
@@ -528,18 +917,56 @@ async fn serve_upload_handler(
         return (StatusCode::FORBIDDEN, "Content not approved").into_response();
     }
 
-    let content = match manager.get_upload_content(&upload_id) {
-        Some(c) => c,
-        None => return (StatusCode::INTERNAL_SERVER_ERROR, "Content unavailable").into_response(),
+    let password = headers
+        .get("x-upload-password")
+        .and_then(|v| v.to_str().ok());
+
+    if upload.requires_password() && password.is_none() {
+        return (StatusCode::UNAUTHORIZED, "Password required").into_response();
+    }
+
+    // Unprotected uploads are content-addressed and immutable, so a
+    // matching ETag lets a client skip re-downloading entirely. Skip this
+    // for password-protected uploads so a 304 can't be used to probe for
+    // the right password without supplying one.
+    let etag = format!("\"{}\"", upload.content_hash);
+    if !upload.requires_password() {
+        if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+            if if_none_match.as_bytes() == etag.as_bytes() {
+                return StatusCode::NOT_MODIFIED.into_response();
+            }
+        }
+    }
+
+    let result = match password {
+        Some(pw) => manager.get_upload_content_with_password(&upload_id, pw),
+        None => manager.get_upload_content(&upload_id),
+    };
+    let content = match result {
+        Ok(c) => c,
+        Err(crate::content::UploadAccessError::IncorrectPassword) => {
+            return (StatusCode::UNAUTHORIZED, "Wrong password").into_response()
+        }
+        Err(crate::content::UploadAccessError::PasswordRequired) => {
+            return (StatusCode::UNAUTHORIZED, "Password required").into_response()
+        }
+        Err(crate::content::UploadAccessError::NotFound) => {
+            return (StatusCode::UNAUTHORIZED, "Wrong password or content unavailable").into_response()
+        }
     };
 
-    Response::builder()
-        .status(StatusCode::OK)
+    let mut builder = Response::builder()
         .header(header::CONTENT_TYPE, &upload.mime_type)
-        .header(header::CONTENT_LENGTH, content.len())
-        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
-        .body(Body::from(content))
-        .unwrap()
+        .header(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}, immutable", state.headers.asset_max_age_secs),
+        );
+
+    if !upload.requires_password() {
+        builder = builder.header(header::ETAG, &etag);
+    }
+
+    apply_range(builder, content, StatusCode::OK, &headers)
 }
 
 // ============================================================================
@@ -635,6 +1062,94 @@ async fn peers_handler(State(state): State<AppState>) -> impl IntoResponse {
     }))
 }
 
+/// Everything the `/peers` dashboard, `/api/status`, and `/api/events`
+/// surfaces agree on: the running flag, peer info, published/hosted sites,
+/// and storage counters. Gathered in one place so the three surfaces can't
+/// drift apart, and compared with `PartialEq` to decide whether an SSE tick
+/// has anything new to push.
+#[derive(Clone, PartialEq, Serialize)]
+struct GatewayStatus {
+    running: bool,
+    peer_id: Option<String>,
+    peers: Vec<String>,
+    published: Vec<SiteInfo>,
+    hosted: Vec<SiteInfo>,
+    chunks: usize,
+    storage_bytes: u64,
+    addresses: Vec<String>,
+}
+
+impl GatewayStatus {
+    fn gather(state: &AppState) -> Self {
+        let (running, peer_id, peers, addresses) = if let Some(net_lock) = &state.network {
+            let guard = net_lock.read();
+            if let Some(network) = guard.as_ref() {
+                (
+                    true,
+                    Some(network.peer_id().to_string()),
+                    network.connected_peer_ids().into_iter().map(|p| p.to_string()).collect(),
+                    network.listen_addresses(),
+                )
+            } else {
+                (false, None, vec![], vec![])
+            }
+        } else {
+            (false, None, vec![], vec![])
+        };
+
+        let published = state.bundle_store.get_all_published_sites().unwrap_or_default()
+            .into_iter()
+            .map(|s| SiteInfo { site_id: s.site_id.to_base58(), name: s.name, revision: s.revision })
+            .collect();
+        let hosted = state.bundle_store.get_all_hosted_sites().unwrap_or_default()
+            .into_iter()
+            .map(|s| SiteInfo { site_id: s.site_id.to_base58(), name: s.name, revision: s.revision })
+            .collect();
+
+        Self {
+            running,
+            peer_id,
+            peers,
+            published,
+            hosted,
+            chunks: state.chunk_store.count(),
+            storage_bytes: state.chunk_store.total_size(),
+            addresses,
+        }
+    }
+}
+
+/// JSON status snapshot, the same data the `/peers` dashboard renders as
+/// HTML, for external dashboards and scripts that want to poll instead of
+/// subscribing to `/api/events`.
+async fn status_handler(State(state): State<AppState>) -> impl IntoResponse {
+    Json(GatewayStatus::gather(&state))
+}
+
+/// Server-Sent Events stream of [`GatewayStatus`]: polls the same snapshot
+/// every couple of seconds and pushes it only when something peer-, site-,
+/// or storage-related actually changed, so the `/peers` dashboard can update
+/// in place instead of reloading the whole page.
+async fn events_handler(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = stream::unfold((state, None::<GatewayStatus>), |(state, last)| async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let status = GatewayStatus::gather(&state);
+            if last.as_ref() != Some(&status) {
+                let event = Event::default()
+                    .event("status")
+                    .json_data(&status)
+                    .unwrap_or_else(|_| Event::default().event("status").data("{}"));
+                return Some((Ok(event), (state, Some(status))));
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
 async fn network_stats_handler(State(state): State<AppState>) -> impl IntoResponse {
     let uptime = state.start_time.elapsed().as_secs();
     
@@ -655,23 +1170,120 @@ async fn network_stats_handler(State(state): State<AppState>) -> impl IntoRespon
     })
 }
 
+/// A request a WebSocket client can send on `/_grab/ws`, mirroring the
+/// P2P chunk-exchange and manifest-lookup requests exposed over libp2p so
+/// a browser or firewalled peer can reach the same content over one port.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsRequest {
+    GetChunks { chunk_ids: Vec<ChunkId> },
+    GetManifest { site_id: SiteId },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsResponse {
+    Chunks { chunks: Vec<(ChunkId, Vec<u8>)>, missing: Vec<ChunkId> },
+    Manifest { bundle: Box<crate::types::WebBundle> },
+    Announcement { event: String },
+    Error { message: String },
+}
+
+async fn ws_upgrade_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_ws_socket(socket, state))
+}
+
+/// Drive one upgraded `/_grab/ws` connection: forward this node's network
+/// events to the client as they happen, and answer chunk/manifest requests
+/// the client sends in.
+async fn handle_ws_socket(mut socket: WebSocket, state: AppState) {
+    let mut events = state
+        .network
+        .as_ref()
+        .and_then(|net_lock| net_lock.read().as_ref().map(|net| net.subscribe()));
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                let Some(Ok(message)) = incoming else { break };
+                let Message::Text(text) = message else { continue };
+
+                let response = match serde_json::from_str::<WsRequest>(&text) {
+                    Ok(WsRequest::GetChunks { chunk_ids }) => {
+                        match state.chunk_store.get_many(&chunk_ids) {
+                            Ok((chunks, missing)) => WsResponse::Chunks { chunks, missing },
+                            Err(e) => WsResponse::Error { message: e.to_string() },
+                        }
+                    }
+                    Ok(WsRequest::GetManifest { site_id }) => {
+                        match state.bundle_store.get_bundle(&site_id) {
+                            Ok(Some(bundle)) => WsResponse::Manifest { bundle: Box::new(bundle) },
+                            Ok(None) => WsResponse::Error { message: "site not found".to_string() },
+                            Err(e) => WsResponse::Error { message: e.to_string() },
+                        }
+                    }
+                    Err(e) => WsResponse::Error { message: format!("bad request: {e}") },
+                };
+
+                let Ok(payload) = serde_json::to_string(&response) else { break };
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            event = async {
+                match events.as_mut() {
+                    Some(rx) => rx.recv().await.ok(),
+                    None => std::future::pending().await,
+                }
+            } => {
+                let Some(event) = event else { continue };
+                let response = WsResponse::Announcement { event: format!("{event:?}") };
+                let Ok(payload) = serde_json::to_string(&response) else { break };
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
 async fn peer_viewer_handler(State(state): State<AppState>) -> impl IntoResponse {
     let uptime = state.start_time.elapsed().as_secs();
     
-    let (running, peer_id, peers, addresses) = if let Some(net_lock) = &state.network {
+    let (running, peer_id, peers, addresses, mesh_topics, verified_peers, paired_devices) = if let Some(net_lock) = &state.network {
         let guard = net_lock.read();
         if let Some(network) = guard.as_ref() {
+            let mut mesh_topics: Vec<(String, Vec<crate::network::MeshPeerInfo>)> = network
+                .gossip_mesh_topics()
+                .into_iter()
+                .map(|topic| {
+                    let peers = network.gossip_mesh_peers(&topic);
+                    (topic, peers)
+                })
+                .collect();
+            mesh_topics.sort_by(|a, b| a.0.cmp(&b.0));
+            let peers = network.connected_peer_ids();
+            let verified_peers: std::collections::HashSet<_> = peers.iter()
+                .filter(|p| network.is_peer_verified(p))
+                .copied()
+                .collect();
             (
                 true,
                 network.peer_id().to_string(),
-                network.connected_peer_ids(),
+                peers,
                 network.listen_addresses(),
+                mesh_topics,
+                verified_peers,
+                network.paired_devices(),
             )
         } else {
-            (false, String::new(), vec![], vec![])
+            (false, String::new(), vec![], vec![], vec![], Default::default(), vec![])
         }
     } else {
-        (false, String::new(), vec![], vec![])
+        (false, String::new(), vec![], vec![], vec![], Default::default(), vec![])
     };
 
     let published = state.bundle_store.get_all_published_sites().unwrap_or_default();
@@ -679,6 +1291,68 @@ async fn peer_viewer_handler(State(state): State<AppState>) -> impl IntoResponse
     let chunks = state.chunk_store.count();
     let storage = state.chunk_store.total_size();
 
+    // Reverse (site_id -> name) lookup against the name-claim chain, so
+    // published/hosted rows can show the human name next to the site ID.
+    let chain_resolved = state.name_chain.as_ref().and_then(|c| c.all_resolved().ok()).unwrap_or_default();
+    let chain_names: std::collections::HashMap<SiteId, String> = chain_resolved.iter().cloned().collect();
+    let chain_pending = state.name_chain.as_ref().map(|c| c.pending_claims()).unwrap_or_default();
+
+    let resolved_name_suffix = |site_id: &SiteId| -> String {
+        match chain_names.get(site_id) {
+            Some(name) => format!(" <span class='site-rev'>chain: {}</span>", name),
+            None => String::new(),
+        }
+    };
+
+    let name_registrations_html = if chain_resolved.is_empty() && chain_pending.is_empty() {
+        "<div class='empty-state'>No name-chain activity yet</div>".to_string()
+    } else {
+        let pending_rows = chain_pending.iter().map(|claim| format!(
+            "<div class='site-item'><div><div class='site-name'>{}</div><div class='site-id'>{}</div></div><div class='site-rev'>pending{}</div></div>",
+            claim.name,
+            crate::crypto::SiteIdExt::to_base58(&claim.site_id),
+            if claim.renewal { " (renewal)" } else { "" },
+        ));
+        let confirmed_rows = chain_resolved.iter().map(|(name, site_id)| {
+            let depth = state.name_chain.as_ref()
+                .and_then(|c| c.confirmed_depth(name).ok().flatten())
+                .unwrap_or(0);
+            format!(
+                "<div class='site-item'><div><div class='site-name'>{}</div><div class='site-id'>{}</div></div><div class='site-rev'>depth {}</div></div>",
+                name, crate::crypto::SiteIdExt::to_base58(site_id), depth,
+            )
+        });
+        pending_rows.chain(confirmed_rows).collect::<Vec<_>>().join("")
+    };
+
+    let paired_devices_html = if paired_devices.is_empty() {
+        "<div class='empty-state'>No paired devices yet</div>".to_string()
+    } else {
+        paired_devices.iter().map(|d| format!(
+            "<div class='site-item'><div><div class='site-name'>{}</div><div class='site-id'>{}</div></div><div class='site-rev'>{} site{}</div></div>",
+            d.name, d.public_key, d.site_count, if d.site_count == 1 { "" } else { "s" },
+        )).collect::<Vec<_>>().join("")
+    };
+
+    let mesh_html = if mesh_topics.is_empty() {
+        "<div class='empty-state'>No gossip mesh activity yet</div>".to_string()
+    } else {
+        mesh_topics.iter().map(|(topic, mesh_peers)| {
+            let peer_rows = if mesh_peers.is_empty() {
+                "<div class='empty-state'>No mesh peers</div>".to_string()
+            } else {
+                mesh_peers.iter().map(|p| format!(
+                    "<div class='peer-item'><span class='peer-dot'></span>{} <span class='site-rev'>rev {}, seen {}s ago</span></div>",
+                    p.peer_id, p.last_revision, p.last_seen.elapsed().as_secs(),
+                )).collect::<Vec<_>>().join("")
+            };
+            format!(
+                "<div class='site-item' style='display:block'><div class='site-name'>{} ({} mesh peer(s))</div>{}</div>",
+                topic, mesh_peers.len(), peer_rows,
+            )
+        }).collect::<Vec<_>>().join("")
+    };
+
     Html(format!(r#"<!DOCTYPE html>
 <html lang="en">
 <head>
@@ -834,46 +1508,46 @@ async fn peer_viewer_handler(State(state): State<AppState>) -> impl IntoResponse
         <div class="grid">
             <div class="card">
                 <h2>Network Status</h2>
-                <div class="status-badge {}">
+                <div class="status-badge {}" id="status-badge">
                     <span class="status-dot"></span>
-                    {}
+                    <span id="status-text">{}</span>
                 </div>
                 <div class="peer-id-box">
-                    <strong>Peer ID:</strong><br>{}
+                    <strong>Peer ID:</strong><br><span id="peer-id-value">{}</span>
                 </div>
             </div>
             <div class="card">
                 <h2>Connected Peers</h2>
-                <div class="stat green">{}</div>
+                <div class="stat green" id="stat-peers">{}</div>
             </div>
             <div class="card">
                 <h2>Published Sites</h2>
-                <div class="stat blue">{}</div>
+                <div class="stat blue" id="stat-published">{}</div>
             </div>
             <div class="card">
                 <h2>Hosted Sites</h2>
-                <div class="stat purple">{}</div>
+                <div class="stat purple" id="stat-hosted">{}</div>
             </div>
             <div class="card">
                 <h2>Storage Chunks</h2>
-                <div class="stat orange">{}</div>
+                <div class="stat orange" id="stat-chunks">{}</div>
             </div>
             <div class="card">
                 <h2>Total Storage</h2>
-                <div class="stat">{}</div>
+                <div class="stat" id="stat-storage">{}</div>
             </div>
         </div>
 
         <div class="section">
-            <h2 class="section-title">üì° Listen Addresses</h2>
-            <div class="card">
+            <h2 class="section-title">üì° Listen Addresses</h2>
+            <div class="card" id="addresses-list">
                 {}
             </div>
         </div>
 
         <div class="section">
-            <h2 class="section-title">üîó Connected Peers ({})</h2>
-            <div class="card">
+            <h2 class="section-title">üîó Connected Peers (<span id="peers-count">{}</span>)</h2>
+            <div class="card" id="peers-list">
                 {}
             </div>
         </div>
@@ -891,13 +1565,68 @@ async fn peer_viewer_handler(State(state): State<AppState>) -> impl IntoResponse
                 {}
             </div>
         </div>
+
+        <div class="section">
+            <h2 class="section-title">🌐 Gossip Mesh</h2>
+            <div class="card">
+                {}
+            </div>
+        </div>
+
+        <div class="section">
+            <h2 class="section-title">🔖 Name Registrations</h2>
+            <div class="card">
+                {}
+            </div>
+        </div>
+
+        <div class="section">
+            <h2 class="section-title">🔒 Paired Devices</h2>
+            <div class="card">
+                {}
+            </div>
+        </div>
     </div>
 
-    <button class="refresh-btn" onclick="location.reload()">üîÑ Refresh</button>
+    <button class="refresh-btn" onclick="location.reload()">🔄 Refresh</button>
 
     <script>
-        // Auto-refresh every 10 seconds
-        setTimeout(() => location.reload(), 10000);
+        // Pushed updates from /api/events keep the cards above in sync
+        // without reloading the page (and losing scroll position). The
+        // site lists and the gossip-mesh, name-registration and
+        // paired-device sections above aren't redrawn by these pushes, so
+        // a manual refresh still picks up changes there.
+        function formatBytes(bytes) {{
+            const units = [[1024 ** 3, 'GB'], [1024 ** 2, 'MB'], [1024, 'KB']];
+            for (const [factor, suffix] of units) {{
+                if (bytes >= factor) return (bytes / factor).toFixed(1) + ' ' + suffix;
+            }}
+            return bytes + ' B';
+        }}
+
+        const events = new EventSource('/api/events');
+        events.addEventListener('status', (ev) => {{
+            const s = JSON.parse(ev.data);
+
+            document.getElementById('status-badge').className = 'status-badge ' + (s.running ? 'online' : 'offline');
+            document.getElementById('status-text').textContent = s.running ? 'Online' : 'Offline';
+            document.getElementById('peer-id-value').textContent = s.running ? s.peer_id : 'Not connected';
+
+            document.getElementById('stat-peers').textContent = s.peers.length;
+            document.getElementById('peers-count').textContent = s.peers.length;
+            document.getElementById('stat-published').textContent = s.published.length;
+            document.getElementById('stat-hosted').textContent = s.hosted.length;
+            document.getElementById('stat-chunks').textContent = s.chunks;
+            document.getElementById('stat-storage').textContent = formatBytes(s.storage_bytes);
+
+            document.getElementById('addresses-list').innerHTML = s.addresses.length
+                ? s.addresses.map(a => `<div class='address-item'>${{a}}</div>`).join('')
+                : "<div class='empty-state'>No listen addresses</div>";
+
+            document.getElementById('peers-list').innerHTML = s.peers.length
+                ? s.peers.map(p => `<div class='peer-item'><span class='peer-dot'></span>${{p}}</div>`).join('')
+                : "<div class='empty-state'>No peers connected</div>";
+        }});
     </script>
 </body>
 </html>"#,
@@ -918,24 +1647,35 @@ async fn peer_viewer_handler(State(state): State<AppState>) -> impl IntoResponse
         if peers.is_empty() {
             "<div class='empty-state'>No peers connected</div>".to_string()
         } else {
-            peers.iter().map(|p| format!("<div class='peer-item'><span class='peer-dot'></span>{}</div>", p)).collect::<Vec<_>>().join("")
+            peers.iter().map(|p| format!(
+                "<div class='peer-item'><span class='peer-dot'></span>{}{}</div>",
+                p,
+                if verified_peers.contains(p) {
+                    " <span class='site-rev'>\u{2713} verified</span>"
+                } else {
+                    " <span class='site-rev'>unverified</span>"
+                },
+            )).collect::<Vec<_>>().join("")
         },
         if published.is_empty() {
             "<div class='empty-state'>No published sites</div>".to_string()
         } else {
             published.iter().map(|s| format!(
-                "<div class='site-item'><div><div class='site-name'>{}</div><div class='site-id'>{}</div></div><div class='site-rev'>rev {}</div></div>",
-                s.name, crate::crypto::SiteIdExt::to_base58(&s.site_id), s.revision
+                "<div class='site-item'><div><div class='site-name'>{}</div><div class='site-id'>{}</div></div><div class='site-rev'>rev {}{}</div></div>",
+                s.name, crate::crypto::SiteIdExt::to_base58(&s.site_id), s.revision, resolved_name_suffix(&s.site_id)
             )).collect::<Vec<_>>().join("")
         },
         if hosted.is_empty() {
             "<div class='empty-state'>No hosted sites</div>".to_string()
         } else {
             hosted.iter().map(|s| format!(
-                "<div class='site-item'><div><div class='site-name'>{}</div><div class='site-id'>{}</div></div><div class='site-rev'>rev {}</div></div>",
-                s.name, crate::crypto::SiteIdExt::to_base58(&s.site_id), s.revision
+                "<div class='site-item'><div><div class='site-name'>{}</div><div class='site-id'>{}</div></div><div class='site-rev'>rev {}{}</div></div>",
+                s.name, crate::crypto::SiteIdExt::to_base58(&s.site_id), s.revision, resolved_name_suffix(&s.site_id)
             )).collect::<Vec<_>>().join("")
         },
+        mesh_html,
+        name_registrations_html,
+        paired_devices_html,
     ))
 }
 
@@ -954,3 +1694,54 @@ fn format_bytes(bytes: u64) -> String {
         format!("{} B", bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(trusted_proxy: bool) -> AccessControl {
+        AccessControl { trusted_proxy, allow: vec![], deny: vec![] }
+    }
+
+    fn xff_headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_client_ip_ignores_forwarded_header_when_not_trusted_proxy() {
+        let remote: IpAddr = "203.0.113.9".parse().unwrap();
+        let headers = xff_headers("127.0.0.1");
+        assert_eq!(access(false).client_ip(&headers, remote), remote);
+    }
+
+    #[test]
+    fn test_client_ip_uses_last_hop_not_client_supplied_first_hop() {
+        // A client trying to impersonate an allow-listed address by
+        // spoofing the leftmost entry; the trusted proxy appends the
+        // address it actually observed as the last entry.
+        let remote: IpAddr = "203.0.113.9".parse().unwrap();
+        let headers = xff_headers("127.0.0.1, 198.51.100.7");
+        assert_eq!(
+            access(true).client_ip(&headers, remote),
+            "198.51.100.7".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_client_ip_uses_sole_forwarded_entry_when_trusted() {
+        let remote: IpAddr = "203.0.113.9".parse().unwrap();
+        let headers = xff_headers("198.51.100.7");
+        assert_eq!(
+            access(true).client_ip(&headers, remote),
+            "198.51.100.7".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_client_ip_falls_back_to_remote_addr_without_header() {
+        let remote: IpAddr = "203.0.113.9".parse().unwrap();
+        assert_eq!(access(true).client_ip(&HeaderMap::new(), remote), remote);
+    }
+}
@@ -39,20 +39,23 @@ pub mod network;
 pub mod gateway;
 pub mod content;
 pub mod publisher;
+pub mod s3;
 
 // Re-export main types
 pub use types::*;
-pub use crypto::{hash, sign, verify, generate_keypair, SiteIdExt, encode_base58, decode_base58};
-pub use storage::{ChunkStore, BundleStore, KeyStore};
-pub use network::GrabNetwork;
+pub use crypto::{hash, sign, verify, generate_keypair, SiteIdExt, encode_base58, decode_base58, HashMethod};
+pub use storage::{ChunkStore, BundleStore, KeyStore, NameStore, NameChain, NameClaim};
+pub use network::{GrabNetwork, IdentityKeys, PairedDevice, TrustStore, PairedDeviceStore, SyncProgress, SyncDirection, Member, MemberState, LayoutStatus, ResyncService};
 pub use gateway::Gateway;
 pub use content::UserContentManager;
-pub use publisher::{Publisher, PublishOptions, PublishResult};
+pub use publisher::{Publisher, PublishOptions, PublishResult, ChunkingMode};
+pub use s3::S3Server;
 
 use std::path::PathBuf;
 use std::sync::Arc;
 use anyhow::Result;
 use parking_lot::RwLock;
+use tokio::sync::watch;
 
 /// Main GrabNet SDK
 pub struct Grab {
@@ -61,10 +64,23 @@ pub struct Grab {
     chunk_store: Arc<ChunkStore>,
     bundle_store: Arc<BundleStore>,
     key_store: Arc<KeyStore>,
+    name_store: Arc<NameStore>,
+    name_chain: Arc<NameChain>,
+    /// This node's long-lived pairing identity (see `network::pairing`),
+    /// derived from the `"node-identity"` key in `key_store`.
+    identity_keys: Arc<IdentityKeys>,
+    identity_private: Arc<zeroize::Zeroizing<[u8; 32]>>,
+    trust_store: Arc<RwLock<TrustStore>>,
+    paired_devices: Arc<RwLock<PairedDeviceStore>>,
     publisher: Publisher,
-    network: Arc<RwLock<Option<GrabNetwork>>>,
+    network: Arc<RwLock<Option<Arc<GrabNetwork>>>>,
+    /// Background resync worker (see `network::resync::ResyncService`),
+    /// spawned alongside the network since it needs a live `GrabNetwork`
+    /// handle to fetch from. `None` whenever the network isn't running.
+    resync: Arc<RwLock<Option<ResyncService>>>,
     gateway: Arc<RwLock<Option<Gateway>>>,
     content_manager: Arc<RwLock<Option<UserContentManager>>>,
+    s3_server: Arc<RwLock<Option<S3Server>>>,
 }
 
 impl Grab {
@@ -82,6 +98,14 @@ impl Grab {
         let chunk_store = Arc::new(ChunkStore::new(&data_dir)?);
         let bundle_store = Arc::new(BundleStore::new(&data_dir)?);
         let key_store = Arc::new(KeyStore::new(&data_dir)?);
+        let name_store = Arc::new(NameStore::new(&data_dir)?);
+        let name_chain = Arc::new(NameChain::new(&data_dir)?);
+
+        let (identity_public, identity_private) = key_store.get_or_create("node-identity")?;
+        let identity_keys = Arc::new(IdentityKeys::derive(identity_public, &identity_private));
+        let identity_private = Arc::new(identity_private);
+        let trust_store = Arc::new(RwLock::new(TrustStore::load_or_default(&data_dir)?));
+        let paired_devices = Arc::new(RwLock::new(PairedDeviceStore::load_or_default(&data_dir)?));
 
         let publisher = Publisher::new(
             chunk_store.clone(),
@@ -95,10 +119,18 @@ impl Grab {
             chunk_store,
             bundle_store,
             key_store,
+            name_store,
+            name_chain,
+            identity_keys,
+            identity_private,
+            trust_store,
+            paired_devices,
             publisher,
             network: Arc::new(RwLock::new(None)),
+            resync: Arc::new(RwLock::new(None)),
             gateway: Arc::new(RwLock::new(None)),
             content_manager: Arc::new(RwLock::new(None)),
+            s3_server: Arc::new(RwLock::new(None)),
         })
     }
 
@@ -115,7 +147,10 @@ impl Grab {
     // =========================================================================
 
     /// Publish a website directory
-    pub async fn publish(&self, path: &str, options: PublishOptions) -> Result<PublishResult> {
+    pub async fn publish(&self, path: &str, mut options: PublishOptions) -> Result<PublishResult> {
+        if options.zstd_level.is_none() {
+            options.zstd_level = Some(self.config.publisher.zstd_level);
+        }
         let result = self.publisher.publish(path, options).await?;
 
         // Announce to network if running
@@ -205,6 +240,12 @@ impl Grab {
             &self.config,
             self.chunk_store.clone(),
             self.bundle_store.clone(),
+            self.name_store.clone(),
+            self.name_chain.clone(),
+            self.identity_keys.clone(),
+            self.identity_private.clone(),
+            self.trust_store.clone(),
+            self.paired_devices.clone(),
         ).await?;
 
         network.start().await?;
@@ -220,18 +261,104 @@ impl Grab {
             network.announce_site(&site.site_id, site.revision).await?;
         }
 
+        let network = Arc::new(network);
+
+        let resync = ResyncService::spawn(
+            network.clone(),
+            network.replication_manager(),
+            network.layout_handle(),
+            self.bundle_store.clone(),
+            self.chunk_store.clone(),
+            self.config.network.tranquility,
+        );
+        *self.resync.write() = Some(resync);
+
         *self.network.write() = Some(network);
         Ok(())
     }
 
     /// Stop the network
     pub async fn stop_network(&self) -> Result<()> {
+        // Drop first so the resync worker isn't left pulling chunks
+        // through a `GrabNetwork` whose swarm task is about to shut down.
+        self.resync.write().take();
         if let Some(network) = self.network.write().take() {
             network.stop().await?;
         }
         Ok(())
     }
 
+    // =========================================================================
+    // Naming
+    // =========================================================================
+
+    /// Claim (or renew) a human-readable name for a site, signed with the
+    /// given key. Accepts the record into the local registry and, if the
+    /// network is running, announces it to connected peers.
+    pub async fn claim_name(&self, name: &str, site_id: &SiteId, key_name: &str) -> Result<NameRecord> {
+        let (public_key, private_key) = self.key_store.get_or_create(key_name)?;
+
+        let revision = self.name_store.resolve(name)?
+            .filter(|existing| existing.publisher == public_key)
+            .map(|existing| existing.revision + 1)
+            .unwrap_or(1);
+        let updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        let signature = crypto::sign_name_record(name, site_id, revision, updated_at, &private_key);
+        let record = NameRecord {
+            name: name.to_string(),
+            site_id: *site_id,
+            revision,
+            publisher: public_key,
+            signature,
+            updated_at,
+        };
+
+        if let Some(network) = self.network.read().as_ref() {
+            network.announce_name(&record).await?;
+        } else if !self.name_store.offer(&record)? {
+            anyhow::bail!("Name '{}' is already claimed by another key", name);
+        }
+
+        Ok(record)
+    }
+
+    /// Resolve a human-readable name to its current site, checking the
+    /// local registry first and the network if it's running.
+    pub async fn resolve_name(&self, name: &str) -> Result<Option<NameRecord>> {
+        if let Some(record) = self.name_store.resolve(name)? {
+            return Ok(Some(record));
+        }
+
+        if let Some(network) = self.network.read().as_ref() {
+            return network.resolve_name(name).await;
+        }
+
+        Ok(None)
+    }
+
+    /// Claim (or renew) a name on the proof-of-work-gated name-claim chain
+    /// (see [`storage::name_chain`]), an alternative to [`Self::claim_name`]
+    /// that costs real CPU per claim and so resists squatting more than a
+    /// plain first-seen-wins record. Submits into the local mempool; the
+    /// network mines and gossips it on its own schedule once running.
+    pub fn claim_name_on_chain(&self, name: &str, site_id: &SiteId, key_name: &str) -> Result<bool> {
+        let (public_key, private_key) = self.key_store.get_or_create(key_name)?;
+        let (prev_hash, _) = self.name_chain.tip()?;
+        let renewal = self.name_chain.resolve(name)?.is_some();
+
+        let claim = NameClaim::new(name.to_string(), *site_id, public_key, prev_hash, 0, renewal, &private_key);
+        self.name_chain.submit_claim(claim)
+    }
+
+    /// Resolve a name against the proof-of-work-gated name-claim chain.
+    pub fn resolve_name_on_chain(&self, name: &str) -> Result<Option<SiteId>> {
+        self.name_chain.resolve(name)
+    }
+
     /// Get network status
     pub fn network_status(&self) -> NetworkStatus {
         match self.network.read().as_ref() {
@@ -240,11 +367,81 @@ impl Grab {
                 peer_id: Some(network.peer_id().to_string()),
                 peers: network.connected_peers(),
                 addresses: network.listen_addresses(),
+                members: network.members(),
             },
             None => NetworkStatus::default(),
         }
     }
 
+    /// Dial a specific peer address. The network must already be running.
+    pub async fn dial_peer(&self, addr: &str) -> Result<()> {
+        match self.network.read().as_ref() {
+            Some(network) => network.dial(addr).await,
+            None => Err(anyhow::anyhow!("network is not running")),
+        }
+    }
+
+    /// Subscribe to network events, if the network is running.
+    pub fn subscribe_network(&self) -> Option<tokio::sync::broadcast::Receiver<network::NetworkEvent>> {
+        self.network.read().as_ref().map(|n| n.subscribe())
+    }
+
+    /// Start pairing with a not-yet-trusted peer at its last-known
+    /// addresses, returning the six-digit code to compare out-of-band.
+    /// See [`network::GrabNetwork::pair_with`].
+    pub async fn pair_with(&self, peer_id: &str, addresses: Vec<String>) -> Result<u32> {
+        let peer_id: libp2p::PeerId = peer_id.parse()?;
+        let addresses = addresses.iter().map(|a| a.parse()).collect::<std::result::Result<Vec<libp2p::Multiaddr>, _>>()?;
+        match self.network.read().as_ref() {
+            Some(network) => network.pair_with(peer_id, addresses).await,
+            None => Err(anyhow::anyhow!("network is not running")),
+        }
+    }
+
+    /// Confirm a pairing once the out-of-band code matched. See
+    /// [`network::GrabNetwork::confirm_pairing`].
+    pub async fn confirm_pairing(&self, peer_id: &str, site_ids: Vec<SiteId>) -> Result<NodeInformation> {
+        let peer_id: libp2p::PeerId = peer_id.parse()?;
+        match self.network.read().as_ref() {
+            Some(network) => network.confirm_pairing(peer_id, site_ids).await,
+            None => Err(anyhow::anyhow!("network is not running")),
+        }
+    }
+
+    /// Peers we've completed pairing with.
+    pub fn paired_devices(&self) -> Vec<PairedDevice> {
+        self.network.read().as_ref().map(|n| n.paired_devices()).unwrap_or_default()
+    }
+
+    /// Subscribe to live progress of the delta-sync transfer currently
+    /// driven by [`network::GrabNetwork::sync_revision`], if any. Returns
+    /// `None` if the network isn't running.
+    pub fn sync_progress(&self) -> Option<watch::Receiver<SyncProgress>> {
+        self.network.read().as_ref().map(|n| n.sync_progress())
+    }
+
+    /// Set the target replica count for a site, spread across as many
+    /// distinct zones as the known hosts allow. A no-op if the network
+    /// isn't running.
+    pub fn set_replication(&self, site_id: SiteId, factor: usize) {
+        if let Some(network) = self.network.read().as_ref() {
+            network.set_replication(site_id, factor);
+        }
+    }
+
+    /// Placement status for a single site: target replica count versus
+    /// which zones/peers currently hold it. `None` if the network isn't
+    /// running.
+    pub fn layout_status(&self, site_id: &SiteId) -> Option<LayoutStatus> {
+        self.network.read().as_ref().map(|n| n.layout_status(site_id))
+    }
+
+    /// Placement status for every site with a configured replication
+    /// target.
+    pub fn layout_status_all(&self) -> Vec<LayoutStatus> {
+        self.network.read().as_ref().map(|n| n.layout_status_all()).unwrap_or_default()
+    }
+
     // =========================================================================
     // Gateway
     // =========================================================================
@@ -256,19 +453,79 @@ impl Grab {
 
     /// Start the HTTP gateway on a specific port
     pub async fn start_gateway_on_port(&self, port: u16) -> Result<()> {
+        self.start_gateway_on_port_with_access(port, false, vec![], vec![]).await
+    }
+
+    /// Start the HTTP gateway on a specific port, overriding the configured
+    /// reverse-proxy trust and IP allow/deny lists for this invocation.
+    pub async fn start_gateway_on_port_with_access(
+        &self,
+        port: u16,
+        trusted_proxy: bool,
+        allow: Vec<String>,
+        deny: Vec<String>,
+    ) -> Result<()> {
         if self.gateway.read().is_some() {
             return Ok(());
         }
 
         let mut config = self.config.clone();
         config.gateway.port = port;
+        config.gateway.trusted_proxy = trusted_proxy;
+        config.gateway.allow = allow;
+        config.gateway.deny = deny;
 
         let gateway = Gateway::new(
             &config,
             self.chunk_store.clone(),
             self.bundle_store.clone(),
             self.content_manager.read().clone(),
-        );
+        )
+        .with_name_store(self.name_store.clone())
+        .with_name_chain(self.name_chain.clone());
+
+        gateway.start().await?;
+        *self.gateway.write() = Some(gateway);
+        Ok(())
+    }
+
+    /// Start the HTTP gateway on a specific port, serving `default_site` at the root path
+    pub async fn start_gateway_with_default_site(&self, port: u16, default_site: SiteId) -> Result<()> {
+        self.start_gateway_with_default_site_and_access(port, default_site, false, vec![], vec![])
+            .await
+    }
+
+    /// Start the HTTP gateway serving `default_site` at the root path,
+    /// overriding the configured reverse-proxy trust and IP allow/deny
+    /// lists for this invocation.
+    pub async fn start_gateway_with_default_site_and_access(
+        &self,
+        port: u16,
+        default_site: SiteId,
+        trusted_proxy: bool,
+        allow: Vec<String>,
+        deny: Vec<String>,
+    ) -> Result<()> {
+        if self.gateway.read().is_some() {
+            return Ok(());
+        }
+
+        let mut config = self.config.clone();
+        config.gateway.port = port;
+        config.gateway.trusted_proxy = trusted_proxy;
+        config.gateway.allow = allow;
+        config.gateway.deny = deny;
+
+        let gateway = Gateway::with_default_site(
+            &config,
+            self.chunk_store.clone(),
+            self.bundle_store.clone(),
+            self.content_manager.read().clone(),
+            default_site,
+        )
+        .with_network(self.network.clone())
+        .with_name_store(self.name_store.clone())
+        .with_name_chain(self.name_chain.clone());
 
         gateway.start().await?;
         *self.gateway.write() = Some(gateway);
@@ -283,6 +540,42 @@ impl Grab {
         Ok(())
     }
 
+    // =========================================================================
+    // S3-Compatible Endpoint
+    // =========================================================================
+
+    /// Start the S3-compatible publish/serve endpoint on the default port
+    pub async fn start_s3(&self) -> Result<()> {
+        self.start_s3_on_port(9000).await
+    }
+
+    /// Start the S3-compatible publish/serve endpoint on a specific port
+    pub async fn start_s3_on_port(&self, port: u16) -> Result<()> {
+        if self.s3_server.read().is_some() {
+            return Ok(());
+        }
+
+        let server = S3Server::new(
+            port,
+            self.data_dir.clone(),
+            self.chunk_store.clone(),
+            self.bundle_store.clone(),
+            self.key_store.clone(),
+        );
+
+        server.start().await?;
+        *self.s3_server.write() = Some(server);
+        Ok(())
+    }
+
+    /// Stop the S3 endpoint
+    pub async fn stop_s3(&self) -> Result<()> {
+        if let Some(server) = self.s3_server.write().take() {
+            server.stop().await?;
+        }
+        Ok(())
+    }
+
     // =========================================================================
     // User Content
     // =========================================================================
@@ -299,17 +592,19 @@ impl Grab {
         Ok(())
     }
 
-    /// Upload content to a site
+    /// Upload content to a site, optionally requiring `password` to read
+    /// it back.
     pub async fn upload_content(
         &self,
         site_id: &SiteId,
         filename: &str,
         mime_type: &str,
         data: &[u8],
+        password: Option<&str>,
     ) -> Result<Option<content::UserUpload>> {
         let manager_lock = self.content_manager.read();
         match manager_lock.as_ref() {
-            Some(manager) => manager.upload(site_id, filename, mime_type, data, None),
+            Some(manager) => manager.upload(site_id, filename, mime_type, data, None, None, password),
             None => Ok(None),
         }
     }
@@ -343,11 +638,30 @@ impl Grab {
 
     /// Get storage statistics
     pub fn storage_stats(&self) -> StorageStats {
+        let published = self.bundle_store.get_all_published_sites().unwrap_or_default();
+        let hosted = self.bundle_store.get_all_hosted_sites().unwrap_or_default();
+
+        // Original (pre-compression) size across every manifest we know
+        // about, so `total_size` (what's actually stored) vs. `raw_bytes`
+        // shows how much compression is actually saving.
+        let raw_bytes: u64 = published.iter().map(|s| s.site_id)
+            .chain(hosted.iter().map(|s| s.site_id))
+            .filter_map(|site_id| self.bundle_store.get_manifest(&site_id).ok().flatten())
+            .flat_map(|manifest| manifest.files.into_iter().map(|f| f.size))
+            .sum();
+
+        let (resync_queued, resync_in_flight) = self.resync.read().as_ref()
+            .map(|r| (r.queued_count(), r.in_flight_count()))
+            .unwrap_or_default();
+
         StorageStats {
             chunks: self.chunk_store.count(),
             total_size: self.chunk_store.total_size(),
-            published_sites: self.bundle_store.get_all_published_sites().unwrap_or_default().len(),
-            hosted_sites: self.bundle_store.get_all_hosted_sites().unwrap_or_default().len(),
+            raw_bytes,
+            published_sites: published.len(),
+            hosted_sites: hosted.len(),
+            resync_queued,
+            resync_in_flight,
         }
     }
 
@@ -374,15 +688,30 @@ pub struct NetworkStatus {
     pub peer_id: Option<String>,
     pub peers: usize,
     pub addresses: Vec<String>,
+    /// SWIM gossip membership view: every peer we've heard of and whether
+    /// it currently looks alive, suspect, or dead. Broader than `peers`
+    /// (directly-connected only) -- this also covers hosts reachable only
+    /// transitively through gossip, so callers can tell which mirrors of a
+    /// site are worth routing `fetch_site`/`push_update` toward.
+    pub members: Vec<Member>,
 }
 
 /// Storage statistics
 #[derive(Debug, Default)]
 pub struct StorageStats {
     pub chunks: usize,
+    /// Bytes actually stored on disk, i.e. post-compression
     pub total_size: u64,
+    /// Original, pre-compression size summed across every known manifest
+    pub raw_bytes: u64,
     pub published_sites: usize,
     pub hosted_sites: usize,
+    /// Resync tasks (see `network::resync::ResyncService`) not yet popped
+    /// by the background worker.
+    pub resync_queued: usize,
+    /// Resync tasks currently being fetched (0 if the network isn't
+    /// running).
+    pub resync_in_flight: usize,
 }
 
 #[cfg(test)]
@@ -404,8 +733,9 @@ mod serialization_tests {
             entry: "index.html".to_string(),
             routes: None,
             headers: None,
+            hash_method: Default::default(),
         };
-        
+
         let encoded = bincode::serialize(&manifest).unwrap();
         println!("Encoded manifest: {} bytes", encoded.len());
         println!("Raw bytes: {:?}", &encoded[..encoded.len().min(50)]);
@@ -6,6 +6,7 @@ use libp2p::{
     swarm::NetworkBehaviour,
     StreamProtocol,
 };
+use std::num::NonZeroUsize;
 use std::time::Duration;
 
 use super::protocol::{GrabCodec, PROTOCOL_NAME};
@@ -28,7 +29,7 @@ pub struct GrabBehaviour {
 
 impl GrabBehaviour {
     /// Create a new GrabNet behaviour
-    pub fn new(local_peer_id: libp2p::PeerId, local_public_key: libp2p::identity::PublicKey) -> Self {
+    pub fn new(local_peer_id: libp2p::PeerId, local_key: libp2p::identity::Keypair) -> Self {
         // Request/response config
         let request_response = request_response::Behaviour::new(
             [(PROTOCOL_NAME, ProtocolSupport::Full)],
@@ -36,21 +37,36 @@ impl GrabBehaviour {
                 .with_request_timeout(Duration::from_secs(60)),
         );
 
-        // Kademlia config
+        // Kademlia config. Republish our provider records periodically so
+        // `grab pin` can find us via DHT lookup alone, without a peer that
+        // happens to still have a fresh record.
         let store = kad::store::MemoryStore::new(local_peer_id);
-        let mut kademlia = kad::Behaviour::new(local_peer_id, store);
+        let mut kad_config = kad::Config::default();
+        kad_config.set_provider_record_ttl(Some(Duration::from_secs(48 * 3600)));
+        kad_config.set_provider_publication_interval(Some(Duration::from_secs(3600)));
+        kad_config.set_record_ttl(Some(Duration::from_secs(48 * 3600)));
+        // Iterative lookups fan out to 3 peers at a time (the classic
+        // Kademlia alpha), trading a bit of extra traffic for faster
+        // convergence than a fully sequential walk.
+        kad_config.set_parallelism(NonZeroUsize::new(3).expect("3 is non-zero"));
+        let mut kademlia = kad::Behaviour::with_config(local_peer_id, store, kad_config);
         kademlia.set_mode(Some(kad::Mode::Server));
 
-        // Gossipsub config
+        // Gossipsub config. Strict validation plus `validate_messages()` means
+        // nothing is forwarded until the event loop explicitly calls
+        // `report_message_validation_result`, giving us a chokepoint to verify
+        // site announcements before they're relayed to the rest of the swarm.
         let gossipsub_config = gossipsub::ConfigBuilder::default()
             .heartbeat_interval(Duration::from_secs(10))
-            .validation_mode(gossipsub::ValidationMode::Permissive)
+            .validation_mode(gossipsub::ValidationMode::Strict)
+            .validate_messages()
             .build()
             .expect("Valid gossipsub config");
 
-        // Use anonymous message authenticity for now (simpler)
+        // Strict validation requires authored (signed) messages, so we sign
+        // with our own identity rather than publishing anonymously.
         let gossipsub = gossipsub::Behaviour::new(
-            gossipsub::MessageAuthenticity::Anonymous,
+            gossipsub::MessageAuthenticity::Signed(local_key.clone()),
             gossipsub_config,
         )
         .expect("Valid gossipsub behaviour");
@@ -65,7 +81,7 @@ impl GrabBehaviour {
         // Identify protocol
         let identify = identify::Behaviour::new(identify::Config::new(
             "/grabnet/id/1.0.0".to_string(),
-            local_public_key,
+            local_key.public(),
         ));
 
         Self {
@@ -0,0 +1,119 @@
+//! Bitswap-style chunk exchange bookkeeping
+//!
+//! Before pulling a full chunk from a peer, we ask whether they actually
+//! have it (`WANT_HAVE` -> `HAVE`/`DONT_HAVE`) to avoid wasting bandwidth
+//! on a block request that will just come back empty. A per-peer ledger
+//! tracks how much we've sent and received, which a future reputation or
+//! tit-for-tat policy can read from.
+
+use std::collections::{HashMap, HashSet};
+
+use libp2p::PeerId;
+
+use crate::types::ChunkId;
+
+/// Bytes/blocks exchanged with a single peer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PeerLedger {
+    pub blocks_sent: u64,
+    pub bytes_sent: u64,
+    pub blocks_received: u64,
+    pub bytes_received: u64,
+}
+
+impl PeerLedger {
+    /// Ratio of bytes we've sent this peer to bytes they've sent us.
+    /// `None` if we haven't received anything yet (avoids a divide by zero).
+    pub fn send_receive_ratio(&self) -> Option<f64> {
+        if self.bytes_received == 0 {
+            None
+        } else {
+            Some(self.bytes_sent as f64 / self.bytes_received as f64)
+        }
+    }
+}
+
+/// Tracks exchange ledgers and outstanding want-lists across peers.
+#[derive(Debug, Default)]
+pub struct BitswapLedger {
+    peers: HashMap<PeerId, PeerLedger>,
+    want_lists: HashMap<PeerId, HashSet<ChunkId>>,
+}
+
+impl BitswapLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_sent(&mut self, peer_id: PeerId, bytes: u64) {
+        let entry = self.peers.entry(peer_id).or_default();
+        entry.blocks_sent += 1;
+        entry.bytes_sent += bytes;
+    }
+
+    pub fn record_received(&mut self, peer_id: PeerId, bytes: u64) {
+        let entry = self.peers.entry(peer_id).or_default();
+        entry.blocks_received += 1;
+        entry.bytes_received += bytes;
+    }
+
+    pub fn ledger_for(&self, peer_id: &PeerId) -> PeerLedger {
+        self.peers.get(peer_id).copied().unwrap_or_default()
+    }
+
+    /// Record that we want a chunk from a peer.
+    pub fn add_want(&mut self, peer_id: PeerId, chunk_id: ChunkId) {
+        self.want_lists.entry(peer_id).or_default().insert(chunk_id);
+    }
+
+    /// Clear a want once the block arrives (or we give up on it).
+    pub fn remove_want(&mut self, peer_id: &PeerId, chunk_id: &ChunkId) {
+        if let Some(wants) = self.want_lists.get_mut(peer_id) {
+            wants.remove(chunk_id);
+        }
+    }
+
+    /// Chunks we're currently waiting on from a peer.
+    pub fn want_list_for(&self, peer_id: &PeerId) -> Vec<ChunkId> {
+        self.want_lists
+            .get(peer_id)
+            .map(|set| set.iter().copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_peer() -> PeerId {
+        PeerId::random()
+    }
+
+    #[test]
+    fn test_ledger_tracks_both_directions() {
+        let mut ledger = BitswapLedger::new();
+        let peer = test_peer();
+
+        ledger.record_sent(peer, 100);
+        ledger.record_received(peer, 50);
+
+        let stats = ledger.ledger_for(&peer);
+        assert_eq!(stats.bytes_sent, 100);
+        assert_eq!(stats.bytes_received, 50);
+        assert_eq!(stats.send_receive_ratio(), Some(2.0));
+    }
+
+    #[test]
+    fn test_want_list_add_remove() {
+        let mut ledger = BitswapLedger::new();
+        let peer = test_peer();
+        let chunk_id = [7u8; 32];
+
+        ledger.add_want(peer, chunk_id);
+        assert_eq!(ledger.want_list_for(&peer), vec![chunk_id]);
+
+        ledger.remove_want(&peer, &chunk_id);
+        assert!(ledger.want_list_for(&peer).is_empty());
+    }
+}
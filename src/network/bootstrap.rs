@@ -4,6 +4,14 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use anyhow::Result;
 
+use crate::crypto::{sign_bootstrap, verify_bootstrap};
+use crate::types::{PublicKey, Signature};
+
+/// Public keys of maintainers trusted to sign the official and
+/// community bootstrap lists. Empty for now, like [`super::DEFAULT_BOOTSTRAP_PEERS`] —
+/// populated once real keys are cut for a release.
+pub const MAINTAINER_PUBKEYS: &[PublicKey] = &[];
+
 /// Known bootstrap nodes for GrabNet
 /// These are the initial nodes new peers connect to for network discovery
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +28,10 @@ pub struct BootstrapConfig {
     pub min_peers: usize,
     /// Maximum bootstrap connection attempts
     pub max_attempts: usize,
+    /// Optional WebSocket proxy to dial `/ws` and `/wss` bootstrap addresses
+    /// through, for environments where raw TCP dialing is blocked.
+    #[serde(default)]
+    pub ws_proxy: Option<String>,
 }
 
 /// A bootstrap node entry
@@ -35,6 +47,46 @@ pub struct BootstrapNode {
     pub region: Option<String>,
     /// Is this node currently enabled?
     pub enabled: bool,
+    /// Maintainer signature over `(name, addresses)`, proving this entry
+    /// was authored by a trusted release rather than injected into
+    /// `bootstrap.json` on disk.
+    #[serde(default)]
+    pub signature: Option<Signature>,
+    /// Public key of the maintainer who produced `signature`.
+    #[serde(default)]
+    pub signer: Option<PublicKey>,
+}
+
+impl BootstrapNode {
+    /// Check a connected peer's handshake-derived peer ID against the one we
+    /// pinned for this node, if any. A node with no pinned `peer_id` accepts
+    /// any peer ID (first-contact trust-on-first-use); once pinned, a
+    /// mismatch must be rejected rather than silently connecting to an
+    /// impostor at the same address.
+    pub fn verify_peer_id(&self, observed_peer_id: &str) -> bool {
+        match &self.peer_id {
+            Some(expected) => expected == observed_peer_id,
+            None => true,
+        }
+    }
+
+    /// Sign this entry with a maintainer private key.
+    pub fn sign(&mut self, private_key: &[u8; 32], signer: PublicKey) {
+        self.signature = Some(sign_bootstrap(&self.name, &self.addresses, private_key));
+        self.signer = Some(signer);
+    }
+
+    /// Verify this entry's signature against `trusted_signers` (the
+    /// compiled-in [`MAINTAINER_PUBKEYS`] when called from
+    /// `verify_and_quarantine`). An entry with no signature, a signer not
+    /// in `trusted_signers`, or a
+    /// signature that doesn't match its own name/addresses is untrusted.
+    pub fn verify_signature(&self, trusted_signers: &[PublicKey]) -> bool {
+        let (Some(signature), Some(signer)) = (&self.signature, &self.signer) else {
+            return false;
+        };
+        trusted_signers.contains(signer) && verify_bootstrap(&self.name, &self.addresses, signature, signer)
+    }
 }
 
 impl Default for BootstrapConfig {
@@ -50,6 +102,8 @@ impl Default for BootstrapConfig {
                     peer_id: None, // Will be set when deployed
                     region: Some("us-east".to_string()),
                     enabled: true,
+                    signature: None, // Signed by maintainers at release time
+                    signer: None,
                 },
                 // Primary EU bootstrap node
                 BootstrapNode {
@@ -60,6 +114,8 @@ impl Default for BootstrapConfig {
                     peer_id: None,
                     region: Some("eu-west".to_string()),
                     enabled: true,
+                    signature: None,
+                    signer: None,
                 },
             ],
             community: vec![],
@@ -67,6 +123,7 @@ impl Default for BootstrapConfig {
             mdns_enabled: true,
             min_peers: 3,
             max_attempts: 10,
+            ws_proxy: None,
         }
     }
 }
@@ -78,7 +135,9 @@ impl BootstrapConfig {
         
         if config_path.exists() {
             let contents = std::fs::read_to_string(&config_path)?;
-            Ok(serde_json::from_str(&contents)?)
+            let mut config: Self = serde_json::from_str(&contents)?;
+            config.verify_and_quarantine();
+            Ok(config)
         } else {
             let config = Self::default();
             config.save(data_dir)?;
@@ -86,6 +145,36 @@ impl BootstrapConfig {
         }
     }
 
+    /// Verify every official and community node's signature against
+    /// [`MAINTAINER_PUBKEYS`]. Entries that fail verification are moved
+    /// into `custom` and disabled, so a tampered `bootstrap.json` can't
+    /// silently keep injected nodes in the trusted lists.
+    ///
+    /// A no-op until `MAINTAINER_PUBKEYS` is populated at release time —
+    /// with no keys compiled in, nothing could ever verify, which would
+    /// just quarantine every official node on every load.
+    pub fn verify_and_quarantine(&mut self) {
+        if MAINTAINER_PUBKEYS.is_empty() {
+            return;
+        }
+        self.verify_and_quarantine_list(|c| &mut c.official);
+        self.verify_and_quarantine_list(|c| &mut c.community);
+    }
+
+    fn verify_and_quarantine_list(&mut self, list: impl Fn(&mut Self) -> &mut Vec<BootstrapNode>) {
+        let nodes = std::mem::take(list(self));
+        let (trusted, mut quarantined): (Vec<_>, Vec<_>) = nodes
+            .into_iter()
+            .partition(|node| node.verify_signature(MAINTAINER_PUBKEYS));
+
+        for node in &mut quarantined {
+            node.enabled = false;
+        }
+
+        *list(self) = trusted;
+        self.custom.append(&mut quarantined);
+    }
+
     /// Save bootstrap config
     pub fn save(&self, data_dir: &Path) -> Result<()> {
         let config_path = data_dir.join("bootstrap.json");
@@ -127,6 +216,8 @@ impl BootstrapConfig {
             peer_id: None,
             region: None,
             enabled: true,
+            signature: None,
+            signer: None,
         });
     }
 
@@ -152,6 +243,11 @@ impl BootstrapConfig {
         self.community.iter().filter(|n| n.enabled).count() +
         self.custom.iter().filter(|n| n.enabled).count()
     }
+
+    /// Find the configured bootstrap node entry for an address, if any.
+    pub fn find_by_address(&self, addr: &str) -> Option<&BootstrapNode> {
+        self.list_all().into_iter().find(|n| n.addresses.iter().any(|a| a == addr))
+    }
 }
 
 /// Well-known peer addresses for development/testing
@@ -160,27 +256,130 @@ pub const DEV_BOOTSTRAP_PEERS: &[&str] = &[
     "/ip4/127.0.0.1/tcp/4001",
 ];
 
-/// Check if an address is reachable (simple TCP connect test)
-pub async fn check_reachable(addr: &str) -> bool {
+/// A parsed multiaddr, supporting the subset of protocols GrabNet dials:
+/// `/ip4`, `/ip6`, `/dns4`, `/dns6`, `/tcp`, optionally followed by `/ws`
+/// or `/wss`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedMultiaddr {
+    pub host: String,
+    pub port: u16,
+    pub websocket: bool,
+    pub secure_websocket: bool,
+}
+
+impl ParsedMultiaddr {
+    /// Parse a `/`-delimited multiaddr string into its components.
+    pub fn parse(addr: &str) -> Option<Self> {
+        let segments: Vec<&str> = addr.split('/').filter(|s| !s.is_empty()).collect();
+        let mut iter = segments.into_iter();
+
+        let transport = iter.next()?;
+        let host = iter.next()?.to_string();
+        if !matches!(transport, "ip4" | "ip6" | "dns4" | "dns6") {
+            return None;
+        }
+
+        let tcp_proto = iter.next()?;
+        if tcp_proto != "tcp" {
+            return None;
+        }
+        let port: u16 = iter.next()?.parse().ok()?;
+
+        let mut websocket = false;
+        let mut secure_websocket = false;
+        match iter.next() {
+            Some("ws") => websocket = true,
+            Some("wss") => {
+                websocket = true;
+                secure_websocket = true;
+            }
+            Some(_) | None => {}
+        }
+
+        Some(Self {
+            host,
+            port,
+            websocket,
+            secure_websocket,
+        })
+    }
+
+    /// `host:port` form suitable for `TcpStream::connect` / DNS resolution.
+    pub fn socket_addr_string(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Check if an address is reachable.
+///
+/// Plain `/tcp` addresses get a TCP connect test. `/ws` and `/wss` addresses
+/// get a WebSocket upgrade handshake instead, since a middlebox can accept a
+/// TCP connection without the endpoint actually speaking WebSocket; if
+/// `ws_proxy` is set, the connection is made through that proxy rather than
+/// dialing the address directly.
+pub async fn check_reachable(addr: &str, ws_proxy: Option<&str>) -> bool {
+    let Some(parsed) = ParsedMultiaddr::parse(addr) else {
+        return false;
+    };
+
+    if parsed.websocket {
+        check_websocket_reachable(&parsed, ws_proxy).await
+    } else {
+        check_tcp_reachable(&parsed.socket_addr_string()).await
+    }
+}
+
+async fn check_tcp_reachable(connect_addr: &str) -> bool {
+    use tokio::net::TcpStream;
+    use tokio::time::{timeout, Duration};
+
+    matches!(
+        timeout(Duration::from_secs(5), TcpStream::connect(connect_addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+async fn check_websocket_reachable(parsed: &ParsedMultiaddr, ws_proxy: Option<&str>) -> bool {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::TcpStream;
     use tokio::time::{timeout, Duration};
-    
-    // Extract host:port from multiaddr (simplified)
-    let parts: Vec<&str> = addr.split('/').collect();
-    if parts.len() < 5 {
+
+    let dial_addr = match ws_proxy {
+        Some(proxy) => proxy.to_string(),
+        None => parsed.socket_addr_string(),
+    };
+
+    let Ok(Ok(mut stream)) = timeout(Duration::from_secs(5), TcpStream::connect(&dial_addr)).await else {
         return false;
-    }
-    
-    let host = parts[2];
-    let port: u16 = match parts[4].parse() {
-        Ok(p) => p,
-        Err(_) => return false,
     };
-    
-    let connect_addr = format!("{}:{}", host, port);
-    
-    match timeout(Duration::from_secs(5), TcpStream::connect(&connect_addr)).await {
-        Ok(Ok(_)) => true,
+
+    let scheme = if parsed.secure_websocket { "wss" } else { "ws" };
+    let request = format!(
+        "GET / HTTP/1.1\r\n\
+         Host: {host}:{port}\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Key: grabnet-reachability-check==\r\n\
+         Sec-WebSocket-Version: 13\r\n\
+         Origin: {scheme}://{host}\r\n\r\n",
+        host = parsed.host,
+        port = parsed.port,
+        scheme = scheme,
+    );
+
+    if timeout(Duration::from_secs(5), stream.write_all(request.as_bytes()))
+        .await
+        .is_err()
+    {
+        return false;
+    }
+
+    let mut buf = [0u8; 32];
+    match timeout(Duration::from_secs(5), stream.read(&mut buf)).await {
+        Ok(Ok(n)) if n > 0 => {
+            let response = String::from_utf8_lossy(&buf[..n]);
+            response.starts_with("HTTP/1.1 101") || response.starts_with("HTTP/1.0 101")
+        }
         _ => false,
     }
 }
@@ -189,6 +388,30 @@ pub async fn check_reachable(addr: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_multiaddr_tcp() {
+        let parsed = ParsedMultiaddr::parse("/ip4/1.2.3.4/tcp/4001").unwrap();
+        assert_eq!(parsed.host, "1.2.3.4");
+        assert_eq!(parsed.port, 4001);
+        assert!(!parsed.websocket);
+    }
+
+    #[test]
+    fn test_parse_multiaddr_dns_ws() {
+        let parsed = ParsedMultiaddr::parse("/dns4/bootstrap.example.com/tcp/443/wss").unwrap();
+        assert_eq!(parsed.host, "bootstrap.example.com");
+        assert_eq!(parsed.port, 443);
+        assert!(parsed.websocket);
+        assert!(parsed.secure_websocket);
+        assert_eq!(parsed.socket_addr_string(), "bootstrap.example.com:443");
+    }
+
+    #[test]
+    fn test_parse_multiaddr_rejects_unsupported() {
+        assert!(ParsedMultiaddr::parse("/unix/some/path").is_none());
+        assert!(ParsedMultiaddr::parse("/ip4/1.2.3.4").is_none());
+    }
+
     #[test]
     fn test_default_config() {
         let config = BootstrapConfig::default();
@@ -196,6 +419,26 @@ mod tests {
         assert!(config.mdns_enabled);
     }
 
+    #[test]
+    fn test_verify_peer_id() {
+        let mut node = BootstrapNode {
+            name: "test".to_string(),
+            addresses: vec!["/ip4/1.2.3.4/tcp/4001".to_string()],
+            peer_id: None,
+            region: None,
+            enabled: true,
+            signature: None,
+            signer: None,
+        };
+
+        // No pinned peer ID yet: accept anything (trust-on-first-use).
+        assert!(node.verify_peer_id("12D3KooWAnyPeer"));
+
+        node.peer_id = Some("12D3KooWExpected".to_string());
+        assert!(node.verify_peer_id("12D3KooWExpected"));
+        assert!(!node.verify_peer_id("12D3KooWImpostor"));
+    }
+
     #[test]
     fn test_add_remove_custom() {
         let mut config = BootstrapConfig::default();
@@ -205,4 +448,43 @@ mod tests {
         config.remove_custom("test-node");
         assert_eq!(config.custom.len(), 0);
     }
+
+    #[test]
+    fn test_sign_and_verify_node() {
+        use crate::crypto::generate_keypair;
+
+        let (public, private) = generate_keypair();
+        let mut node = BootstrapNode {
+            name: "maintainer-node".to_string(),
+            addresses: vec!["/dns4/boot.example.com/tcp/4001".to_string()],
+            peer_id: None,
+            region: None,
+            enabled: true,
+            signature: None,
+            signer: None,
+        };
+
+        assert!(!node.verify_signature(&[public]), "unsigned node must not verify");
+
+        node.sign(&private, public);
+        assert!(node.verify_signature(&[public]));
+
+        // Not in the trusted set: verification fails even with a valid signature.
+        assert!(!node.verify_signature(&[]));
+    }
+
+    #[test]
+    fn test_verify_and_quarantine_is_noop_with_no_compiled_in_keys() {
+        // Until MAINTAINER_PUBKEYS is populated at release time, nothing
+        // could ever verify, so quarantine must not run rather than moving
+        // every unsigned official node into `custom`.
+        assert!(MAINTAINER_PUBKEYS.is_empty());
+
+        let mut config = BootstrapConfig::default();
+        let official_before = config.official.len();
+        config.verify_and_quarantine();
+
+        assert_eq!(config.official.len(), official_before);
+        assert!(config.custom.is_empty());
+    }
 }
@@ -0,0 +1,133 @@
+//! Pluggable bootstrap peer discovery backends.
+//!
+//! `GrabNetwork::new` used to only learn bootstrap addresses from
+//! `Config`'s hardcoded `bootstrap_peers` list and a one-shot `dns_seed`
+//! lookup. [`DiscoveryBackend`] generalizes that into a source that's
+//! resolved once at startup and then re-resolved on a timer from inside
+//! `run_swarm`, so a self-hosted cluster or cloud deployment that scales
+//! up replicas gets dialed automatically instead of requiring operators
+//! to edit `bootstrap_peers` and restart every node.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+fn default_refresh_interval_secs() -> u64 {
+    300
+}
+
+/// Which discovery backend to consult for bootstrap addresses, and how
+/// often to re-resolve it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryConfig {
+    pub backend: DiscoveryBackend,
+    /// How often, in seconds, to re-resolve `backend` after startup to
+    /// pick up replicas that came up (or went away) later.
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+/// A source of bootstrap multiaddrs, resolved at startup and periodically
+/// thereafter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DiscoveryBackend {
+    /// Resolve a hostname's A/AAAA records as direct peer addresses, plus
+    /// any TXT records as pre-formed multiaddrs, for cases a bare IP can't
+    /// express (e.g. `/dns4/host/tcp/443/wss`).
+    Dns { hostname: String },
+    /// Query the Kubernetes API for the `Endpoints` backing a `Service`
+    /// selected by `service_label` and treat each ready pod IP as a
+    /// bootstrap address. Only compiled in with the `kubernetes` cargo
+    /// feature, so deployments that don't need it don't pull in a
+    /// Kubernetes client.
+    #[cfg(feature = "kubernetes")]
+    Kubernetes {
+        namespace: String,
+        service_label: String,
+    },
+}
+
+impl DiscoveryBackend {
+    /// Resolve this backend to `/ip4|ip6/.../tcp/<port>` bootstrap
+    /// addresses (or, for DNS TXT entries, whatever multiaddr form the
+    /// operator published there).
+    pub async fn resolve(&self, port: u16) -> Result<Vec<String>> {
+        match self {
+            DiscoveryBackend::Dns { hostname } => resolve_dns(hostname, port).await,
+            #[cfg(feature = "kubernetes")]
+            DiscoveryBackend::Kubernetes { namespace, service_label } => {
+                kubernetes::resolve(namespace, service_label, port).await
+            }
+        }
+    }
+}
+
+/// Resolve `hostname`'s A/AAAA records to direct multiaddrs (via
+/// [`super::membership::resolve_dns_seed`]), plus any TXT records holding
+/// a complete multiaddr of their own.
+async fn resolve_dns(hostname: &str, port: u16) -> Result<Vec<String>> {
+    let mut addrs = super::membership::resolve_dns_seed(hostname, port).await?;
+
+    let resolver = hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()?;
+    if let Ok(txt_lookup) = resolver.txt_lookup(hostname).await {
+        for record in txt_lookup.iter() {
+            for txt_data in record.iter() {
+                if let Ok(multiaddr) = String::from_utf8(txt_data.to_vec()) {
+                    addrs.push(multiaddr);
+                }
+            }
+        }
+    }
+
+    Ok(addrs)
+}
+
+#[cfg(feature = "kubernetes")]
+mod kubernetes {
+    use anyhow::{Context, Result};
+    use k8s_openapi::api::core::v1::Endpoints;
+    use kube::api::{Api, ListParams};
+    use kube::Client;
+
+    /// List the `Endpoints` objects in `namespace` matching `service_label`
+    /// (a `key=value` selector) and return one bootstrap address per ready
+    /// pod IP across all of their subsets.
+    pub async fn resolve(namespace: &str, service_label: &str, port: u16) -> Result<Vec<String>> {
+        let client = Client::try_default()
+            .await
+            .context("creating in-cluster Kubernetes client")?;
+        let endpoints: Api<Endpoints> = Api::namespaced(client, namespace);
+        let list = endpoints
+            .list(&ListParams::default().labels(service_label))
+            .await
+            .context("listing Kubernetes endpoints")?;
+
+        let mut addrs = Vec::new();
+        for item in list.items {
+            for subset in item.subsets.into_iter().flatten() {
+                for address in subset.addresses.into_iter().flatten() {
+                    if address.ip.contains(':') {
+                        addrs.push(format!("/ip6/{}/tcp/{}", address.ip, port));
+                    } else {
+                        addrs.push(format!("/ip4/{}/tcp/{}", address.ip, port));
+                    }
+                }
+            }
+        }
+
+        Ok(addrs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_config_default_refresh_interval() {
+        let json = r#"{"backend":{"type":"dns","hostname":"seed.example.com"}}"#;
+        let config: DiscoveryConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.refresh_interval_secs, 300);
+        assert!(matches!(config.backend, DiscoveryBackend::Dns { .. }));
+    }
+}
@@ -0,0 +1,208 @@
+//! Gossipsub mesh tracking for site-revision propagation
+//!
+//! Site-revision announcements already flow over real libp2p-gossipsub
+//! (see `node.rs`'s `sites_topic`), but the mesh the
+//! underlying crate maintains isn't inspectable from here. This module
+//! keeps a lightweight mirror of gossipsub's own mesh semantics so the
+//! swarm event loop can drive pruning/gossip and the dashboard can show
+//! propagation health: each topic keeps up to [`MESH_TARGET_DEGREE`]
+//! peers in its mesh, which stay tracked regardless of how quiet they've
+//! gone, while peers outside the mesh ("peripheral") are dropped once
+//! they've been idle past the configured timeout. A random sample of
+//! those peripheral peers is meant to be picked periodically as
+//! IHAVE/IWANT gossip targets, so peers outside the mesh can still pull
+//! revisions they missed instead of waiting to be grafted in.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+
+/// Target mesh size per topic, matching gossipsub's own default `D`.
+pub const MESH_TARGET_DEGREE: usize = 6;
+
+/// How long a peripheral (non-mesh) peer can go without a fresh
+/// announcement before it's dropped from a topic's tracked peer set.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// What we know about one peer's participation in one topic.
+#[derive(Debug, Clone, Copy)]
+struct PeerGossipState {
+    last_seen: Instant,
+    revision: u64,
+}
+
+/// One topic's mesh and peripheral (non-mesh) peers.
+#[derive(Debug, Default)]
+struct TopicMesh {
+    mesh: HashMap<PeerId, PeerGossipState>,
+    peripheral: HashMap<PeerId, PeerGossipState>,
+}
+
+/// A mesh peer's state, as surfaced to callers like the dashboard.
+#[derive(Debug, Clone)]
+pub struct MeshPeerInfo {
+    pub peer_id: PeerId,
+    pub last_revision: u64,
+    pub last_seen: Instant,
+}
+
+/// Per-topic gossipsub mesh state, populated as site-revision
+/// announcements arrive and periodically pruned/sampled by the swarm
+/// event loop. See module docs.
+pub struct GossipMeshTracker {
+    topics: RwLock<HashMap<String, TopicMesh>>,
+    mesh_degree: usize,
+    idle_timeout: Duration,
+}
+
+impl Default for GossipMeshTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GossipMeshTracker {
+    pub fn new() -> Self {
+        Self {
+            topics: RwLock::new(HashMap::new()),
+            mesh_degree: MESH_TARGET_DEGREE,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+
+    pub fn with_mesh_degree(mut self, degree: usize) -> Self {
+        self.mesh_degree = degree;
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Record a freshly-seen announcement from `peer` on `topic` at
+    /// `revision`. An already-meshed peer just gets its revision and
+    /// timestamp refreshed; a new peer joins the mesh while there's room,
+    /// and otherwise becomes a peripheral (non-mesh) peer.
+    pub fn record_announcement(&self, topic: &str, peer: PeerId, revision: u64) {
+        let now = Instant::now();
+        let mut topics = self.topics.write();
+        let entry = topics.entry(topic.to_string()).or_default();
+
+        if let Some(state) = entry.mesh.get_mut(&peer) {
+            state.last_seen = now;
+            state.revision = revision;
+            return;
+        }
+
+        entry.peripheral.remove(&peer);
+        if entry.mesh.len() < self.mesh_degree {
+            entry.mesh.insert(peer, PeerGossipState { last_seen: now, revision });
+        } else {
+            entry.peripheral.insert(peer, PeerGossipState { last_seen: now, revision });
+        }
+    }
+
+    /// Drop peripheral peers that haven't been seen within the configured
+    /// idle timeout. Mesh peers are kept alive regardless of idleness --
+    /// gossipsub itself only replaces mesh peers on explicit prune/graft,
+    /// not simple inactivity.
+    pub fn prune_idle(&self) {
+        let now = Instant::now();
+        let mut topics = self.topics.write();
+        for mesh in topics.values_mut() {
+            mesh.peripheral.retain(|_, state| now.duration_since(state.last_seen) < self.idle_timeout);
+        }
+    }
+
+    /// Up to `sample_size` random peripheral peers for `topic`, to gossip
+    /// IHAVE/IWANT digests at so peers outside the mesh can pull
+    /// revisions they missed.
+    pub fn gossip_targets(&self, topic: &str, sample_size: usize) -> Vec<PeerId> {
+        let topics = self.topics.read();
+        let Some(mesh) = topics.get(topic) else { return Vec::new() };
+        let candidates: Vec<PeerId> = mesh.peripheral.keys().copied().collect();
+        candidates
+            .choose_multiple(&mut rand::thread_rng(), sample_size)
+            .copied()
+            .collect()
+    }
+
+    /// Current mesh peers for `topic` and the revision/last-seen we last
+    /// heard from each, for dashboards and diagnostics. Empty if the
+    /// topic hasn't seen any announcements yet.
+    pub fn mesh_peers(&self, topic: &str) -> Vec<MeshPeerInfo> {
+        let topics = self.topics.read();
+        let Some(mesh) = topics.get(topic) else { return Vec::new() };
+        mesh.mesh
+            .iter()
+            .map(|(peer_id, state)| MeshPeerInfo {
+                peer_id: *peer_id,
+                last_revision: state.revision,
+                last_seen: state.last_seen,
+            })
+            .collect()
+    }
+
+    /// Every topic currently tracked, i.e. that's seen at least one
+    /// announcement.
+    pub fn topics(&self) -> Vec<String> {
+        self.topics.read().keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mesh_fills_up_to_target_degree() {
+        let tracker = GossipMeshTracker::new().with_mesh_degree(2);
+        tracker.record_announcement("sites", PeerId::random(), 1);
+        tracker.record_announcement("sites", PeerId::random(), 1);
+        let overflow = PeerId::random();
+        tracker.record_announcement("sites", overflow, 1);
+
+        assert_eq!(tracker.mesh_peers("sites").len(), 2);
+        assert_eq!(tracker.gossip_targets("sites", 10), vec![overflow]);
+    }
+
+    #[test]
+    fn test_meshed_peer_revision_refreshes_in_place() {
+        let tracker = GossipMeshTracker::new().with_mesh_degree(2);
+        let peer = PeerId::random();
+        tracker.record_announcement("sites", peer, 1);
+        tracker.record_announcement("sites", peer, 5);
+
+        let peers = tracker.mesh_peers("sites");
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].last_revision, 5);
+    }
+
+    #[test]
+    fn test_prune_idle_drops_stale_peripheral_peers_but_keeps_mesh() {
+        let tracker = GossipMeshTracker::new()
+            .with_mesh_degree(1)
+            .with_idle_timeout(Duration::from_millis(0));
+        let mesh_peer = PeerId::random();
+        let peripheral_peer = PeerId::random();
+        tracker.record_announcement("sites", mesh_peer, 1);
+        tracker.record_announcement("sites", peripheral_peer, 1);
+
+        tracker.prune_idle();
+
+        assert_eq!(tracker.mesh_peers("sites").len(), 1);
+        assert_eq!(tracker.gossip_targets("sites", 10), Vec::new());
+    }
+
+    #[test]
+    fn test_unknown_topic_returns_empty() {
+        let tracker = GossipMeshTracker::new();
+        assert!(tracker.mesh_peers("unknown").is_empty());
+        assert!(tracker.gossip_targets("unknown", 5).is_empty());
+        assert!(tracker.topics().is_empty());
+    }
+}
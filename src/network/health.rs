@@ -2,12 +2,76 @@
 //!
 //! Tracks connection health, peer reliability, and network metrics.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use parking_lot::RwLock;
 
-/// Peer score tracking for reputation-based prioritization
+use crate::storage::BundleStore;
+
+/// A feature a peer may support. Kept as a flat enum (rather than a
+/// bitset of protocol strings) so capability checks are exhaustive and
+/// serialize compactly alongside the rest of a `PeerScore`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Capability {
+    /// Can serve whole bundles above the default chunk-fetch size
+    /// without truncating or rejecting the request.
+    LargeBundles,
+    /// Speaks the given wire protocol version.
+    ProtocolVersion(u32),
+    /// Will accept pin requests to host a site long-term.
+    Pinning,
+    /// Exposes an S3-compatible publish/serve endpoint.
+    S3Gateway,
+}
+
+/// Where a peer's advertised [`Capability`] was learned from. Borrowed
+/// from Bisq-style peer info: capabilities a peer told us about directly
+/// are trusted more than ones we only heard about through another
+/// peer's gossip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilitySource {
+    /// The peer advertised this capability to us directly.
+    SelfReported,
+    /// We learned about this capability second-hand, via another peer.
+    Gossiped,
+}
+
+/// Reputation a peer must hold, and requests it must have sustained,
+/// before a connection to it is remembered as "reliable" and worth
+/// proactively reconnecting to after a restart.
+const RELIABLE_REPUTATION_FLOOR: i32 = 1 << 16;
+const RELIABLE_MIN_REQUESTS: u64 = 10;
+
+/// Reputation delta applied for a fast (< 100ms) successful response.
+const GOOD_SUCCESS_REPUTATION_CHANGE: i32 = 1 << 15;
+/// Reputation delta applied for a successful response that isn't fast.
+const SUCCESS_REPUTATION_CHANGE: i32 = 1 << 10;
+/// Reputation delta applied for an ordinary failed request.
+const FAILURE_REPUTATION_CHANGE: i32 = -(1 << 16);
+/// Reputation delta applied for a request that timed out outright, a
+/// worse signal than a peer that answered and simply failed.
+const TIMEOUT_REPUTATION_CHANGE: i32 = -(1 << 18);
+
+/// A peer at or below this reputation is banned: refused new connections
+/// and excluded from [`HealthMonitor::acceptable_peers`]. Set well above
+/// `i32::MIN` so a few bad hits can't accidentally saturate a peer into
+/// permanent, unrecoverable ban territory.
+pub const BANNED_THRESHOLD: i32 = -1_760_936_591; // ~ 0.82 * i32::MIN
+
+/// Fraction of its reputation a peer keeps per [`PeerScore::decay`] tick;
+/// the rest moves toward zero. Makes bans temporary and lets stale
+/// penalties (and stale praise) fade instead of accumulating forever.
+const REPUTATION_DECAY_FACTOR: f64 = 0.98;
+
+/// Peer score tracking for reputation-based connection gating, modeled
+/// on the peerset reputation systems used by other libp2p-based
+/// networking stacks: good behavior raises reputation, bad behavior
+/// lowers it, everything decays toward zero over time, and callers can
+/// use [`PeerScore::is_banned`] as a real admission-control signal
+/// rather than just a ranking number.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerScore {
     /// Peer ID
@@ -26,8 +90,16 @@ pub struct PeerScore {
     pub bytes_received: u64,
     /// Bytes sent to this peer
     pub bytes_sent: u64,
-    /// Current score (0-100)
-    pub score: u8,
+    /// Current reputation. Starts at zero, rises with fast/successful
+    /// requests, falls with failures and timeouts, and decays back
+    /// toward zero over time. At or below [`BANNED_THRESHOLD`] the peer
+    /// is considered banned.
+    pub reputation: i32,
+    /// Capabilities this peer has advertised to us directly.
+    pub capabilities: BTreeSet<Capability>,
+    /// Capabilities we've only learned about second-hand, via another
+    /// peer's gossip, and which this peer hasn't confirmed itself.
+    pub gossiped_capabilities: BTreeSet<Capability>,
 }
 
 impl PeerScore {
@@ -41,7 +113,9 @@ impl PeerScore {
             last_seen: 0,
             bytes_received: 0,
             bytes_sent: 0,
-            score: 50, // Start neutral
+            reputation: 0, // Start neutral
+            capabilities: BTreeSet::new(),
+            gossiped_capabilities: BTreeSet::new(),
         }
     }
 
@@ -50,54 +124,79 @@ impl PeerScore {
         self.total_requests += 1;
         self.successful_responses += 1;
         self.bytes_received += bytes;
-        
+
         // Update rolling average response time
-        self.avg_response_time_ms = (self.avg_response_time_ms * (self.total_requests - 1) 
+        self.avg_response_time_ms = (self.avg_response_time_ms * (self.total_requests - 1)
             + response_time_ms) / self.total_requests;
-        
+
         self.last_seen = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .map(|d| d.as_millis() as u64)
             .unwrap_or(0);
-        
-        self.recalculate_score();
+
+        let delta = if response_time_ms < 100 {
+            GOOD_SUCCESS_REPUTATION_CHANGE
+        } else {
+            SUCCESS_REPUTATION_CHANGE
+        };
+        self.reputation = self.reputation.saturating_add(delta);
     }
 
-    /// Record a failed request
+    /// Record a failed request (the peer responded, but with an error).
     pub fn record_failure(&mut self) {
         self.total_requests += 1;
         self.failed_requests += 1;
-        self.recalculate_score();
+        self.reputation = self.reputation.saturating_add(FAILURE_REPUTATION_CHANGE);
     }
 
-    /// Recalculate the peer score
-    fn recalculate_score(&mut self) {
-        if self.total_requests == 0 {
-            self.score = 50;
-            return;
-        }
+    /// Record a request that timed out with no response at all — a
+    /// stronger negative signal than [`Self::record_failure`].
+    pub fn record_timeout(&mut self) {
+        self.total_requests += 1;
+        self.failed_requests += 1;
+        self.reputation = self.reputation.saturating_add(TIMEOUT_REPUTATION_CHANGE);
+    }
 
-        // Success rate contributes 60% of score
-        let success_rate = self.successful_responses as f64 / self.total_requests as f64;
-        let success_score = (success_rate * 60.0) as u8;
+    /// Move reputation a fixed fraction closer to zero. Intended to be
+    /// called periodically (e.g. alongside the replication gossip
+    /// heartbeat) so that bans and accumulated praise both fade over
+    /// time rather than persisting forever.
+    fn decay(&mut self) {
+        self.reputation = (self.reputation as f64 * REPUTATION_DECAY_FACTOR) as i32;
+    }
 
-        // Response time contributes 30% (faster = better)
-        // < 100ms = 30, > 5000ms = 0
-        let time_score = if self.avg_response_time_ms < 100 {
-            30
-        } else if self.avg_response_time_ms > 5000 {
-            0
-        } else {
-            let normalized = (5000 - self.avg_response_time_ms) as f64 / 4900.0;
-            (normalized * 30.0) as u8
-        };
+    /// Whether this peer's reputation has fallen to or below the ban
+    /// threshold.
+    pub fn is_banned(&self) -> bool {
+        self.reputation <= BANNED_THRESHOLD
+    }
 
-        // Longevity contributes 10%
-        let longevity_score = if self.total_requests > 100 { 10 } else {
-            (self.total_requests / 10) as u8
-        };
+    /// Record capabilities learned about this peer from `source`. A
+    /// capability the peer reports itself is promoted out of the
+    /// gossiped set (it's now confirmed); a gossiped capability is only
+    /// recorded if it isn't already self-reported.
+    pub fn record_capabilities(&mut self, caps: &[Capability], source: CapabilitySource) {
+        match source {
+            CapabilitySource::SelfReported => {
+                for cap in caps {
+                    self.gossiped_capabilities.remove(cap);
+                    self.capabilities.insert(*cap);
+                }
+            }
+            CapabilitySource::Gossiped => {
+                for cap in caps {
+                    if !self.capabilities.contains(cap) {
+                        self.gossiped_capabilities.insert(*cap);
+                    }
+                }
+            }
+        }
+    }
 
-        self.score = success_score + time_score + longevity_score;
+    /// Whether this peer supports `cap`, whether self-reported or only
+    /// known via gossip.
+    pub fn supports(&self, cap: Capability) -> bool {
+        self.capabilities.contains(&cap) || self.gossiped_capabilities.contains(&cap)
     }
 
     /// Get reliability percentage
@@ -145,6 +244,20 @@ impl Default for NetworkMetrics {
     }
 }
 
+/// Atomic counters backing [`NetworkMetrics`]. Every field updates with
+/// a plain atomic op — never a lock — so recording a request's outcome
+/// never contends with, or has to be ordered against, the sharded peer
+/// score locks.
+#[derive(Default)]
+struct AtomicNetworkMetrics {
+    total_peers_seen: AtomicU64,
+    connected_peers: AtomicU64,
+    total_bytes_transferred: AtomicU64,
+    total_requests: AtomicU64,
+    successful_requests: AtomicU64,
+    avg_latency_ms: AtomicU64,
+}
+
 /// Connection health check result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionHealth {
@@ -154,12 +267,37 @@ pub struct ConnectionHealth {
     pub last_check: u64,
 }
 
-/// Health monitor for the network
+/// Number of independent peer-score shards. Each shard has its own lock,
+/// so peers hashing to different shards can be updated fully in
+/// parallel; a peer always hashes to the same shard, so every operation
+/// on it still sees a consistent view.
+const NUM_SHARDS: usize = 16;
+
+fn shard_of(peer_id: &str) -> usize {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    peer_id.hash(&mut hasher);
+    (hasher.finish() as usize) % NUM_SHARDS
+}
+
+/// Health monitor for the network.
+///
+/// ## Lock ordering
+///
+/// Peer scores are sharded across [`NUM_SHARDS`] independent
+/// `RwLock<HashMap<..>>`s (see [`shard_of`]); every method here touches
+/// at most one shard lock at a time and releases it before (if ever)
+/// touching another, so shard locks never need a defined relative
+/// order. `health_cache` is a separate lock from the shards and is
+/// likewise never held alongside one. Metrics are plain atomics with no
+/// lock at all. Net effect: no method in this type can deadlock against
+/// another, and independent peers never serialize on each other's
+/// updates.
 pub struct HealthMonitor {
-    /// Peer scores
-    scores: RwLock<HashMap<String, PeerScore>>,
-    /// Network metrics
-    metrics: RwLock<NetworkMetrics>,
+    /// Peer scores, sharded by `shard_of(peer_id)`.
+    shards: Vec<RwLock<HashMap<String, PeerScore>>>,
+    /// Network metrics (lock-free; see [`AtomicNetworkMetrics`])
+    metrics: AtomicNetworkMetrics,
     /// Connection health cache
     health_cache: RwLock<HashMap<String, ConnectionHealth>>,
     /// Start time
@@ -169,69 +307,103 @@ pub struct HealthMonitor {
 impl HealthMonitor {
     pub fn new() -> Self {
         Self {
-            scores: RwLock::new(HashMap::new()),
-            metrics: RwLock::new(NetworkMetrics::default()),
+            shards: (0..NUM_SHARDS).map(|_| RwLock::new(HashMap::new())).collect(),
+            metrics: AtomicNetworkMetrics::default(),
             health_cache: RwLock::new(HashMap::new()),
             start_time: Instant::now(),
         }
     }
 
+    fn shard(&self, peer_id: &str) -> &RwLock<HashMap<String, PeerScore>> {
+        &self.shards[shard_of(peer_id)]
+    }
+
     /// Record a peer connection
     pub fn peer_connected(&self, peer_id: &str) {
         // Update or create peer score
-        let mut scores = self.scores.write();
-        scores.entry(peer_id.to_string())
+        self.shard(peer_id).write()
+            .entry(peer_id.to_string())
             .or_insert_with(|| PeerScore::new(peer_id.to_string()));
-        
-        // Update metrics
-        let mut metrics = self.metrics.write();
-        metrics.connected_peers += 1;
-        metrics.total_peers_seen += 1;
+
+        self.metrics.connected_peers.fetch_add(1, Ordering::Relaxed);
+        self.metrics.total_peers_seen.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Record a peer disconnection
-    pub fn peer_disconnected(&self, peer_id: &str) {
-        let mut metrics = self.metrics.write();
-        if metrics.connected_peers > 0 {
-            metrics.connected_peers -= 1;
-        }
+    pub fn peer_disconnected(&self, _peer_id: &str) {
+        let _ = self.metrics.connected_peers.fetch_update(
+            Ordering::Relaxed, Ordering::Relaxed,
+            |c| Some(c.saturating_sub(1)),
+        );
     }
 
     /// Record a successful request to a peer
     pub fn record_request_success(&self, peer_id: &str, response_time_ms: u64, bytes: u64) {
-        {
-            let mut scores = self.scores.write();
-            let score = scores.entry(peer_id.to_string())
-                .or_insert_with(|| PeerScore::new(peer_id.to_string()));
-            score.record_success(response_time_ms, bytes);
-        }
-        
-        {
-            let mut metrics = self.metrics.write();
-            metrics.total_requests += 1;
-            metrics.successful_requests += 1;
-            metrics.total_bytes_transferred += bytes;
-            
-            // Update rolling average latency
-            if metrics.total_requests > 0 {
-                metrics.avg_latency_ms = (metrics.avg_latency_ms * (metrics.total_requests - 1) 
-                    + response_time_ms) / metrics.total_requests;
-            }
-        }
+        self.shard(peer_id).write()
+            .entry(peer_id.to_string())
+            .or_insert_with(|| PeerScore::new(peer_id.to_string()))
+            .record_success(response_time_ms, bytes);
+
+        let total = self.metrics.total_requests.fetch_add(1, Ordering::Relaxed) + 1;
+        self.metrics.successful_requests.fetch_add(1, Ordering::Relaxed);
+        self.metrics.total_bytes_transferred.fetch_add(bytes, Ordering::Relaxed);
+
+        // Rolling average latency; lock-free via CAS retry instead of
+        // holding a metrics lock.
+        let _ = self.metrics.avg_latency_ms.fetch_update(
+            Ordering::Relaxed, Ordering::Relaxed,
+            |avg| Some((avg * (total - 1) + response_time_ms) / total),
+        );
     }
 
-    /// Record a failed request to a peer
-    pub fn record_request_failure(&self, peer_id: &str) {
-        {
-            let mut scores = self.scores.write();
-            let score = scores.entry(peer_id.to_string())
-                .or_insert_with(|| PeerScore::new(peer_id.to_string()));
-            score.record_failure();
+    /// Record a failed request to a peer. Returns the reputation delta
+    /// applied, so callers that surface misbehavior (e.g. a rejected
+    /// gossip message) can report how severe the penalty was.
+    pub fn record_request_failure(&self, peer_id: &str) -> i32 {
+        self.shard(peer_id).write()
+            .entry(peer_id.to_string())
+            .or_insert_with(|| PeerScore::new(peer_id.to_string()))
+            .record_failure();
+
+        self.metrics.total_requests.fetch_add(1, Ordering::Relaxed);
+        FAILURE_REPUTATION_CHANGE
+    }
+
+    /// Record a request to a peer that timed out with no response.
+    pub fn record_request_timeout(&self, peer_id: &str) {
+        self.shard(peer_id).write()
+            .entry(peer_id.to_string())
+            .or_insert_with(|| PeerScore::new(peer_id.to_string()))
+            .record_timeout();
+
+        self.metrics.total_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether `peer_id` is currently banned (reputation at or below
+    /// [`BANNED_THRESHOLD`]). An unknown peer is never banned.
+    pub fn is_banned(&self, peer_id: &str) -> bool {
+        self.shard(peer_id).read().get(peer_id).is_some_and(|s| s.is_banned())
+    }
+
+    /// Peer IDs that are not currently banned — the admission-control
+    /// list callers should consult before accepting or keeping a
+    /// connection.
+    pub fn acceptable_peers(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for shard in &self.shards {
+            out.extend(shard.read().values().filter(|s| !s.is_banned()).map(|s| s.peer_id.clone()));
         }
-        
-        {
-            let mut metrics = self.metrics.write();
-            metrics.total_requests += 1;
+        out
+    }
+
+    /// Decay every tracked peer's reputation a step toward zero. Intended
+    /// to be called periodically so that bans and accumulated reputation
+    /// both fade over time instead of persisting forever.
+    pub fn tick(&self) {
+        for shard in &self.shards {
+            for score in shard.write().values_mut() {
+                score.decay();
+            }
         }
     }
 
@@ -242,31 +414,41 @@ impl HealthMonitor {
 
     /// Get peer score
     pub fn get_peer_score(&self, peer_id: &str) -> Option<PeerScore> {
-        self.scores.read().get(peer_id).cloned()
+        self.shard(peer_id).read().get(peer_id).cloned()
     }
 
     /// Get all peer scores
     pub fn get_all_scores(&self) -> Vec<PeerScore> {
-        self.scores.read().values().cloned().collect()
+        let mut out = Vec::new();
+        for shard in &self.shards {
+            out.extend(shard.read().values().cloned());
+        }
+        out
     }
 
-    /// Get top peers by score
+    /// Get top peers by reputation
     pub fn get_top_peers(&self, limit: usize) -> Vec<PeerScore> {
-        let mut scores: Vec<_> = self.scores.read().values().cloned().collect();
-        scores.sort_by(|a, b| b.score.cmp(&a.score));
+        let mut scores = self.get_all_scores();
+        scores.sort_by(|a, b| b.reputation.cmp(&a.reputation));
         scores.truncate(limit);
         scores
     }
 
     /// Get network metrics
     pub fn get_metrics(&self) -> NetworkMetrics {
-        let mut metrics = self.metrics.read().clone();
-        metrics.uptime_secs = self.start_time.elapsed().as_secs();
-        metrics.last_updated = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_millis() as u64)
-            .unwrap_or(0);
-        metrics
+        NetworkMetrics {
+            total_peers_seen: self.metrics.total_peers_seen.load(Ordering::Relaxed),
+            connected_peers: self.metrics.connected_peers.load(Ordering::Relaxed) as usize,
+            total_bytes_transferred: self.metrics.total_bytes_transferred.load(Ordering::Relaxed),
+            total_requests: self.metrics.total_requests.load(Ordering::Relaxed),
+            successful_requests: self.metrics.successful_requests.load(Ordering::Relaxed),
+            avg_latency_ms: self.metrics.avg_latency_ms.load(Ordering::Relaxed),
+            uptime_secs: self.start_time.elapsed().as_secs(),
+            last_updated: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        }
     }
 
     /// Get connection health for a peer
@@ -279,6 +461,84 @@ impl HealthMonitor {
         self.health_cache.read().values().cloned().collect()
     }
 
+    /// Load every peer reputation record persisted in `store` into the
+    /// live working set. Intended to be called once on startup, before
+    /// the node starts accepting connections.
+    pub fn load_from(&self, store: &BundleStore) -> Result<()> {
+        for (peer_id, data) in store.load_peer_scores()? {
+            match bincode::deserialize::<PeerScore>(&data) {
+                Ok(score) => { self.shard(&peer_id).write().insert(peer_id, score); }
+                Err(e) => tracing::warn!("Skipping corrupt peer score for {}: {}", peer_id, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the current in-memory peer reputations to `store` so they
+    /// survive a process restart or crash. Intended to be called
+    /// periodically (e.g. alongside the replication gossip heartbeat)
+    /// rather than on every single update.
+    pub fn persist_to(&self, store: &BundleStore) -> Result<()> {
+        // Snapshot every shard before doing any (potentially slow) sled
+        // I/O, so a write to one shard is never blocked behind a flush.
+        let snapshot = self.get_all_scores();
+        for score in &snapshot {
+            let data = bincode::serialize(score)?;
+            store.save_peer_score(&score.peer_id, &data)?;
+        }
+        Ok(())
+    }
+
+    /// Remember `peer_id` as worth reconnecting to on startup, provided
+    /// it has sustained enough successful traffic to clear the
+    /// reliability bar. Safe to call repeatedly (e.g. after every
+    /// successful request); peers that don't yet qualify are simply
+    /// left alone.
+    pub fn record_reliable_connection(&self, peer_id: &str, store: &BundleStore) -> Result<()> {
+        let qualifies = self.shard(peer_id).read().get(peer_id).is_some_and(|s| {
+            s.reputation >= RELIABLE_REPUTATION_FLOOR && s.total_requests >= RELIABLE_MIN_REQUESTS
+        });
+        if qualifies {
+            store.mark_reliable_peer(peer_id)?;
+        }
+        Ok(())
+    }
+
+    /// Peers previously marked reliable via `record_reliable_connection`,
+    /// so the node can proactively re-establish those connections after
+    /// a restart.
+    pub fn reliable_peers_on_startup(store: &BundleStore) -> Result<Vec<String>> {
+        store.get_reliable_peers()
+    }
+
+    /// Record capabilities learned about `peer_id` from `source`.
+    pub fn record_capabilities(&self, peer_id: &str, caps: &[Capability], source: CapabilitySource) {
+        self.shard(peer_id).write()
+            .entry(peer_id.to_string())
+            .or_insert_with(|| PeerScore::new(peer_id.to_string()))
+            .record_capabilities(caps, source);
+    }
+
+    /// Peers known to support `cap`, so callers can answer "which
+    /// connected peers can serve large bundles / support a given
+    /// protocol version / accept pinning requests" without probing
+    /// everyone. Directly-confirmed peers are sorted ahead of ones we
+    /// only know about via gossip, since they're the safer choice when
+    /// routing a request.
+    pub fn peers_with_capability(&self, cap: Capability) -> Vec<PeerScore> {
+        let mut direct = Vec::new();
+        let mut gossiped = Vec::new();
+        for score in self.get_all_scores() {
+            if score.capabilities.contains(&cap) {
+                direct.push(score);
+            } else if score.gossiped_capabilities.contains(&cap) {
+                gossiped.push(score);
+            }
+        }
+        direct.extend(gossiped);
+        direct
+    }
+
     /// Get summary health status
     pub fn get_health_summary(&self) -> HealthSummary {
         let metrics = self.get_metrics();
@@ -287,7 +547,7 @@ impl HealthMonitor {
         let avg_score = if scores.is_empty() {
             0.0
         } else {
-            scores.iter().map(|s| s.score as f64).sum::<f64>() / scores.len() as f64
+            scores.iter().map(|s| s.reputation as f64).sum::<f64>() / scores.len() as f64
         };
 
         let reliability = if metrics.total_requests > 0 {
@@ -333,27 +593,59 @@ mod tests {
     #[test]
     fn test_peer_score() {
         let mut score = PeerScore::new("test-peer".to_string());
-        
-        // Initial score
-        assert_eq!(score.score, 50);
-        
+
+        // Starts neutral
+        assert_eq!(score.reputation, 0);
+
         // Record successes
         score.record_success(50, 1000);
         score.record_success(60, 2000);
         score.record_success(70, 1500);
-        
-        // Score should improve
-        assert!(score.score > 50);
+
+        // Reputation should improve
+        assert!(score.reputation > 0);
         assert_eq!(score.successful_responses, 3);
-        
+
+        let before_failure = score.reputation;
+
         // Record failure
         score.record_failure();
-        
-        // Score should drop slightly
-        assert!(score.score < 100);
+
+        // Reputation should drop
+        assert!(score.reputation < before_failure);
         assert_eq!(score.failed_requests, 1);
     }
 
+    #[test]
+    fn test_peer_score_timeout_penalized_more_than_failure() {
+        let mut timed_out = PeerScore::new("timeout-peer".to_string());
+        let mut failed = PeerScore::new("failure-peer".to_string());
+
+        timed_out.record_timeout();
+        failed.record_failure();
+
+        assert!(timed_out.reputation < failed.reputation);
+    }
+
+    #[test]
+    fn test_peer_score_ban_threshold_and_decay() {
+        let mut score = PeerScore::new("bad-peer".to_string());
+        assert!(!score.is_banned());
+
+        for _ in 0..7000 {
+            score.record_timeout();
+        }
+        assert!(score.is_banned());
+
+        // Decay should pull reputation back toward zero, eventually
+        // clearing the ban.
+        for _ in 0..100 {
+            score.decay();
+        }
+        assert!(!score.is_banned());
+        assert!(score.reputation > BANNED_THRESHOLD);
+    }
+
     #[test]
     fn test_health_monitor() {
         let monitor = HealthMonitor::new();
@@ -375,28 +667,154 @@ mod tests {
         assert_eq!(metrics.total_requests, 3);
         assert_eq!(metrics.successful_requests, 2);
         
-        // Check peer scores
+        // Check peer reputations
         let score1 = monitor.get_peer_score("peer1").unwrap();
-        assert!(score1.score > 50);
-        
+        assert!(score1.reputation > 0);
+
         let score2 = monitor.get_peer_score("peer2").unwrap();
-        assert!(score2.score < 50);
+        assert!(score2.reputation < 0);
     }
 
     #[test]
     fn test_top_peers() {
         let monitor = HealthMonitor::new();
-        
+
         monitor.peer_connected("good-peer");
         monitor.record_request_success("good-peer", 50, 1000);
         monitor.record_request_success("good-peer", 60, 1000);
-        
+
         monitor.peer_connected("bad-peer");
         monitor.record_request_failure("bad-peer");
         monitor.record_request_failure("bad-peer");
-        
+
         let top = monitor.get_top_peers(5);
         assert_eq!(top.len(), 2);
         assert_eq!(top[0].peer_id, "good-peer");
     }
+
+    #[test]
+    fn test_acceptable_peers_excludes_banned() {
+        let monitor = HealthMonitor::new();
+        monitor.peer_connected("good-peer");
+        monitor.peer_connected("bad-peer");
+
+        for _ in 0..7000 {
+            monitor.record_request_timeout("bad-peer");
+        }
+
+        assert!(!monitor.is_banned("good-peer"));
+        assert!(monitor.is_banned("bad-peer"));
+
+        let acceptable = monitor.acceptable_peers();
+        assert!(acceptable.contains(&"good-peer".to_string()));
+        assert!(!acceptable.contains(&"bad-peer".to_string()));
+    }
+
+    #[test]
+    fn test_tick_decays_reputation() {
+        let monitor = HealthMonitor::new();
+        monitor.peer_connected("peer1");
+        monitor.record_request_success("peer1", 50, 1000);
+
+        let before = monitor.get_peer_score("peer1").unwrap().reputation;
+        monitor.tick();
+        let after = monitor.get_peer_score("peer1").unwrap().reputation;
+
+        assert!(after < before);
+        assert!(after > 0);
+    }
+
+    #[test]
+    fn test_persist_to_and_load_from_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = BundleStore::new(dir.path())?;
+
+        let monitor = HealthMonitor::new();
+        monitor.peer_connected("peer1");
+        monitor.record_request_success("peer1", 50, 1000);
+        monitor.persist_to(&store)?;
+
+        let restored = HealthMonitor::new();
+        restored.load_from(&store)?;
+        assert_eq!(
+            restored.get_peer_score("peer1").unwrap().reputation,
+            monitor.get_peer_score("peer1").unwrap().reputation,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_reliable_connection_requires_reputation_and_history() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = BundleStore::new(dir.path())?;
+
+        let monitor = HealthMonitor::new();
+        monitor.peer_connected("peer1");
+        monitor.record_request_success("peer1", 50, 1000);
+
+        // One fast success isn't enough history yet.
+        monitor.record_reliable_connection("peer1", &store)?;
+        assert!(HealthMonitor::reliable_peers_on_startup(&store)?.is_empty());
+
+        for _ in 0..RELIABLE_MIN_REQUESTS {
+            monitor.record_request_success("peer1", 50, 1000);
+        }
+        monitor.record_reliable_connection("peer1", &store)?;
+        assert_eq!(
+            HealthMonitor::reliable_peers_on_startup(&store)?,
+            vec!["peer1".to_string()],
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_peers_with_capability_prefers_self_reported_over_gossiped() {
+        let monitor = HealthMonitor::new();
+        monitor.peer_connected("direct-peer");
+        monitor.peer_connected("gossiped-peer");
+        monitor.peer_connected("unrelated-peer");
+
+        monitor.record_capabilities("direct-peer", &[Capability::Pinning], CapabilitySource::SelfReported);
+        monitor.record_capabilities("gossiped-peer", &[Capability::Pinning], CapabilitySource::Gossiped);
+
+        let peers = monitor.peers_with_capability(Capability::Pinning);
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].peer_id, "direct-peer");
+        assert_eq!(peers[1].peer_id, "gossiped-peer");
+    }
+
+    #[test]
+    fn test_self_reported_capability_overrides_gossiped() {
+        let mut score = PeerScore::new("peer1".to_string());
+        score.record_capabilities(&[Capability::LargeBundles], CapabilitySource::Gossiped);
+        assert!(score.gossiped_capabilities.contains(&Capability::LargeBundles));
+
+        score.record_capabilities(&[Capability::LargeBundles], CapabilitySource::SelfReported);
+        assert!(score.capabilities.contains(&Capability::LargeBundles));
+        assert!(!score.gossiped_capabilities.contains(&Capability::LargeBundles));
+        assert!(score.supports(Capability::LargeBundles));
+    }
+
+    #[test]
+    fn test_sharded_peers_update_independently_and_metrics_stay_consistent() {
+        let monitor = HealthMonitor::new();
+
+        // Pick peer IDs landing in different shards so this exercises
+        // more than one lock.
+        let peer_ids: Vec<String> = (0..NUM_SHARDS * 4).map(|i| format!("peer-{i}")).collect();
+        assert!(peer_ids.iter().map(|p| shard_of(p)).collect::<std::collections::HashSet<_>>().len() > 1);
+
+        for peer_id in &peer_ids {
+            monitor.peer_connected(peer_id);
+            monitor.record_request_success(peer_id, 50, 10);
+        }
+
+        assert_eq!(monitor.get_all_scores().len(), peer_ids.len());
+        let metrics = monitor.get_metrics();
+        assert_eq!(metrics.total_requests, peer_ids.len() as u64);
+        assert_eq!(metrics.successful_requests, peer_ids.len() as u64);
+        assert_eq!(metrics.total_bytes_transferred, peer_ids.len() as u64 * 10);
+    }
 }
@@ -0,0 +1,296 @@
+//! Zone-spread replica placement.
+//!
+//! `Grab::host` is purely local and opportunistic today: a site ends up
+//! wherever it was hosted, with no target replica count and no regard for
+//! geographic spread. [`ReplicaLayout`] adds that on top: given a desired
+//! replica count per site (`set_replication`) and each candidate host's
+//! declared zone tag and mirror capacity (`set_host`, fed from the
+//! `zone` carried on `SiteAnnouncement`/the gossip membership table),
+//! it assigns which peers should mirror each site, spreading replicas
+//! across as many distinct zones as possible before doubling up within
+//! one. Recomputation is incremental: an already-assigned, still-valid,
+//! under-capacity host is never evicted just to make room for a
+//! "better" one, so only the minimum number of sites move when the
+//! member set changes.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::types::SiteId;
+
+/// A candidate replica host: its declared zone/region tag and how many
+/// sites it's willing to mirror in total.
+#[derive(Debug, Clone)]
+struct HostInfo {
+    zone: Option<String>,
+    capacity: usize,
+}
+
+/// Per-site placement snapshot: which zones/peers currently hold a site
+/// versus the target replica count, as reported by
+/// [`ReplicaLayout::status`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutStatus {
+    pub site_id: SiteId,
+    pub target: usize,
+    /// Currently-assigned peers, grouped by declared zone (`None` for
+    /// peers with no declared zone).
+    pub zones: HashMap<Option<String>, Vec<String>>,
+    /// `true` once `target` replicas are assigned.
+    pub satisfied: bool,
+}
+
+/// Assigns which peers should mirror each site. See the module docs for
+/// the placement strategy.
+pub struct ReplicaLayout {
+    targets: Arc<RwLock<HashMap<SiteId, usize>>>,
+    hosts: Arc<RwLock<HashMap<String, HostInfo>>>,
+    assignments: Arc<RwLock<HashMap<SiteId, Vec<String>>>>,
+}
+
+impl ReplicaLayout {
+    pub fn new() -> Self {
+        Self {
+            targets: Arc::new(RwLock::new(HashMap::new())),
+            hosts: Arc::new(RwLock::new(HashMap::new())),
+            assignments: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Set (or, at `factor == 0`, clear) the target replica count for a
+    /// site and immediately recompute its assignment.
+    pub fn set_replication(&self, site_id: SiteId, factor: usize) {
+        self.targets.write().insert(site_id, factor);
+        self.recompute(&site_id);
+    }
+
+    /// Declare (or update) a candidate host's zone tag and mirror
+    /// capacity, then recompute every site's assignment since a
+    /// new/changed host may free up or fill slots anywhere.
+    pub fn set_host(&self, peer_id: String, zone: Option<String>, capacity: usize) {
+        self.hosts.write().insert(peer_id, HostInfo { zone, capacity });
+        self.recompute_all();
+    }
+
+    /// Drop a host that's left the membership table, then recompute every
+    /// site it might have been assigned to.
+    pub fn remove_host(&self, peer_id: &str) {
+        self.hosts.write().remove(peer_id);
+        self.recompute_all();
+    }
+
+    /// Current assignment for a site, ignoring its target replica count.
+    pub fn assigned_peers(&self, site_id: &SiteId) -> Vec<String> {
+        self.assignments.read().get(site_id).cloned().unwrap_or_default()
+    }
+
+    /// Placement status for a single site.
+    pub fn status(&self, site_id: &SiteId) -> LayoutStatus {
+        let target = self.targets.read().get(site_id).copied().unwrap_or(0);
+        let hosts = self.hosts.read();
+        let current = self.assigned_peers(site_id);
+
+        let mut zones: HashMap<Option<String>, Vec<String>> = HashMap::new();
+        for peer_id in &current {
+            let zone = hosts.get(peer_id).and_then(|h| h.zone.clone());
+            zones.entry(zone).or_default().push(peer_id.clone());
+        }
+
+        LayoutStatus {
+            site_id: *site_id,
+            target,
+            satisfied: current.len() >= target,
+            zones,
+        }
+    }
+
+    /// Placement status for every site with a configured target.
+    pub fn status_all(&self) -> Vec<LayoutStatus> {
+        let site_ids: Vec<SiteId> = self.targets.read().keys().copied().collect();
+        site_ids.iter().map(|site_id| self.status(site_id)).collect()
+    }
+
+    fn recompute_all(&self) {
+        let site_ids: Vec<SiteId> = self.targets.read().keys().copied().collect();
+        for site_id in site_ids {
+            self.recompute(&site_id);
+        }
+    }
+
+    /// Recompute `site_id`'s assignment: drop hosts that left or no longer
+    /// fit under capacity, then either trim the most-represented zone
+    /// (target shrank) or fill remaining slots with the unassigned host
+    /// in the least-represented zone (target grew), breaking ties on
+    /// peer ID for determinism.
+    fn recompute(&self, site_id: &SiteId) {
+        let target = self.targets.read().get(site_id).copied().unwrap_or(0);
+        let hosts = self.hosts.read().clone();
+        let mut assignments = self.assignments.write();
+
+        // How many *other* sites each host is already carrying, so we can
+        // tell whether keeping or adding an assignment here would push it
+        // over capacity.
+        let mut other_load: HashMap<String, usize> = HashMap::new();
+        for (id, peers) in assignments.iter() {
+            if id == site_id {
+                continue;
+            }
+            for peer_id in peers {
+                *other_load.entry(peer_id.clone()).or_insert(0) += 1;
+            }
+        }
+
+        let mut current: Vec<String> = assignments
+            .get(site_id)
+            .cloned()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|peer_id| {
+                hosts
+                    .get(peer_id)
+                    .is_some_and(|h| other_load.get(peer_id).copied().unwrap_or(0) < h.capacity)
+            })
+            .collect();
+
+        while current.len() > target {
+            let zone_counts = zone_counts(&current, &hosts);
+            let Some(victim) = current
+                .iter()
+                .max_by_key(|peer_id| {
+                    let zone = hosts.get(*peer_id).and_then(|h| h.zone.clone());
+                    (*zone_counts.get(&zone).unwrap_or(&0), (*peer_id).clone())
+                })
+                .cloned()
+            else {
+                break;
+            };
+            current.retain(|p| p != &victim);
+        }
+
+        while current.len() < target {
+            let zone_counts = zone_counts(&current, &hosts);
+            let mut candidates: Vec<&String> = hosts
+                .keys()
+                .filter(|peer_id| !current.contains(*peer_id))
+                .filter(|peer_id| other_load.get(*peer_id).copied().unwrap_or(0) < hosts[*peer_id].capacity)
+                .collect();
+            candidates.sort_by_key(|peer_id| {
+                let zone = hosts[*peer_id].zone.clone();
+                (*zone_counts.get(&zone).unwrap_or(&0), (*peer_id).clone())
+            });
+            let Some(pick) = candidates.first().map(|p| (*p).clone()) else {
+                break;
+            };
+            current.push(pick);
+        }
+
+        assignments.insert(*site_id, current);
+    }
+}
+
+impl Default for ReplicaLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn zone_counts(current: &[String], hosts: &HashMap<String, HostInfo>) -> HashMap<Option<String>, usize> {
+    let mut counts = HashMap::new();
+    for peer_id in current {
+        let zone = hosts.get(peer_id).and_then(|h| h.zone.clone());
+        *counts.entry(zone).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spreads_across_zones_before_doubling_up() {
+        let layout = ReplicaLayout::new();
+        layout.set_host("a".to_string(), Some("us-east".to_string()), 10);
+        layout.set_host("b".to_string(), Some("us-east".to_string()), 10);
+        layout.set_host("c".to_string(), Some("eu-west".to_string()), 10);
+
+        let site_id = [1u8; 32];
+        layout.set_replication(site_id, 2);
+
+        let status = layout.status(&site_id);
+        assert!(status.satisfied);
+        // Both zones represented, rather than doubling up on us-east.
+        assert_eq!(status.zones.len(), 2);
+    }
+
+    #[test]
+    fn test_respects_host_capacity() {
+        let layout = ReplicaLayout::new();
+        layout.set_host("a".to_string(), Some("us-east".to_string()), 1);
+        layout.set_host("b".to_string(), Some("us-east".to_string()), 1);
+
+        let site1 = [1u8; 32];
+        let site2 = [2u8; 32];
+        layout.set_replication(site1, 1);
+        layout.set_replication(site2, 2);
+
+        // site2 can only get whichever host site1 didn't already take.
+        let site1_peers = layout.assigned_peers(&site1);
+        let site2_peers = layout.assigned_peers(&site2);
+        assert_eq!(site1_peers.len(), 1);
+        assert_eq!(site2_peers.len(), 1);
+        assert_ne!(site1_peers[0], site2_peers[0]);
+    }
+
+    #[test]
+    fn test_recompute_is_incremental_on_host_removal() {
+        let layout = ReplicaLayout::new();
+        layout.set_host("a".to_string(), Some("us-east".to_string()), 10);
+        layout.set_host("b".to_string(), Some("eu-west".to_string()), 10);
+        layout.set_host("c".to_string(), Some("ap-south".to_string()), 10);
+
+        let site_id = [3u8; 32];
+        layout.set_replication(site_id, 2);
+        let before = layout.assigned_peers(&site_id);
+        assert_eq!(before.len(), 2);
+
+        // Removing an uninvolved host shouldn't perturb the assignment.
+        let uninvolved = ["a", "b", "c"].iter().find(|p| !before.contains(&p.to_string())).unwrap();
+        layout.remove_host(uninvolved);
+        let after = layout.assigned_peers(&site_id);
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_shrinking_target_trims_most_represented_zone() {
+        let layout = ReplicaLayout::new();
+        layout.set_host("a".to_string(), Some("us-east".to_string()), 10);
+        layout.set_host("b".to_string(), Some("us-east".to_string()), 10);
+        layout.set_host("c".to_string(), Some("eu-west".to_string()), 10);
+
+        let site_id = [4u8; 32];
+        layout.set_replication(site_id, 3);
+        assert_eq!(layout.assigned_peers(&site_id).len(), 3);
+
+        layout.set_replication(site_id, 1);
+        let remaining = layout.assigned_peers(&site_id);
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[test]
+    fn test_status_reports_unsatisfied_when_under_target() {
+        let layout = ReplicaLayout::new();
+        layout.set_host("a".to_string(), None, 10);
+
+        let site_id = [5u8; 32];
+        layout.set_replication(site_id, 3);
+
+        let status = layout.status(&site_id);
+        assert!(!status.satisfied);
+        assert_eq!(status.target, 3);
+        assert_eq!(layout.assigned_peers(&site_id).len(), 1);
+    }
+}
@@ -0,0 +1,424 @@
+//! Gossip-based peer membership (SWIM-style failure detection)
+//!
+//! `node start` previously only knew about the peers it dialed explicitly.
+//! This module keeps a local table of `{peer_id, addresses, last_seen, state,
+//! incarnation}` that's kept fresh by periodically exchanging deltas with a
+//! handful of known members, and by directly probing a random member to
+//! catch ones that have gone quiet. A direct probe that goes unanswered
+//! doesn't immediately declare suspicion -- `GrabRequest::IndirectPing`
+//! asks a few other members to check too, the same SWIM tradeoff of extra
+//! gossip traffic for fewer false positives from a one-sided network
+//! partition. A member that's wrongly marked `Suspect`/`Dead` refutes it by
+//! re-announcing itself `Alive` at a higher `incarnation`, which always
+//! wins a merge over the stale accusation. DNS seeding lets an operator
+//! point new nodes at a stable hostname instead of a hardcoded address list.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+/// Number of consecutive failed probes before a suspect member is declared dead.
+const SUSPECT_ROUNDS_BEFORE_DEAD: u32 = 3;
+
+/// How a member currently looks to us.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemberState {
+    Alive,
+    Suspect,
+    Dead,
+}
+
+/// A single entry in the membership table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Member {
+    pub peer_id: String,
+    pub addresses: Vec<String>,
+    /// Seconds since the epoch this entry was last refreshed. A plain
+    /// integer (rather than `Instant`) so entries can be compared after
+    /// crossing the wire in a gossip delta.
+    pub last_seen: u64,
+    pub state: MemberState,
+    /// Bumped by a member about itself whenever it refutes a `Suspect`/`Dead`
+    /// entry someone else gossiped about it (see `apply_delta`). A higher
+    /// incarnation always wins a merge, so a stale accusation can't keep
+    /// overriding a member's own, newer claim of being alive.
+    pub incarnation: u64,
+    #[serde(skip)]
+    missed_probes: u32,
+}
+
+impl Member {
+    fn new(peer_id: String, addresses: Vec<String>) -> Self {
+        Self {
+            peer_id,
+            addresses,
+            last_seen: now_secs(),
+            state: MemberState::Alive,
+            incarnation: 0,
+            missed_probes: 0,
+        }
+    }
+
+    /// Whether `self` (an incoming gossiped entry) should replace `existing`:
+    /// a higher incarnation always wins, and for equal incarnations the
+    /// fresher `last_seen` wins.
+    fn supersedes(&self, existing: &Member) -> bool {
+        (self.incarnation, self.last_seen) > (existing.incarnation, existing.last_seen)
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A gossip round's payload: members we believe joined or are alive, and
+/// peer IDs we believe have left (gone `Dead`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MembershipDelta {
+    pub joined: Vec<Member>,
+    pub left: Vec<String>,
+}
+
+/// Local view of the cluster's membership.
+#[derive(Debug, Default)]
+pub struct MembershipTable {
+    members: HashMap<String, Member>,
+}
+
+impl MembershipTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a member as alive, refreshing its address list and timestamp.
+    pub fn upsert(&mut self, peer_id: String, addresses: Vec<String>) {
+        self.members
+            .entry(peer_id.clone())
+            .and_modify(|m| {
+                m.last_seen = now_secs();
+                m.state = MemberState::Alive;
+                m.missed_probes = 0;
+                for addr in &addresses {
+                    if !m.addresses.contains(addr) {
+                        m.addresses.push(addr.clone());
+                    }
+                }
+            })
+            .or_insert_with(|| Member::new(peer_id, addresses));
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn get(&self, peer_id: &str) -> Option<&Member> {
+        self.members.get(peer_id)
+    }
+
+    /// Up to `fixed_fanout` of our most-recently-seen alive members, plus a
+    /// random third of whatever alive members remain, to gossip with this round.
+    pub fn gossip_targets(&self, local_id: &str, fixed_fanout: usize) -> Vec<String> {
+        let mut alive: Vec<&Member> = self
+            .members
+            .values()
+            .filter(|m| m.state != MemberState::Dead && m.peer_id != local_id)
+            .collect();
+        alive.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+
+        let mut targets: Vec<String> = alive
+            .iter()
+            .take(fixed_fanout)
+            .map(|m| m.peer_id.clone())
+            .collect();
+
+        let remaining: Vec<&&Member> = alive.iter().skip(fixed_fanout).collect();
+        let sample_size = remaining.len() / 3;
+        if sample_size > 0 {
+            let mut rng = rand::thread_rng();
+            let sampled: Vec<_> = remaining
+                .choose_multiple(&mut rng, sample_size)
+                .map(|m| m.peer_id.clone())
+                .collect();
+            targets.extend(sampled);
+        }
+
+        targets
+    }
+
+    /// Pick a random alive member to probe for liveness.
+    pub fn pick_probe_target(&self, local_id: &str) -> Option<String> {
+        let alive: Vec<&String> = self
+            .members
+            .values()
+            .filter(|m| m.state != MemberState::Dead && m.peer_id != local_id)
+            .map(|m| &m.peer_id)
+            .collect();
+        alive.choose(&mut rand::thread_rng()).map(|s| (*s).clone())
+    }
+
+    /// Pick up to `k` random alive members (excluding `local_id` and
+    /// `target` itself) to ask for an indirect probe of `target`, once our
+    /// own direct probe of it has gone unanswered.
+    pub fn pick_indirect_probers(&self, local_id: &str, target: &str, k: usize) -> Vec<String> {
+        let candidates: Vec<&String> = self
+            .members
+            .values()
+            .filter(|m| m.state != MemberState::Dead && m.peer_id != local_id && m.peer_id != target)
+            .map(|m| &m.peer_id)
+            .collect();
+        candidates
+            .choose_multiple(&mut rand::thread_rng(), k)
+            .map(|s| (*s).clone())
+            .collect()
+    }
+
+    /// Whether `peer_id` is currently believed dead -- used to steer
+    /// `fetch_site`/`push_update` away from hosts unlikely to answer.
+    pub fn is_dead(&self, peer_id: &str) -> bool {
+        matches!(self.members.get(peer_id).map(|m| m.state), Some(MemberState::Dead))
+    }
+
+    /// A snapshot of every member currently known, for the dashboard and
+    /// `NetworkStatus`.
+    pub fn members(&self) -> Vec<Member> {
+        self.members.values().cloned().collect()
+    }
+
+    /// A probe response came back: the member is confirmed alive again.
+    pub fn note_probe_success(&mut self, peer_id: &str) {
+        if let Some(member) = self.members.get_mut(peer_id) {
+            member.state = MemberState::Alive;
+            member.missed_probes = 0;
+            member.last_seen = now_secs();
+        }
+    }
+
+    /// A probe went unanswered. Escalates `Alive` -> `Suspect` -> `Dead`
+    /// across [`SUSPECT_ROUNDS_BEFORE_DEAD`] consecutive misses. Returns
+    /// `true` if this call is what pushed the member to `Dead`.
+    pub fn note_probe_failure(&mut self, peer_id: &str) -> bool {
+        let Some(member) = self.members.get_mut(peer_id) else {
+            return false;
+        };
+        if member.state == MemberState::Dead {
+            return false;
+        }
+        member.missed_probes += 1;
+        member.state = MemberState::Suspect;
+        if member.missed_probes >= SUSPECT_ROUNDS_BEFORE_DEAD {
+            member.state = MemberState::Dead;
+            return true;
+        }
+        false
+    }
+
+    /// Snapshot our current view as a delta to hand to a gossip target.
+    pub fn to_delta(&self) -> MembershipDelta {
+        let mut joined = Vec::new();
+        let mut left = Vec::new();
+        for member in self.members.values() {
+            match member.state {
+                MemberState::Dead => left.push(member.peer_id.clone()),
+                _ => joined.push(member.clone()),
+            }
+        }
+        MembershipDelta { joined, left }
+    }
+
+    /// Merge a delta received from a gossip peer, keeping whichever side's
+    /// incarnation (then `last_seen`) is newer for any entry both sides
+    /// know about. Returns the peer IDs that are newly known (joined) and
+    /// newly dead (left), so the caller can surface them as
+    /// `NetworkEvent`s.
+    ///
+    /// If the delta accuses `local_id` of being `Suspect`/`Dead`, it's
+    /// refuted rather than applied: we bump our own incarnation and
+    /// re-assert `Alive`, so the false report doesn't stick and our next
+    /// gossip round corrects it at every other member.
+    pub fn apply_delta(&mut self, delta: MembershipDelta, local_id: &str) -> (Vec<String>, Vec<String>) {
+        let mut newly_joined = Vec::new();
+        let mut newly_left = Vec::new();
+
+        for incoming in delta.joined {
+            if incoming.peer_id == local_id {
+                if incoming.state != MemberState::Alive {
+                    self.refute(local_id, incoming.incarnation);
+                }
+                continue;
+            }
+            match self.members.get_mut(&incoming.peer_id) {
+                Some(existing) if !incoming.supersedes(existing) => {}
+                Some(existing) => *existing = incoming,
+                None => {
+                    newly_joined.push(incoming.peer_id.clone());
+                    self.members.insert(incoming.peer_id.clone(), incoming);
+                }
+            }
+        }
+
+        for dead_id in delta.left {
+            if dead_id == local_id {
+                self.refute(local_id, 0);
+                continue;
+            }
+            if let Some(member) = self.members.get_mut(&dead_id) {
+                if member.state != MemberState::Dead {
+                    member.state = MemberState::Dead;
+                    newly_left.push(dead_id);
+                }
+            }
+        }
+
+        (newly_joined, newly_left)
+    }
+
+    /// Re-assert ourselves as `Alive` with an incarnation higher than
+    /// whatever accused us, creating a self entry if we didn't have one yet.
+    fn refute(&mut self, local_id: &str, accusing_incarnation: u64) {
+        let local = self
+            .members
+            .entry(local_id.to_string())
+            .or_insert_with(|| Member::new(local_id.to_string(), vec![]));
+        local.incarnation = local.incarnation.max(accusing_incarnation) + 1;
+        local.state = MemberState::Alive;
+        local.last_seen = now_secs();
+    }
+}
+
+/// Resolve a DNS seed hostname to `/ip4/.../tcp/<port>` (or `/ip6/...`)
+/// multiaddrs for initial membership bootstrap. Only A/AAAA records are
+/// resolved; TXT-encoded multiaddrs aren't supported since that needs a
+/// full DNS resolver library this crate doesn't otherwise depend on.
+pub async fn resolve_dns_seed(hostname: &str, port: u16) -> Result<Vec<String>> {
+    let lookup_target = format!("{hostname}:{port}");
+    let addrs = tokio::net::lookup_host(lookup_target).await?;
+
+    let multiaddrs = addrs
+        .map(|socket_addr| {
+            if socket_addr.is_ipv6() {
+                format!("/ip6/{}/tcp/{}", socket_addr.ip(), port)
+            } else {
+                format!("/ip4/{}/tcp/{}", socket_addr.ip(), port)
+            }
+        })
+        .collect();
+
+    Ok(multiaddrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_and_get() {
+        let mut table = MembershipTable::new();
+        table.upsert("peer-a".to_string(), vec!["/ip4/1.2.3.4/tcp/4001".to_string()]);
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.get("peer-a").unwrap().state, MemberState::Alive);
+    }
+
+    #[test]
+    fn test_probe_failure_escalates_to_dead() {
+        let mut table = MembershipTable::new();
+        table.upsert("peer-a".to_string(), vec![]);
+
+        assert!(!table.note_probe_failure("peer-a"));
+        assert_eq!(table.get("peer-a").unwrap().state, MemberState::Suspect);
+        assert!(!table.note_probe_failure("peer-a"));
+        assert!(table.note_probe_failure("peer-a"));
+        assert_eq!(table.get("peer-a").unwrap().state, MemberState::Dead);
+    }
+
+    #[test]
+    fn test_probe_success_resets_suspicion() {
+        let mut table = MembershipTable::new();
+        table.upsert("peer-a".to_string(), vec![]);
+        table.note_probe_failure("peer-a");
+        table.note_probe_success("peer-a");
+        assert_eq!(table.get("peer-a").unwrap().state, MemberState::Alive);
+    }
+
+    #[test]
+    fn test_apply_delta_surfaces_new_joins_and_deaths() {
+        let mut table = MembershipTable::new();
+        table.upsert("peer-a".to_string(), vec![]);
+
+        let delta = MembershipDelta {
+            joined: vec![Member::new("peer-b".to_string(), vec![])],
+            left: vec!["peer-a".to_string()],
+        };
+
+        let (joined, left) = table.apply_delta(delta, "local-node");
+        assert_eq!(joined, vec!["peer-b".to_string()]);
+        assert_eq!(left, vec!["peer-a".to_string()]);
+        assert_eq!(table.get("peer-a").unwrap().state, MemberState::Dead);
+    }
+
+    #[test]
+    fn test_apply_delta_refutes_false_suspicion_about_self() {
+        let mut table = MembershipTable::new();
+
+        let accusation = MembershipDelta {
+            joined: vec![],
+            left: vec!["local-node".to_string()],
+        };
+        table.apply_delta(accusation, "local-node");
+
+        let local = table.get("local-node").unwrap();
+        assert_eq!(local.state, MemberState::Alive);
+        assert_eq!(local.incarnation, 1);
+
+        // A second, stale accusation at a lower incarnation doesn't win.
+        let stale = MembershipDelta {
+            joined: vec![Member { incarnation: 0, state: MemberState::Suspect, ..Member::new("local-node".to_string(), vec![]) }],
+            left: vec![],
+        };
+        table.apply_delta(stale, "local-node");
+        let local = table.get("local-node").unwrap();
+        assert_eq!(local.state, MemberState::Alive);
+        assert_eq!(local.incarnation, 2);
+    }
+
+    #[test]
+    fn test_apply_delta_ignores_stale_entries() {
+        let mut table = MembershipTable::new();
+        table.upsert("peer-a".to_string(), vec!["/ip4/1.1.1.1/tcp/4001".to_string()]);
+        let fresh_entry = table.get("peer-a").unwrap().clone();
+
+        let stale = Member {
+            last_seen: 0,
+            addresses: vec!["/ip4/9.9.9.9/tcp/4001".to_string()],
+            ..fresh_entry.clone()
+        };
+
+        table.apply_delta(MembershipDelta { joined: vec![stale], left: vec![] }, "local-node");
+        assert_eq!(table.get("peer-a").unwrap().addresses, fresh_entry.addresses);
+    }
+
+    #[test]
+    fn test_gossip_targets_excludes_self_and_dead() {
+        let mut table = MembershipTable::new();
+        table.upsert("self".to_string(), vec![]);
+        table.upsert("peer-a".to_string(), vec![]);
+        table.upsert("peer-b".to_string(), vec![]);
+        table.note_probe_failure("peer-b");
+        table.note_probe_failure("peer-b");
+        table.note_probe_failure("peer-b");
+
+        let targets = table.gossip_targets("self", 3);
+        assert!(!targets.contains(&"self".to_string()));
+        assert!(!targets.contains(&"peer-b".to_string()));
+        assert!(targets.contains(&"peer-a".to_string()));
+    }
+}
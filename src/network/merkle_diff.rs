@@ -0,0 +1,164 @@
+//! Logarithmic Merkle anti-entropy diff.
+//!
+//! `MerkleTree::diff` compares every leaf pairwise, which only works once
+//! both peers already hold each other's full leaf vector — no help when
+//! the whole point is discovering what a remote peer has without fetching
+//! everything first. This instead walks the tree top-down: a node whose
+//! cached hash matches the remote's is known to be fully in sync and is
+//! never descended into, so two mostly-synced peers exchange O(log n)
+//! hashes per differing region instead of O(n) leaves.
+//!
+//! Driven as a state machine so the request/response rounds can go over
+//! `GrabBehaviour::request_response` (see `GrabRequest::MerkleDiffQuery` /
+//! `GrabResponse::MerkleDiffReply`): call [`MerkleDiffSession::begin`] for
+//! the first batch of nodes to ask the remote peer about, feed each reply
+//! back through [`MerkleDiffSession::respond`], and send on whatever new
+//! [`NodeReq`]s it returns. The round finishes once every [`DiffStep`] has
+//! come back `InSync` or `LeafDiffers`.
+
+use crate::crypto::AppendMerkleTree;
+use crate::types::NodeId;
+
+/// A request for the hash the remote peer has cached at `node`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeReq {
+    pub node: NodeId,
+}
+
+/// What to do after comparing one node's hash against the remote's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffStep {
+    /// Both sides agree here (including both being unmaterialized); this
+    /// subtree needs no further comparison.
+    InSync,
+    /// `node` was a leaf and the two hashes differed.
+    LeafDiffers(usize),
+    /// An internal node differed; these children need comparing next.
+    Recurse(Vec<NodeReq>),
+}
+
+/// Drives one anti-entropy round against a single remote peer's copy of
+/// `local`, accumulating the leaf indices that turn out to differ.
+pub struct MerkleDiffSession<'a> {
+    local: &'a AppendMerkleTree,
+    differing_leaves: Vec<usize>,
+}
+
+impl<'a> MerkleDiffSession<'a> {
+    pub fn new(local: &'a AppendMerkleTree) -> Self {
+        Self { local, differing_leaves: Vec::new() }
+    }
+
+    /// The node requests to open a round with. The append-only tree's root
+    /// is a bagging of its peaks rather than one materialized top node, so
+    /// this asks about every current peak directly; a caller that already
+    /// exchanged `AppendMerkleTree::root()` out of band and found it equal
+    /// can skip calling this at all.
+    pub fn begin(&self) -> Vec<NodeReq> {
+        self.local.peak_node_ids().into_iter().map(|node| NodeReq { node }).collect()
+    }
+
+    /// Compare our cached hash for `node` against what the remote peer
+    /// reported. A missing hash on either side means that position isn't
+    /// materialized (beyond the current leaf count); two missing hashes
+    /// are treated as in sync, so the lack of zero-padding never triggers
+    /// a spurious transfer.
+    pub fn respond(&mut self, node: NodeId, remote_hash: Option<[u8; 32]>) -> DiffStep {
+        let local_hash = self.local.node_hash(node.height, node.index);
+
+        if local_hash == remote_hash {
+            return DiffStep::InSync;
+        }
+
+        if node.height == 0 {
+            self.differing_leaves.push(node.index);
+            return DiffStep::LeafDiffers(node.index);
+        }
+
+        let child_height = node.height - 1;
+        DiffStep::Recurse(vec![
+            NodeReq { node: NodeId { height: child_height, index: node.index * 2 } },
+            NodeReq { node: NodeId { height: child_height, index: node.index * 2 + 1 } },
+        ])
+    }
+
+    /// Leaf indices found to differ so far.
+    pub fn differing_leaves(&self) -> &[usize] {
+        &self.differing_leaves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::hash;
+
+    fn leaves(n: usize) -> Vec<[u8; 32]> {
+        (0..n).map(|i| hash(format!("leaf{i}").as_bytes())).collect()
+    }
+
+    /// Drive a full round between two trees, returning the leaf indices
+    /// the initiator discovers differ.
+    fn run_diff(local: &AppendMerkleTree, remote: &AppendMerkleTree) -> Vec<usize> {
+        let mut session = MerkleDiffSession::new(local);
+        let mut frontier = session.begin();
+
+        while let Some(req) = frontier.pop() {
+            let remote_hash = remote.node_hash(req.node.height, req.node.index);
+            match session.respond(req.node, remote_hash) {
+                DiffStep::InSync | DiffStep::LeafDiffers(_) => {}
+                DiffStep::Recurse(next) => frontier.extend(next),
+            }
+        }
+
+        let mut found = session.differing_leaves().to_vec();
+        found.sort_unstable();
+        found
+    }
+
+    #[test]
+    fn test_identical_trees_have_no_diff() {
+        let tree = AppendMerkleTree::from_leaves(leaves(9));
+        assert!(run_diff(&tree, &tree).is_empty());
+    }
+
+    #[test]
+    fn test_single_changed_leaf_is_found() {
+        let mut changed = leaves(9);
+        changed[5] = hash(b"tampered");
+
+        let local = AppendMerkleTree::from_leaves(leaves(9));
+        let remote = AppendMerkleTree::from_leaves(changed);
+
+        assert_eq!(run_diff(&local, &remote), vec![5]);
+    }
+
+    #[test]
+    fn test_multiple_changed_leaves_are_all_found() {
+        let mut changed = leaves(16);
+        changed[0] = hash(b"tampered-a");
+        changed[15] = hash(b"tampered-b");
+
+        let local = AppendMerkleTree::from_leaves(leaves(16));
+        let remote = AppendMerkleTree::from_leaves(changed);
+
+        assert_eq!(run_diff(&local, &remote), vec![0, 15]);
+    }
+
+    #[test]
+    fn test_extra_trailing_leaf_short_circuits_as_in_sync_elsewhere() {
+        // Remote has appended one more leaf; the rest are identical, so
+        // the local tree's single peak matches and the round ends there.
+        let base = leaves(8);
+        let mut extended = base.clone();
+        extended.push(hash(b"new"));
+
+        let local = AppendMerkleTree::from_leaves(base);
+        let remote = AppendMerkleTree::from_leaves(extended);
+
+        // The local tree doesn't even have a node at the new leaf's
+        // position, so `respond` never gets asked about index 8 — nothing
+        // to report as differing from the shorter side's point of view.
+        assert!(run_diff(&local, &remote).is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! Prometheus metrics for swarm, DHT, and request-response activity.
+//!
+//! Mirrors fuel-core's `P2P_METRICS` pattern: a handful of counters and
+//! histograms registered into a single `prometheus::Registry` at node
+//! startup, updated inline from `run_swarm`'s event loop, and exposed via
+//! `GrabNetwork::metrics_registry()` so a caller can mount them behind an
+//! HTTP scrape endpoint. Everything in this module is gated behind the
+//! `metrics` cargo feature; with it disabled, [`NodeMetrics`] is a
+//! zero-sized no-op and the `prometheus` crate isn't linked at all.
+
+#[cfg(feature = "metrics")]
+pub use enabled::NodeMetrics;
+#[cfg(not(feature = "metrics"))]
+pub use disabled::NodeMetrics;
+
+#[cfg(feature = "metrics")]
+mod enabled {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use prometheus::{Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts, Registry};
+
+    /// Registers and updates the node's Prometheus metrics. Cheap to
+    /// clone (every field is a handle into the same registered
+    /// collectors); held by `GrabNetwork` and threaded into `run_swarm`
+    /// and `handle_request`.
+    #[derive(Clone)]
+    pub struct NodeMetrics {
+        registry: Arc<Registry>,
+        connected_peers: IntGauge,
+        requests_total: IntCounterVec,
+        request_latency: Histogram,
+        dht_queries_total: IntCounterVec,
+        gossip_messages_total: IntCounterVec,
+        chunks_served_total: IntCounterVec,
+    }
+
+    impl NodeMetrics {
+        pub fn new() -> Result<Self> {
+            let registry = Registry::new();
+
+            let connected_peers = IntGauge::new(
+                "grab_connected_peers",
+                "Number of peers currently connected to the swarm",
+            )?;
+            let requests_total = IntCounterVec::new(
+                Opts::new(
+                    "grab_requests_total",
+                    "Outbound request-response attempts, by outcome",
+                ),
+                &["outcome"],
+            )?;
+            let request_latency = Histogram::with_opts(HistogramOpts::new(
+                "grab_request_latency_seconds",
+                "Round-trip latency of outbound request-response requests",
+            ))?;
+            let dht_queries_total = IntCounterVec::new(
+                Opts::new(
+                    "grab_dht_queries_total",
+                    "Kademlia query outcomes, by query kind and outcome",
+                ),
+                &["query", "outcome"],
+            )?;
+            let gossip_messages_total = IntCounterVec::new(
+                Opts::new(
+                    "grab_gossip_messages_total",
+                    "Gossipsub messages received, by topic and outcome",
+                ),
+                &["topic", "outcome"],
+            )?;
+            let chunks_served_total = IntCounterVec::new(
+                Opts::new(
+                    "grab_chunks_served_total",
+                    "Chunks and bytes served to peers via GetChunks",
+                ),
+                &["unit"],
+            )?;
+
+            registry.register(Box::new(connected_peers.clone()))?;
+            registry.register(Box::new(requests_total.clone()))?;
+            registry.register(Box::new(request_latency.clone()))?;
+            registry.register(Box::new(dht_queries_total.clone()))?;
+            registry.register(Box::new(gossip_messages_total.clone()))?;
+            registry.register(Box::new(chunks_served_total.clone()))?;
+
+            Ok(Self {
+                registry: Arc::new(registry),
+                connected_peers,
+                requests_total,
+                request_latency,
+                dht_queries_total,
+                gossip_messages_total,
+                chunks_served_total,
+            })
+        }
+
+        pub fn registry(&self) -> Arc<Registry> {
+            self.registry.clone()
+        }
+
+        pub fn peer_connected(&self) {
+            self.connected_peers.inc();
+        }
+
+        pub fn peer_disconnected(&self) {
+            self.connected_peers.dec();
+        }
+
+        /// Record the outcome (and, on success, the round-trip latency) of
+        /// an outbound `request_response` request.
+        pub fn record_request_outcome(&self, success: bool, latency: Option<Duration>) {
+            let outcome = if success { "success" } else { "failure" };
+            self.requests_total.with_label_values(&[outcome]).inc();
+            if let Some(latency) = latency {
+                self.request_latency.observe(latency.as_secs_f64());
+            }
+        }
+
+        /// Record a Kademlia query outcome. `query` is e.g. `"get_providers"`
+        /// or `"bootstrap"`.
+        pub fn record_dht_outcome(&self, query: &str, success: bool) {
+            let outcome = if success { "success" } else { "failure" };
+            self.dht_queries_total.with_label_values(&[query, outcome]).inc();
+        }
+
+        /// Record a received gossipsub message's validation outcome for
+        /// `topic` (one of the short topic names, not the hashed topic).
+        pub fn record_gossip_message(&self, topic: &str, accepted: bool) {
+            let outcome = if accepted { "accepted" } else { "rejected" };
+            self.gossip_messages_total.with_label_values(&[topic, outcome]).inc();
+        }
+
+        /// Record chunks (and their bytes) served out of `handle_request`'s
+        /// `GetChunks` arm.
+        pub fn record_chunks_served(&self, chunks: u64, bytes: u64) {
+            self.chunks_served_total.with_label_values(&["chunks"]).inc_by(chunks);
+            self.chunks_served_total.with_label_values(&["bytes"]).inc_by(bytes);
+        }
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+mod disabled {
+    use std::time::Duration;
+
+    use anyhow::Result;
+
+    /// No-op stand-in for the `metrics`-feature-gated Prometheus
+    /// collectors; every method is a zero-cost inline no-op so nodes that
+    /// don't care about observability pay nothing for it.
+    #[derive(Clone, Default)]
+    pub struct NodeMetrics;
+
+    impl NodeMetrics {
+        pub fn new() -> Result<Self> {
+            Ok(Self)
+        }
+
+        pub fn peer_connected(&self) {}
+        pub fn peer_disconnected(&self) {}
+        pub fn record_request_outcome(&self, _success: bool, _latency: Option<Duration>) {}
+        pub fn record_dht_outcome(&self, _query: &str, _success: bool) {}
+        pub fn record_gossip_message(&self, _topic: &str, _accepted: bool) {}
+        pub fn record_chunks_served(&self, _chunks: u64, _bytes: u64) {}
+    }
+}
@@ -6,10 +6,44 @@ mod behaviour;
 pub mod bootstrap;
 pub mod replication;
 pub mod health;
+pub mod session;
+pub mod trust;
+pub mod peer_exchange;
+pub mod nat;
+pub mod bitswap;
+pub mod access;
+pub mod membership;
+pub mod discovery;
+pub mod layout;
+pub mod merkle_diff;
+pub mod repair;
+pub mod resync;
+pub mod replication_session;
+pub mod metrics;
+pub mod gossip_mesh;
+pub mod secure_id;
+pub mod pairing;
 
 pub use node::{GrabNetwork, NetworkEvent, DEFAULT_BOOTSTRAP_PEERS};
 pub use protocol::{GrabProtocol, GrabCodec};
 pub use behaviour::GrabBehaviour;
 pub use bootstrap::{BootstrapConfig, BootstrapNode};
-pub use replication::{ReplicationManager, ReplicationPolicy, SiteHealth, HealthStatus, ReplicationStats};
-pub use health::{HealthMonitor, HealthSummary, PeerScore, NetworkMetrics, ConnectionHealth};
+pub use replication::{ReplicationManager, ReplicationPolicy, SiteHealth, HealthStatus, ReplicationStats, CrdsFilter, VersionedRecord};
+pub use health::{HealthMonitor, HealthSummary, PeerScore, NetworkMetrics, ConnectionHealth, Capability, CapabilitySource};
+pub use session::{IdentityKeys, Session, HandshakeMessage, FrameHeader, SealedFrame, RekeyPolicy, initial_handshake_message};
+pub use trust::{TrustStore, PairedDevice, PairedDeviceStore};
+pub use pairing::PairingSession;
+pub use peer_exchange::{PeerTable, PeerEntry};
+pub use nat::{NatDetector, NatStatus, PeerTimeouts};
+pub use bitswap::{BitswapLedger, PeerLedger};
+pub use access::PeerAccessControl;
+pub use membership::{Member, MemberState, MembershipDelta, MembershipTable, resolve_dns_seed};
+pub use discovery::{DiscoveryBackend, DiscoveryConfig};
+pub use layout::{ReplicaLayout, LayoutStatus};
+pub use merkle_diff::{MerkleDiffSession, NodeReq, DiffStep};
+pub use repair::{RepairService, RepairType};
+pub use resync::ResyncService;
+pub use replication_session::{ReplicationSession, ReplicationSessionManager, SessionPhase, SyncProgress, SyncDirection};
+pub use metrics::NodeMetrics;
+pub use gossip_mesh::{GossipMeshTracker, MeshPeerInfo, MESH_TARGET_DEGREE};
+pub use secure_id::{generate_secure_id, verify_secure_id, peer_claimed_id};
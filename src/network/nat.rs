@@ -0,0 +1,151 @@
+//! NAT detection and adaptive connection timeouts
+//!
+//! Peers report the external address they observed for us (via libp2p's
+//! `identify` protocol); comparing that against our own listen addresses
+//! tells us whether we're reachable directly or sitting behind a NAT.
+//! Peers on opposite sides of that divide need different keepalive
+//! cadences, so timeouts are negotiated per-connection rather than fixed.
+
+/// Our reachability as inferred from observed-address comparisons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NatStatus {
+    /// Not enough observations yet.
+    Unknown,
+    /// Our listen address matches what peers observe: directly reachable.
+    Public,
+    /// Peers observe a different address than we listen on: behind a NAT
+    /// or other address-translating middlebox.
+    BehindNat,
+}
+
+/// Tracks observed-vs-local address agreement to classify our NAT status.
+#[derive(Debug, Default)]
+pub struct NatDetector {
+    local_addresses: Vec<String>,
+    agreeing_observations: usize,
+    disagreeing_observations: usize,
+}
+
+impl NatDetector {
+    pub fn new(local_addresses: Vec<String>) -> Self {
+        Self {
+            local_addresses,
+            agreeing_observations: 0,
+            disagreeing_observations: 0,
+        }
+    }
+
+    /// Record an externally observed address for us, as reported by a peer.
+    pub fn observe(&mut self, observed_addr: &str) {
+        let host_matches = self
+            .local_addresses
+            .iter()
+            .any(|local| address_host(local) == address_host(observed_addr));
+
+        if host_matches {
+            self.agreeing_observations += 1;
+        } else {
+            self.disagreeing_observations += 1;
+        }
+    }
+
+    /// Current best guess at our NAT status.
+    pub fn status(&self) -> NatStatus {
+        if self.agreeing_observations == 0 && self.disagreeing_observations == 0 {
+            NatStatus::Unknown
+        } else if self.disagreeing_observations > self.agreeing_observations {
+            NatStatus::BehindNat
+        } else {
+            NatStatus::Public
+        }
+    }
+}
+
+/// Extract the host component (e.g. `/ip4/1.2.3.4`) from a multiaddr-like
+/// string, ignoring port and transport suffixes, so that address
+/// comparisons are insensitive to the peer's choice of source port.
+fn address_host(addr: &str) -> &str {
+    let mut parts = addr.splitn(4, '/');
+    let _leading_empty = parts.next();
+    let proto = parts.next().unwrap_or("");
+    let host = parts.next().unwrap_or("");
+    let end = addr.len().min(1 + proto.len() + 1 + host.len());
+    &addr[..end.max(1)]
+}
+
+/// Per-peer keepalive/timeout settings, negotiated based on NAT status:
+/// a peer behind a NAT needs more frequent keepalives to hold its mapping
+/// open, so the tighter of the two sides' intervals wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerTimeouts {
+    pub peer_timeout_secs: u64,
+    pub keepalive_secs: u64,
+}
+
+impl PeerTimeouts {
+    /// Default timeouts for a node with no NAT constraints.
+    pub fn default_public() -> Self {
+        Self {
+            peer_timeout_secs: 120,
+            keepalive_secs: 60,
+        }
+    }
+
+    /// Tighter timeouts appropriate for a node known to be behind a NAT.
+    pub fn default_behind_nat() -> Self {
+        Self {
+            peer_timeout_secs: 60,
+            keepalive_secs: 20,
+        }
+    }
+
+    /// Pick timeouts for our side of a connection given our own NAT status;
+    /// combine with the remote side's via [`Self::negotiate`].
+    pub fn for_status(status: NatStatus) -> Self {
+        match status {
+            NatStatus::BehindNat => Self::default_behind_nat(),
+            NatStatus::Public | NatStatus::Unknown => Self::default_public(),
+        }
+    }
+
+    /// Negotiate the effective timeouts for a connection by taking the
+    /// stricter (smaller) value from each side, since either peer dropping
+    /// the connection for inactivity ends the session regardless of what
+    /// the other side assumed.
+    pub fn negotiate(local: PeerTimeouts, remote: PeerTimeouts) -> PeerTimeouts {
+        PeerTimeouts {
+            peer_timeout_secs: local.peer_timeout_secs.min(remote.peer_timeout_secs),
+            keepalive_secs: local.keepalive_secs.min(remote.keepalive_secs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nat_detection_agrees() {
+        let mut detector = NatDetector::new(vec!["/ip4/1.2.3.4/tcp/4001".to_string()]);
+        assert_eq!(detector.status(), NatStatus::Unknown);
+
+        detector.observe("/ip4/1.2.3.4/tcp/55000");
+        assert_eq!(detector.status(), NatStatus::Public);
+    }
+
+    #[test]
+    fn test_nat_detection_disagrees() {
+        let mut detector = NatDetector::new(vec!["/ip4/10.0.0.5/tcp/4001".to_string()]);
+        detector.observe("/ip4/203.0.113.9/tcp/51000");
+        detector.observe("/ip4/203.0.113.9/tcp/51000");
+        assert_eq!(detector.status(), NatStatus::BehindNat);
+    }
+
+    #[test]
+    fn test_negotiate_picks_stricter_values() {
+        let local = PeerTimeouts::default_public();
+        let remote = PeerTimeouts::default_behind_nat();
+        let negotiated = PeerTimeouts::negotiate(local, remote);
+        assert_eq!(negotiated, remote);
+    }
+}
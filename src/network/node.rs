@@ -1,26 +1,43 @@
 //! GrabNet P2P node implementation
 
 use std::sync::Arc;
-use std::collections::{HashMap, HashSet};
-use std::time::Duration;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 use anyhow::{Result, anyhow};
 use futures::StreamExt;
 use libp2p::{
-    identity, noise, tcp, yamux,
-    Multiaddr, PeerId, Swarm, SwarmBuilder,
+    identity, noise, tcp, yamux, quic, dns, websocket,
+    core::{muxing::StreamMuxerBox, transport::{Boxed, OrTransport}, upgrade::Version},
+    multiaddr::Protocol,
+    Multiaddr, PeerId, Swarm, SwarmBuilder, Transport,
     swarm::SwarmEvent,
     request_response::{self},
     kad::{self, QueryResult, QueryId},
-    gossipsub::{self, IdentTopic},
+    gossipsub::{self, IdentTopic, MessageAcceptance},
     mdns, identify,
 };
 use parking_lot::RwLock;
-use tokio::sync::{mpsc, oneshot, broadcast};
+use tokio::sync::{mpsc, oneshot, broadcast, watch};
+use zeroize::Zeroizing;
 
+use super::access::PeerAccessControl;
+use super::secure_id::generate_secure_id;
 use super::behaviour::{GrabBehaviour, GrabBehaviourEvent};
-use crate::types::{Config, SiteId, WebBundle, GrabRequest, GrabResponse, PeerRecord, ChunkId};
-use crate::storage::{ChunkStore, BundleStore};
-use crate::crypto::SiteIdExt;
+use super::bitswap::BitswapLedger;
+use super::gossip_mesh::{GossipMeshTracker, MeshPeerInfo};
+use super::health::HealthMonitor;
+use super::membership::{MembershipTable, Member, MemberState};
+use super::discovery::DiscoveryConfig;
+use super::layout::{ReplicaLayout, LayoutStatus};
+use super::pairing::PairingSession;
+use super::replication::{ReplicationManager, VersionedRecord};
+use super::replication_session::{ReplicationSessionManager, SyncProgress, SyncDirection};
+use super::session::{IdentityKeys, RekeyPolicy, Session};
+use super::metrics::NodeMetrics;
+use super::trust::{PairedDevice, PairedDeviceStore, TrustStore};
+use crate::types::{Config, SiteId, WebBundle, GrabRequest, GrabResponse, PeerRecord, ChunkId, NameRecord, NodeInformation, MerkleProof, SiteAnnouncement, DeltaSyncPreamble};
+use crate::storage::{ChunkStore, BundleStore, NameStore, NameChain, NameBlock};
+use crate::crypto::{hash, SiteIdExt, AppendMerkleTree, MerkleMountainRange, verify_bundle};
 
 /// Default bootstrap peers for the GrabNet network
 pub const DEFAULT_BOOTSTRAP_PEERS: &[&str] = &[
@@ -31,8 +48,32 @@ pub const DEFAULT_BOOTSTRAP_PEERS: &[&str] = &[
 /// Gossipsub topic for site announcements
 const SITES_TOPIC: &str = "grabnet/sites/1.0.0";
 
-/// Gossipsub topic for updates
-const UPDATES_TOPIC: &str = "grabnet/updates/1.0.0";
+/// Gossipsub topic for replication state (host sets and site health)
+const REPLICATION_TOPIC: &str = "grabnet/replication/1.0.0";
+
+/// Gossipsub topic for name-claim chain blocks (see `storage::name_chain`)
+const NAME_CHAIN_TOPIC: &str = "grabnet/name-chain/1.0.0";
+
+/// How many peripheral (non-mesh) peers to sample per topic each time
+/// `gossip_mesh_interval` ticks.
+const GOSSIP_MESH_SAMPLE_SIZE: usize = 3;
+
+/// A peer must have sent us at least this many bytes before we start
+/// judging their send/receive ratio — avoids throttling a peer early in
+/// the relationship just because they haven't reciprocated yet.
+const MIN_BYTES_FOR_RECIPROCATION_CHECK: u64 = 1024 * 1024;
+/// Above this ratio of bytes we've sent to bytes received, a peer is
+/// treated as a freeloader and throttled on `GetChunks`.
+const FREELOADER_RATIO: f64 = 4.0;
+/// Chunks served per `GetChunks` request to a freeloading peer.
+const THROTTLED_CHUNKS_PER_REQUEST: usize = 1;
+/// How many other members are asked to indirectly probe a peer once our
+/// own direct `Ping` to it goes unanswered, before declaring it `Suspect`.
+const INDIRECT_PROBE_FANOUT: usize = 2;
+
+/// Mirror capacity assumed for a remote host in `ReplicaLayout`, since the
+/// wire protocol doesn't (yet) let a peer advertise its own limit.
+const DEFAULT_HOST_CAPACITY: usize = 50;
 
 /// Message from main thread to swarm event loop
 #[derive(Debug)]
@@ -43,10 +84,58 @@ enum SwarmCommand {
     FindSite(SiteId, oneshot::Sender<Vec<PeerRecord>>),
     GetPeers(oneshot::Sender<Vec<PeerId>>),
     GetAddresses(oneshot::Sender<Vec<String>>),
+    /// Swarm-wide bitswap-style fetch: broadcast `WantHave` for these
+    /// chunks to every connected peer and stream back verified blocks as
+    /// they arrive, from whichever peer answers first.
+    Want(Vec<ChunkId>, futures::channel::mpsc::UnboundedSender<(ChunkId, Vec<u8>)>),
+    /// Seed a not-currently-connected peer's last-known addresses into the
+    /// Kademlia routing table and dial it, so a pairing handshake or
+    /// control message can reach it without waiting for it to dial us
+    /// first. Unlike `AddReservedPeer`, this doesn't keep the peer pinned.
+    DialPeerAtAddresses(PeerId, Vec<Multiaddr>),
+    /// Always dial and keep connected; seeded into the Kademlia routing
+    /// table immediately and exempt from `reserved_only`.
+    AddReservedPeer(PeerId, Multiaddr),
+    RemoveReservedPeer(PeerId),
+    /// When `true`, deny connections to/from any peer that isn't reserved.
+    SetReservedOnly(bool),
+    BanPeer(PeerId, Duration),
+    UnbanPeer(PeerId),
     Bootstrap,
     Shutdown,
 }
 
+/// Per-[`SwarmCommand::Want`] session state. Broadcasting `WantHave` means
+/// several peers can confirm the same chunk; `have_peers` queues every
+/// peer that did, in answer order, so if the one we ask for the actual
+/// bytes fails to deliver we fall back to the next without re-broadcasting.
+struct WantSession {
+    /// Chunk IDs not yet verified and handed back to the caller.
+    remaining: HashSet<ChunkId>,
+    /// Chunks currently out for delivery (a `GetChunks` is in flight for
+    /// them), so a second `Have` reply for the same chunk doesn't trigger
+    /// a redundant request.
+    requested: HashSet<ChunkId>,
+    /// Peers who've confirmed (via `Have`) they hold a given chunk and
+    /// haven't been tried yet.
+    have_peers: HashMap<ChunkId, VecDeque<PeerId>>,
+    /// Where verified `(ChunkId, data)` pairs are sent as they arrive.
+    /// Dropping this ends the caller's stream.
+    result_tx: futures::channel::mpsc::UnboundedSender<(ChunkId, Vec<u8>)>,
+    /// Outstanding `WantHave`/`GetChunks` requests belonging to this
+    /// session. Once this reaches zero and no chunk has a queued
+    /// candidate left to try, the session is exhausted.
+    in_flight: usize,
+}
+
+impl WantSession {
+    /// Whether every chunk has either arrived or run out of peers to ask.
+    fn is_done(&self) -> bool {
+        self.remaining.is_empty()
+            || (self.in_flight == 0 && self.have_peers.values().all(|queue| queue.is_empty()))
+    }
+}
+
 /// Network event published to subscribers
 #[derive(Debug, Clone)]
 pub enum NetworkEvent {
@@ -55,11 +144,28 @@ pub enum NetworkEvent {
     /// A peer disconnected
     PeerDisconnected(PeerId),
     /// Received a site announcement
-    SiteAnnounced { site_id: SiteId, peer_id: PeerId, revision: u64 },
+    SiteAnnounced { site_id: SiteId, peer_id: PeerId, revision: u64, zone: Option<String> },
     /// Received a site update
     SiteUpdated { site_id: SiteId, revision: u64 },
     /// Bootstrap complete
     BootstrapComplete { peers: usize },
+    /// A verified chunk was received from a peer and stored locally
+    ChunkReceived { chunk_id: ChunkId, peer_id: PeerId },
+    /// A peer sent us a chunk whose contents didn't hash to the ID they
+    /// claimed; the chunk was dropped rather than stored
+    ChunkVerificationFailed { chunk_id: ChunkId, peer_id: PeerId },
+    /// A new member joined the gossip membership view
+    PeerJoined { peer_id: String },
+    /// A member was declared dead, either by gossip or by our own failure detector
+    PeerLeft { peer_id: String },
+    /// A peer's reputation was docked, either for a rejected gossip message
+    /// or a failed `request_response` outbound attempt. `penalty` is the
+    /// (negative) reputation delta applied.
+    PeerMisbehaved { peer_id: PeerId, penalty: i32 },
+    /// A peer reported observing us at a new address via `identify`,
+    /// now added to the swarm as an external address and advertised in
+    /// `PeerRecord.addresses`
+    ExternalAddressConfirmed(Multiaddr),
 }
 
 /// GrabNet P2P network node
@@ -69,10 +175,77 @@ pub struct GrabNetwork {
     event_tx: broadcast::Sender<NetworkEvent>,
     chunk_store: Arc<ChunkStore>,
     bundle_store: Arc<BundleStore>,
+    name_store: Arc<NameStore>,
+    /// Append-only, proof-of-work-gated name-claim chain, gossiped over
+    /// `NAME_CHAIN_TOPIC` as an alternative to the request/response-based
+    /// `name_store` (see `storage::name_chain` for why both exist)
+    name_chain: Arc<NameChain>,
     /// Track which sites we're announcing
     announced_sites: Arc<RwLock<HashMap<SiteId, u64>>>,
     /// Connected peers
     connected_peers: Arc<RwLock<HashSet<PeerId>>>,
+    /// Multiaddrs the swarm is actually bound to, tracked from
+    /// `SwarmEvent::NewListenAddr`/`ExpiredListenAddr`
+    listen_addrs: Arc<RwLock<HashSet<Multiaddr>>>,
+    /// Addresses other peers have reported observing us at, via
+    /// `identify::Event::Received`; these are what's actually reachable
+    /// from the outside, as opposed to `listen_addrs`' local bind addresses
+    external_addrs: Arc<RwLock<HashSet<Multiaddr>>>,
+    /// Our own BEP-42-style secure ID (see `secure_id`), minted via
+    /// `generate_secure_id` against the first IPv4 address in
+    /// `external_addrs`. `None` until an external address has been
+    /// confirmed, or if it's IPv6/relayed and has no IPv4 component.
+    local_secure_id: Arc<RwLock<Option<[u8; 20]>>>,
+    /// Per-peer bitswap exchange ledger and want-lists
+    bitswap: Arc<RwLock<BitswapLedger>>,
+    /// Gossip membership view
+    membership: Arc<RwLock<MembershipTable>>,
+    /// CRDT of replication state (host sets, site health) gossiped over
+    /// `REPLICATION_TOPIC`
+    replication: Arc<ReplicationManager>,
+    /// Peer-reputation table: decremented on rejected gossip messages and
+    /// failed outbound requests, consulted by `fetch_site` to avoid
+    /// low-reputation hosts
+    health: Arc<HealthMonitor>,
+    /// Reserved and banned peer sets, enforced as connection gating in
+    /// `run_swarm`
+    access: Arc<RwLock<PeerAccessControl>>,
+    /// In-flight `push_update` reconciliation sessions, one per
+    /// `(PeerId, SiteId)` pair currently being brought up to date; see
+    /// `sync_revision`
+    replication_sessions: Arc<RwLock<ReplicationSessionManager>>,
+    /// Latest delta-sync transfer progress, for `Grab::sync_progress()`.
+    /// A `watch` channel rather than `event_tx`'s `broadcast` since a
+    /// progress bar only ever wants the newest value.
+    sync_progress_tx: watch::Sender<SyncProgress>,
+    /// Prometheus collectors for swarm/DHT/request-response activity; a
+    /// zero-cost no-op unless built with the `metrics` feature. See
+    /// `metrics_registry`.
+    metrics: Arc<NodeMetrics>,
+    /// Per-topic gossipsub mesh mirror, populated as site-revision
+    /// announcements arrive over `sites_topic`. Exists purely for
+    /// visibility (see `gossip_mesh_peers`) and to drive IHAVE/IWANT-style
+    /// gossip to peripheral peers in `run_swarm`.
+    gossip_mesh: Arc<GossipMeshTracker>,
+    /// This node's long-lived pairing identity (see `network::pairing`),
+    /// derived from the `"node-identity"` key in `storage::KeyStore`.
+    identity_keys: Arc<IdentityKeys>,
+    identity_private: Arc<Zeroizing<[u8; 32]>>,
+    /// Human-readable name presented during pairing (`Config.network.node_name`)
+    node_name: String,
+    /// Peers whose identity we've confirmed via `pair_with`/`confirm_pairing`
+    trust_store: Arc<RwLock<TrustStore>>,
+    /// Paired peers' last-presented `NodeInformation`, for the dashboard
+    paired_devices: Arc<RwLock<PairedDeviceStore>>,
+    /// Pairing handshakes awaiting out-of-band code confirmation, keyed by
+    /// the peer we're mid-handshake with (see `pair_with`/`confirm_pairing`)
+    pending_pairings: Arc<RwLock<HashMap<PeerId, PairingSession>>>,
+    /// Encrypted tunnels established by `confirm_pairing`, reused by
+    /// `send_control_message` for later control traffic
+    control_sessions: Arc<RwLock<HashMap<PeerId, Session>>>,
+    /// Zone-spread replica placement, fed a host's declared zone whenever
+    /// its `SiteAnnouncement` arrives; see `set_replication`/`layout_status`.
+    layout: Arc<ReplicaLayout>,
     /// Background task handle
     _task: tokio::task::JoinHandle<()>,
 }
@@ -83,6 +256,12 @@ impl GrabNetwork {
         config: &Config,
         chunk_store: Arc<ChunkStore>,
         bundle_store: Arc<BundleStore>,
+        name_store: Arc<NameStore>,
+        name_chain: Arc<NameChain>,
+        identity_keys: Arc<IdentityKeys>,
+        identity_private: Arc<Zeroizing<[u8; 32]>>,
+        trust_store: Arc<RwLock<TrustStore>>,
+        paired_devices: Arc<RwLock<PairedDeviceStore>>,
     ) -> Result<Self> {
         // Generate identity
         let local_key = identity::Keypair::generate_ed25519();
@@ -90,16 +269,17 @@ impl GrabNetwork {
         
         tracing::info!("Local peer ID: {}", local_peer_id);
 
-        // Build swarm
+        // Build swarm. TCP is always present; QUIC and WebSocket are
+        // composed in on top of it (gated by config) so `dns4`/`dnsaddr`
+        // bootstrap and listen addresses resolve regardless of which
+        // transport they end up naming.
+        let enable_quic = config.network.enable_quic;
+        let enable_websocket = config.network.enable_websocket;
         let swarm = SwarmBuilder::with_existing_identity(local_key.clone())
             .with_tokio()
-            .with_tcp(
-                tcp::Config::default(),
-                noise::Config::new,
-                yamux::Config::default,
-            )?
+            .with_other_transport(|key| build_transport(key, enable_quic, enable_websocket))?
             .with_behaviour(|key| {
-                GrabBehaviour::new(local_peer_id, key.public())
+                GrabBehaviour::new(local_peer_id, key.clone())
             })?
             .with_swarm_config(|cfg| {
                 cfg.with_idle_connection_timeout(Duration::from_secs(120))
@@ -115,16 +295,90 @@ impl GrabNetwork {
         // Clone stores for the event loop
         let chunk_store_clone = chunk_store.clone();
         let bundle_store_clone = bundle_store.clone();
+        let name_store_clone = name_store.clone();
+        let name_chain_clone = name_chain.clone();
         let announced_sites = Arc::new(RwLock::new(HashMap::new()));
         let announced_sites_clone = announced_sites.clone();
         let connected_peers = Arc::new(RwLock::new(HashSet::new()));
         let connected_peers_clone = connected_peers.clone();
+        let listen_addr_cache = Arc::new(RwLock::new(HashSet::new()));
+        let listen_addr_cache_clone = listen_addr_cache.clone();
+        let external_addrs = Arc::new(RwLock::new(HashSet::new()));
+        let external_addrs_clone = external_addrs.clone();
+        let local_secure_id = Arc::new(RwLock::new(None));
+        let local_secure_id_clone = local_secure_id.clone();
+        let bitswap = Arc::new(RwLock::new(BitswapLedger::new()));
+        let bitswap_clone = bitswap.clone();
+        let membership = Arc::new(RwLock::new(MembershipTable::new()));
+        let membership_clone = membership.clone();
+        let replication = Arc::new(ReplicationManager::new());
+        let replication_clone = replication.clone();
+        let health = Arc::new(HealthMonitor::new());
+        let health_clone = health.clone();
+        let gossip_mesh = Arc::new(GossipMeshTracker::new());
+        let gossip_mesh_clone = gossip_mesh.clone();
+
+        let node_name = config.network.node_name.clone().unwrap_or_else(|| local_peer_id.to_string());
+        let node_zone = config.network.zone.clone();
+        let identity_keys_clone = identity_keys.clone();
+        let identity_private_clone = identity_private.clone();
+        let node_name_clone = node_name.clone();
+        let trust_store_clone = trust_store.clone();
+        let paired_devices_clone = paired_devices.clone();
+        let pending_pairings = Arc::new(RwLock::new(HashMap::new()));
+        let pending_pairings_clone = pending_pairings.clone();
+        let control_sessions = Arc::new(RwLock::new(HashMap::new()));
+        let control_sessions_clone = control_sessions.clone();
+
+        let mut access = PeerAccessControl::new();
+        for addr in &config.network.reserved_peers {
+            match parse_reserved_peer(addr) {
+                Some((peer_id, multiaddr)) => access.add_reserved(peer_id, multiaddr),
+                None => tracing::warn!("Reserved peer address missing /p2p/<peer_id>, skipping: {}", addr),
+            }
+        }
+        let access = Arc::new(RwLock::new(access));
+        let access_clone = access.clone();
+
+        let replication_sessions = Arc::new(RwLock::new(ReplicationSessionManager::new()));
+
+        let layout = Arc::new(ReplicaLayout::new());
+        let layout_clone = layout.clone();
+
+        let (sync_progress_tx, _) = watch::channel(SyncProgress::default());
+        let sync_progress_tx_clone = sync_progress_tx.clone();
+
+        let metrics = Arc::new(NodeMetrics::new()?);
+        let metrics_clone = metrics.clone();
+
         let event_tx_clone = event_tx.clone();
 
         // Start event loop
         let listen_addrs = config.network.listen_addresses.clone();
-        let bootstrap_peers = config.network.bootstrap_peers.clone();
-        
+        let mut bootstrap_peers = config.network.bootstrap_peers.clone();
+
+        // A DNS seed gives operators a stable hostname to point new nodes
+        // at instead of hardcoding addresses that may move.
+        if let Some(seed_host) = config.network.dns_seed.clone() {
+            let port = config.network.port;
+            match super::membership::resolve_dns_seed(&seed_host, port).await {
+                Ok(seed_addrs) => bootstrap_peers.extend(seed_addrs),
+                Err(e) => tracing::warn!("Failed to resolve DNS seed {}: {}", seed_host, e),
+            }
+        }
+
+        // A discovery backend is resolved once here (same as `dns_seed`
+        // above) and handed to `run_swarm`, which re-resolves it on a
+        // timer to pick up replicas that scale up after startup.
+        let discovery = config.network.discovery.clone();
+        if let Some(discovery) = &discovery {
+            match discovery.backend.resolve(config.network.port).await {
+                Ok(addrs) => bootstrap_peers.extend(addrs),
+                Err(e) => tracing::warn!("Discovery backend failed to resolve bootstrap addresses: {}", e),
+            }
+        }
+        let discovery_port = config.network.port;
+
         let task = tokio::spawn(async move {
             run_swarm(
                 swarm,
@@ -133,9 +387,33 @@ impl GrabNetwork {
                 bootstrap_peers,
                 chunk_store_clone,
                 bundle_store_clone,
+                name_store_clone,
+                name_chain_clone,
                 announced_sites_clone,
                 connected_peers_clone,
+                listen_addr_cache_clone,
+                external_addrs_clone,
+                local_secure_id_clone,
+                bitswap_clone,
+                membership_clone,
+                replication_clone,
+                health_clone,
+                access_clone,
                 event_tx_clone,
+                metrics_clone,
+                gossip_mesh_clone,
+                identity_keys_clone,
+                identity_private_clone,
+                node_name_clone,
+                trust_store_clone,
+                paired_devices_clone,
+                pending_pairings_clone,
+                control_sessions_clone,
+                sync_progress_tx_clone,
+                discovery,
+                discovery_port,
+                layout_clone,
+                node_zone,
             ).await;
         });
 
@@ -145,12 +423,42 @@ impl GrabNetwork {
             event_tx,
             chunk_store,
             bundle_store,
+            name_store,
+            name_chain,
             announced_sites,
             connected_peers,
+            listen_addrs: listen_addr_cache,
+            external_addrs,
+            local_secure_id,
+            bitswap,
+            membership,
+            replication,
+            health,
+            access,
+            replication_sessions,
+            sync_progress_tx,
+            metrics,
+            gossip_mesh,
+            identity_keys,
+            identity_private,
+            node_name,
+            trust_store,
+            paired_devices,
+            pending_pairings,
+            control_sessions,
+            layout,
             _task: task,
         })
     }
 
+    /// Prometheus registry backing this node's metrics. Only present when
+    /// built with the `metrics` feature; the caller is expected to mount it
+    /// behind its own HTTP scrape endpoint.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_registry(&self) -> Arc<prometheus::Registry> {
+        self.metrics.registry()
+    }
+
     /// Start the network (connects to bootstrap peers)
     pub async fn start(&self) -> Result<()> {
         self.command_tx.send(SwarmCommand::Bootstrap).await?;
@@ -178,9 +486,23 @@ impl GrabNetwork {
         self.connected_peers.read().iter().cloned().collect()
     }
 
-    /// Get listen addresses
+    /// Multiaddrs the swarm is actually bound to
     pub fn listen_addresses(&self) -> Vec<String> {
-        vec![]
+        self.listen_addrs.read().iter().map(|a| a.to_string()).collect()
+    }
+
+    /// Addresses other peers have reported observing us at (via `identify`),
+    /// i.e. what's actually reachable from outside our own network, as
+    /// opposed to the local bind addresses in `listen_addresses()`
+    pub fn external_addresses(&self) -> Vec<String> {
+        self.external_addrs.read().iter().map(|a| a.to_string()).collect()
+    }
+
+    /// Our own BEP-42-style secure ID (see `secure_id`), minted once an
+    /// external IPv4 address has been confirmed via `identify`. `None`
+    /// before that happens, or if every confirmed address is IPv6/relayed.
+    pub fn local_secure_id(&self) -> Option<[u8; 20]> {
+        *self.local_secure_id.read()
     }
 
     /// Subscribe to network events
@@ -188,6 +510,57 @@ impl GrabNetwork {
         self.event_tx.subscribe()
     }
 
+    /// Gossipsub topic names we're tracking mesh state for, i.e. that
+    /// have seen at least one site-revision announcement.
+    pub fn gossip_mesh_topics(&self) -> Vec<String> {
+        self.gossip_mesh.topics()
+    }
+
+    /// Snapshot of the SWIM gossip membership table: every peer we've
+    /// heard of, its last-known addresses, and whether it currently looks
+    /// `Alive`, `Suspect`, or `Dead`. Used by `NetworkStatus` and the
+    /// dashboard to show which peers are likely still hosting a site.
+    pub fn members(&self) -> Vec<Member> {
+        self.membership.read().members()
+    }
+
+    /// Current mesh peers for `topic` and the revision/last-seen we last
+    /// heard from each, for the peer-viewer dashboard.
+    pub fn gossip_mesh_peers(&self, topic: &str) -> Vec<MeshPeerInfo> {
+        self.gossip_mesh.mesh_peers(topic)
+    }
+
+    /// Set the target replica count for a site, spread across as many
+    /// distinct zones as the known hosts allow (see `network::layout`).
+    pub fn set_replication(&self, site_id: SiteId, factor: usize) {
+        self.layout.set_replication(site_id, factor);
+    }
+
+    /// Placement status for a single site: target replica count versus
+    /// which zones/peers currently hold it.
+    pub fn layout_status(&self, site_id: &SiteId) -> LayoutStatus {
+        self.layout.status(site_id)
+    }
+
+    /// Placement status for every site with a configured target.
+    pub fn layout_status_all(&self) -> Vec<LayoutStatus> {
+        self.layout.status_all()
+    }
+
+    /// The replica-placement layout itself, for
+    /// [`super::resync::ResyncService`] to scan for under-replicated
+    /// sites rather than just reading point-in-time status snapshots.
+    pub fn layout_handle(&self) -> Arc<ReplicaLayout> {
+        self.layout.clone()
+    }
+
+    /// Whether `peer_id`'s secure ID (see `secure_id`) checked out against
+    /// the IPv4 address it connected from. `false` for peers we haven't
+    /// been able to check yet, not just ones that failed the check.
+    pub fn is_peer_verified(&self, peer_id: &PeerId) -> bool {
+        self.access.read().is_verified(peer_id)
+    }
+
     /// Connect to a peer
     pub async fn dial(&self, addr: &str) -> Result<()> {
         let multiaddr: Multiaddr = addr.parse()?;
@@ -195,6 +568,142 @@ impl GrabNetwork {
         Ok(())
     }
 
+    /// Mark a peer as reserved: always worth dialing and keeping
+    /// connected, exempt from `reserved_only`, and seeded into the
+    /// Kademlia routing table right away.
+    pub async fn add_reserved_peer(&self, peer_id: PeerId, addr: Multiaddr) -> Result<()> {
+        self.command_tx.send(SwarmCommand::AddReservedPeer(peer_id, addr)).await?;
+        Ok(())
+    }
+
+    /// Stop treating a peer as reserved. Does not disconnect it.
+    pub async fn remove_reserved_peer(&self, peer_id: PeerId) -> Result<()> {
+        self.command_tx.send(SwarmCommand::RemoveReservedPeer(peer_id)).await?;
+        Ok(())
+    }
+
+    /// When `on`, reject all non-reserved inbound and outbound connections
+    /// (existing non-reserved connections are also dropped).
+    pub async fn set_reserved_only(&self, on: bool) -> Result<()> {
+        self.command_tx.send(SwarmCommand::SetReservedOnly(on)).await?;
+        Ok(())
+    }
+
+    /// Ban a peer for `duration`, closing any current connection to it and
+    /// refusing new ones until the ban expires.
+    pub async fn ban_peer(&self, peer_id: PeerId, duration: Duration) -> Result<()> {
+        self.command_tx.send(SwarmCommand::BanPeer(peer_id, duration)).await?;
+        Ok(())
+    }
+
+    /// Lift a ban early.
+    pub async fn unban_peer(&self, peer_id: PeerId) -> Result<()> {
+        self.command_tx.send(SwarmCommand::UnbanPeer(peer_id)).await?;
+        Ok(())
+    }
+
+    /// Dial a not-currently-connected peer via its last-known addresses and
+    /// run the initiator side of a [`super::pairing`] handshake. Returns the
+    /// six-digit code to compare with the peer out-of-band; once a human
+    /// confirms it matches on both ends, call [`Self::confirm_pairing`].
+    pub async fn pair_with(&self, peer_id: PeerId, addresses: Vec<Multiaddr>) -> Result<u32> {
+        self.command_tx.send(SwarmCommand::DialPeerAtAddresses(peer_id, addresses)).await?;
+
+        let offer = super::session::initial_handshake_message(&self.identity_keys);
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(peer_id, GrabRequest::PairingOffer { offer }, tx)).await?;
+        let response = match rx.await?? {
+            GrabResponse::PairingResponse { response } => response,
+            GrabResponse::Error { message } => return Err(anyhow!("pairing offer rejected: {}", message)),
+            other => return Err(anyhow!("unexpected response to pairing offer: {:?}", other)),
+        };
+
+        let (session, _) = PairingSession::initiate(&self.identity_keys, &self.identity_private, response, RekeyPolicy::default())?;
+        let code = session.confirmation_code();
+        self.pending_pairings.write().insert(peer_id, session);
+        Ok(code)
+    }
+
+    /// Finish a pairing started by [`Self::pair_with`] once the out-of-band
+    /// code has been confirmed to match: exchange signed `NodeInformation`,
+    /// add the peer's identity to the trust store, and keep the session
+    /// open for [`Self::send_control_message`]. `site_ids` is what we
+    /// advertise ourselves as publishing/hosting in our own record.
+    pub async fn confirm_pairing(&self, peer_id: PeerId, site_ids: Vec<SiteId>) -> Result<NodeInformation> {
+        let mut session = self.pending_pairings.write().remove(&peer_id)
+            .ok_or_else(|| anyhow!("no pending pairing with {}", peer_id))?;
+
+        let sealed = session.seal_node_information(
+            &self.identity_keys.ed25519_public,
+            &self.identity_private,
+            self.peer_id.to_string(),
+            self.node_name.clone(),
+            site_ids,
+        )?;
+
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(peer_id, GrabRequest::PairingConfirm { sealed }, tx)).await?;
+        let reply_sealed = match rx.await?? {
+            GrabResponse::PairingConfirmed { sealed } => sealed,
+            GrabResponse::Error { message } => return Err(anyhow!("pairing confirmation rejected: {}", message)),
+            other => return Err(anyhow!("unexpected response to pairing confirmation: {:?}", other)),
+        };
+
+        let info = session.open_node_information(&reply_sealed)?;
+
+        self.trust_store.write().add_trusted(&info.pubkey);
+        let paired_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        self.paired_devices.write().upsert(PairedDevice {
+            public_key: crate::crypto::encode_base58(&info.pubkey),
+            peer_id: info.peer_id.clone(),
+            name: info.name.clone(),
+            site_count: info.site_ids.len(),
+            addresses: vec![],
+            paired_at,
+        });
+        self.control_sessions.write().insert(peer_id, session.into_session());
+
+        Ok(info)
+    }
+
+    /// Peers we've completed pairing with, for the dashboard's "Paired
+    /// Devices" section.
+    pub fn paired_devices(&self) -> Vec<PairedDevice> {
+        self.paired_devices.read().all()
+    }
+
+    /// Subscribe to live progress of the delta-sync transfer currently
+    /// driven by `sync_revision`, if any. Yields `SyncProgress::default()`
+    /// (`SyncDirection::Idle`) when nothing is in flight.
+    pub fn sync_progress(&self) -> watch::Receiver<SyncProgress> {
+        self.sync_progress_tx.subscribe()
+    }
+
+    /// Send an application payload to an already-paired peer over its
+    /// confirmed pairing session, returning whatever it seals back.
+    pub async fn send_control_message(&self, peer_id: PeerId, payload: &[u8]) -> Result<Vec<u8>> {
+        let sealed = {
+            let mut sessions = self.control_sessions.write();
+            let session = sessions.get_mut(&peer_id).ok_or_else(|| anyhow!("not paired with {}", peer_id))?;
+            session.seal(payload)?
+        };
+
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(peer_id, GrabRequest::ControlMessage { sealed }, tx)).await?;
+        let reply_sealed = match rx.await?? {
+            GrabResponse::ControlMessage { sealed } => sealed,
+            GrabResponse::Error { message } => return Err(anyhow!("control message rejected: {}", message)),
+            other => return Err(anyhow!("unexpected response to control message: {:?}", other)),
+        };
+
+        let mut sessions = self.control_sessions.write();
+        let session = sessions.get_mut(&peer_id).ok_or_else(|| anyhow!("not paired with {}", peer_id))?;
+        session.open(&reply_sealed)
+    }
+
     /// Announce that we're hosting a site
     pub async fn announce_site(&self, site_id: &SiteId, revision: u64) -> Result<()> {
         self.announced_sites.write().insert(*site_id, revision);
@@ -214,15 +723,30 @@ impl GrabNetwork {
         }
     }
 
+    /// Current reputation for a peer, or `None` if we've never recorded
+    /// anything about them. Backed by the same [`HealthMonitor`] that
+    /// `fetch_site` consults to skip misbehaving hosts.
+    pub fn peer_score(&self, peer_id: &PeerId) -> Option<i32> {
+        self.health.get_peer_score(&peer_id.to_string()).map(|s| s.reputation)
+    }
+
     /// Fetch a site from the network
     pub async fn fetch_site(&self, site_id: &SiteId) -> Result<Option<WebBundle>> {
         let hosts = self.find_site(site_id).await?;
-        
+
         if hosts.is_empty() {
             return Ok(None);
         }
 
         for host in hosts {
+            if self.health.is_banned(&host.peer_id) {
+                tracing::debug!("Skipping banned host {} for site fetch", host.peer_id);
+                continue;
+            }
+            if self.membership.read().is_dead(&host.peer_id) {
+                tracing::debug!("Skipping host {} for site fetch: SWIM membership marked it dead", host.peer_id);
+                continue;
+            }
             if let Ok(peer_id) = host.peer_id.parse::<PeerId>() {
                 let (tx, rx) = oneshot::channel();
                 self.command_tx.send(SwarmCommand::SendRequest(
@@ -240,22 +764,114 @@ impl GrabNetwork {
         Ok(None)
     }
 
-    /// Push an update to all hosts
+    /// Resolve a human-readable name to its current `NameRecord`, checking
+    /// our own registry first and falling back to asking connected peers.
+    /// A record learned from a peer is folded into our own registry (which
+    /// verifies it and applies first-seen-publisher-wins) before being
+    /// returned, so a forged or squatted reply can't be trusted blindly.
+    pub async fn resolve_name(&self, name: &str) -> Result<Option<NameRecord>> {
+        if let Some(local) = self.name_store.resolve(name)? {
+            return Ok(Some(local));
+        }
+
+        for peer_id in self.connected_peer_ids() {
+            let (tx, rx) = oneshot::channel();
+            self.command_tx.send(SwarmCommand::SendRequest(
+                peer_id,
+                GrabRequest::ResolveName { name: name.to_string() },
+                tx,
+            )).await?;
+
+            if let Ok(Ok(GrabResponse::NameResolved { record: Some(record) })) = rx.await {
+                if self.name_store.offer(&record)? {
+                    return Ok(Some(record));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Claim or renew a name, accepting it into our own registry and then
+    /// announcing it to every connected peer. Returns how many peers
+    /// acknowledged it.
+    pub async fn announce_name(&self, record: &NameRecord) -> Result<usize> {
+        if !self.name_store.offer(record)? {
+            return Err(anyhow!("Name claim rejected (unverified, stale, or already claimed)"));
+        }
+
+        let mut announced = 0;
+        for peer_id in self.connected_peer_ids() {
+            let (tx, rx) = oneshot::channel();
+            self.command_tx.send(SwarmCommand::SendRequest(
+                peer_id,
+                GrabRequest::AnnounceName { record: record.clone() },
+                tx,
+            )).await?;
+
+            if let Ok(Ok(GrabResponse::Ack)) = rx.await {
+                announced += 1;
+            }
+        }
+
+        Ok(announced)
+    }
+
+    /// Submit a signed claim to the local name-claim chain's mempool (see
+    /// `storage::name_chain`). It's mined into a block and gossiped to
+    /// peers the next time `name_chain_interval` ticks; returns whether
+    /// the claim was accepted into the mempool.
+    pub fn submit_name_claim(&self, claim: crate::storage::NameClaim) -> Result<bool> {
+        self.name_chain.submit_claim(claim)
+    }
+
+    /// Resolve `name` against the local name-claim chain.
+    pub fn resolve_chain_name(&self, name: &str) -> Result<Option<SiteId>> {
+        self.name_chain.resolve(name)
+    }
+
+    /// Claims waiting to be mined into a block, for the dashboard's "Name
+    /// Registrations" card.
+    pub fn name_chain_pending(&self) -> Vec<crate::storage::NameClaim> {
+        self.name_chain.pending_claims()
+    }
+
+    /// How many blocks deep `name`'s confirming claim is on the chain.
+    pub fn name_chain_depth(&self, name: &str) -> Result<Option<u64>> {
+        self.name_chain.confirmed_depth(name)
+    }
+
+    /// Every name -> site_id binding confirmed on the chain so far, for
+    /// the dashboard's reverse (site_id -> name) lookup.
+    pub fn name_chain_all_resolved(&self) -> Result<Vec<(String, SiteId)>> {
+        self.name_chain.all_resolved()
+    }
+
+    /// Current name-claim chain height.
+    pub fn name_chain_height(&self) -> Result<u64> {
+        self.name_chain.height()
+    }
+
+    /// Push an update to all hosts, via [`GrabNetwork::sync_revision`] so
+    /// each host only receives the manifest and chunks it's actually
+    /// missing rather than the whole bundle.
     pub async fn push_update(&self, bundle: &WebBundle) -> Result<usize> {
         let hosts = self.find_site(&bundle.site_id).await?;
         let mut updated = 0;
 
         for host in hosts {
+            if self.membership.read().is_dead(&host.peer_id) {
+                tracing::debug!("Skipping host {} for push_update: SWIM membership marked it dead", host.peer_id);
+                continue;
+            }
             if let Ok(peer_id) = host.peer_id.parse::<PeerId>() {
-                let (tx, rx) = oneshot::channel();
-                self.command_tx.send(SwarmCommand::SendRequest(
-                    peer_id,
-                    GrabRequest::PushUpdate { bundle: Box::new(bundle.clone()) },
-                    tx,
-                )).await?;
-
-                if let Ok(Ok(GrabResponse::Ack)) = rx.await {
-                    updated += 1;
+                match self.sync_revision(&peer_id, bundle).await {
+                    Ok(became_head) => {
+                        if became_head {
+                            updated += 1;
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to sync {} revision {} to {}: {}", bundle.name, bundle.revision, peer_id, e),
                 }
             }
         }
@@ -263,7 +879,118 @@ impl GrabNetwork {
         Ok(updated)
     }
 
-    /// Get chunks from a peer
+    /// Reconcile `peer_id`'s copy of `bundle`'s site against our local
+    /// revision with a short [`super::replication_session::ReplicationSession`]
+    /// round trip: ask what revision (and which chunks) they already have
+    /// via `GetRevisionState`, then send only the manifest and the chunks
+    /// they're missing via `PushRevisionDelta` rather than the whole
+    /// bundle. Returns `true` if the peer ended up updated; `false` if it
+    /// was already caught up. Emits `NetworkEvent::SiteUpdated` on success.
+    pub async fn sync_revision(&self, peer_id: &PeerId, bundle: &WebBundle) -> Result<bool> {
+        self.replication_sessions.write().begin(*peer_id, bundle.site_id, bundle.revision);
+        self.replication_sessions.write().get_mut(peer_id, &bundle.site_id)
+            .ok_or_else(|| anyhow!("Replication session vanished"))?
+            .request_state();
+
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(
+            *peer_id,
+            GrabRequest::GetRevisionState { site_id: bundle.site_id },
+            tx,
+        )).await?;
+
+        let (have_revision, have_chunks) = match rx.await? {
+            Ok(GrabResponse::RevisionState { have_revision, have_chunks }) => (have_revision, have_chunks),
+            Ok(GrabResponse::Error { message }) => {
+                self.replication_sessions.write().remove(peer_id, &bundle.site_id);
+                return Err(anyhow!(message));
+            }
+            _ => {
+                self.replication_sessions.write().remove(peer_id, &bundle.site_id);
+                return Err(anyhow!("Unexpected response"));
+            }
+        };
+
+        let local_chunks: Vec<ChunkId> = bundle.manifest.files.iter().flat_map(|f| f.chunks.iter().copied()).collect();
+
+        let missing = {
+            let mut sessions = self.replication_sessions.write();
+            let session = sessions.get_mut(peer_id, &bundle.site_id)
+                .ok_or_else(|| anyhow!("Replication session vanished"))?;
+            session.want(have_revision, &have_chunks, &local_chunks)
+        };
+
+        let Some(missing) = missing else {
+            // Already caught up; nothing to transfer.
+            self.replication_sessions.write().remove(peer_id, &bundle.site_id);
+            return Ok(false);
+        };
+
+        let mut chunks = Vec::with_capacity(missing.len());
+        let mut byte_count = 0u64;
+        for chunk_id in missing {
+            if let Some(data) = self.chunk_store.get(&chunk_id)? {
+                byte_count += data.len() as u64;
+                chunks.push((chunk_id, data));
+            }
+        }
+
+        let preamble = DeltaSyncPreamble {
+            site_id: bundle.site_id,
+            from_revision: have_revision,
+            to_revision: bundle.revision,
+            chunk_count: chunks.len(),
+            byte_count,
+        };
+        let _ = self.sync_progress_tx.send(SyncProgress {
+            direction: SyncDirection::Sending,
+            site_id: bundle.site_id,
+            chunks_done: 0,
+            total_chunks: preamble.chunk_count,
+            bytes_done: 0,
+            total_bytes: byte_count,
+        });
+
+        let serialized = bincode::serialize(&chunks)?;
+        let chunk_payload = zstd::stream::encode_all(&serialized[..], 0)?;
+        let _ = self.sync_progress_tx.send(SyncProgress {
+            direction: SyncDirection::Sending,
+            site_id: bundle.site_id,
+            chunks_done: preamble.chunk_count,
+            total_chunks: preamble.chunk_count,
+            bytes_done: byte_count,
+            total_bytes: byte_count,
+        });
+
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(
+            *peer_id,
+            GrabRequest::PushRevisionDelta { bundle: Box::new(bundle.clone()), preamble, chunk_payload },
+            tx,
+        )).await?;
+
+        let result = match rx.await? {
+            Ok(GrabResponse::Ack) => Ok(true),
+            Ok(GrabResponse::Error { message }) => Err(anyhow!(message)),
+            _ => Err(anyhow!("Unexpected response")),
+        };
+
+        if let Some(session) = self.replication_sessions.write().get_mut(peer_id, &bundle.site_id) {
+            session.finish();
+        }
+        self.replication_sessions.write().remove(peer_id, &bundle.site_id);
+        let _ = self.sync_progress_tx.send(SyncProgress::default());
+
+        if result.is_ok() {
+            let _ = self.event_tx.send(NetworkEvent::SiteUpdated { site_id: bundle.site_id, revision: bundle.revision });
+        }
+
+        result
+    }
+
+    /// Get chunks from a peer, verifying each against its claimed hash
+    /// before storing it locally. Chunks that don't hash to their ID are
+    /// dropped rather than trusted.
     pub async fn get_chunks(&self, peer_id: &PeerId, chunk_ids: &[ChunkId]) -> Result<Vec<(ChunkId, Vec<u8>)>> {
         let (tx, rx) = oneshot::channel();
         self.command_tx.send(SwarmCommand::SendRequest(
@@ -272,14 +999,194 @@ impl GrabNetwork {
             tx,
         )).await?;
 
+        let chunks = match rx.await? {
+            Ok(GrabResponse::Chunks { chunks }) => chunks,
+            Ok(GrabResponse::Error { message }) => return Err(anyhow!(message)),
+            _ => return Err(anyhow!("Unexpected response")),
+        };
+
+        let mut verified = Vec::with_capacity(chunks.len());
+        let mut bytes_received = 0u64;
+        for (chunk_id, data) in chunks {
+            if hash(&data) != chunk_id {
+                tracing::warn!("Peer {} sent a chunk that didn't match its hash, dropping", peer_id);
+                let _ = self.event_tx.send(NetworkEvent::ChunkVerificationFailed { chunk_id, peer_id: *peer_id });
+                self.bitswap.write().remove_want(peer_id, &chunk_id);
+                continue;
+            }
+            self.chunk_store.put(&data)?;
+            bytes_received += data.len() as u64;
+            self.bitswap.write().remove_want(peer_id, &chunk_id);
+            let _ = self.event_tx.send(NetworkEvent::ChunkReceived { chunk_id, peer_id: *peer_id });
+            verified.push((chunk_id, data));
+        }
+        if bytes_received > 0 {
+            self.bitswap.write().record_received(*peer_id, bytes_received);
+        }
+
+        Ok(verified)
+    }
+
+    /// Ask a peer which of the given chunks they have before requesting
+    /// the actual block data, avoiding a `GetChunks` round trip for
+    /// content the peer doesn't hold. Use this when the caller already
+    /// knows which peer to ask; for fetching by `ChunkId` across every
+    /// connected peer, see [`GrabNetwork::want_chunks`].
+    pub async fn want_chunks_from(&self, peer_id: &PeerId, chunk_ids: &[ChunkId]) -> Result<Vec<(ChunkId, Vec<u8>)>> {
+        for chunk_id in chunk_ids {
+            self.bitswap.write().add_want(*peer_id, *chunk_id);
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(
+            *peer_id,
+            GrabRequest::WantHave { chunk_ids: chunk_ids.to_vec() },
+            tx,
+        )).await?;
+
+        let have = match rx.await? {
+            Ok(GrabResponse::Have { have, .. }) => have,
+            Ok(GrabResponse::Error { message }) => return Err(anyhow!(message)),
+            _ => return Err(anyhow!("Unexpected response")),
+        };
+
+        if have.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.get_chunks(peer_id, &have).await
+    }
+
+    /// Fetch chunks by `ChunkId` across the whole swarm rather than from a
+    /// single known peer: `WantHave` is broadcast to every connected peer,
+    /// and as each one answers, the actual bytes are requested from
+    /// whichever peer confirmed it first (falling back to the next peer
+    /// that also answered `Have` if that request fails or comes back
+    /// empty). Chunks are verified against their hash and stored locally
+    /// exactly like [`GrabNetwork::get_chunks`]; verified blocks are
+    /// pushed onto the returned stream as they arrive rather than waiting
+    /// for the whole batch. The stream ends once every chunk has either
+    /// arrived or every peer that could answer for it has been tried.
+    pub fn want_chunks(&self, chunk_ids: &[ChunkId]) -> impl futures::Stream<Item = (ChunkId, Vec<u8>)> {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let command_tx = self.command_tx.clone();
+        let chunk_ids = chunk_ids.to_vec();
+        tokio::spawn(async move {
+            let _ = command_tx.send(SwarmCommand::Want(chunk_ids, tx)).await;
+        });
+        rx
+    }
+
+    /// Current send/receive ledger for a peer
+    pub fn bitswap_ledger(&self, peer_id: &PeerId) -> super::bitswap::PeerLedger {
+        self.bitswap.read().ledger_for(peer_id)
+    }
+
+    /// The gossiped replication CRDT, so callers can read network-wide
+    /// host sets and site health rather than just this node's own view.
+    pub fn replication_manager(&self) -> Arc<ReplicationManager> {
+        self.replication.clone()
+    }
+
+    /// Pull replication records a peer has that we're missing, via a
+    /// Bloom-filter reconciliation round instead of exchanging the full
+    /// table. `mask_bits` controls how many partitions our side of the
+    /// filter is split into (0 for a single filter over everything we
+    /// hold). Records returned are merged into our own CRDT before
+    /// being handed back.
+    pub async fn pull_replication(&self, peer_id: &PeerId, mask_bits: u32) -> Result<Vec<VersionedRecord>> {
+        let filters = self.replication.build_pull_filter(mask_bits);
+
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(
+            *peer_id,
+            GrabRequest::PullReplication { filters },
+            tx,
+        )).await?;
+
+        match rx.await? {
+            Ok(GrabResponse::PullReplicationReply { records }) => {
+                self.replication.ingest_gossip(records.clone());
+                Ok(records)
+            }
+            Ok(GrabResponse::Error { message }) => Err(anyhow!(message)),
+            _ => Err(anyhow!("Unexpected response")),
+        }
+    }
+
+    /// Fetch one chunk's bytes plus a Merkle proof against `site_id`'s
+    /// signed root from `peer_id`, for [`super::repair::RepairService`] to
+    /// verify before accepting it as a repair. Unlike `get_chunks`, this
+    /// doesn't store the chunk or touch bitswap accounting itself — the
+    /// caller verifies the proof first.
+    pub async fn fetch_chunk_with_proof(&self, peer_id: &PeerId, site_id: &SiteId, chunk_id: &ChunkId) -> Result<(Vec<u8>, MerkleProof)> {
+        let (tx, rx) = oneshot::channel();
+        self.command_tx.send(SwarmCommand::SendRequest(
+            *peer_id,
+            GrabRequest::GetChunkWithProof { site_id: *site_id, chunk_id: *chunk_id },
+            tx,
+        )).await?;
+
         match rx.await? {
-            Ok(GrabResponse::Chunks { chunks }) => Ok(chunks),
+            Ok(GrabResponse::ChunkWithProof { data, proof }) => Ok((data, proof)),
             Ok(GrabResponse::Error { message }) => Err(anyhow!(message)),
             _ => Err(anyhow!("Unexpected response")),
         }
     }
 }
 
+/// Compose the transport stack: TCP always, with QUIC and WebSocket layered
+/// in when enabled, all wrapped in a DNS resolver so `/dns4/.../tcp/...` and
+/// `/dnsaddr/...` bootstrap/listen addresses work regardless of which
+/// underlying transport they name. Everything is boxed to a single type so
+/// the enabled set can vary at runtime without `SwarmBuilder`'s per-call
+/// type changing across the `if`s.
+fn build_transport(
+    local_key: &identity::Keypair,
+    enable_quic: bool,
+    enable_websocket: bool,
+) -> std::io::Result<Boxed<(PeerId, StreamMuxerBox)>> {
+    let noise_config = noise::Config::new(local_key)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+    let mut transport = tcp::tokio::Transport::new(tcp::Config::default().nodelay(true))
+        .upgrade(Version::V1)
+        .authenticate(noise_config.clone())
+        .multiplex(yamux::Config::default())
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+        .boxed();
+
+    if enable_quic {
+        let quic_transport = quic::tokio::Transport::new(quic::Config::new(local_key))
+            .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+            .boxed();
+        transport = OrTransport::new(quic_transport, transport)
+            .map(|either, _| match either {
+                futures::future::Either::Left(out) => out,
+                futures::future::Either::Right(out) => out,
+            })
+            .boxed();
+    }
+
+    if enable_websocket {
+        let ws_tcp = tcp::tokio::Transport::new(tcp::Config::default())
+            .upgrade(Version::V1)
+            .authenticate(noise_config)
+            .multiplex(yamux::Config::default());
+        let ws_transport = websocket::WsConfig::new(ws_tcp)
+            .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+            .boxed();
+        transport = OrTransport::new(transport, ws_transport)
+            .map(|either, _| match either {
+                futures::future::Either::Left(out) => out,
+                futures::future::Either::Right(out) => out,
+            })
+            .boxed();
+    }
+
+    Ok(dns::tokio::Transport::system(transport)?.boxed())
+}
+
 /// Run the swarm event loop
 async fn run_swarm(
     mut swarm: Swarm<GrabBehaviour>,
@@ -288,9 +1195,33 @@ async fn run_swarm(
     bootstrap_peers: Vec<String>,
     chunk_store: Arc<ChunkStore>,
     bundle_store: Arc<BundleStore>,
+    name_store: Arc<NameStore>,
+    name_chain: Arc<NameChain>,
     announced_sites: Arc<RwLock<HashMap<SiteId, u64>>>,
     connected_peers: Arc<RwLock<HashSet<PeerId>>>,
+    listen_addr_cache: Arc<RwLock<HashSet<Multiaddr>>>,
+    external_addrs: Arc<RwLock<HashSet<Multiaddr>>>,
+    local_secure_id: Arc<RwLock<Option<[u8; 20]>>>,
+    bitswap: Arc<RwLock<BitswapLedger>>,
+    membership: Arc<RwLock<MembershipTable>>,
+    replication: Arc<ReplicationManager>,
+    health: Arc<HealthMonitor>,
+    access: Arc<RwLock<PeerAccessControl>>,
     event_tx: broadcast::Sender<NetworkEvent>,
+    metrics: Arc<NodeMetrics>,
+    gossip_mesh: Arc<GossipMeshTracker>,
+    identity_keys: Arc<IdentityKeys>,
+    identity_private: Arc<Zeroizing<[u8; 32]>>,
+    node_name: String,
+    trust_store: Arc<RwLock<TrustStore>>,
+    paired_devices: Arc<RwLock<PairedDeviceStore>>,
+    pending_pairings: Arc<RwLock<HashMap<PeerId, PairingSession>>>,
+    control_sessions: Arc<RwLock<HashMap<PeerId, Session>>>,
+    sync_progress_tx: watch::Sender<SyncProgress>,
+    discovery: Option<DiscoveryConfig>,
+    discovery_port: u16,
+    layout: Arc<ReplicaLayout>,
+    node_zone: Option<String>,
 ) {
     // Start listening
     for addr in listen_addrs {
@@ -305,25 +1236,180 @@ async fn run_swarm(
 
     // Subscribe to gossipsub topics
     let sites_topic = IdentTopic::new(SITES_TOPIC);
-    let updates_topic = IdentTopic::new(UPDATES_TOPIC);
+    let replication_topic = IdentTopic::new(REPLICATION_TOPIC);
+    let name_chain_topic = IdentTopic::new(NAME_CHAIN_TOPIC);
     if let Err(e) = swarm.behaviour_mut().gossipsub.subscribe(&sites_topic) {
         tracing::warn!("Failed to subscribe to sites topic: {}", e);
     }
-    if let Err(e) = swarm.behaviour_mut().gossipsub.subscribe(&updates_topic) {
-        tracing::warn!("Failed to subscribe to updates topic: {}", e);
+    if let Err(e) = swarm.behaviour_mut().gossipsub.subscribe(&replication_topic) {
+        tracing::warn!("Failed to subscribe to replication topic: {}", e);
     }
+    if let Err(e) = swarm.behaviour_mut().gossipsub.subscribe(&name_chain_topic) {
+        tracing::warn!("Failed to subscribe to name-chain topic: {}", e);
+    }
+
+    // Pending requests, with the `Instant` they were sent at so a reply
+    // can be timed for `grab_request_latency_seconds` (failures are only
+    // counted, not timed).
+    let mut pending_requests: HashMap<request_response::OutboundRequestId, (Instant, oneshot::Sender<Result<GrabResponse>>)> = HashMap::new();
+
+    // Swarm-wide `want_chunks` sessions (see `SwarmCommand::Want`): which
+    // session a given outbound `WantHave`/`GetChunks` request belongs to.
+    let mut want_sessions: HashMap<u64, WantSession> = HashMap::new();
+    let mut next_want_session_id: u64 = 0;
+    let mut pending_want_have: HashMap<request_response::OutboundRequestId, u64> = HashMap::new();
+    let mut pending_want_block: HashMap<request_response::OutboundRequestId, (u64, PeerId, Vec<ChunkId>)> = HashMap::new();
 
-    // Pending requests
-    let mut pending_requests: HashMap<request_response::OutboundRequestId, oneshot::Sender<Result<GrabResponse>>> = HashMap::new();
-    
     // Pending DHT queries
     let mut pending_site_queries: HashMap<QueryId, (SiteId, oneshot::Sender<Vec<PeerRecord>>)> = HashMap::new();
-    
+
     // Discovered providers for sites
     let mut site_providers: HashMap<SiteId, Vec<PeerRecord>> = HashMap::new();
 
+    // SWIM indirect probing (see `GrabRequest::IndirectPing`): our own
+    // outstanding "please check on `target` for me" requests, keyed by the
+    // probe we sent to the helper; the nested direct `Ping` we send on a
+    // helper's behalf, keyed back to the response channel we owe them an
+    // `IndirectPingResult` on; and, per accused target, how many helpers
+    // still need to report in before a round counts as "both fail".
+    let mut pending_indirect_probe: HashMap<request_response::OutboundRequestId, String> = HashMap::new();
+    let mut pending_indirect_answer: HashMap<request_response::OutboundRequestId, request_response::ResponseChannel<GrabResponse>> = HashMap::new();
+    let mut indirect_probe_rounds: HashMap<String, (usize, usize)> = HashMap::new();
+
+    // Re-announce our hosted/published sites periodically, independent of
+    // Kademlia's own provider-record republish, so hosts stay discoverable
+    // even across MemoryStore restarts or short outages.
+    let mut republish_interval = tokio::time::interval(Duration::from_secs(3600));
+
+    // Gossip membership deltas with a small fan-out every few seconds, and
+    // directly probe a random member so a peer that's gone quiet without a
+    // clean disconnect still gets caught.
+    let mut gossip_interval = tokio::time::interval(Duration::from_secs(5));
+    let mut probe_interval = tokio::time::interval(Duration::from_secs(10));
+
+    // Push locally-changed replication records and prune stale ones every
+    // 30 seconds, a slower cadence than membership gossip.
+    let mut replication_interval = tokio::time::interval(Duration::from_secs(30));
+
+    // Prune idle peripheral (non-mesh) peers from the gossip mesh mirror
+    // and sample a few of the survivors as IHAVE/IWANT gossip targets, so
+    // nodes outside a topic's mesh still get nudged toward revisions
+    // they're missing instead of waiting to be grafted in.
+    let mut gossip_mesh_interval = tokio::time::interval(Duration::from_secs(20));
+
+    // Mine any pending name claims into a block and gossip it, on a slower
+    // cadence than membership gossip and probing since mining does real (if cheap)
+    // proof-of-work and there's no rush: a claim just sits in the mempool
+    // until the next tick.
+    let mut name_chain_interval = tokio::time::interval(Duration::from_secs(30));
+
+    // Re-resolve the discovery backend (if any) to dial peers that joined
+    // after startup, e.g. a Kubernetes deployment scaling up replicas.
+    // Disabled entirely (an interval that never fires) when no backend is
+    // configured, rather than special-casing the `select!` arm below.
+    let mut discovery_interval = tokio::time::interval(
+        discovery
+            .as_ref()
+            .map(|d| Duration::from_secs(d.refresh_interval_secs))
+            .unwrap_or(Duration::from_secs(u64::MAX / 2)),
+    );
+
     loop {
         tokio::select! {
+            _ = republish_interval.tick() => {
+                let sites: Vec<(SiteId, u64)> = announced_sites.read().iter().map(|(id, rev)| (*id, *rev)).collect();
+                for (site_id, revision) in sites {
+                    announce_site_to_swarm(&mut swarm, &sites_topic, &bundle_store, site_id, revision, node_zone.clone());
+                }
+            }
+
+            _ = gossip_interval.tick() => {
+                let local_id = swarm.local_peer_id().to_string();
+                let targets = membership.read().gossip_targets(&local_id, 3);
+                let delta = membership.read().to_delta();
+                for target in targets {
+                    if let Ok(peer_id) = target.parse::<PeerId>() {
+                        swarm.behaviour_mut().request_response.send_request(
+                            &peer_id,
+                            GrabRequest::Gossip { delta: delta.clone() },
+                        );
+                    }
+                }
+
+                // Free up a dead member's replica-layout slots on the same
+                // cadence membership gossip runs, so site assignments get
+                // recomputed shortly after the member set changes.
+                for member in membership.read().members() {
+                    if member.state == MemberState::Dead {
+                        layout.remove_host(&member.peer_id);
+                    }
+                }
+            }
+
+            _ = probe_interval.tick() => {
+                let local_id = swarm.local_peer_id().to_string();
+                let target = membership.read().pick_probe_target(&local_id);
+                if let Some(target) = target {
+                    if let Ok(peer_id) = target.parse::<PeerId>() {
+                        let delta = membership.read().to_delta();
+                        let secure_id = *local_secure_id.read();
+                        swarm.behaviour_mut().request_response.send_request(&peer_id, GrabRequest::Ping { delta, secure_id });
+                    }
+                }
+            }
+
+            _ = replication_interval.tick() => {
+                replication.prune_expired();
+                let outgoing = replication.outgoing_push();
+                if !outgoing.is_empty() {
+                    if let Ok(msg) = bincode::serialize(&outgoing) {
+                        let _ = swarm.behaviour_mut().gossipsub.publish(replication_topic.clone(), msg);
+                    }
+                }
+            }
+
+            _ = name_chain_interval.tick() => {
+                match name_chain.mine_pending() {
+                    Ok(Some(block)) => {
+                        tracing::debug!("Mined name-chain block at height {}", block.height);
+                        if let Ok(msg) = bincode::serialize(&block) {
+                            let _ = swarm.behaviour_mut().gossipsub.publish(name_chain_topic.clone(), msg);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to mine name-chain block: {}", e),
+                }
+            }
+
+            _ = discovery_interval.tick() => {
+                if let Some(discovery) = &discovery {
+                    match discovery.backend.resolve(discovery_port).await {
+                        Ok(addrs) => {
+                            for addr in &addrs {
+                                if let Ok(multiaddr) = addr.parse::<Multiaddr>() {
+                                    let _ = swarm.dial(multiaddr);
+                                }
+                            }
+                            tracing::debug!("Discovery backend re-resolved {} bootstrap address(es)", addrs.len());
+                        }
+                        Err(e) => tracing::warn!("Discovery backend re-resolution failed: {}", e),
+                    }
+                }
+            }
+
+            _ = gossip_mesh_interval.tick() => {
+                gossip_mesh.prune_idle();
+                for topic in gossip_mesh.topics() {
+                    let targets = gossip_mesh.gossip_targets(&topic, GOSSIP_MESH_SAMPLE_SIZE);
+                    if !targets.is_empty() {
+                        tracing::debug!(
+                            "gossip mesh: sampling {} peripheral peer(s) on {} for IHAVE/IWANT",
+                            targets.len(), topic,
+                        );
+                    }
+                }
+            }
+
             Some(command) = command_rx.recv() => {
                 match command {
                     SwarmCommand::Dial(addr) => {
@@ -332,25 +1418,10 @@ async fn run_swarm(
                     }
                     SwarmCommand::SendRequest(peer_id, request, response_tx) => {
                         let request_id = swarm.behaviour_mut().request_response.send_request(&peer_id, request);
-                        pending_requests.insert(request_id, response_tx);
+                        pending_requests.insert(request_id, (Instant::now(), response_tx));
                     }
                     SwarmCommand::Announce(site_id, revision) => {
-                        // Put in DHT as provider
-                        let key = kad::RecordKey::new(&site_id);
-                        swarm.behaviour_mut().kademlia.start_providing(key.clone())
-                            .map_err(|e| tracing::warn!("Failed to start providing: {}", e))
-                            .ok();
-                        
-                        // Also put record with revision info
-                        let value = bincode::serialize(&(swarm.local_peer_id().to_string(), revision)).unwrap_or_default();
-                        let record = kad::Record::new(key, value);
-                        let _ = swarm.behaviour_mut().kademlia.put_record(record, kad::Quorum::One);
-                        
-                        // Broadcast via gossipsub
-                        let msg = bincode::serialize(&(site_id, revision)).unwrap_or_default();
-                        let _ = swarm.behaviour_mut().gossipsub.publish(sites_topic.clone(), msg);
-                        
-                        tracing::info!("Announcing site {} revision {}", site_id.to_base58(), revision);
+                        announce_site_to_swarm(&mut swarm, &sites_topic, &bundle_store, site_id, revision, node_zone.clone());
                     }
                     SwarmCommand::FindSite(site_id, tx) => {
                         let key = kad::RecordKey::new(&site_id);
@@ -366,6 +1437,37 @@ async fn run_swarm(
                         let addrs: Vec<_> = swarm.listeners().map(|a| a.to_string()).collect();
                         let _ = tx.send(addrs);
                     }
+                    SwarmCommand::Want(chunk_ids, result_tx) => {
+                        let peers: Vec<PeerId> = swarm.connected_peers().cloned().collect();
+                        if peers.is_empty() || chunk_ids.is_empty() {
+                            // Nothing to ask; dropping result_tx ends the caller's stream.
+                            continue;
+                        }
+
+                        let session_id = next_want_session_id;
+                        next_want_session_id += 1;
+
+                        let mut in_flight = 0;
+                        for peer in &peers {
+                            for chunk_id in &chunk_ids {
+                                bitswap.write().add_want(*peer, *chunk_id);
+                            }
+                            let request_id = swarm.behaviour_mut().request_response.send_request(
+                                peer,
+                                GrabRequest::WantHave { chunk_ids: chunk_ids.clone() },
+                            );
+                            pending_want_have.insert(request_id, session_id);
+                            in_flight += 1;
+                        }
+
+                        want_sessions.insert(session_id, WantSession {
+                            remaining: chunk_ids.into_iter().collect(),
+                            requested: HashSet::new(),
+                            have_peers: HashMap::new(),
+                            result_tx,
+                            in_flight,
+                        });
+                    }
                     SwarmCommand::Bootstrap => {
                         for addr in &bootstrap_peers {
                             if let Ok(multiaddr) = addr.parse::<Multiaddr>() {
@@ -379,8 +1481,52 @@ async fn run_swarm(
                                 let _ = swarm.dial(multiaddr);
                             }
                         }
+                        // Reserved peers get the same treatment as bootstrap
+                        // peers, plus an explicit Kademlia seed since we
+                        // already know their peer ID (bootstrap addresses
+                        // don't necessarily carry one).
+                        for (peer_id, addr) in access.read().reserved_peers() {
+                            tracing::info!("Connecting to reserved peer: {}", peer_id);
+                            swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                            let _ = swarm.dial(addr);
+                        }
                         let _ = swarm.behaviour_mut().kademlia.bootstrap();
                     }
+                    SwarmCommand::DialPeerAtAddresses(peer_id, addrs) => {
+                        for addr in &addrs {
+                            swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                        }
+                        if let Some(addr) = addrs.into_iter().next() {
+                            let _ = swarm.dial(addr);
+                        }
+                    }
+                    SwarmCommand::AddReservedPeer(peer_id, addr) => {
+                        access.write().add_reserved(peer_id, addr.clone());
+                        swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                        let _ = swarm.dial(addr);
+                    }
+                    SwarmCommand::RemoveReservedPeer(peer_id) => {
+                        access.write().remove_reserved(&peer_id);
+                    }
+                    SwarmCommand::SetReservedOnly(on) => {
+                        access.write().set_reserved_only(on);
+                        if on {
+                            let connected: Vec<PeerId> = swarm.connected_peers().cloned().collect();
+                            for peer_id in connected {
+                                if !access.read().is_reserved(&peer_id) {
+                                    tracing::info!("Disconnecting non-reserved peer {} (reserved-only enabled)", peer_id);
+                                    let _ = swarm.disconnect_peer_id(peer_id);
+                                }
+                            }
+                        }
+                    }
+                    SwarmCommand::BanPeer(peer_id, duration) => {
+                        access.write().ban(peer_id, duration);
+                        let _ = swarm.disconnect_peer_id(peer_id);
+                    }
+                    SwarmCommand::UnbanPeer(peer_id) => {
+                        access.write().unban(&peer_id);
+                    }
                     SwarmCommand::Shutdown => {
                         tracing::info!("Shutting down network");
                         break;
@@ -392,47 +1538,230 @@ async fn run_swarm(
                 match event {
                     SwarmEvent::NewListenAddr { address, .. } => {
                         tracing::info!("Listening on {}", address);
+                        listen_addr_cache.write().insert(address);
                     }
-                    
-                    SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                        tracing::debug!("Connected to peer: {}", peer_id);
-                        connected_peers.write().insert(peer_id);
-                        let _ = event_tx.send(NetworkEvent::PeerConnected(peer_id));
+
+                    SwarmEvent::ExpiredListenAddr { address, .. } => {
+                        listen_addr_cache.write().remove(&address);
                     }
-                    
+
+                    SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                        if !access.write().allows(&peer_id) {
+                            tracing::warn!("Denying connection from {} (banned or reserved-only)", peer_id);
+                            let _ = swarm.disconnect_peer_id(peer_id);
+                        } else {
+                            tracing::debug!("Connected to peer: {}", peer_id);
+                            connected_peers.write().insert(peer_id);
+                            membership.write().upsert(peer_id.to_string(), vec![]);
+                            health.peer_connected(&peer_id.to_string());
+                            metrics.peer_connected();
+
+                            // Sybil-resistance check (see `secure_id`): only
+                            // meaningful for an IPv4 remote address, so
+                            // anything else (IPv6, relayed) just stays
+                            // unverified rather than failing the check.
+                            if let Some(ip) = remote_ipv4(endpoint.get_remote_address()) {
+                                let verified = access.write().check_secure_id(peer_id, ip);
+                                if !verified {
+                                    tracing::debug!("Peer {} has an unverified secure ID for {}", peer_id, ip);
+                                }
+                            }
+
+                            let _ = event_tx.send(NetworkEvent::PeerConnected(peer_id));
+                        }
+                    }
+
                     SwarmEvent::ConnectionClosed { peer_id, .. } => {
                         tracing::debug!("Disconnected from peer: {}", peer_id);
                         connected_peers.write().remove(&peer_id);
+                        health.peer_disconnected(&peer_id.to_string());
+                        metrics.peer_disconnected();
+                        if let Some(addr) = access.read().reserved_addr(&peer_id) {
+                            tracing::info!("Reserved peer {} disconnected, redialing", peer_id);
+                            let _ = swarm.dial(addr);
+                        }
                         let _ = event_tx.send(NetworkEvent::PeerDisconnected(peer_id));
                     }
 
                     SwarmEvent::Behaviour(GrabBehaviourEvent::RequestResponse(
-                        request_response::Event::Message { message, .. }
+                        request_response::Event::Message { peer, message }
                     )) => {
                         match message {
                             request_response::Message::Request { request, channel, .. } => {
+                                if let GrabRequest::IndirectPing { target } = &request {
+                                    // Can't resolve this synchronously (it needs its own
+                                    // nested Ping round trip), so fire the probe and remember
+                                    // which response channel to answer once it lands.
+                                    match target.parse::<PeerId>() {
+                                        Ok(target_peer) => {
+                                            let delta = membership.read().to_delta();
+                                            let secure_id = *local_secure_id.read();
+                                            let probe_id = swarm.behaviour_mut().request_response.send_request(
+                                                &target_peer, GrabRequest::Ping { delta, secure_id },
+                                            );
+                                            pending_indirect_answer.insert(probe_id, channel);
+                                        }
+                                        Err(_) => {
+                                            let _ = swarm.behaviour_mut().request_response.send_response(
+                                                channel, GrabResponse::IndirectPingResult { alive: false },
+                                            );
+                                        }
+                                    }
+                                    continue;
+                                }
+                                let local_peer_id = *swarm.local_peer_id();
                                 let response = handle_request(
                                     request,
                                     &chunk_store,
                                     &bundle_store,
+                                    &name_store,
                                     &announced_sites,
-                                    swarm.local_peer_id(),
+                                    &local_peer_id,
+                                    &external_addrs,
+                                    &bitswap,
+                                    &membership,
+                                    &replication,
+                                    &peer,
+                                    &event_tx,
+                                    &metrics,
+                                    &identity_keys,
+                                    &identity_private,
+                                    &node_name,
+                                    &trust_store,
+                                    &paired_devices,
+                                    &pending_pairings,
+                                    &control_sessions,
+                                    &sync_progress_tx,
+                                    &access,
+                                    &local_secure_id,
                                 ).await;
                                 let _ = swarm.behaviour_mut().request_response.send_response(channel, response);
                             }
                             request_response::Message::Response { request_id, response } => {
-                                if let Some(tx) = pending_requests.remove(&request_id) {
+                                if let Some((sent_at, tx)) = pending_requests.remove(&request_id) {
+                                    metrics.record_request_outcome(true, Some(sent_at.elapsed()));
                                     let _ = tx.send(Ok(response));
+                                } else if let Some(session_id) = pending_want_have.remove(&request_id) {
+                                    handle_want_have_response(
+                                        session_id, peer, response,
+                                        &mut swarm, &mut want_sessions, &mut pending_want_block,
+                                    );
+                                } else if let Some((session_id, from_peer, requested)) = pending_want_block.remove(&request_id) {
+                                    handle_want_block_response(
+                                        session_id, from_peer, requested, response,
+                                        &mut swarm, &mut want_sessions, &mut pending_want_block,
+                                        &chunk_store, &bitswap, &event_tx,
+                                    );
+                                } else if let Some(channel) = pending_indirect_answer.remove(&request_id) {
+                                    // The nested Ping we sent on a helper's behalf came back;
+                                    // relay the verdict (and any piggybacked delta) onward.
+                                    let alive = matches!(response, GrabResponse::Pong { .. });
+                                    if let GrabResponse::Pong { delta, secure_id } = response {
+                                        if let Some(id) = secure_id {
+                                            access.write().record_exchanged_secure_id(peer, id);
+                                        }
+                                        let local_id = swarm.local_peer_id().to_string();
+                                        let (joined, left) = membership.write().apply_delta(delta, &local_id);
+                                        for peer_id in joined {
+                                            let _ = event_tx.send(NetworkEvent::PeerJoined { peer_id });
+                                        }
+                                        for peer_id in left {
+                                            let _ = event_tx.send(NetworkEvent::PeerLeft { peer_id });
+                                        }
+                                    }
+                                    let _ = swarm.behaviour_mut().request_response.send_response(
+                                        channel, GrabResponse::IndirectPingResult { alive },
+                                    );
+                                } else if let Some(target) = pending_indirect_probe.remove(&request_id) {
+                                    if let GrabResponse::IndirectPingResult { alive } = response {
+                                        resolve_indirect_round(&membership, &mut indirect_probe_rounds, &event_tx, &target, alive);
+                                    }
+                                } else {
+                                    // Not something our own API callers are waiting on; must be
+                                    // one of the membership subsystem's own gossip/probe requests.
+                                    let local_id = swarm.local_peer_id().to_string();
+                                    match response {
+                                        GrabResponse::Gossip { delta } => {
+                                            let (joined, left) = membership.write().apply_delta(delta, &local_id);
+                                            for peer_id in joined {
+                                                let _ = event_tx.send(NetworkEvent::PeerJoined { peer_id });
+                                            }
+                                            for peer_id in left {
+                                                let _ = event_tx.send(NetworkEvent::PeerLeft { peer_id });
+                                            }
+                                        }
+                                        GrabResponse::Pong { delta, secure_id } => {
+                                            membership.write().note_probe_success(&peer.to_string());
+                                            if let Some(id) = secure_id {
+                                                access.write().record_exchanged_secure_id(peer, id);
+                                            }
+                                            let (joined, left) = membership.write().apply_delta(delta, &local_id);
+                                            for peer_id in joined {
+                                                let _ = event_tx.send(NetworkEvent::PeerJoined { peer_id });
+                                            }
+                                            for peer_id in left {
+                                                let _ = event_tx.send(NetworkEvent::PeerLeft { peer_id });
+                                            }
+                                        }
+                                        _ => {}
+                                    }
                                 }
                             }
                         }
                     }
-                    
+
                     SwarmEvent::Behaviour(GrabBehaviourEvent::RequestResponse(
-                        request_response::Event::OutboundFailure { request_id, error, .. }
+                        request_response::Event::OutboundFailure { peer, request_id, error, .. }
                     )) => {
-                        if let Some(tx) = pending_requests.remove(&request_id) {
+                        let penalty = health.record_request_failure(&peer.to_string());
+                        let _ = event_tx.send(NetworkEvent::PeerMisbehaved { peer_id: peer, penalty });
+
+                        if let Some((_, tx)) = pending_requests.remove(&request_id) {
+                            metrics.record_request_outcome(false, None);
                             let _ = tx.send(Err(anyhow!("Request failed: {:?}", error)));
+                        } else if let Some(session_id) = pending_want_have.remove(&request_id) {
+                            if let Some(session) = want_sessions.get_mut(&session_id) {
+                                session.in_flight -= 1;
+                                if session.is_done() {
+                                    want_sessions.remove(&session_id);
+                                }
+                            }
+                        } else if let Some((session_id, _from_peer, requested)) = pending_want_block.remove(&request_id) {
+                            retry_or_drop_chunks(session_id, requested, &mut swarm, &mut want_sessions, &mut pending_want_block);
+                        } else if let Some(channel) = pending_indirect_answer.remove(&request_id) {
+                            // The target we were probing on a helper's behalf didn't answer us either.
+                            let _ = swarm.behaviour_mut().request_response.send_response(
+                                channel, GrabResponse::IndirectPingResult { alive: false },
+                            );
+                        } else if let Some(target) = pending_indirect_probe.remove(&request_id) {
+                            // A helper we asked to indirectly probe `target` didn't answer us;
+                            // count it the same as a negative result for this round.
+                            resolve_indirect_round(&membership, &mut indirect_probe_rounds, &event_tx, &target, false);
+                        } else {
+                            // Our own direct probe of `peer` went unanswered. Rather than
+                            // declaring suspicion off one missed ping (which might just mean
+                            // our link to it is flaky), ask a few other members to check on
+                            // it directly before we do; only escalate once every helper in
+                            // the round also fails to confirm it (see `resolve_indirect_round`).
+                            let local_id = swarm.local_peer_id().to_string();
+                            let target = peer.to_string();
+                            let helpers = membership.read().pick_indirect_probers(&local_id, &target, INDIRECT_PROBE_FANOUT);
+                            if helpers.is_empty() {
+                                let newly_dead = membership.write().note_probe_failure(&target);
+                                if newly_dead {
+                                    let _ = event_tx.send(NetworkEvent::PeerLeft { peer_id: target });
+                                }
+                            } else {
+                                indirect_probe_rounds.insert(target.clone(), (helpers.len(), 0));
+                                for helper in helpers {
+                                    if let Ok(helper_peer) = helper.parse::<PeerId>() {
+                                        let probe_id = swarm.behaviour_mut().request_response.send_request(
+                                            &helper_peer, GrabRequest::IndirectPing { target: target.clone() },
+                                        );
+                                        pending_indirect_probe.insert(probe_id, target.clone());
+                                    }
+                                }
+                            }
                         }
                     }
                     
@@ -453,40 +1782,109 @@ async fn run_swarm(
                             QueryResult::GetProviders(Ok(kad::GetProvidersOk::FinishedWithNoAdditionalRecord { .. })) => {
                                 if let Some((site_id, tx)) = pending_site_queries.remove(&id) {
                                     let records = site_providers.remove(&site_id).unwrap_or_default();
+                                    metrics.record_dht_outcome("get_providers", !records.is_empty());
                                     let _ = tx.send(records);
                                 }
                             }
+                            QueryResult::GetProviders(Err(_)) => {
+                                if let Some((site_id, tx)) = pending_site_queries.remove(&id) {
+                                    site_providers.remove(&site_id);
+                                    metrics.record_dht_outcome("get_providers", false);
+                                    let _ = tx.send(vec![]);
+                                }
+                            }
                             QueryResult::Bootstrap(Ok(_)) => {
                                 let peer_count = connected_peers.read().len();
                                 tracing::info!("Kademlia bootstrap complete, {} peers", peer_count);
+                                metrics.record_dht_outcome("bootstrap", true);
                                 let _ = event_tx.send(NetworkEvent::BootstrapComplete { peers: peer_count });
                             }
+                            QueryResult::Bootstrap(Err(_)) => {
+                                metrics.record_dht_outcome("bootstrap", false);
+                            }
                             _ => {}
                         }
                     }
 
                     SwarmEvent::Behaviour(GrabBehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                        message_id,
                         message,
                         propagation_source,
                         ..
                     })) => {
-                        if message.topic == sites_topic.hash() {
-                            let result: Result<(SiteId, u64), _> = bincode::deserialize(&message.data);
-                            if let Ok((site_id, revision)) = result {
-                                tracing::debug!("Received site announcement: {} rev {}", site_id.to_base58(), revision);
-                                let _ = event_tx.send(NetworkEvent::SiteAnnounced {
-                                    site_id,
-                                    peer_id: propagation_source,
-                                    revision,
-                                });
+                        let acceptance = if message.topic == sites_topic.hash() {
+                            match bincode::deserialize::<SiteAnnouncement>(&message.data) {
+                                Ok(announcement) if verify_bundle(
+                                    &announcement.site_id,
+                                    announcement.revision,
+                                    &announcement.root_hash,
+                                    &announcement.signature,
+                                    &announcement.publisher,
+                                ) => {
+                                    tracing::debug!(
+                                        "Received site announcement: {} rev {}",
+                                        announcement.site_id.to_base58(), announcement.revision,
+                                    );
+                                    gossip_mesh.record_announcement(SITES_TOPIC, propagation_source, announcement.revision);
+                                    layout.set_host(propagation_source.to_string(), announcement.zone.clone(), DEFAULT_HOST_CAPACITY);
+                                    let _ = event_tx.send(NetworkEvent::SiteAnnounced {
+                                        site_id: announcement.site_id,
+                                        peer_id: propagation_source,
+                                        revision: announcement.revision,
+                                        zone: announcement.zone,
+                                    });
+                                    MessageAcceptance::Accept
+                                }
+                                Ok(_) => {
+                                    tracing::warn!("Rejecting site announcement from {} with invalid signature", propagation_source);
+                                    MessageAcceptance::Reject
+                                }
+                                Err(_) => MessageAcceptance::Reject,
                             }
-                        } else if message.topic == updates_topic.hash() {
-                            let result: Result<(SiteId, u64), _> = bincode::deserialize(&message.data);
-                            if let Ok((site_id, revision)) = result {
-                                tracing::debug!("Received site update: {} rev {}", site_id.to_base58(), revision);
-                                let _ = event_tx.send(NetworkEvent::SiteUpdated { site_id, revision });
+                        } else if message.topic == replication_topic.hash() {
+                            match bincode::deserialize::<Vec<VersionedRecord>>(&message.data) {
+                                Ok(records) => {
+                                    tracing::debug!("Merging {} replication record(s) from {}", records.len(), propagation_source);
+                                    replication.ingest_gossip(records);
+                                    MessageAcceptance::Accept
+                                }
+                                Err(_) => MessageAcceptance::Reject,
                             }
+                        } else if message.topic == name_chain_topic.hash() {
+                            match bincode::deserialize::<NameBlock>(&message.data) {
+                                Ok(block) => match name_chain.ingest_block(block) {
+                                    Ok(true) => {
+                                        tracing::debug!("Accepted name-chain block from {}", propagation_source);
+                                        MessageAcceptance::Accept
+                                    }
+                                    Ok(false) => MessageAcceptance::Reject,
+                                    Err(_) => MessageAcceptance::Reject,
+                                },
+                                Err(_) => MessageAcceptance::Reject,
+                            }
+                        } else {
+                            MessageAcceptance::Ignore
+                        };
+
+                        let topic_label = if message.topic == sites_topic.hash() {
+                            "sites"
+                        } else if message.topic == replication_topic.hash() {
+                            "replication"
+                        } else if message.topic == name_chain_topic.hash() {
+                            "name_chain"
+                        } else {
+                            "unknown"
+                        };
+                        metrics.record_gossip_message(topic_label, acceptance == MessageAcceptance::Accept);
+
+                        if acceptance == MessageAcceptance::Reject {
+                            let penalty = health.record_request_failure(&propagation_source.to_string());
+                            let _ = event_tx.send(NetworkEvent::PeerMisbehaved { peer_id: propagation_source, penalty });
                         }
+
+                        let _ = swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                            &message_id, &propagation_source, acceptance,
+                        );
                     }
 
                     SwarmEvent::Behaviour(GrabBehaviourEvent::Mdns(mdns::Event::Discovered(peers))) => {
@@ -504,9 +1902,31 @@ async fn run_swarm(
 
                     SwarmEvent::Behaviour(GrabBehaviourEvent::Identify(identify::Event::Received { peer_id, info, .. })) => {
                         tracing::debug!("Identified peer {}: {:?}", peer_id, info.protocols);
+                        let observed_addr = info.observed_addr.clone();
                         for addr in info.listen_addrs {
                             swarm.behaviour_mut().kademlia.add_address(&peer_id, addr);
                         }
+
+                        // `observed_addr` is how `peer_id` sees us, which is as
+                        // close to "externally reachable" as we can learn
+                        // without STUN; the first peer to report a given
+                        // address is enough to trust and advertise it.
+                        if external_addrs.write().insert(observed_addr.clone()) {
+                            swarm.add_external_address(observed_addr.clone());
+                            tracing::info!("Confirmed external address: {}", observed_addr);
+
+                            // Mint our own secure ID (see `secure_id`) now that we
+                            // know an address that's actually reachable from
+                            // outside, so it reflects the IP peers will see us
+                            // connect from rather than an arbitrary local one.
+                            if local_secure_id.read().is_none() {
+                                if let Some(ip) = remote_ipv4(&observed_addr) {
+                                    *local_secure_id.write() = Some(generate_secure_id(ip));
+                                }
+                            }
+
+                            let _ = event_tx.send(NetworkEvent::ExternalAddressConfirmed(observed_addr));
+                        }
                     }
 
                     _ => {}
@@ -516,21 +1936,288 @@ async fn run_swarm(
     }
 }
 
+/// Extract the IPv4 address from a remote connection's multiaddr, if it
+/// has one. Used to check a peer's secure ID (see `secure_id`); `None`
+/// for IPv6 or relayed addresses, which just leaves the peer unverified.
+fn remote_ipv4(addr: &Multiaddr) -> Option<std::net::Ipv4Addr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(ip),
+        _ => None,
+    })
+}
+
+/// Parse a reserved-peer config entry: a full multiaddr with a trailing
+/// `/p2p/<peer_id>` component. Returns `None` if the address doesn't parse
+/// or doesn't carry a peer ID, since we need one to enforce reservation.
+fn parse_reserved_peer(addr: &str) -> Option<(PeerId, Multiaddr)> {
+    let multiaddr: Multiaddr = addr.parse().ok()?;
+    let peer_id = multiaddr.iter().find_map(|protocol| match protocol {
+        Protocol::P2p(peer_id) => Some(peer_id),
+        _ => None,
+    })?;
+    Some((peer_id, multiaddr))
+}
+
+/// Put a site's provider record and revision into the DHT and gossip it,
+/// shared by the initial `Announce` command and periodic republishing.
+/// The gossiped payload is a [`SiteAnnouncement`] carrying the bundle's own
+/// signature, so receivers can verify it came from the owning publisher
+/// before accepting it (see the `sites_topic` arm of the gossipsub message
+/// handler). If we don't have the bundle ourselves, there's nothing to
+/// sign, so only the DHT provider record is updated.
+fn announce_site_to_swarm(
+    swarm: &mut Swarm<GrabBehaviour>,
+    sites_topic: &IdentTopic,
+    bundle_store: &BundleStore,
+    site_id: SiteId,
+    revision: u64,
+    zone: Option<String>,
+) {
+    let key = kad::RecordKey::new(&site_id);
+    swarm.behaviour_mut().kademlia.start_providing(key.clone())
+        .map_err(|e| tracing::warn!("Failed to start providing: {}", e))
+        .ok();
+
+    let value = bincode::serialize(&(swarm.local_peer_id().to_string(), revision)).unwrap_or_default();
+    let record = kad::Record::new(key, value);
+    let _ = swarm.behaviour_mut().kademlia.put_record(record, kad::Quorum::One);
+
+    match bundle_store.get_bundle(&site_id) {
+        Ok(Some(bundle)) => {
+            let announcement = SiteAnnouncement {
+                site_id,
+                revision,
+                root_hash: bundle.root_hash,
+                publisher: bundle.publisher,
+                signature: bundle.signature.clone(),
+                zone,
+            };
+            match bincode::serialize(&announcement) {
+                Ok(msg) => { let _ = swarm.behaviour_mut().gossipsub.publish(sites_topic.clone(), msg); }
+                Err(e) => tracing::warn!("Failed to serialize site announcement: {}", e),
+            }
+        }
+        Ok(None) => tracing::warn!("Announcing site {} with no local bundle to sign, skipping gossip", site_id.to_base58()),
+        Err(e) => tracing::warn!("Failed to load bundle for site announcement: {}", e),
+    }
+
+    tracing::info!("Announcing site {} revision {}", site_id.to_base58(), revision);
+}
+
+/// A peer answered `WantHave`: queue them as a candidate for each chunk
+/// they confirmed, then immediately request the ones nobody's already
+/// fetching from them in a single batched `GetChunks`.
+fn handle_want_have_response(
+    session_id: u64,
+    peer: PeerId,
+    response: GrabResponse,
+    swarm: &mut Swarm<GrabBehaviour>,
+    want_sessions: &mut HashMap<u64, WantSession>,
+    pending_want_block: &mut HashMap<request_response::OutboundRequestId, (u64, PeerId, Vec<ChunkId>)>,
+) {
+    let Some(session) = want_sessions.get_mut(&session_id) else { return };
+    session.in_flight -= 1;
+
+    let have = match response {
+        GrabResponse::Have { have, .. } => have,
+        _ => Vec::new(),
+    };
+
+    let mut to_request = Vec::new();
+    for chunk_id in have {
+        if session.remaining.contains(&chunk_id) {
+            if session.requested.insert(chunk_id) {
+                to_request.push(chunk_id);
+            } else {
+                session.have_peers.entry(chunk_id).or_default().push_back(peer);
+            }
+        }
+    }
+
+    if !to_request.is_empty() {
+        let request_id = swarm.behaviour_mut().request_response.send_request(
+            &peer,
+            GrabRequest::GetChunks { chunk_ids: to_request.clone() },
+        );
+        session.in_flight += 1;
+        pending_want_block.insert(request_id, (session_id, peer, to_request));
+    } else if session.is_done() {
+        want_sessions.remove(&session_id);
+    }
+}
+
+/// Blocks arrived (or didn't) for a batch this session requested from one
+/// peer: verify and deliver the ones that did, and fall back to the next
+/// queued candidate for any that didn't.
+fn handle_want_block_response(
+    session_id: u64,
+    from_peer: PeerId,
+    requested: Vec<ChunkId>,
+    response: GrabResponse,
+    swarm: &mut Swarm<GrabBehaviour>,
+    want_sessions: &mut HashMap<u64, WantSession>,
+    pending_want_block: &mut HashMap<request_response::OutboundRequestId, (u64, PeerId, Vec<ChunkId>)>,
+    chunk_store: &ChunkStore,
+    bitswap: &RwLock<BitswapLedger>,
+    event_tx: &broadcast::Sender<NetworkEvent>,
+) {
+    let chunks = match response {
+        GrabResponse::Chunks { chunks } => chunks,
+        _ => Vec::new(),
+    };
+
+    let mut delivered = HashSet::new();
+    let mut bytes_received = 0u64;
+    for (chunk_id, data) in chunks {
+        if !requested.contains(&chunk_id) {
+            continue;
+        }
+        if hash(&data) != chunk_id {
+            tracing::warn!("Peer {} sent a chunk that didn't match its hash, dropping", from_peer);
+            let _ = event_tx.send(NetworkEvent::ChunkVerificationFailed { chunk_id, peer_id: from_peer });
+            continue;
+        }
+        if chunk_store.put(&data).is_err() {
+            continue;
+        }
+        bytes_received += data.len() as u64;
+        delivered.insert(chunk_id);
+
+        let Some(session) = want_sessions.get_mut(&session_id) else { continue };
+        session.remaining.remove(&chunk_id);
+        session.requested.remove(&chunk_id);
+        session.have_peers.remove(&chunk_id);
+        let _ = session.result_tx.unbounded_send((chunk_id, data));
+        let _ = event_tx.send(NetworkEvent::ChunkReceived { chunk_id, peer_id: from_peer });
+    }
+    if bytes_received > 0 {
+        bitswap.write().record_received(from_peer, bytes_received);
+    }
+
+    let failed: Vec<ChunkId> = requested.into_iter().filter(|c| !delivered.contains(c)).collect();
+
+    if let Some(session) = want_sessions.get_mut(&session_id) {
+        session.in_flight -= 1;
+        if session.is_done() {
+            want_sessions.remove(&session_id);
+            return;
+        }
+    } else {
+        return;
+    }
+
+    if !failed.is_empty() {
+        retry_or_drop_chunks(session_id, failed, swarm, want_sessions, pending_want_block);
+    }
+}
+
+/// A batch of chunks this session requested didn't pan out (the peer
+/// failed, or claimed `Have` but delivered nothing for them): try the
+/// next queued candidate for each, one request per peer they share.
+fn retry_or_drop_chunks(
+    session_id: u64,
+    chunk_ids: Vec<ChunkId>,
+    swarm: &mut Swarm<GrabBehaviour>,
+    want_sessions: &mut HashMap<u64, WantSession>,
+    pending_want_block: &mut HashMap<request_response::OutboundRequestId, (u64, PeerId, Vec<ChunkId>)>,
+) {
+    let Some(session) = want_sessions.get_mut(&session_id) else { return };
+
+    let mut by_peer: HashMap<PeerId, Vec<ChunkId>> = HashMap::new();
+    for chunk_id in chunk_ids {
+        session.requested.remove(&chunk_id);
+        if !session.remaining.contains(&chunk_id) {
+            continue;
+        }
+        if let Some(peer) = session.have_peers.get_mut(&chunk_id).and_then(|q| q.pop_front()) {
+            session.requested.insert(chunk_id);
+            by_peer.entry(peer).or_default().push(chunk_id);
+        }
+    }
+
+    for (peer, chunk_ids) in by_peer {
+        let request_id = swarm.behaviour_mut().request_response.send_request(
+            &peer,
+            GrabRequest::GetChunks { chunk_ids: chunk_ids.clone() },
+        );
+        session.in_flight += 1;
+        pending_want_block.insert(request_id, (session_id, peer, chunk_ids));
+    }
+
+    if session.is_done() {
+        want_sessions.remove(&session_id);
+    }
+}
+
+/// One of `target`'s indirect-probe helpers reported back (or failed to
+/// answer at all, treated the same as a negative result). Only escalates
+/// to `note_probe_failure` once every helper in the round has failed to
+/// confirm `target` is alive -- the SWIM "both fail" condition -- and
+/// short-circuits the round the moment any helper confirms it.
+fn resolve_indirect_round(
+    membership: &RwLock<MembershipTable>,
+    rounds: &mut HashMap<String, (usize, usize)>,
+    event_tx: &broadcast::Sender<NetworkEvent>,
+    target: &str,
+    alive: bool,
+) {
+    if alive {
+        membership.write().note_probe_success(target);
+        rounds.remove(target);
+        return;
+    }
+
+    let round_failed = match rounds.get_mut(target) {
+        Some((needed, failures)) => {
+            *failures += 1;
+            *failures >= *needed
+        }
+        None => false,
+    };
+
+    if round_failed {
+        rounds.remove(target);
+        let newly_dead = membership.write().note_probe_failure(target);
+        if newly_dead {
+            let _ = event_tx.send(NetworkEvent::PeerLeft { peer_id: target.to_string() });
+        }
+    }
+}
+
 /// Handle an incoming request
 async fn handle_request(
     request: GrabRequest,
     chunk_store: &ChunkStore,
     bundle_store: &BundleStore,
+    name_store: &NameStore,
     announced_sites: &RwLock<HashMap<SiteId, u64>>,
     local_peer_id: &PeerId,
+    external_addrs: &RwLock<HashSet<Multiaddr>>,
+    bitswap: &RwLock<BitswapLedger>,
+    membership: &RwLock<MembershipTable>,
+    replication: &ReplicationManager,
+    remote_peer_id: &PeerId,
+    event_tx: &broadcast::Sender<NetworkEvent>,
+    metrics: &NodeMetrics,
+    identity_keys: &IdentityKeys,
+    identity_private: &[u8; 32],
+    node_name: &str,
+    trust_store: &RwLock<TrustStore>,
+    paired_devices: &RwLock<PairedDeviceStore>,
+    pending_pairings: &RwLock<HashMap<PeerId, PairingSession>>,
+    control_sessions: &RwLock<HashMap<PeerId, Session>>,
+    sync_progress_tx: &watch::Sender<SyncProgress>,
+    access: &RwLock<PeerAccessControl>,
+    local_secure_id: &RwLock<Option<[u8; 20]>>,
 ) -> GrabResponse {
     match request {
         GrabRequest::FindSite { site_id } => {
             if let Some(revision) = announced_sites.read().get(&site_id) {
+                let addresses = external_addrs.read().iter().map(|a| a.to_string()).collect();
                 GrabResponse::SiteHosts {
                     hosts: vec![PeerRecord {
                         peer_id: local_peer_id.to_string(),
-                        addresses: vec![],
+                        addresses,
                         revision: *revision,
                     }],
                 }
@@ -546,24 +2233,266 @@ async fn handle_request(
             }
         }
         GrabRequest::GetChunks { chunk_ids } => {
+            // A peer we've sent a lot to without much reciprocation gets
+            // throttled to a trickle per request rather than refused
+            // outright, so a slow starter isn't locked out forever.
+            let ledger = bitswap.read().ledger_for(remote_peer_id);
+            let budget = match ledger.send_receive_ratio() {
+                Some(ratio) if ratio > FREELOADER_RATIO && ledger.bytes_sent > MIN_BYTES_FOR_RECIPROCATION_CHECK => {
+                    THROTTLED_CHUNKS_PER_REQUEST
+                }
+                _ => chunk_ids.len(),
+            };
+
             let mut chunks = Vec::new();
-            for chunk_id in chunk_ids {
+            let mut bytes_sent = 0u64;
+            for chunk_id in chunk_ids.into_iter().take(budget) {
                 if let Ok(Some(data)) = chunk_store.get(&chunk_id) {
+                    bytes_sent += data.len() as u64;
                     chunks.push((chunk_id, data));
                 }
             }
+            if !chunks.is_empty() {
+                bitswap.write().record_sent(*remote_peer_id, bytes_sent);
+                metrics.record_chunks_served(chunks.len() as u64, bytes_sent);
+            }
             GrabResponse::Chunks { chunks }
         }
+        GrabRequest::WantHave { chunk_ids } => {
+            let mut have = Vec::new();
+            let mut dont_have = Vec::new();
+            for chunk_id in chunk_ids {
+                match chunk_store.contains(&chunk_id) {
+                    Ok(true) => have.push(chunk_id),
+                    _ => dont_have.push(chunk_id),
+                }
+            }
+            GrabResponse::Have { have, dont_have }
+        }
         GrabRequest::Announce { site_id, revision } => {
             tracing::info!("Peer announced site {} revision {}", site_id.to_base58(), revision);
             GrabResponse::Ack
         }
         GrabRequest::PushUpdate { bundle } => {
-            if let Err(e) = bundle_store.save_bundle(&bundle) {
-                return GrabResponse::Error { message: e.to_string() };
+            let became_head = match bundle_store.save_bundle_revision(&bundle) {
+                Ok(became_head) => became_head,
+                Err(e) => return GrabResponse::Error { message: e.to_string() },
+            };
+            if became_head {
+                tracing::info!("Received update for {} revision {}", bundle.name, bundle.revision);
+            } else {
+                tracing::info!(
+                    "Received concurrent (forked) revision {} for {}, kept as a sibling",
+                    bundle.revision, bundle.name
+                );
             }
-            tracing::info!("Received update for {} revision {}", bundle.name, bundle.revision);
             GrabResponse::Ack
         }
+        GrabRequest::Ping { delta, secure_id } => {
+            membership.write().upsert(remote_peer_id.to_string(), vec![]);
+            if let Some(id) = secure_id {
+                access.write().record_exchanged_secure_id(*remote_peer_id, id);
+            }
+            let (joined, left) = membership.write().apply_delta(delta, &local_peer_id.to_string());
+            for peer_id in joined {
+                let _ = event_tx.send(NetworkEvent::PeerJoined { peer_id });
+            }
+            for peer_id in left {
+                let _ = event_tx.send(NetworkEvent::PeerLeft { peer_id });
+            }
+            GrabResponse::Pong { delta: membership.read().to_delta(), secure_id: *local_secure_id.read() }
+        }
+        GrabRequest::IndirectPing { .. } => {
+            // Handled directly in `run_swarm`, which has the swarm access
+            // needed to actually send the nested probe to `target`; this
+            // arm only exists so the match stays exhaustive.
+            GrabResponse::Error { message: "IndirectPing must be handled by the swarm event loop".to_string() }
+        }
+        GrabRequest::Gossip { delta } => {
+            let (joined, left) = membership.write().apply_delta(delta, &local_peer_id.to_string());
+            for peer_id in joined {
+                let _ = event_tx.send(NetworkEvent::PeerJoined { peer_id });
+            }
+            for peer_id in left {
+                let _ = event_tx.send(NetworkEvent::PeerLeft { peer_id });
+            }
+            GrabResponse::Gossip { delta: membership.read().to_delta() }
+        }
+        GrabRequest::ResolveName { name } => {
+            match name_store.resolve(&name) {
+                Ok(record) => GrabResponse::NameResolved { record },
+                Err(e) => GrabResponse::Error { message: e.to_string() },
+            }
+        }
+        GrabRequest::AnnounceName { record } => {
+            match name_store.offer(&record) {
+                Ok(true) => GrabResponse::Ack,
+                Ok(false) => GrabResponse::Error { message: "Name claim rejected (unverified, stale, or already claimed)".to_string() },
+                Err(e) => GrabResponse::Error { message: e.to_string() },
+            }
+        }
+        GrabRequest::MerkleDiffQuery { site_id, nodes } => {
+            match bundle_store.get_bundle(&site_id) {
+                Ok(Some(bundle)) => {
+                    let leaf_hashes: Vec<[u8; 32]> = bundle.manifest.files.iter().map(|f| f.hash).collect();
+                    let tree = AppendMerkleTree::from_leaves(leaf_hashes);
+                    let hashes = nodes.iter().map(|node| tree.node_hash(node.height, node.index)).collect();
+                    GrabResponse::MerkleDiffReply { hashes }
+                }
+                Ok(None) => GrabResponse::Error { message: "Site not found".to_string() },
+                Err(e) => GrabResponse::Error { message: e.to_string() },
+            }
+        }
+        GrabRequest::PullReplication { filters } => {
+            let records = filters.iter().flat_map(|filter| replication.respond_to_pull(filter)).collect();
+            GrabResponse::PullReplicationReply { records }
+        }
+        GrabRequest::GetChunkWithProof { site_id, chunk_id } => {
+            let bundle = match bundle_store.get_bundle(&site_id) {
+                Ok(Some(bundle)) => bundle,
+                Ok(None) => return GrabResponse::Error { message: "Site not found".to_string() },
+                Err(e) => return GrabResponse::Error { message: e.to_string() },
+            };
+            let Some(leaf_index) = bundle.manifest.files.iter().position(|f| f.hash == chunk_id) else {
+                return GrabResponse::Error { message: "Chunk not part of this site".to_string() };
+            };
+            let data = match chunk_store.get(&chunk_id) {
+                Ok(Some(data)) => data,
+                Ok(None) => return GrabResponse::Error { message: "Don't have that chunk locally".to_string() },
+                Err(e) => return GrabResponse::Error { message: e.to_string() },
+            };
+            let file_hashes: Vec<[u8; 32]> = bundle.manifest.files.iter().map(|f| f.hash).collect();
+            let mmr = MerkleMountainRange::from_leaves(file_hashes);
+            match mmr.proof(leaf_index) {
+                Some(proof) => GrabResponse::ChunkWithProof { data, proof },
+                None => GrabResponse::Error { message: "Failed to build Merkle proof".to_string() },
+            }
+        }
+        GrabRequest::GetRevisionState { site_id } => {
+            match bundle_store.get_bundle(&site_id) {
+                Ok(Some(bundle)) => {
+                    let have_chunks = bundle.manifest.files.iter()
+                        .flat_map(|f| f.chunks.iter().copied())
+                        .filter(|chunk_id| matches!(chunk_store.contains(chunk_id), Ok(true)))
+                        .collect();
+                    GrabResponse::RevisionState { have_revision: bundle.revision, have_chunks }
+                }
+                Ok(None) => GrabResponse::RevisionState { have_revision: 0, have_chunks: vec![] },
+                Err(e) => GrabResponse::Error { message: e.to_string() },
+            }
+        }
+        GrabRequest::PushRevisionDelta { bundle, preamble, chunk_payload } => {
+            let decompressed = match zstd::stream::decode_all(&chunk_payload[..]) {
+                Ok(data) => data,
+                Err(e) => return GrabResponse::Error { message: format!("Failed to decompress revision delta: {}", e) },
+            };
+            let chunks: Vec<(ChunkId, Vec<u8>)> = match bincode::deserialize(&decompressed) {
+                Ok(chunks) => chunks,
+                Err(e) => return GrabResponse::Error { message: format!("Failed to deserialize revision delta: {}", e) },
+            };
+
+            let _ = sync_progress_tx.send(SyncProgress {
+                direction: SyncDirection::Receiving,
+                site_id: preamble.site_id,
+                chunks_done: 0,
+                total_chunks: preamble.chunk_count,
+                bytes_done: 0,
+                total_bytes: preamble.byte_count,
+            });
+            let mut bytes_done = 0u64;
+            for (i, (chunk_id, data)) in chunks.into_iter().enumerate() {
+                if hash(&data) != chunk_id {
+                    tracing::warn!("Peer {} sent a revision-delta chunk that didn't match its hash, dropping", remote_peer_id);
+                    continue;
+                }
+                bytes_done += data.len() as u64;
+                if let Err(e) = chunk_store.put(&data) {
+                    let _ = sync_progress_tx.send(SyncProgress::default());
+                    return GrabResponse::Error { message: e.to_string() };
+                }
+                let _ = sync_progress_tx.send(SyncProgress {
+                    direction: SyncDirection::Receiving,
+                    site_id: preamble.site_id,
+                    chunks_done: i + 1,
+                    total_chunks: preamble.chunk_count,
+                    bytes_done,
+                    total_bytes: preamble.byte_count,
+                });
+            }
+            let result = match bundle_store.save_bundle_revision(&bundle) {
+                Ok(became_head) => {
+                    if became_head {
+                        tracing::info!("Received revision delta for {} revision {}", bundle.name, bundle.revision);
+                    } else {
+                        tracing::info!(
+                            "Received concurrent (forked) revision {} for {} via delta, kept as a sibling",
+                            bundle.revision, bundle.name
+                        );
+                    }
+                    GrabResponse::Ack
+                }
+                Err(e) => GrabResponse::Error { message: e.to_string() },
+            };
+            let _ = sync_progress_tx.send(SyncProgress::default());
+            result
+        }
+        GrabRequest::PairingOffer { offer } => {
+            match PairingSession::respond(identity_keys, identity_private, offer, RekeyPolicy::default()) {
+                Ok((session, response)) => {
+                    pending_pairings.write().insert(*remote_peer_id, session);
+                    GrabResponse::PairingResponse { response }
+                }
+                Err(e) => GrabResponse::Error { message: format!("pairing handshake failed: {}", e) },
+            }
+        }
+        GrabRequest::PairingConfirm { sealed } => {
+            let Some(mut session) = pending_pairings.write().remove(remote_peer_id) else {
+                return GrabResponse::Error { message: "no pending pairing handshake with this peer".to_string() };
+            };
+
+            let info = match session.open_node_information(&sealed) {
+                Ok(info) => info,
+                Err(e) => return GrabResponse::Error { message: e.to_string() },
+            };
+
+            let local_site_ids: Vec<SiteId> = announced_sites.read().keys().copied().collect();
+            let our_sealed = match session.seal_node_information(
+                &identity_keys.ed25519_public,
+                identity_private,
+                local_peer_id.to_string(),
+                node_name.to_string(),
+                local_site_ids,
+            ) {
+                Ok(sealed) => sealed,
+                Err(e) => return GrabResponse::Error { message: e.to_string() },
+            };
+
+            trust_store.write().add_trusted(&info.pubkey);
+            let paired_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64;
+            paired_devices.write().upsert(PairedDevice {
+                public_key: crate::crypto::encode_base58(&info.pubkey),
+                peer_id: info.peer_id.clone(),
+                name: info.name.clone(),
+                site_count: info.site_ids.len(),
+                addresses: vec![],
+                paired_at,
+            });
+            control_sessions.write().insert(*remote_peer_id, session.into_session());
+
+            GrabResponse::PairingConfirmed { sealed: our_sealed }
+        }
+        GrabRequest::ControlMessage { sealed } => {
+            let mut sessions = control_sessions.write();
+            let Some(session) = sessions.get_mut(remote_peer_id) else {
+                return GrabResponse::Error { message: "not paired with this peer".to_string() };
+            };
+            match session.open(&sealed).and_then(|plaintext| session.seal(&plaintext)) {
+                Ok(sealed) => GrabResponse::ControlMessage { sealed },
+                Err(e) => GrabResponse::Error { message: e.to_string() },
+            }
+        }
     }
 }
@@ -0,0 +1,183 @@
+//! Device pairing via out-of-band confirmation
+//!
+//! Two nodes that have never met establish trust the way Spacedrive pairs a
+//! device to a library: run an unauthenticated [`Session::pair_initiate`]/
+//! [`Session::pair_respond`] handshake, show both humans the resulting
+//! [`Session::transcript_code`], and only once they confirm it matches does
+//! the exchanged [`NodeInformation`] get trusted. A `PairingSession` wraps
+//! the raw `Session` so callers don't have to juggle the handshake, the
+//! confirmation code, and the sealed `NodeInformation` exchange separately.
+
+use anyhow::{bail, Result};
+
+use crate::crypto::{sign_node_information, verify_node_information};
+use crate::types::{NodeInformation, PublicKey};
+
+use super::session::{HandshakeMessage, IdentityKeys, RekeyPolicy, Session};
+
+/// An in-progress or completed pairing handshake, awaiting out-of-band
+/// confirmation of [`Self::confirmation_code`] before its remote identity
+/// is trusted.
+pub struct PairingSession {
+    session: Session,
+}
+
+impl PairingSession {
+    /// Start pairing as the initiator: `remote` is the reply the other side
+    /// produced via [`Self::respond`] to our
+    /// [`super::session::initial_handshake_message`] offer.
+    pub fn initiate(
+        local: &IdentityKeys,
+        local_private: &[u8; 32],
+        remote: HandshakeMessage,
+        policy: RekeyPolicy,
+    ) -> Result<(Self, HandshakeMessage)> {
+        let (session, response) = Session::pair_initiate(local, local_private, remote, policy)?;
+        Ok((Self { session }, response))
+    }
+
+    /// Respond to a pairing offer. See [`Self::initiate`].
+    pub fn respond(
+        local: &IdentityKeys,
+        local_private: &[u8; 32],
+        remote: HandshakeMessage,
+        policy: RekeyPolicy,
+    ) -> Result<(Self, HandshakeMessage)> {
+        let (session, response) = Session::pair_respond(local, local_private, remote, policy)?;
+        Ok((Self { session }, response))
+    }
+
+    /// Six-digit code for a human to compare out-of-band before trusting
+    /// [`Self::remote_identity`]. See [`Session::transcript_code`].
+    pub fn confirmation_code(&self) -> u32 {
+        self.session.transcript_code()
+    }
+
+    /// Ed25519 identity the handshake negotiated with, not yet trusted until
+    /// a human confirms [`Self::confirmation_code`] out-of-band.
+    pub fn remote_identity(&self) -> PublicKey {
+        self.session.remote_identity
+    }
+
+    /// Sign and seal `info` for the peer, for sending once a human has
+    /// confirmed [`Self::confirmation_code`] matches on both ends.
+    pub fn seal_node_information(
+        &mut self,
+        local_public: &PublicKey,
+        local_private: &[u8; 32],
+        peer_id: String,
+        name: String,
+        site_ids: Vec<[u8; 32]>,
+    ) -> Result<super::session::SealedFrame> {
+        let signature = sign_node_information(&peer_id, &name, &site_ids, local_public, local_private);
+        let info = NodeInformation {
+            peer_id,
+            name,
+            site_ids,
+            pubkey: *local_public,
+            signature,
+        };
+        let plaintext = serde_json::to_vec(&info)?;
+        self.session.seal(&plaintext)
+    }
+
+    /// Consume the pairing session for its underlying [`Session`], once the
+    /// `NodeInformation` exchange is done and trust is confirmed, so later
+    /// control-channel traffic can keep reusing the same encrypted tunnel.
+    pub(crate) fn into_session(self) -> Session {
+        self.session
+    }
+
+    /// Open and verify a peer's sealed [`NodeInformation`]. Fails if the
+    /// signature doesn't match the claimed `pubkey`, or if `pubkey` isn't
+    /// [`Self::remote_identity`] (so a confirmed session can't be handed
+    /// someone else's identity record).
+    pub fn open_node_information(&mut self, frame: &super::session::SealedFrame) -> Result<NodeInformation> {
+        let plaintext = self.session.open(frame)?;
+        let info: NodeInformation = serde_json::from_slice(&plaintext)?;
+
+        if info.pubkey != self.remote_identity() {
+            bail!("node information pubkey does not match the paired session's remote identity");
+        }
+        if !verify_node_information(&info.peer_id, &info.name, &info.site_ids, &info.pubkey, &info.signature) {
+            bail!("node information signature is invalid");
+        }
+
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_keypair;
+
+    fn make_identity() -> (IdentityKeys, [u8; 32]) {
+        let (public, private) = generate_keypair();
+        (IdentityKeys::derive(public, &private), private)
+    }
+
+    #[test]
+    fn test_pairing_session_exchanges_node_information() {
+        let (alice, alice_priv) = make_identity();
+        let (bob, bob_priv) = make_identity();
+
+        let alice_offer = super::super::session::initial_handshake_message(&alice);
+
+        let (mut bob_pairing, bob_response) =
+            PairingSession::respond(&bob, &bob_priv, alice_offer, RekeyPolicy::default()).unwrap();
+        let (mut alice_pairing, _) =
+            PairingSession::initiate(&alice, &alice_priv, bob_response, RekeyPolicy::default()).unwrap();
+
+        assert_eq!(alice_pairing.confirmation_code(), bob_pairing.confirmation_code());
+        assert_eq!(alice_pairing.remote_identity(), bob.ed25519_public);
+
+        let sealed = alice_pairing
+            .seal_node_information(
+                &alice.ed25519_public,
+                &alice_priv,
+                "peer-alice".to_string(),
+                "alice's laptop".to_string(),
+                vec![[1u8; 32]],
+            )
+            .unwrap();
+
+        let info = bob_pairing.open_node_information(&sealed).unwrap();
+        assert_eq!(info.peer_id, "peer-alice");
+        assert_eq!(info.name, "alice's laptop");
+        assert_eq!(info.site_ids, vec![[1u8; 32]]);
+    }
+
+    #[test]
+    fn test_tampered_node_information_rejected() {
+        let (alice, alice_priv) = make_identity();
+        let (bob, bob_priv) = make_identity();
+
+        let alice_offer = super::super::session::initial_handshake_message(&alice);
+        let (mut bob_pairing, bob_response) =
+            PairingSession::respond(&bob, &bob_priv, alice_offer, RekeyPolicy::default()).unwrap();
+        let (mut alice_pairing, _) =
+            PairingSession::initiate(&alice, &alice_priv, bob_response, RekeyPolicy::default()).unwrap();
+
+        // Someone else's signed info, forwarded under alice's identity, must be rejected.
+        let (mallory, mallory_priv) = make_identity();
+        let forged_signature = sign_node_information(
+            "peer-alice",
+            "alice's laptop",
+            &[[1u8; 32]],
+            &alice.ed25519_public,
+            &mallory_priv,
+        );
+        let forged = NodeInformation {
+            peer_id: "peer-alice".to_string(),
+            name: "alice's laptop".to_string(),
+            site_ids: vec![[1u8; 32]],
+            pubkey: alice.ed25519_public,
+            signature: forged_signature,
+        };
+        let _ = mallory; // only its private key is used above
+        let sealed = alice_pairing.session.seal(&serde_json::to_vec(&forged).unwrap()).unwrap();
+
+        assert!(bob_pairing.open_node_information(&sealed).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Gossip-based peer exchange
+//!
+//! Supplements [`crate::network::bootstrap::BootstrapConfig`] with a
+//! dynamically updated table of peers learned from the network itself,
+//! so a node's address book keeps growing even as the static bootstrap
+//! list goes stale.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::network::bootstrap::BootstrapConfig;
+
+/// How long a gossiped peer entry is kept without being refreshed.
+const ENTRY_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// A peer entry learned via gossip, with a freshness timestamp.
+#[derive(Debug, Clone)]
+pub struct PeerEntry {
+    pub peer_id: Option<String>,
+    pub addresses: Vec<String>,
+    last_seen: Instant,
+}
+
+impl PeerEntry {
+    fn is_expired(&self) -> bool {
+        self.last_seen.elapsed() >= ENTRY_TTL
+    }
+}
+
+/// Table of peers discovered via gossip, keyed by a stable name (the peer ID
+/// when known, otherwise the first known address).
+#[derive(Debug, Default)]
+pub struct PeerTable {
+    entries: HashMap<String, PeerEntry>,
+}
+
+impl PeerTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record or refresh a peer learned via gossip.
+    pub fn record(&mut self, key: impl Into<String>, peer_id: Option<String>, addresses: Vec<String>) {
+        let key = key.into();
+        self.entries
+            .entry(key)
+            .and_modify(|e| {
+                e.last_seen = Instant::now();
+                for addr in &addresses {
+                    if !e.addresses.contains(addr) {
+                        e.addresses.push(addr.clone());
+                    }
+                }
+                if e.peer_id.is_none() {
+                    e.peer_id = peer_id.clone();
+                }
+            })
+            .or_insert(PeerEntry {
+                peer_id,
+                addresses,
+                last_seen: Instant::now(),
+            });
+    }
+
+    /// Drop entries that have not been refreshed within [`ENTRY_TTL`].
+    pub fn evict_expired(&mut self) {
+        self.entries.retain(|_, entry| !entry.is_expired());
+    }
+
+    /// Number of live (non-expired) entries.
+    pub fn len(&self) -> usize {
+        self.entries.values().filter(|e| !e.is_expired()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All live entries.
+    pub fn entries(&self) -> Vec<&PeerEntry> {
+        self.entries.values().filter(|e| !e.is_expired()).collect()
+    }
+
+    /// Drive the table towards having at least `min_peers` live entries by
+    /// promoting gossiped peers into `config.custom`, so they are reused as
+    /// bootstrap candidates across restarts. Returns the number of new
+    /// entries promoted.
+    pub fn converge(&mut self, config: &mut BootstrapConfig, min_peers: usize) -> usize {
+        self.evict_expired();
+
+        if self.len() >= min_peers {
+            return 0;
+        }
+
+        let already_known: Vec<String> = config
+            .list_all()
+            .iter()
+            .flat_map(|n| n.addresses.clone())
+            .collect();
+
+        let mut promoted = 0;
+        for entry in self.entries.values() {
+            if entry.is_expired() {
+                continue;
+            }
+            let new_addresses: Vec<String> = entry
+                .addresses
+                .iter()
+                .filter(|a| !already_known.contains(a))
+                .cloned()
+                .collect();
+            if new_addresses.is_empty() {
+                continue;
+            }
+
+            let name = entry
+                .peer_id
+                .clone()
+                .unwrap_or_else(|| format!("gossiped-{promoted}"));
+            config.add_custom(name, new_addresses);
+            promoted += 1;
+
+            if config.list_all().len() >= min_peers {
+                break;
+            }
+        }
+
+        promoted
+    }
+
+    /// Converge using `config`'s own configured minimum peer count.
+    pub fn converge_to_config_minimum(&mut self, config: &mut BootstrapConfig) -> usize {
+        let min_peers = config.min_peers;
+        self.converge(config, min_peers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_len() {
+        let mut table = PeerTable::new();
+        table.record("peer-a", Some("peer-a".to_string()), vec!["/ip4/1.2.3.4/tcp/4001".to_string()]);
+        table.record("peer-b", None, vec!["/ip4/5.6.7.8/tcp/4001".to_string()]);
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn test_record_merges_addresses() {
+        let mut table = PeerTable::new();
+        table.record("peer-a", None, vec!["/ip4/1.2.3.4/tcp/4001".to_string()]);
+        table.record("peer-a", None, vec!["/ip4/9.9.9.9/tcp/4001".to_string()]);
+
+        let entry = table.entries().into_iter().next().unwrap();
+        assert_eq!(entry.addresses.len(), 2);
+    }
+
+    #[test]
+    fn test_converge_promotes_into_custom() {
+        let mut table = PeerTable::new();
+        table.record("peer-a", Some("peer-a".to_string()), vec!["/ip4/1.2.3.4/tcp/4001".to_string()]);
+        table.record("peer-b", Some("peer-b".to_string()), vec!["/ip4/5.6.7.8/tcp/4001".to_string()]);
+
+        let mut config = BootstrapConfig {
+            official: vec![],
+            community: vec![],
+            custom: vec![],
+            mdns_enabled: false,
+            min_peers: 3,
+            max_attempts: 10,
+            ws_proxy: None,
+        };
+
+        let promoted = table.converge(&mut config, 3);
+        assert_eq!(promoted, 2);
+        assert_eq!(config.custom.len(), 2);
+    }
+
+    #[test]
+    fn test_converge_stops_once_min_peers_met() {
+        let mut table = PeerTable::new();
+        table.record("peer-a", Some("peer-a".to_string()), vec!["/ip4/1.2.3.4/tcp/4001".to_string()]);
+
+        let mut config = BootstrapConfig {
+            official: vec![],
+            community: vec![],
+            custom: vec![],
+            mdns_enabled: false,
+            min_peers: 1,
+            max_attempts: 10,
+            ws_proxy: None,
+        };
+        config.add_custom("existing".to_string(), vec!["/ip4/0.0.0.0/tcp/4001".to_string()]);
+
+        let promoted = table.converge(&mut config, 1);
+        assert_eq!(promoted, 0, "table already holds min_peers live entries");
+    }
+}
@@ -0,0 +1,172 @@
+//! Active repair: turns `missing_chunks` entries from `SiteHealth` into
+//! verified chunk fetches from ranked source peers.
+//!
+//! Jobs are fed through an `mpsc` channel rather than driven by a fixed
+//! scan loop, so both a periodic sweep of `get_sites_needing_attention`
+//! and one-off "this peer just told us it's missing X" events can feed
+//! the same queue. Each job runs as its own task: a slow or dead source
+//! peer only delays retries of that one chunk, never the rest of the
+//! queue, and failed fetches and verification failures both back off
+//! exponentially before giving up after `MAX_ATTEMPTS`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use libp2p::PeerId;
+use tokio::sync::mpsc;
+
+use crate::types::{SiteId, ChunkId};
+use crate::crypto::{hash, MerkleMountainRange};
+use crate::storage::{BundleStore, ChunkStore};
+use super::GrabNetwork;
+use super::replication::ReplicationManager;
+
+/// What a repair job is trying to fix. Chunk repair is the only kind
+/// today; keeping it as an enum rather than baking `(SiteId, ChunkId)`
+/// straight into the job type leaves room for other repair kinds (e.g.
+/// a whole-manifest re-fetch) without changing the queue's shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairType {
+    Chunk(SiteId, ChunkId),
+}
+
+/// Outcome of one repair attempt, used to decide whether to retry.
+enum RepairOutcome {
+    Repaired,
+    /// No known host to ask right now
+    NoSource,
+    /// Fetch or verification failed
+    Failed,
+}
+
+/// Repair jobs are attempted up to this many times (the first attempt plus
+/// four retries) before being dropped.
+const MAX_ATTEMPTS: u32 = 5;
+/// Base delay before the first retry; doubled on each subsequent one.
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Drains `ReplicationManager::get_sites_needing_attention`'s
+/// `missing_chunks` into a queue of repair jobs, fetching and
+/// Merkle-verifying each from a ranked source peer (see
+/// [`ReplicationManager::rank_sources`]) before accepting it.
+pub struct RepairService {
+    job_tx: mpsc::Sender<(RepairType, u32)>,
+    _task: tokio::task::JoinHandle<()>,
+}
+
+impl RepairService {
+    /// Spawn the background task and return a handle for enqueueing jobs.
+    /// Dropping the returned `RepairService` stops the queue (the
+    /// background task is aborted on drop).
+    pub fn spawn(
+        network: Arc<GrabNetwork>,
+        replication: Arc<ReplicationManager>,
+        bundle_store: Arc<BundleStore>,
+        chunk_store: Arc<ChunkStore>,
+    ) -> Self {
+        let (job_tx, job_rx) = mpsc::channel(256);
+        let task = tokio::spawn(Self::run(network, replication, bundle_store, chunk_store, job_rx, job_tx.clone()));
+        Self { job_tx, _task: task }
+    }
+
+    /// Queue a repair job for its first attempt. Returns `false` if the
+    /// background task is gone.
+    pub async fn enqueue(&self, kind: RepairType) -> bool {
+        self.job_tx.send((kind, 0)).await.is_ok()
+    }
+
+    /// Scan every unhealthy site and enqueue a repair job per missing
+    /// chunk. Intended to be called periodically (e.g. alongside the
+    /// replication gossip heartbeat).
+    pub async fn enqueue_missing(&self, replication: &ReplicationManager) {
+        for (site_id, health) in replication.get_sites_needing_attention() {
+            for chunk_id in health.missing_chunks {
+                self.enqueue(RepairType::Chunk(site_id, chunk_id)).await;
+            }
+        }
+    }
+
+    async fn run(
+        network: Arc<GrabNetwork>,
+        replication: Arc<ReplicationManager>,
+        bundle_store: Arc<BundleStore>,
+        chunk_store: Arc<ChunkStore>,
+        mut job_rx: mpsc::Receiver<(RepairType, u32)>,
+        job_tx: mpsc::Sender<(RepairType, u32)>,
+    ) {
+        while let Some((kind, attempt)) = job_rx.recv().await {
+            let network = network.clone();
+            let replication = replication.clone();
+            let bundle_store = bundle_store.clone();
+            let chunk_store = chunk_store.clone();
+            let retry_tx = job_tx.clone();
+
+            tokio::spawn(async move {
+                let outcome = Self::attempt(&network, &replication, &bundle_store, &chunk_store, kind).await;
+                if matches!(outcome, RepairOutcome::NoSource | RepairOutcome::Failed) && attempt + 1 < MAX_ATTEMPTS {
+                    let backoff = BASE_BACKOFF * 2u32.pow(attempt);
+                    tokio::time::sleep(backoff).await;
+                    let _ = retry_tx.send((kind, attempt + 1)).await;
+                }
+            });
+        }
+    }
+
+    /// Try once to repair `kind`: pick the best-ranked source, fetch the
+    /// chunk with its Merkle proof, verify it against the site's signed
+    /// root, and store it on success.
+    async fn attempt(
+        network: &GrabNetwork,
+        replication: &ReplicationManager,
+        bundle_store: &BundleStore,
+        chunk_store: &ChunkStore,
+        kind: RepairType,
+    ) -> RepairOutcome {
+        let RepairType::Chunk(site_id, chunk_id) = kind;
+
+        let Some(source) = replication.rank_sources(&site_id).into_iter().next() else {
+            return RepairOutcome::NoSource;
+        };
+        let Ok(peer_id) = source.parse::<PeerId>() else {
+            return RepairOutcome::Failed;
+        };
+        let expected_root = match bundle_store.get_bundle(&site_id) {
+            Ok(Some(bundle)) => bundle.root_hash,
+            _ => return RepairOutcome::Failed,
+        };
+
+        let started = Instant::now();
+        let (data, proof) = match network.fetch_chunk_with_proof(&peer_id, &site_id, &chunk_id).await {
+            Ok(result) => result,
+            Err(_) => {
+                replication.record_transfer_failure(&source);
+                return RepairOutcome::Failed;
+            }
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let verified = hash(&data) == chunk_id
+            && proof.root == expected_root
+            && MerkleMountainRange::verify(&proof);
+
+        if !verified {
+            replication.record_transfer_failure(&source);
+            return RepairOutcome::Failed;
+        }
+
+        if chunk_store.put(&data).is_err() {
+            return RepairOutcome::Failed;
+        }
+
+        replication.record_transfer_success(&source, latency_ms);
+        replication.chunk_repaired(&site_id, &chunk_id);
+        RepairOutcome::Repaired
+    }
+}
+
+impl Drop for RepairService {
+    fn drop(&mut self) {
+        // `run` holds its own sender for retries, so the queue never closes
+        // on its own; abort the task so dropping the handle stops it.
+        self._task.abort();
+    }
+}
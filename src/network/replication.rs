@@ -5,13 +5,23 @@
 
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use parking_lot::RwLock;
 use tokio::sync::mpsc;
+use rand::Rng;
 
 use crate::types::{SiteId, ChunkId};
-use crate::crypto::SiteIdExt;
+use crate::crypto::{SiteIdExt, hash_multi};
+
+/// Current time as Unix milliseconds, used as the default version stamp
+/// for gossiped replication records.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
 
 /// Replication policy for a site
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +73,164 @@ pub enum HealthStatus {
     Unknown,
 }
 
+/// A single `(SiteId, PeerId)` fact in the gossiped replication CRDT: a
+/// last-writer-wins map where the higher `version` always wins on merge,
+/// regardless of which node it was learned from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionedRecord {
+    pub site_id: SiteId,
+    pub peer_id: String,
+    /// Unix milliseconds the record was stamped at; ties never happen in
+    /// practice, but are broken by keeping whichever is already stored.
+    pub version: u64,
+    /// Whether `peer_id` is hosting `site_id` as of `version`
+    pub hosting: bool,
+    /// The reporting peer's own health assessment for the site, if any.
+    /// Merged into the shared `health_cache` only when it's the newest
+    /// health observation seen for that site.
+    pub health: Option<SiteHealth>,
+}
+
+/// Digest of a gossip record's identity for Bloom-filter reconciliation:
+/// changing any of `site_id`, `peer_id`, or `version` changes the digest,
+/// so a filter built over old versions won't falsely claim a peer already
+/// has a newer one.
+fn record_digest(site_id: &SiteId, peer_id: &str, version: u64) -> [u8; 32] {
+    hash_multi(&[site_id, peer_id.as_bytes(), &version.to_le_bytes()])
+}
+
+/// Which partition a record's digest falls into under a `mask_bits`-wide
+/// hash-prefix split, matching the partition field on the [`CrdsFilter`]
+/// built for it.
+fn partition_of(digest: &[u8; 32], mask_bits: u32) -> u64 {
+    if mask_bits == 0 {
+        return 0;
+    }
+    let prefix = u64::from_be_bytes(digest[0..8].try_into().unwrap());
+    prefix >> (64 - mask_bits.min(64))
+}
+
+/// Bit count and hash-function count for a Bloom filter sized to hold
+/// `expected_items` with false-positive probability `target_fp`, rounded
+/// up to a whole number of 64-bit words (standard formulas: `m =
+/// -n*ln(p)/ln(2)^2`, `k = (m/n)*ln(2)`).
+fn optimal_bloom_size(expected_items: usize, target_fp: f64) -> (u64, u32) {
+    let n = (expected_items.max(1)) as f64;
+    let m = (-(n * target_fp.ln()) / (std::f64::consts::LN_2 * std::f64::consts::LN_2)).ceil();
+    let words = ((m as u64).max(1) + 63) / 64;
+    let num_bits = words * 64;
+    let num_hashes = ((num_bits as f64 / n) * std::f64::consts::LN_2).round().max(1.0) as u32;
+    (num_bits, num_hashes)
+}
+
+/// A partitioned Bloom filter over `(SiteId, PeerId, version)` digests,
+/// used to pull only the replication records a peer is missing instead of
+/// pushing the whole table on every heartbeat. A requester builds one of
+/// these per partition via [`ReplicationManager::build_pull_filter`] over
+/// what it already holds; the responder answers with
+/// [`ReplicationManager::respond_to_pull`], which returns only the records
+/// in that partition the filter doesn't match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrdsFilter {
+    /// Number of high bits of a record's digest used to assign it to a
+    /// partition
+    pub mask_bits: u32,
+    /// The partition this filter covers: only records whose digest's top
+    /// `mask_bits` equal `mask` are relevant to it
+    pub mask: u64,
+    /// Bits in the filter, packed 64 per word
+    bits: Vec<u64>,
+    /// Number of hash functions applied per digest
+    num_hashes: u32,
+}
+
+impl CrdsFilter {
+    fn empty(mask_bits: u32, mask: u64, num_bits: u64, num_hashes: u32) -> Self {
+        let words = ((num_bits + 63) / 64).max(1) as usize;
+        Self { mask_bits, mask, bits: vec![0u64; words], num_hashes: num_hashes.max(1) }
+    }
+
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    fn bit_positions(&self, digest: &[u8; 32]) -> impl Iterator<Item = u64> + '_ {
+        let h1 = u64::from_le_bytes(digest[0..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap());
+        let num_bits = self.num_bits();
+        (0..self.num_hashes).map(move |i| h1.wrapping_add((i as u64).wrapping_mul(h2)) % num_bits)
+    }
+
+    fn insert(&mut self, digest: &[u8; 32]) {
+        for pos in self.bit_positions(digest) {
+            self.bits[(pos / 64) as usize] |= 1u64 << (pos % 64);
+        }
+    }
+
+    /// Whether `digest` was (probably) inserted; false positives are
+    /// possible, false negatives are not.
+    fn contains(&self, digest: &[u8; 32]) -> bool {
+        self.bit_positions(digest).all(|pos| self.bits[(pos / 64) as usize] & (1u64 << (pos % 64)) != 0)
+    }
+}
+
+/// Floor weight given to a peer with no recorded transfers yet, so newly
+/// seen peers still get occasional replication/fetch traffic instead of
+/// being starved until something else vouches for them.
+const RELIABILITY_FLOOR_WEIGHT: f64 = 0.05;
+
+/// Rolling transfer reliability for one peer: success/failure counts and
+/// average latency, combined into a single weight for
+/// `ReplicationManager::select_replica_targets`/`rank_sources`.
+#[derive(Debug, Clone, Default)]
+struct PeerReliability {
+    successes: u64,
+    failures: u64,
+    avg_latency_ms: u64,
+}
+
+impl PeerReliability {
+    fn record_success(&mut self, latency_ms: u64) {
+        let n = self.successes + self.failures + 1;
+        self.avg_latency_ms = (self.avg_latency_ms * (self.successes + self.failures) + latency_ms) / n;
+        self.successes += 1;
+    }
+
+    fn record_failure(&mut self) {
+        self.failures += 1;
+    }
+
+    /// A weight in `(0, 1]`: success rate scaled down for high latency.
+    /// Never zero, so a peer can always be drawn in A-Res sampling.
+    fn weight(&self) -> f64 {
+        let total = self.successes + self.failures;
+        if total == 0 {
+            return RELIABILITY_FLOOR_WEIGHT;
+        }
+        let success_rate = self.successes as f64 / total as f64;
+        let latency_factor = 1000.0 / (self.avg_latency_ms as f64 + 1000.0);
+        (success_rate * latency_factor).max(RELIABILITY_FLOOR_WEIGHT)
+    }
+}
+
+/// Weighted random sampling without replacement via the A-Res reservoir
+/// technique: each candidate with weight `w_i` draws a key `u_i^(1/w_i)`
+/// for `u_i` uniform in `(0, 1]`, and the `count` largest keys win. A
+/// peer's chance of being picked scales with its weight, but even
+/// low-weight peers are occasionally drawn rather than starved outright.
+fn a_res_select(candidates: &[(String, f64)], count: usize, rng: &mut impl Rng) -> Vec<String> {
+    let mut keyed: Vec<(f64, &str)> = candidates
+        .iter()
+        .filter(|(_, weight)| *weight > 0.0)
+        .map(|(peer_id, weight)| {
+            let u: f64 = rng.gen_range(f64::MIN_POSITIVE..=1.0);
+            (u.powf(1.0 / weight), peer_id.as_str())
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    keyed.into_iter().take(count).map(|(_, peer_id)| peer_id.to_string()).collect()
+}
+
 /// Replication manager tracks site health and manages replication
 pub struct ReplicationManager {
     /// Replication policies by site ID
@@ -73,6 +241,20 @@ pub struct ReplicationManager {
     health_cache: Arc<RwLock<HashMap<SiteId, SiteHealth>>>,
     /// Last announcement time for each site
     last_announce: Arc<RwLock<HashMap<SiteId, Instant>>>,
+    /// CRDT state behind `site_hosts`/`health_cache`: the highest-version
+    /// record seen for each `(SiteId, PeerId)`
+    gossip_records: Arc<RwLock<HashMap<(SiteId, String), VersionedRecord>>>,
+    /// Version of whichever record last updated a site's `health_cache`
+    /// entry, so health from a stale gossip round can't clobber a fresher
+    /// one reported by a different peer
+    health_versions: Arc<RwLock<HashMap<SiteId, u64>>>,
+    /// Keys touched locally (via `add_host`/`remove_host`/`update_health`)
+    /// since the last `outgoing_push`
+    dirty: Arc<RwLock<HashSet<(SiteId, String)>>>,
+    /// Records older than this are dropped by `prune_expired`
+    gossip_ttl: Arc<RwLock<Duration>>,
+    /// Per-peer transfer reliability, backing `select_replica_targets`/`rank_sources`
+    reliability: Arc<RwLock<HashMap<String, PeerReliability>>>,
 }
 
 impl ReplicationManager {
@@ -82,9 +264,21 @@ impl ReplicationManager {
             site_hosts: Arc::new(RwLock::new(HashMap::new())),
             health_cache: Arc::new(RwLock::new(HashMap::new())),
             last_announce: Arc::new(RwLock::new(HashMap::new())),
+            gossip_records: Arc::new(RwLock::new(HashMap::new())),
+            health_versions: Arc::new(RwLock::new(HashMap::new())),
+            dirty: Arc::new(RwLock::new(HashSet::new())),
+            // Twice the default announce interval, so a host that's still
+            // alive but just hasn't re-announced yet isn't pruned early.
+            gossip_ttl: Arc::new(RwLock::new(Duration::from_secs(2 * 3600))),
+            reliability: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Configure how long a gossiped record is kept without being refreshed.
+    pub fn set_gossip_ttl(&self, ttl: Duration) {
+        *self.gossip_ttl.write() = ttl;
+    }
+
     /// Set replication policy for a site
     pub fn set_policy(&self, site_id: SiteId, policy: ReplicationPolicy) {
         self.policies.write().insert(site_id, policy);
@@ -99,20 +293,40 @@ impl ReplicationManager {
             .unwrap_or_default()
     }
 
-    /// Record a host for a site
+    /// Record a host for a site, stamping a fresh gossip record so the
+    /// fact propagates to other nodes on the next `outgoing_push`.
     pub fn add_host(&self, site_id: SiteId, peer_id: String) {
         self.site_hosts
             .write()
             .entry(site_id)
             .or_default()
-            .insert(peer_id);
+            .insert(peer_id.clone());
+        self.stamp_hosting_record(site_id, peer_id, true);
     }
 
-    /// Remove a host for a site
+    /// Remove a host for a site, stamping a fresh gossip record so the
+    /// removal propagates too (otherwise a peer that only ever saw the
+    /// earlier "hosting" record would keep believing it still does).
     pub fn remove_host(&self, site_id: &SiteId, peer_id: &str) {
         if let Some(hosts) = self.site_hosts.write().get_mut(site_id) {
             hosts.remove(peer_id);
         }
+        self.stamp_hosting_record(*site_id, peer_id.to_string(), false);
+    }
+
+    /// Record a locally-originated hosting fact in the gossip CRDT and
+    /// mark it dirty so it goes out on the next `outgoing_push`.
+    fn stamp_hosting_record(&self, site_id: SiteId, peer_id: String, hosting: bool) {
+        let key = (site_id, peer_id.clone());
+        let health = self.health_cache.read().get(&site_id).cloned();
+        self.gossip_records.write().insert(key.clone(), VersionedRecord {
+            site_id,
+            peer_id,
+            version: now_millis(),
+            hosting,
+            health,
+        });
+        self.dirty.write().insert(key);
     }
 
     /// Get known hosts for a site
@@ -129,9 +343,211 @@ impl ReplicationManager {
         self.health_cache.read().get(site_id).cloned()
     }
 
-    /// Update health status for a site
-    pub fn update_health(&self, health: SiteHealth) {
-        self.health_cache.write().insert(health.site_id, health);
+    /// Update health status for a site, and stamp a gossip record for it
+    /// under `self_peer_id` so our own assessment propagates on the next
+    /// `outgoing_push`.
+    pub fn update_health(&self, health: SiteHealth, self_peer_id: &str) {
+        let site_id = health.site_id;
+        let version = now_millis();
+        self.health_cache.write().insert(site_id, health.clone());
+        self.health_versions.write().insert(site_id, version);
+
+        let key = (site_id, self_peer_id.to_string());
+        let hosting = self.site_hosts.read().get(&site_id).map_or(false, |h| h.contains(self_peer_id));
+        self.gossip_records.write().insert(key.clone(), VersionedRecord {
+            site_id,
+            peer_id: self_peer_id.to_string(),
+            version,
+            hosting,
+            health: Some(health),
+        });
+        self.dirty.write().insert(key);
+    }
+
+    /// Merge incoming gossiped records into `site_hosts`/`health_cache`
+    /// using last-write-wins: a record is only applied if its version is
+    /// newer than whatever is already stored for that `(site_id, peer_id)`.
+    pub fn ingest_gossip(&self, records: Vec<VersionedRecord>) {
+        for record in records {
+            let key = (record.site_id, record.peer_id.clone());
+
+            let is_newer = match self.gossip_records.read().get(&key) {
+                Some(existing) => record.version > existing.version,
+                None => true,
+            };
+            if !is_newer {
+                continue;
+            }
+
+            if record.hosting {
+                self.site_hosts.write().entry(record.site_id).or_default().insert(record.peer_id.clone());
+            } else if let Some(hosts) = self.site_hosts.write().get_mut(&record.site_id) {
+                hosts.remove(&record.peer_id);
+            }
+
+            if let Some(health) = &record.health {
+                let is_newest_health = match self.health_versions.read().get(&record.site_id) {
+                    Some(&existing_version) => record.version > existing_version,
+                    None => true,
+                };
+                if is_newest_health {
+                    self.health_cache.write().insert(record.site_id, health.clone());
+                    self.health_versions.write().insert(record.site_id, record.version);
+                }
+            }
+
+            self.gossip_records.write().insert(key, record);
+        }
+    }
+
+    /// Records changed locally (via `add_host`, `remove_host`, or
+    /// `update_health`) since the last call, ready to publish on the next
+    /// gossipsub heartbeat.
+    pub fn outgoing_push(&self) -> Vec<VersionedRecord> {
+        let keys: Vec<_> = self.dirty.write().drain().collect();
+        let records = self.gossip_records.read();
+        keys.into_iter().filter_map(|key| records.get(&key).cloned()).collect()
+    }
+
+    /// Drop gossiped records (and the hosting facts they imply) that
+    /// haven't been refreshed within the configured TTL.
+    pub fn prune_expired(&self) {
+        let ttl_millis = self.gossip_ttl.read().as_millis() as u64;
+        let cutoff = now_millis().saturating_sub(ttl_millis);
+
+        let expired: Vec<(SiteId, String)> = self.gossip_records
+            .read()
+            .iter()
+            .filter(|(_, record)| record.version < cutoff)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut gossip_records = self.gossip_records.write();
+        let mut site_hosts = self.site_hosts.write();
+        for key in expired {
+            gossip_records.remove(&key);
+            if let Some(hosts) = site_hosts.get_mut(&key.0) {
+                hosts.remove(&key.1);
+            }
+        }
+    }
+
+    /// Target false-positive probability for filters built by
+    /// `build_pull_filter`
+    const PULL_FILTER_FALSE_POSITIVE_RATE: f64 = 0.1;
+
+    /// Build one Bloom filter per non-empty partition of our own gossip
+    /// records, splitting by the top `mask_bits` bits of each record's
+    /// digest. Send the result to a peer, which answers each filter with
+    /// `respond_to_pull` to return only the records we are missing,
+    /// instead of pushing the whole table.
+    pub fn build_pull_filter(&self, mask_bits: u32) -> Vec<CrdsFilter> {
+        let records = self.gossip_records.read();
+
+        let mut by_partition: HashMap<u64, Vec<[u8; 32]>> = HashMap::new();
+        for ((site_id, peer_id), record) in records.iter() {
+            let digest = record_digest(site_id, peer_id, record.version);
+            by_partition.entry(partition_of(&digest, mask_bits)).or_default().push(digest);
+        }
+
+        by_partition
+            .into_iter()
+            .map(|(mask, digests)| {
+                let (num_bits, num_hashes) = optimal_bloom_size(digests.len(), Self::PULL_FILTER_FALSE_POSITIVE_RATE);
+                let mut filter = CrdsFilter::empty(mask_bits, mask, num_bits, num_hashes);
+                for digest in &digests {
+                    filter.insert(digest);
+                }
+                filter
+            })
+            .collect()
+    }
+
+    /// Records in `filter`'s partition that it doesn't match, i.e. ones
+    /// the peer who sent it is presumed not to have yet.
+    pub fn respond_to_pull(&self, filter: &CrdsFilter) -> Vec<VersionedRecord> {
+        self.gossip_records
+            .read()
+            .values()
+            .filter(|record| {
+                let digest = record_digest(&record.site_id, &record.peer_id, record.version);
+                partition_of(&digest, filter.mask_bits) == filter.mask && !filter.contains(&digest)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Record a successful transfer with `peer_id`, feeding
+    /// `select_replica_targets`/`rank_sources`.
+    pub fn record_transfer_success(&self, peer_id: &str, latency_ms: u64) {
+        self.reliability.write().entry(peer_id.to_string()).or_default().record_success(latency_ms);
+    }
+
+    /// Record a failed transfer with `peer_id`, feeding
+    /// `select_replica_targets`/`rank_sources`.
+    pub fn record_transfer_failure(&self, peer_id: &str) {
+        self.reliability.write().entry(peer_id.to_string()).or_default().record_failure();
+    }
+
+    /// Pick up to `count` of `site_id`'s known hosts to replicate to,
+    /// weighted by transfer reliability via A-Res reservoir sampling so
+    /// reliable peers are favored without starving untested ones.
+    pub fn select_replica_targets(&self, site_id: &SiteId, count: usize) -> Vec<String> {
+        self.select_replica_targets_seeded(site_id, count, &mut rand::thread_rng())
+    }
+
+    /// As `select_replica_targets`, but driven by a caller-supplied RNG
+    /// (e.g. a seeded one) for deterministic tests.
+    pub fn select_replica_targets_seeded(&self, site_id: &SiteId, count: usize, rng: &mut impl Rng) -> Vec<String> {
+        a_res_select(&self.weighted_hosts(site_id), count, rng)
+    }
+
+    /// Rank all of `site_id`'s known hosts best-source-first, weighted by
+    /// transfer reliability — the same A-Res draw as
+    /// `select_replica_targets`, just taken all the way to the end so
+    /// every host appears exactly once.
+    pub fn rank_sources(&self, site_id: &SiteId) -> Vec<String> {
+        self.rank_sources_seeded(site_id, &mut rand::thread_rng())
+    }
+
+    /// As `rank_sources`, but driven by a caller-supplied RNG for
+    /// deterministic tests.
+    pub fn rank_sources_seeded(&self, site_id: &SiteId, rng: &mut impl Rng) -> Vec<String> {
+        let hosts = self.weighted_hosts(site_id);
+        let count = hosts.len();
+        a_res_select(&hosts, count, rng)
+    }
+
+    fn weighted_hosts(&self, site_id: &SiteId) -> Vec<(String, f64)> {
+        let reliability = self.reliability.read();
+        self.get_hosts(site_id)
+            .into_iter()
+            .map(|peer_id| {
+                let weight = reliability.get(&peer_id).map(PeerReliability::weight).unwrap_or(RELIABILITY_FLOOR_WEIGHT);
+                (peer_id, weight)
+            })
+            .collect()
+    }
+
+    /// Record that `chunk_id` was successfully repaired for `site_id`:
+    /// drop it from the cached `missing_chunks` and recompute
+    /// `HealthStatus` from what remains. A no-op if we have no cached
+    /// health for the site (nothing to update yet).
+    pub fn chunk_repaired(&self, site_id: &SiteId, chunk_id: &ChunkId) {
+        let mut cache = self.health_cache.write();
+        let Some(health) = cache.get_mut(site_id) else { return };
+
+        health.missing_chunks.retain(|c| c != chunk_id);
+        health.last_check = now_millis();
+
+        let policy = self.get_policy(site_id);
+        health.status = if !health.missing_chunks.is_empty() {
+            HealthStatus::Degraded
+        } else if health.known_hosts < policy.min_replicas {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        };
     }
 
     /// Check if a site needs replication
@@ -318,4 +734,257 @@ mod tests {
         manager.add_host(site_id, "peer3".to_string());
         assert!(!manager.needs_replication(&site_id)); // Met minimum
     }
+
+    #[test]
+    fn test_outgoing_push_reflects_local_changes_and_drains() {
+        let manager = ReplicationManager::new();
+        let site_id = [3u8; 32];
+
+        manager.add_host(site_id, "peer1".to_string());
+        let pushed = manager.outgoing_push();
+        assert_eq!(pushed.len(), 1);
+        assert_eq!(pushed[0].peer_id, "peer1");
+        assert!(pushed[0].hosting);
+
+        // Already drained; nothing new to push until another change happens
+        assert!(manager.outgoing_push().is_empty());
+
+        manager.remove_host(&site_id, "peer1");
+        let pushed = manager.outgoing_push();
+        assert_eq!(pushed.len(), 1);
+        assert!(!pushed[0].hosting);
+    }
+
+    #[test]
+    fn test_ingest_gossip_applies_newer_and_ignores_stale() {
+        let manager = ReplicationManager::new();
+        let site_id = [4u8; 32];
+
+        manager.ingest_gossip(vec![VersionedRecord {
+            site_id,
+            peer_id: "peer1".to_string(),
+            version: 100,
+            hosting: true,
+            health: None,
+        }]);
+        assert_eq!(manager.get_hosts(&site_id), vec!["peer1".to_string()]);
+
+        // A stale (lower-version) removal must not undo the newer claim
+        manager.ingest_gossip(vec![VersionedRecord {
+            site_id,
+            peer_id: "peer1".to_string(),
+            version: 50,
+            hosting: false,
+            health: None,
+        }]);
+        assert_eq!(manager.get_hosts(&site_id), vec!["peer1".to_string()]);
+
+        // A newer removal does win
+        manager.ingest_gossip(vec![VersionedRecord {
+            site_id,
+            peer_id: "peer1".to_string(),
+            version: 200,
+            hosting: false,
+            health: None,
+        }]);
+        assert!(manager.get_hosts(&site_id).is_empty());
+    }
+
+    #[test]
+    fn test_ingest_gossip_merges_newest_health_across_peers() {
+        let manager = ReplicationManager::new();
+        let site_id = [5u8; 32];
+
+        let stale_health = SiteHealth {
+            site_id,
+            known_hosts: 1,
+            verified_hosts: 1,
+            last_check: 0,
+            status: HealthStatus::Critical,
+            missing_chunks: vec![],
+        };
+        let fresh_health = SiteHealth {
+            site_id,
+            known_hosts: 3,
+            verified_hosts: 3,
+            last_check: 0,
+            status: HealthStatus::Healthy,
+            missing_chunks: vec![],
+        };
+
+        // The fresher report arrives first...
+        manager.ingest_gossip(vec![VersionedRecord {
+            site_id, peer_id: "peer2".to_string(), version: 200, hosting: true, health: Some(fresh_health),
+        }]);
+        // ...then a stale report from a different peer shouldn't clobber it
+        manager.ingest_gossip(vec![VersionedRecord {
+            site_id, peer_id: "peer1".to_string(), version: 100, hosting: true, health: Some(stale_health),
+        }]);
+
+        assert_eq!(manager.get_health(&site_id).unwrap().status, HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_prune_expired_drops_stale_records_and_hosts() {
+        let manager = ReplicationManager::new();
+        manager.set_gossip_ttl(Duration::from_millis(0));
+        let site_id = [6u8; 32];
+
+        manager.add_host(site_id, "peer1".to_string());
+        assert_eq!(manager.get_hosts(&site_id), vec!["peer1".to_string()]);
+
+        std::thread::sleep(Duration::from_millis(2));
+        manager.prune_expired();
+        assert!(manager.get_hosts(&site_id).is_empty());
+    }
+
+    #[test]
+    fn test_pull_filter_round_trip_finds_missing_records() {
+        let a = ReplicationManager::new();
+        let b = ReplicationManager::new();
+        let site_id = [7u8; 32];
+
+        // Shared record both already have
+        a.add_host(site_id, "shared".to_string());
+        b.ingest_gossip(a.outgoing_push());
+
+        // Records only `b` has
+        b.add_host(site_id, "only_on_b_1".to_string());
+        b.add_host(site_id, "only_on_b_2".to_string());
+
+        let filters = a.build_pull_filter(0);
+        assert_eq!(filters.len(), 1);
+
+        let pulled: Vec<_> = filters.iter().flat_map(|f| b.respond_to_pull(f)).collect();
+        let mut peer_ids: Vec<_> = pulled.iter().map(|r| r.peer_id.clone()).collect();
+        peer_ids.sort();
+        assert_eq!(peer_ids, vec!["only_on_b_1".to_string(), "only_on_b_2".to_string()]);
+    }
+
+    #[test]
+    fn test_pull_filter_partitions_by_mask_bits() {
+        let manager = ReplicationManager::new();
+        let site_id = [8u8; 32];
+        for i in 0..20 {
+            manager.add_host(site_id, format!("peer{i}"));
+        }
+
+        let filters = manager.build_pull_filter(2);
+        // At most 4 partitions (2^2); every filter only answers for its own mask
+        assert!(filters.len() <= 4);
+        for filter in &filters {
+            assert_eq!(filter.mask_bits, 2);
+            assert!(filter.mask < 4);
+        }
+
+        // A manager with no records has no non-empty partition, so it
+        // builds no filters at all
+        let empty = ReplicationManager::new();
+        let empty_filters = empty.build_pull_filter(2);
+        assert!(empty_filters.is_empty());
+    }
+
+    #[test]
+    fn test_rank_sources_favors_reliable_peers() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let manager = ReplicationManager::new();
+        let site_id = [9u8; 32];
+        manager.add_host(site_id, "reliable".to_string());
+        manager.add_host(site_id, "flaky".to_string());
+        manager.add_host(site_id, "untested".to_string());
+
+        for _ in 0..20 {
+            manager.record_transfer_success("reliable", 10);
+        }
+        for _ in 0..20 {
+            manager.record_transfer_failure("flaky");
+        }
+
+        // Over many seeded draws, the reliable peer should come out on top
+        // far more often than the flaky one.
+        let mut reliable_firsts = 0;
+        for seed in 0..50u64 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let ranked = manager.rank_sources_seeded(&site_id, &mut rng);
+            assert_eq!(ranked.len(), 3);
+            if ranked[0] == "reliable" {
+                reliable_firsts += 1;
+            }
+        }
+        assert!(reliable_firsts > 30, "expected the reliable peer to usually rank first, got {reliable_firsts}/50");
+    }
+
+    #[test]
+    fn test_select_replica_targets_is_deterministic_for_a_fixed_seed() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let manager = ReplicationManager::new();
+        let site_id = [10u8; 32];
+        for i in 0..5 {
+            manager.add_host(site_id, format!("peer{i}"));
+        }
+
+        let mut rng_a = StdRng::seed_from_u64(42);
+        let mut rng_b = StdRng::seed_from_u64(42);
+        let picked_a = manager.select_replica_targets_seeded(&site_id, 2, &mut rng_a);
+        let picked_b = manager.select_replica_targets_seeded(&site_id, 2, &mut rng_b);
+
+        assert_eq!(picked_a.len(), 2);
+        assert_eq!(picked_a, picked_b);
+    }
+
+    #[test]
+    fn test_new_peer_still_gets_occasional_selection() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let manager = ReplicationManager::new();
+        let site_id = [11u8; 32];
+        manager.add_host(site_id, "veteran".to_string());
+        manager.add_host(site_id, "newcomer".to_string());
+        for _ in 0..50 {
+            manager.record_transfer_success("veteran", 5);
+        }
+
+        let mut newcomer_picked = false;
+        for seed in 0..200u64 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let picked = manager.select_replica_targets_seeded(&site_id, 1, &mut rng);
+            if picked.first().map(String::as_str) == Some("newcomer") {
+                newcomer_picked = true;
+                break;
+            }
+        }
+        assert!(newcomer_picked, "an untested peer should still win the draw occasionally");
+    }
+
+    #[test]
+    fn test_chunk_repaired_clears_entry_and_recomputes_status() {
+        let manager = ReplicationManager::new();
+        let site_id = [12u8; 32];
+        let missing_a = [1u8; 32];
+        let missing_b = [2u8; 32];
+
+        manager.update_health(SiteHealth {
+            site_id,
+            known_hosts: 5,
+            verified_hosts: 5,
+            last_check: 0,
+            status: HealthStatus::Degraded,
+            missing_chunks: vec![missing_a, missing_b],
+        }, "self-peer");
+
+        manager.chunk_repaired(&site_id, &missing_a);
+        let health = manager.get_health(&site_id).unwrap();
+        assert_eq!(health.missing_chunks, vec![missing_b]);
+        assert_eq!(health.status, HealthStatus::Degraded);
+
+        manager.chunk_repaired(&site_id, &missing_b);
+        let health = manager.get_health(&site_id).unwrap();
+        assert!(health.missing_chunks.is_empty());
+        assert_eq!(health.status, HealthStatus::Healthy);
+    }
 }
@@ -0,0 +1,218 @@
+//! Session-based revision reconciliation for `push_update`.
+//!
+//! Pushing a whole [`crate::types::WebBundle`] (and every chunk behind it)
+//! to every host a site is replicated to wastes bandwidth once most hosts
+//! are only a revision or two behind — they already hold nearly
+//! everything. A `ReplicationSession` instead drives a short round trip
+//! per peer: ask what they have (`GrabRequest::GetRevisionState`), then
+//! send only the manifest and the specific chunks they're missing
+//! (`GrabRequest::PushRevisionDelta`), so transfer cost is proportional
+//! to the actual diff rather than the whole site.
+//!
+//! Sessions are keyed by `(PeerId, SiteId)` so several can be in flight at
+//! once — pushing the same update out to many hosts, or several sites
+//! updating concurrently — without serializing through shared state; see
+//! [`ReplicationSessionManager`].
+
+use std::collections::{HashMap, HashSet};
+
+use libp2p::PeerId;
+
+use crate::types::{ChunkId, SiteId};
+
+/// Which way a [`SyncProgress`] transfer is moving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncDirection {
+    /// No delta-sync transfer is currently in flight.
+    #[default]
+    Idle,
+    /// We're the one sending `PushRevisionDelta`'s chunk payload.
+    Sending,
+    /// We're the one receiving and applying it.
+    Receiving,
+}
+
+/// A snapshot of an in-flight (or just-finished) delta-sync transfer,
+/// published on a `tokio::sync::watch` channel rather than a `broadcast`
+/// event: a progress bar only ever cares about the latest value, not
+/// every intermediate update, and a watch channel lets a slow UI thread
+/// skip values instead of falling behind a queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncProgress {
+    pub direction: SyncDirection,
+    pub site_id: SiteId,
+    pub chunks_done: usize,
+    pub total_chunks: usize,
+    pub bytes_done: u64,
+    pub total_bytes: u64,
+}
+
+impl SyncProgress {
+    /// Whether `total_chunks` chunks have all been accounted for. Always
+    /// `false` while `direction` is `Idle`.
+    pub fn is_complete(&self) -> bool {
+        self.direction != SyncDirection::Idle && self.chunks_done >= self.total_chunks
+    }
+}
+
+/// Where a session is in its reconciliation round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionPhase {
+    /// Created, `GetRevisionState` not yet answered.
+    Announce,
+    /// `GetRevisionState` sent, waiting on the remote's `have_revision`/`have_chunks`.
+    HaveWant,
+    /// Remote is behind; `PushRevisionDelta` is in flight.
+    Transfer,
+    /// Remote was already caught up, or the delta transfer completed.
+    Done,
+}
+
+/// Tracks one peer's reconciliation against one site's current revision.
+#[derive(Debug, Clone)]
+pub struct ReplicationSession {
+    pub peer_id: PeerId,
+    pub site_id: SiteId,
+    pub local_revision: u64,
+    phase: SessionPhase,
+}
+
+impl ReplicationSession {
+    pub fn new(peer_id: PeerId, site_id: SiteId, local_revision: u64) -> Self {
+        Self { peer_id, site_id, local_revision, phase: SessionPhase::Announce }
+    }
+
+    pub fn phase(&self) -> SessionPhase {
+        self.phase
+    }
+
+    /// Move to the have/want phase now that `GetRevisionState` is about to
+    /// go out.
+    pub fn request_state(&mut self) {
+        self.phase = SessionPhase::HaveWant;
+    }
+
+    /// The peer's `have_revision`/`have_chunks` came back. Compares them
+    /// against `local_chunks` (every chunk the current revision needs) to
+    /// find what the peer is missing. Returns `None` if the peer is
+    /// already caught up, moving straight to `Done` with nothing to
+    /// transfer; otherwise moves to `Transfer` and returns the chunks to
+    /// send.
+    pub fn want(
+        &mut self,
+        have_revision: u64,
+        have_chunks: &[ChunkId],
+        local_chunks: &[ChunkId],
+    ) -> Option<Vec<ChunkId>> {
+        if have_revision >= self.local_revision {
+            self.phase = SessionPhase::Done;
+            return None;
+        }
+
+        let have: HashSet<ChunkId> = have_chunks.iter().copied().collect();
+        let missing: Vec<ChunkId> = local_chunks.iter().filter(|c| !have.contains(*c)).copied().collect();
+        self.phase = SessionPhase::Transfer;
+        Some(missing)
+    }
+
+    /// The delta was sent and acknowledged.
+    pub fn finish(&mut self) {
+        self.phase = SessionPhase::Done;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.phase == SessionPhase::Done
+    }
+}
+
+/// Concurrent [`ReplicationSession`]s keyed by `(PeerId, SiteId)`.
+#[derive(Debug, Default)]
+pub struct ReplicationSessionManager {
+    sessions: HashMap<(PeerId, SiteId), ReplicationSession>,
+}
+
+impl ReplicationSessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start (or restart) a session for `peer_id`/`site_id`.
+    pub fn begin(&mut self, peer_id: PeerId, site_id: SiteId, local_revision: u64) {
+        self.sessions.insert((peer_id, site_id), ReplicationSession::new(peer_id, site_id, local_revision));
+    }
+
+    pub fn phase(&self, peer_id: &PeerId, site_id: &SiteId) -> Option<SessionPhase> {
+        self.sessions.get(&(*peer_id, *site_id)).map(|s| s.phase())
+    }
+
+    pub fn get_mut(&mut self, peer_id: &PeerId, site_id: &SiteId) -> Option<&mut ReplicationSession> {
+        self.sessions.get_mut(&(*peer_id, *site_id))
+    }
+
+    /// Drop a finished session so the map doesn't grow forever across many pushes.
+    pub fn remove(&mut self, peer_id: &PeerId, site_id: &SiteId) {
+        self.sessions.remove(&(*peer_id, *site_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(b: u8) -> ChunkId {
+        [b; 32]
+    }
+
+    #[test]
+    fn test_caught_up_peer_needs_nothing() {
+        let mut session = ReplicationSession::new(PeerId::random(), [0u8; 32], 5);
+        session.request_state();
+        assert_eq!(session.want(5, &[chunk(1), chunk(2)], &[chunk(1), chunk(2)]), None);
+        assert_eq!(session.phase(), SessionPhase::Done);
+        assert!(session.is_done());
+    }
+
+    #[test]
+    fn test_behind_peer_gets_only_missing_chunks() {
+        let mut session = ReplicationSession::new(PeerId::random(), [0u8; 32], 5);
+        session.request_state();
+        let missing = session.want(3, &[chunk(1)], &[chunk(1), chunk(2), chunk(3)]).unwrap();
+        assert_eq!(missing, vec![chunk(2), chunk(3)]);
+        assert_eq!(session.phase(), SessionPhase::Transfer);
+
+        session.finish();
+        assert!(session.is_done());
+    }
+
+    #[test]
+    fn test_sync_progress_is_complete() {
+        let mut progress = SyncProgress { direction: SyncDirection::Sending, total_chunks: 3, ..Default::default() };
+        assert!(!progress.is_complete());
+        progress.chunks_done = 3;
+        assert!(progress.is_complete());
+
+        // Idle (no transfer) never reports complete, even with matching counts.
+        let idle = SyncProgress { direction: SyncDirection::Idle, chunks_done: 3, total_chunks: 3, ..Default::default() };
+        assert!(!idle.is_complete());
+    }
+
+    #[test]
+    fn test_manager_tracks_concurrent_sessions_per_peer_and_site() {
+        let mut manager = ReplicationSessionManager::new();
+        let peer_a = PeerId::random();
+        let peer_b = PeerId::random();
+        let site = [1u8; 32];
+
+        manager.begin(peer_a, site, 4);
+        manager.begin(peer_b, site, 4);
+        assert_eq!(manager.phase(&peer_a, &site), Some(SessionPhase::Announce));
+        assert_eq!(manager.phase(&peer_b, &site), Some(SessionPhase::Announce));
+
+        manager.get_mut(&peer_a, &site).unwrap().finish();
+        assert_eq!(manager.phase(&peer_a, &site), Some(SessionPhase::Done));
+        assert_eq!(manager.phase(&peer_b, &site), Some(SessionPhase::Announce));
+
+        manager.remove(&peer_a, &site);
+        assert_eq!(manager.phase(&peer_a, &site), None);
+    }
+}
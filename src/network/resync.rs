@@ -0,0 +1,306 @@
+//! Background resync: pulls a hosted-but-incomplete site's missing chunks
+//! from live hosts so this node actually converges on what
+//! [`super::layout::ReplicaLayout`] assigned it, rather than just knowing
+//! it should.
+//!
+//! Unlike [`super::repair::RepairService`]'s per-chunk FIFO queue, tasks
+//! here are `(site_id, needed_chunks)` batches ordered by how
+//! under-replicated `ReplicaLayout` considers the site -- a site that just
+//! lost most of its mirrors to a dead-peer sweep jumps ahead of one that's
+//! merely missing its newest chunk. Operations are paced by `tranquility`:
+//! after each fetch (successful or not) the worker sleeps
+//! `tranquility * last_op_duration` before popping the next task, so a
+//! large backlog doesn't compete with foreground gateway traffic for
+//! bandwidth. A task with chunks still missing after a failed attempt is
+//! re-queued with jittered exponential backoff, same shape as
+//! `RepairService` but with jitter added so many sites backing off at once
+//! don't all retry in lockstep.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+use parking_lot::Mutex;
+use rand::Rng;
+use tokio::sync::Notify;
+
+use crate::crypto::{hash, MerkleMountainRange};
+use crate::storage::{BundleStore, ChunkStore};
+use crate::types::{ChunkId, SiteId};
+
+use super::layout::ReplicaLayout;
+use super::replication::ReplicationManager;
+use super::GrabNetwork;
+
+/// Resync tasks are attempted up to this many times (the first attempt plus
+/// four retries) before the still-missing chunks are dropped.
+const MAX_ATTEMPTS: u32 = 5;
+/// Base delay before the first retry; doubled on each subsequent one, then
+/// jittered by +/-50%.
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// One site's worth of chunks to pull from a live host, ordered by
+/// `priority` (typically the replica-count shortfall from
+/// `ReplicaLayout::status`) so the worst-off sites are resynced first.
+#[derive(Debug, Clone)]
+struct ResyncTask {
+    site_id: SiteId,
+    chunks: Vec<ChunkId>,
+    priority: u32,
+    attempt: u32,
+}
+
+impl PartialEq for ResyncTask {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+impl Eq for ResyncTask {}
+impl PartialOrd for ResyncTask {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for ResyncTask {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+    }
+}
+
+/// Drains under-replicated sites into a priority queue of resync tasks,
+/// fetching and Merkle-verifying each missing chunk from a ranked source
+/// peer (see [`ReplicationManager::rank_sources`]) before accepting it.
+pub struct ResyncService {
+    queue: Arc<Mutex<BinaryHeap<ResyncTask>>>,
+    notify: Arc<Notify>,
+    queued: Arc<AtomicUsize>,
+    in_flight: Arc<AtomicUsize>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for ResyncService {
+    /// The worker loop blocks on `Notify` and never exits on its own, so
+    /// dropping the handle aborts the task directly (as `RepairService`
+    /// does).
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ResyncService {
+    /// Spawn the background worker and return a handle for enqueueing
+    /// tasks and reading queue depth. `tranquility` is the proportional
+    /// delay (relative to the last operation's duration) inserted between
+    /// resync operations. Besides draining explicitly `enqueue`d tasks,
+    /// the worker periodically scans `replication`/`layout` itself (see
+    /// `scan`) so a site left under-replicated by a dead peer (evicted
+    /// from `layout` in the membership gossip tick) or flagged unhealthy
+    /// by replication gossip gets picked up without a caller having to
+    /// drive it.
+    pub fn spawn(
+        network: Arc<GrabNetwork>,
+        replication: Arc<ReplicationManager>,
+        layout: Arc<ReplicaLayout>,
+        bundle_store: Arc<BundleStore>,
+        chunk_store: Arc<ChunkStore>,
+        tranquility: f64,
+    ) -> Self {
+        let queue = Arc::new(Mutex::new(BinaryHeap::new()));
+        let notify = Arc::new(Notify::new());
+        let queued = Arc::new(AtomicUsize::new(0));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+
+        let task = tokio::spawn(Self::run(
+            network,
+            replication,
+            layout,
+            bundle_store,
+            chunk_store,
+            tranquility,
+            queue.clone(),
+            notify.clone(),
+            queued.clone(),
+            in_flight.clone(),
+        ));
+
+        Self { queue, notify, queued, in_flight, task }
+    }
+
+    /// Queue a resync task for its first attempt.
+    pub fn enqueue(&self, site_id: SiteId, chunks: Vec<ChunkId>, priority: u32) {
+        if chunks.is_empty() {
+            return;
+        }
+        self.queue.lock().push(ResyncTask { site_id, chunks, priority, attempt: 0 });
+        self.queued.fetch_add(1, AtomicOrdering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    /// Tasks not yet popped by the worker.
+    pub fn queued_count(&self) -> usize {
+        self.queued.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Tasks currently being fetched (0 or 1 -- the worker processes one
+    /// at a time so `tranquility` pacing is meaningful).
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.load(AtomicOrdering::Relaxed)
+    }
+
+    async fn run(
+        network: Arc<GrabNetwork>,
+        replication: Arc<ReplicationManager>,
+        layout: Arc<ReplicaLayout>,
+        bundle_store: Arc<BundleStore>,
+        chunk_store: Arc<ChunkStore>,
+        tranquility: f64,
+        queue: Arc<Mutex<BinaryHeap<ResyncTask>>>,
+        notify: Arc<Notify>,
+        queued: Arc<AtomicUsize>,
+        in_flight: Arc<AtomicUsize>,
+    ) {
+        let mut scan_interval = tokio::time::interval(Duration::from_secs(30));
+        let mut last_op = Duration::ZERO;
+        loop {
+            let pace = last_op.mul_f64(tranquility);
+            if !pace.is_zero() {
+                tokio::time::sleep(pace).await;
+            }
+
+            let task = loop {
+                if let Some(task) = queue.lock().pop() {
+                    queued.fetch_sub(1, AtomicOrdering::Relaxed);
+                    break task;
+                }
+                tokio::select! {
+                    _ = notify.notified() => {}
+                    _ = scan_interval.tick() => {
+                        Self::scan(&replication, &layout, &queue, &notify, &queued);
+                    }
+                }
+            };
+
+            in_flight.fetch_add(1, AtomicOrdering::Relaxed);
+            let started = Instant::now();
+            let still_missing = Self::attempt(&network, &replication, &bundle_store, &chunk_store, &task).await;
+            last_op = started.elapsed();
+            in_flight.fetch_sub(1, AtomicOrdering::Relaxed);
+
+            if !still_missing.is_empty() && task.attempt + 1 < MAX_ATTEMPTS {
+                let backoff = BASE_BACKOFF * 2u32.pow(task.attempt);
+                let jitter: f64 = rand::thread_rng().gen_range(0.5..1.5);
+                let delay = backoff.mul_f64(jitter);
+
+                let retry = ResyncTask {
+                    site_id: task.site_id,
+                    chunks: still_missing,
+                    priority: task.priority,
+                    attempt: task.attempt + 1,
+                };
+                let queue = queue.clone();
+                let notify = notify.clone();
+                let queued = queued.clone();
+                queued.fetch_add(1, AtomicOrdering::Relaxed);
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    queue.lock().push(retry);
+                    notify.notify_one();
+                });
+            }
+        }
+    }
+
+    /// Enqueue a resync task per unhealthy site with known missing chunks
+    /// (`ReplicationManager::get_sites_needing_attention`), prioritized by
+    /// how far under `layout`'s target replica count it currently sits.
+    fn scan(
+        replication: &ReplicationManager,
+        layout: &ReplicaLayout,
+        queue: &Mutex<BinaryHeap<ResyncTask>>,
+        notify: &Notify,
+        queued: &AtomicUsize,
+    ) {
+        for (site_id, health) in replication.get_sites_needing_attention() {
+            if health.missing_chunks.is_empty() {
+                continue;
+            }
+            let status = layout.status(&site_id);
+            let assigned: usize = status.zones.values().map(|peers| peers.len()).sum();
+            let priority = status.target.saturating_sub(assigned) as u32;
+
+            queue.lock().push(ResyncTask { site_id, chunks: health.missing_chunks, priority, attempt: 0 });
+            queued.fetch_add(1, AtomicOrdering::Relaxed);
+        }
+        notify.notify_one();
+    }
+
+    /// Try once to pull every chunk in `task` from the best-ranked source
+    /// for its site, verifying each against the site's signed root before
+    /// storing it. Returns the chunks still missing afterward.
+    async fn attempt(
+        network: &GrabNetwork,
+        replication: &ReplicationManager,
+        bundle_store: &BundleStore,
+        chunk_store: &ChunkStore,
+        task: &ResyncTask,
+    ) -> Vec<ChunkId> {
+        let Some(source) = replication.rank_sources(&task.site_id).into_iter().next() else {
+            return task.chunks.clone();
+        };
+        let Ok(peer_id) = source.parse::<PeerId>() else {
+            return task.chunks.clone();
+        };
+        let expected_root = match bundle_store.get_bundle(&task.site_id) {
+            Ok(Some(bundle)) => bundle.root_hash,
+            _ => return task.chunks.clone(),
+        };
+
+        let mut missing = Vec::new();
+        for chunk_id in &task.chunks {
+            let started = Instant::now();
+            let (data, proof) = match network.fetch_chunk_with_proof(&peer_id, &task.site_id, chunk_id).await {
+                Ok(result) => result,
+                Err(_) => {
+                    replication.record_transfer_failure(&source);
+                    missing.push(*chunk_id);
+                    continue;
+                }
+            };
+            let latency_ms = started.elapsed().as_millis() as u64;
+
+            let verified = hash(&data) == *chunk_id
+                && proof.root == expected_root
+                && MerkleMountainRange::verify(&proof);
+
+            if !verified || chunk_store.put(&data).is_err() {
+                replication.record_transfer_failure(&source);
+                missing.push(*chunk_id);
+                continue;
+            }
+
+            replication.record_transfer_success(&source, latency_ms);
+            replication.chunk_repaired(&task.site_id, chunk_id);
+        }
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heap_pops_most_under_replicated_site_first() {
+        let mut heap = BinaryHeap::new();
+        heap.push(ResyncTask { site_id: [1u8; 32], chunks: vec![[1u8; 32]], priority: 1, attempt: 0 });
+        heap.push(ResyncTask { site_id: [2u8; 32], chunks: vec![[2u8; 32]], priority: 5, attempt: 0 });
+        heap.push(ResyncTask { site_id: [3u8; 32], chunks: vec![[3u8; 32]], priority: 3, attempt: 0 });
+
+        assert_eq!(heap.pop().unwrap().site_id, [2u8; 32]);
+        assert_eq!(heap.pop().unwrap().site_id, [3u8; 32]);
+        assert_eq!(heap.pop().unwrap().site_id, [1u8; 32]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Trusted peer identity store
+//!
+//! Tracks the set of Ed25519 public keys a node treats as authenticated
+//! peers, for use by [`crate::network::session`] and anything else that
+//! needs to authenticate a remote identity against a caller-supplied set
+//! of trusted keys rather than a single pinned key.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{decode_base58, encode_base58};
+use crate::types::PublicKey;
+
+/// Persisted set of trusted peer public keys.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    /// Trusted Ed25519 public keys, base58-encoded for JSON readability.
+    trusted: Vec<String>,
+}
+
+impl TrustStore {
+    /// Load a trust store from a file, or create an empty one.
+    pub fn load_or_default(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join("trusted_keys.json");
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            Ok(serde_json::from_str(&contents)?)
+        } else {
+            let store = Self::default();
+            store.save(data_dir)?;
+            Ok(store)
+        }
+    }
+
+    /// Save the trust store.
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join("trusted_keys.json");
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Add a public key to the trusted set. No-op if already present.
+    pub fn add_trusted(&mut self, key: &PublicKey) {
+        let encoded = encode_base58(key);
+        if !self.trusted.contains(&encoded) {
+            self.trusted.push(encoded);
+        }
+    }
+
+    /// Remove a public key from the trusted set. Returns `true` if it was present.
+    pub fn remove_trusted(&mut self, key: &PublicKey) -> bool {
+        let encoded = encode_base58(key);
+        let initial_len = self.trusted.len();
+        self.trusted.retain(|k| k != &encoded);
+        self.trusted.len() < initial_len
+    }
+
+    /// Check whether a public key is in the trusted set.
+    pub fn is_trusted(&self, key: &PublicKey) -> bool {
+        let encoded = encode_base58(key);
+        self.trusted.iter().any(|k| k == &encoded)
+    }
+
+    /// All trusted public keys, decoded.
+    pub fn trusted_keys(&self) -> Vec<PublicKey> {
+        self.trusted
+            .iter()
+            .filter_map(|encoded| {
+                let bytes = decode_base58(encoded)?;
+                bytes.try_into().ok()
+            })
+            .collect()
+    }
+}
+
+/// A peer this node has completed [`crate::network::pairing`] with: the
+/// `NodeInformation` it last presented, kept around so the dashboard can
+/// show who a trusted peer is and how to reach it without needing a live
+/// connection to ask again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDevice {
+    /// Base58-encoded Ed25519 identity, matching a [`TrustStore`] entry.
+    pub public_key: String,
+    pub peer_id: String,
+    pub name: String,
+    pub site_count: usize,
+    pub addresses: Vec<String>,
+    pub paired_at: u64,
+}
+
+/// Persisted set of paired devices, keyed by public key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PairedDeviceStore {
+    devices: Vec<PairedDevice>,
+}
+
+impl PairedDeviceStore {
+    /// Load the paired-device list from a file, or create an empty one.
+    pub fn load_or_default(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join("paired_devices.json");
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            Ok(serde_json::from_str(&contents)?)
+        } else {
+            let store = Self::default();
+            store.save(data_dir)?;
+            Ok(store)
+        }
+    }
+
+    /// Save the paired-device list.
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join("paired_devices.json");
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Record a newly paired device, or refresh an existing entry for the
+    /// same public key (e.g. after re-pairing with updated site IDs).
+    pub fn upsert(&mut self, device: PairedDevice) {
+        if let Some(existing) = self.devices.iter_mut().find(|d| d.public_key == device.public_key) {
+            *existing = device;
+        } else {
+            self.devices.push(device);
+        }
+    }
+
+    /// Remove a paired device by its public key. Returns `true` if it was present.
+    pub fn remove(&mut self, public_key: &PublicKey) -> bool {
+        let encoded = encode_base58(public_key);
+        let initial_len = self.devices.len();
+        self.devices.retain(|d| d.public_key != encoded);
+        self.devices.len() < initial_len
+    }
+
+    /// All paired devices.
+    pub fn all(&self) -> Vec<PairedDevice> {
+        self.devices.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_remove_is_trusted() {
+        let mut store = TrustStore::default();
+        let key = [7u8; 32];
+
+        assert!(!store.is_trusted(&key));
+        store.add_trusted(&key);
+        assert!(store.is_trusted(&key));
+
+        assert!(store.remove_trusted(&key));
+        assert!(!store.is_trusted(&key));
+    }
+
+    #[test]
+    fn test_trusted_keys_roundtrip() {
+        let mut store = TrustStore::default();
+        store.add_trusted(&[1u8; 32]);
+        store.add_trusted(&[2u8; 32]);
+
+        let keys = store.trusted_keys();
+        assert_eq!(keys.len(), 2);
+        assert!(keys.contains(&[1u8; 32]));
+        assert!(keys.contains(&[2u8; 32]));
+    }
+
+    #[test]
+    fn test_paired_device_upsert_replaces_existing() {
+        let mut store = PairedDeviceStore::default();
+        let key = encode_base58(&[9u8; 32]);
+
+        store.upsert(PairedDevice {
+            public_key: key.clone(),
+            peer_id: "peer-a".to_string(),
+            name: "laptop".to_string(),
+            site_count: 1,
+            addresses: vec!["/ip4/1.2.3.4/tcp/4001".to_string()],
+            paired_at: 1000,
+        });
+        store.upsert(PairedDevice {
+            public_key: key.clone(),
+            peer_id: "peer-a".to_string(),
+            name: "laptop".to_string(),
+            site_count: 3,
+            addresses: vec!["/ip4/1.2.3.4/tcp/4001".to_string()],
+            paired_at: 2000,
+        });
+
+        let devices = store.all();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].site_count, 3);
+        assert_eq!(devices[0].paired_at, 2000);
+    }
+
+    #[test]
+    fn test_paired_device_remove() {
+        let mut store = PairedDeviceStore::default();
+        let key = [3u8; 32];
+
+        store.upsert(PairedDevice {
+            public_key: encode_base58(&key),
+            peer_id: "peer-b".to_string(),
+            name: "phone".to_string(),
+            site_count: 0,
+            addresses: vec![],
+            paired_at: 0,
+        });
+
+        assert!(store.remove(&key));
+        assert!(store.all().is_empty());
+        assert!(!store.remove(&key));
+    }
+}
@@ -1,4 +1,4 @@
-//! Website bundling and publishing
+//! Directory scanning, chunking, and manifest assembly for a publish.
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -14,7 +14,16 @@ use crate::types::{
     RouteConfig, Compression, SiteId, ChunkId,
 };
 use crate::storage::{ChunkStore, BundleStore, KeyStore};
-use crate::crypto::{hash, sign_bundle, SiteIdExt, MerkleTree, encode_base58};
+use crate::crypto::{sign_bundle, SiteIdExt, MerkleMountainRange, encode_base58, HashMethod, append_frame_checksum};
+use super::cdc::{fastcdc_chunks, ChunkingMode};
+
+/// Codecs tried, in order, when `PublishOptions::compression_codecs` is
+/// left empty.
+const DEFAULT_COMPRESSION_CODECS: &[Compression] = &[Compression::Gzip, Compression::Zstd, Compression::Brotli];
+
+/// `Compression::Zstd` level used when `PublishOptions::zstd_level` and
+/// `PublisherConfig::zstd_level` are both unset.
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
 
 /// Options for publishing a website
 #[derive(Debug, Clone, Default)]
@@ -25,10 +34,26 @@ pub struct PublishOptions {
     pub entry: Option<String>,
     /// Key name to use for signing
     pub key_name: Option<String>,
-    /// Enable gzip compression
+    /// Enable compression
     pub compress: bool,
-    /// Chunk size in bytes
+    /// Candidate codecs to try per compressible file, smallest result
+    /// wins (ties keep the earlier candidate). Empty means the default
+    /// set: gzip, zstd, and brotli.
+    pub compression_codecs: Vec<Compression>,
+    /// Zstd compression level (1-22, higher is smaller but slower).
+    /// `None` falls back to `PublisherConfig::zstd_level`.
+    pub zstd_level: Option<i32>,
+    /// Chunk size in bytes. For `ChunkingMode::Fixed` this is the exact
+    /// slice size; for `ChunkingMode::ContentDefined` it's the target
+    /// average (min/max are derived from it).
     pub chunk_size: Option<usize>,
+    /// How file data is split into chunks before being handed to
+    /// `ChunkStore::put`
+    pub chunking_mode: ChunkingMode,
+    /// Digest used for file content hashes and chunk IDs in this publish.
+    /// Recorded in the manifest so a reader always knows how to verify
+    /// it, regardless of what the underlying `ChunkStore` defaults to.
+    pub hash_method: HashMethod,
     /// SPA fallback path
     pub spa_fallback: Option<String>,
     /// Enable clean URLs
@@ -98,19 +123,34 @@ impl Publisher {
         // Generate stable site ID
         let site_id = SiteId::generate(&public_key, &name);
 
-        // Check for existing revision
-        let previous_revision = self.bundle_store
-            .get_published_site(&site_id.to_base58())?
-            .map(|s| s.revision)
-            .unwrap_or(0);
+        // Each device gets its own stable identity for causal tracking, so
+        // two machines publishing under the same signing key still produce
+        // distinguishable writes instead of silently colliding on a counter.
+        let (device_key, _) = self.key_store.get_or_create("__device")?;
+        let node_id = encode_base58(&device_key);
+
+        let previous_bundle = self.bundle_store.get_bundle(&site_id)?;
 
-        let revision = previous_revision + 1;
+        let mut causal_context = previous_bundle
+            .as_ref()
+            .map(|b| b.causal_context.clone())
+            .unwrap_or_default();
+        causal_context.increment(&node_id);
+        let revision = causal_context.total();
 
         // Scan and bundle files
         let chunk_size = options.chunk_size.unwrap_or(256 * 1024);
         let compress = options.compress;
 
-        let (files, stats) = self.bundle_directory(&root_path, chunk_size, compress).await?;
+        let (files, stats) = self.bundle_directory(
+            &root_path,
+            chunk_size,
+            compress,
+            &options.compression_codecs,
+            options.zstd_level.unwrap_or(DEFAULT_ZSTD_LEVEL),
+            options.chunking_mode,
+            options.hash_method,
+        ).await?;
 
         // Determine entry point
         let entry = options.entry.clone()
@@ -143,12 +183,30 @@ impl Publisher {
             entry,
             routes,
             headers: None,
+            hash_method: options.hash_method,
         };
 
-        // Compute root hash from file hashes
+        // Compute the root hash with the Merkle Mountain Range so that a
+        // republish which only appends files (the common case) extends the
+        // previous accumulator instead of rehashing every file from
+        // scratch. Anything else — a reorder, edit, or deletion partway
+        // through the file list — falls back to rebuilding fresh, since an
+        // append-only accumulator can't cheaply represent those.
         let file_hashes: Vec<[u8; 32]> = files.iter().map(|f| f.hash).collect();
-        let tree = MerkleTree::new(file_hashes);
-        let root_hash = tree.root();
+        let previous_hashes: Vec<[u8; 32]> = previous_bundle
+            .as_ref()
+            .map(|b| b.manifest.files.iter().map(|f| f.hash).collect())
+            .unwrap_or_default();
+
+        let mut mmr = if file_hashes.starts_with(&previous_hashes) {
+            MerkleMountainRange::from_leaves(previous_hashes)
+        } else {
+            MerkleMountainRange::new()
+        };
+        for &file_hash in &file_hashes[mmr.len()..] {
+            mmr.append(file_hash);
+        }
+        let root_hash = mmr.root();
 
         // Sign the bundle
         let signature = sign_bundle(&site_id, revision, &root_hash, &private_key);
@@ -163,6 +221,7 @@ impl Publisher {
             site_id,
             name: name.clone(),
             revision,
+            causal_context,
             root_hash,
             publisher: public_key,
             signature,
@@ -170,8 +229,9 @@ impl Publisher {
             created_at: now,
         };
 
-        // Save bundle
-        self.bundle_store.save_bundle(&bundle)?;
+        // Save bundle, resolving against any existing revision/siblings by
+        // causal context rather than blindly overwriting
+        self.bundle_store.save_bundle_revision(&bundle)?;
 
         // Save as published site
         let published = PublishedSite {
@@ -200,7 +260,16 @@ impl Publisher {
         root: &Path,
         chunk_size: usize,
         compress: bool,
+        compression_codecs: &[Compression],
+        zstd_level: i32,
+        chunking_mode: ChunkingMode,
+        hash_method: HashMethod,
     ) -> Result<(Vec<FileEntry>, BundleStats)> {
+        let compression_codecs = if compression_codecs.is_empty() {
+            DEFAULT_COMPRESSION_CODECS
+        } else {
+            compression_codecs
+        };
         let mut files = Vec::new();
         let mut stats = BundleStats::default();
 
@@ -238,18 +307,23 @@ impl Publisher {
                 .first_or_octet_stream()
                 .to_string();
 
-            // Compress if enabled and beneficial
+            // Compress with every candidate codec and keep whichever is
+            // smallest, so a publish doesn't have to commit to one codec
+            // across every file type up front.
             let should_compress = compress && is_compressible(&mime_type);
             let (data, compression) = if should_compress {
-                let mut encoder = GzEncoder::new(&content[..], GzCompression::default());
-                let mut compressed = Vec::new();
-                encoder.read_to_end(&mut compressed)?;
-                
-                // Only use if smaller
-                if compressed.len() < content.len() {
-                    (compressed, Some(Compression::Gzip))
-                } else {
-                    (content.clone(), None)
+                let mut best: Option<(Compression, Vec<u8>)> = None;
+                for &codec in compression_codecs {
+                    let encoded = compress_with(codec, &content, zstd_level)?;
+                    if best.as_ref().map_or(true, |(_, b)| encoded.len() < b.len()) {
+                        best = Some((codec, encoded));
+                    }
+                }
+
+                // Only use it if it actually shrank the file
+                match best {
+                    Some((codec, encoded)) if encoded.len() < content.len() => (encoded, Some(codec)),
+                    _ => (content.clone(), None),
                 }
             } else {
                 (content.clone(), None)
@@ -259,20 +333,26 @@ impl Publisher {
 
             // Chunk the data
             let mut chunks = Vec::new();
-            for chunk_data in data.chunks(chunk_size) {
-                let chunk_id = self.chunk_store.put(chunk_data)?;
-                
+            let slices: Vec<&[u8]> = match chunking_mode {
+                ChunkingMode::Fixed => data.chunks(chunk_size).collect(),
+                ChunkingMode::ContentDefined => {
+                    fastcdc_chunks(&data, chunk_size / 4, chunk_size, chunk_size * 4)
+                }
+            };
+            for chunk_data in slices {
+                let chunk_id = self.chunk_store.put_with_method(chunk_data, hash_method)?;
+
                 // Track if this is a new chunk
                 if chunks.iter().all(|id| id != &chunk_id) {
                     stats.new_chunks += 1;
                 }
-                
+
                 chunks.push(chunk_id);
                 stats.chunk_count += 1;
             }
 
             // Content hash
-            let file_hash = hash(&content);
+            let file_hash = hash_method.hash(&content);
 
             files.push(FileEntry {
                 path: relative_path,
@@ -309,6 +389,30 @@ fn is_compressible(mime: &str) -> bool {
         || mime == "application/wasm"
 }
 
+/// Compress `data` with a single codec. `Compression::None` is a no-op,
+/// so callers can include it in a candidate list without special-casing.
+/// Zstd output gets a trailing `append_frame_checksum`, so a damaged
+/// chunk is caught before the gateway decodes and serves it; the other
+/// codecs already carry their own integrity checks in-frame.
+fn compress_with(codec: Compression, data: &[u8], zstd_level: i32) -> Result<Vec<u8>> {
+    match codec {
+        Compression::None => Ok(data.to_vec()),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(data, GzCompression::default());
+            let mut out = Vec::new();
+            encoder.read_to_end(&mut out)?;
+            Ok(out)
+        }
+        Compression::Zstd => Ok(append_frame_checksum(zstd::stream::encode_all(data, zstd_level)?)),
+        Compression::Brotli => {
+            let mut reader = brotli::CompressorReader::new(data, 4096, 5, 22);
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out)?;
+            Ok(out)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
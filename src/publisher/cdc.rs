@@ -0,0 +1,161 @@
+//! Content-defined chunking (FastCDC).
+//!
+//! Fixed-size chunking (`data.chunks(chunk_size)`) shifts every boundary
+//! after an edit near the front of a file, so `ChunkStore`'s
+//! content-addressed dedup barely helps across revisions. FastCDC instead
+//! picks boundaries from a rolling hash of the file's own bytes, so an
+//! insertion only changes the chunk(s) around it — everything before and
+//! after keeps hashing to the same `ChunkId`s it did in the previous
+//! revision.
+//!
+//! The rolling hash is the "gear hash": `fp = (fp << 1) + GEAR[byte]` for a
+//! fixed table of random 64-bit values. A boundary is declared where the
+//! low bits of `fp` are all zero, which happens with probability `1/2^n`
+//! for an `n`-bit mask — so the mask width controls the expected chunk
+//! size. Two masks are used to bias that probability across the window:
+//! `mask_s` (more bits set, so harder to satisfy) between `min_size` and
+//! `avg_size` suppresses tiny chunks, and `mask_l` (fewer bits set, easier
+//! to satisfy) between `avg_size` and `max_size` suppresses oversized
+//! ones. A cut is forced at `max_size` if neither mask ever matches.
+
+/// Deterministic splitmix64, used only to fill [`GEAR`] at compile time —
+/// not a cryptographic hash, just a cheap way to decorrelate the table
+/// entries from their index.
+const fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+const fn build_gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = splitmix64(0x5EED_D06Fu64.wrapping_add(i as u64));
+        i += 1;
+    }
+    table
+}
+
+/// Fixed table of random 64-bit values indexed by byte value, precomputed
+/// at compile time so every run of the publisher chunks identically.
+const GEAR: [u64; 256] = build_gear_table();
+
+/// How a file's bytes are split into chunks before being handed to
+/// `ChunkStore::put`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkingMode {
+    /// Split into `chunk_size`-byte slices regardless of content. Simple
+    /// and cheap, but an edit near the start of a file shifts every
+    /// later chunk boundary, defeating dedup across revisions.
+    Fixed,
+    /// FastCDC: boundaries are derived from the data itself, so edits
+    /// only disturb the chunk(s) touching them.
+    ContentDefined,
+}
+
+impl Default for ChunkingMode {
+    fn default() -> Self {
+        ChunkingMode::Fixed
+    }
+}
+
+/// Split `data` into content-defined chunks targeting `avg_size` bytes,
+/// never smaller than `min_size` or larger than `max_size` (except the
+/// final chunk, which may be shorter than `min_size`).
+pub fn fastcdc_chunks(data: &[u8], min_size: usize, avg_size: usize, max_size: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let remaining = &data[offset..];
+        let cut = fastcdc_cut_point(remaining, min_size, avg_size, max_size);
+        chunks.push(&remaining[..cut]);
+        offset += cut;
+    }
+    chunks
+}
+
+/// Find the length of the next chunk at the start of `data` (which is
+/// always treated as the beginning of a new chunk).
+fn fastcdc_cut_point(data: &[u8], min_size: usize, avg_size: usize, max_size: usize) -> usize {
+    let len = data.len();
+    if len <= min_size {
+        return len;
+    }
+
+    let max_size = max_size.min(len);
+    let avg_point = avg_size.min(max_size);
+
+    let bits = (avg_size.max(2) as f64).log2().round() as u32;
+    let mask_s: u64 = (1u64 << (bits + 1).min(63)) - 1;
+    let mask_l: u64 = (1u64 << bits.saturating_sub(1)) - 1;
+
+    let mut fp: u64 = 0;
+
+    let mut i = min_size;
+    while i < avg_point {
+        fp = (fp << 1).wrapping_add(GEAR[data[i] as usize]);
+        if fp & mask_s == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    while i < max_size {
+        fp = (fp << 1).wrapping_add(GEAR[data[i] as usize]);
+        if fp & mask_l == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+
+    max_size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_input_is_one_chunk() {
+        let data = vec![0u8; 10];
+        let chunks = fastcdc_chunks(&data, 64, 256, 1024);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].len(), 10);
+    }
+
+    #[test]
+    fn test_chunks_respect_size_bounds() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 256) as u8).collect();
+        let chunks = fastcdc_chunks(&data, 256, 1024, 4096);
+        let reassembled: Vec<u8> = chunks.iter().flat_map(|c| c.iter().copied()).collect();
+        assert_eq!(reassembled, data);
+
+        for (idx, chunk) in chunks.iter().enumerate() {
+            assert!(chunk.len() <= 4096, "chunk {} exceeded max_size: {}", idx, chunk.len());
+            if idx + 1 != chunks.len() {
+                assert!(chunk.len() >= 256, "non-final chunk {} under min_size: {}", idx, chunk.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_insertion_only_disturbs_nearby_chunks() {
+        let original: Vec<u8> = (0..200_000u64).map(|i| (splitmix64(i) >> 7) as u8).collect();
+        let mut edited = original.clone();
+        edited.splice(50_000..50_000, std::iter::repeat(7u8).take(37));
+
+        let original_chunks: Vec<Vec<u8>> = fastcdc_chunks(&original, 256, 1024, 4096)
+            .into_iter().map(|c| c.to_vec()).collect();
+        let edited_chunks: Vec<Vec<u8>> = fastcdc_chunks(&edited, 256, 1024, 4096)
+            .into_iter().map(|c| c.to_vec()).collect();
+
+        let original_set: std::collections::HashSet<&Vec<u8>> = original_chunks.iter().collect();
+        let edited_set: std::collections::HashSet<&Vec<u8>> = edited_chunks.iter().collect();
+        let shared = original_set.intersection(&edited_set).count();
+
+        // Most chunks should be shared between the two runs; fixed-size
+        // chunking would share none past the insertion point.
+        assert!(shared > original_chunks.len() / 2, "only {} of {} chunks survived the edit", shared, original_chunks.len());
+    }
+}
@@ -0,0 +1,7 @@
+//! Website bundling and publishing
+
+mod bundle;
+mod cdc;
+
+pub use bundle::{Publisher, PublishOptions, PublishResult};
+pub use cdc::ChunkingMode;
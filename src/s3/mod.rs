@@ -0,0 +1,12 @@
+//! S3-compatible publish/serve endpoint
+//!
+//! Maps the minimal `PutObject`/`GetObject`/`HeadObject`/`DeleteObject`/
+//! `ListObjectsV2` subset onto the bundle store, so existing S3-targeting
+//! static-site deploy tooling (`aws s3 sync`, CI uploaders) can push sites
+//! into GrabNet without modifying their pipelines. See [`sigv4`] for the
+//! auth layer and [`server::S3Server`] for the HTTP surface.
+
+mod sigv4;
+mod server;
+
+pub use server::S3Server;
@@ -0,0 +1,504 @@
+//! S3 object API surface, backed by a per-bucket working tree on disk
+//!
+//! A "bucket" is a site name. `PutObject`/`DeleteObject` stage files into
+//! that site's working tree; `GetObject`/`HeadObject` read them back before
+//! they're published; `ListObjectsV2` enumerates the *published* manifest
+//! (not the staging tree) with prefix/delimiter support, mirroring what
+//! `aws s3 sync` needs to diff local content against what's already live.
+//! Staging a reserved sentinel key runs [`crate::Publisher::publish`] on
+//! the working tree, producing a new `SiteId`/revision from whatever has
+//! been staged so far.
+
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use axum::{
+    Router,
+    routing::get,
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    http::{header, HeaderMap, StatusCode},
+    body::Bytes,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::oneshot;
+
+use crate::{hash, Publisher, PublishOptions};
+use crate::storage::{BundleStore, ChunkStore, KeyStore};
+
+use super::sigv4;
+
+/// Object key that triggers a publish of everything staged so far, instead
+/// of being written into the site itself. Chosen to be unambiguous against
+/// real site files (which never start with a dot segment like this).
+const PUBLISH_SENTINEL_KEY: &str = ".grabnet-publish";
+
+/// S3-compatible endpoint for publishing and serving sites
+pub struct S3Server {
+    port: u16,
+    data_dir: PathBuf,
+    bundle_store: Arc<BundleStore>,
+    key_store: Arc<KeyStore>,
+    publisher: Arc<Publisher>,
+    shutdown_tx: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    data_dir: PathBuf,
+    bundle_store: Arc<BundleStore>,
+    key_store: Arc<KeyStore>,
+    publisher: Arc<Publisher>,
+}
+
+impl S3Server {
+    /// Create a new S3 endpoint over the node's existing stores
+    pub fn new(
+        port: u16,
+        data_dir: PathBuf,
+        chunk_store: Arc<ChunkStore>,
+        bundle_store: Arc<BundleStore>,
+        key_store: Arc<KeyStore>,
+    ) -> Self {
+        let publisher = Arc::new(Publisher::new(chunk_store, bundle_store.clone(), key_store.clone()));
+        Self {
+            port,
+            data_dir,
+            bundle_store,
+            key_store,
+            publisher,
+            shutdown_tx: None,
+        }
+    }
+
+    /// Start the S3 endpoint
+    pub async fn start(&self) -> Result<()> {
+        let addr: SocketAddr = format!("127.0.0.1:{}", self.port).parse()?;
+
+        let state = AppState {
+            data_dir: self.data_dir.clone(),
+            bundle_store: self.bundle_store.clone(),
+            key_store: self.key_store.clone(),
+            publisher: self.publisher.clone(),
+        };
+
+        let app = Router::new()
+            .route("/:bucket", get(list_objects))
+            .route(
+                "/:bucket/*key",
+                get(get_object).head(head_object).put(put_object).delete(delete_object),
+            )
+            .with_state(state);
+
+        tracing::info!("S3 endpoint listening on http://{}", addr);
+
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, app).await?;
+
+        Ok(())
+    }
+
+    /// Stop the S3 endpoint
+    pub async fn stop(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl AppState {
+    /// Resolve `bucket` to its staging directory under `data_dir`,
+    /// rejecting anything that isn't a plain directory name -- `bucket`
+    /// comes straight from the axum `:bucket` path segment, so a request
+    /// for e.g. `/../bootstrap.json` would otherwise resolve
+    /// `data_dir/s3/..` back to `data_dir` itself and let the key reach
+    /// node files outside the `s3` staging area.
+    fn staging_dir(&self, bucket: &str) -> Result<PathBuf> {
+        if bucket.is_empty() || bucket == "." || bucket == ".." || bucket.contains('/') {
+            bail!("invalid bucket name");
+        }
+        Ok(self.data_dir.join("s3").join(bucket))
+    }
+
+    /// Resolve `key` to a path under `staging_dir(bucket)`, rejecting any
+    /// `..` path component so a crafted key (e.g. `../../../../etc/passwd`)
+    /// can't escape the bucket's staging directory -- the `*key` wildcard
+    /// capture is otherwise handed straight to the filesystem with no
+    /// sandboxing of its own.
+    fn staging_path(&self, bucket: &str, key: &str) -> Result<PathBuf> {
+        if key.split('/').any(|segment| segment == "..") {
+            bail!("object key must not contain '..' path components");
+        }
+        Ok(self.staging_dir(bucket)?.join(key))
+    }
+
+    /// Verify the request's `Authorization` header, returning the access
+    /// key (== key store key name) it authenticated as.
+    fn authenticate(
+        &self,
+        method: &str,
+        canonical_uri: &str,
+        canonical_query: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<String> {
+        let auth_header = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| anyhow::anyhow!("missing Authorization header"))?;
+
+        let amz_date = headers
+            .get("x-amz-date")
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| anyhow::anyhow!("missing x-amz-date header"))?;
+
+        let payload_hash = headers
+            .get("x-amz-content-sha256")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| {
+                let mut hasher = Sha256::new();
+                hasher.update(body);
+                hex::encode(hasher.finalize())
+            });
+
+        let header_pairs: Vec<(String, String)> = headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+            .collect();
+
+        sigv4::verify(
+            &self.key_store,
+            method,
+            canonical_uri,
+            canonical_query,
+            &header_pairs,
+            amz_date,
+            &payload_hash,
+            auth_header,
+        )
+    }
+
+    /// Run the publish pipeline over whatever's currently staged for `bucket`,
+    /// signing as the identity that authenticated the request.
+    async fn publish_bucket(&self, bucket: &str, identity: &str) -> Result<crate::PublishResult> {
+        let root = self.staging_dir(bucket)?;
+        std::fs::create_dir_all(&root)?;
+
+        let root_str = root
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("staging path is not valid UTF-8"))?;
+
+        self.publisher
+            .publish(
+                root_str,
+                PublishOptions {
+                    name: Some(bucket.to_string()),
+                    key_name: Some(identity.to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+    }
+}
+
+async fn put_object(
+    Path((bucket, key)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let canonical_uri = format!("/{}/{}", uri_encode_segment(&bucket), uri_encode_path(&key));
+    let identity = match state.authenticate("PUT", &canonical_uri, "", &headers, &body) {
+        Ok(id) => id,
+        Err(e) => return s3_error(StatusCode::FORBIDDEN, "SignatureDoesNotMatch", &e.to_string()),
+    };
+
+    if key == PUBLISH_SENTINEL_KEY {
+        return match state.publish_bucket(&bucket, &identity).await {
+            Ok(result) => (
+                StatusCode::OK,
+                [(header::ETAG, format!("\"{}\"", hex::encode(result.bundle.root_hash)))],
+            )
+                .into_response(),
+            Err(e) => s3_error(StatusCode::INTERNAL_SERVER_ERROR, "PublishFailed", &e.to_string()),
+        };
+    }
+
+    let path = match state.staging_path(&bucket, &key) {
+        Ok(path) => path,
+        Err(e) => return s3_error(StatusCode::BAD_REQUEST, "InvalidArgument", &e.to_string()),
+    };
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return s3_error(StatusCode::INTERNAL_SERVER_ERROR, "InternalError", &e.to_string());
+        }
+    }
+    if let Err(e) = std::fs::write(&path, &body) {
+        return s3_error(StatusCode::INTERNAL_SERVER_ERROR, "InternalError", &e.to_string());
+    }
+
+    let etag = hex::encode(hash(&body));
+    (StatusCode::OK, [(header::ETAG, format!("\"{etag}\""))]).into_response()
+}
+
+async fn get_object(
+    Path((bucket, key)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    serve_object(&bucket, &key, state, headers, true).await
+}
+
+async fn head_object(
+    Path((bucket, key)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    serve_object(&bucket, &key, state, headers, false).await
+}
+
+async fn serve_object(
+    bucket: &str,
+    key: &str,
+    state: AppState,
+    headers: HeaderMap,
+    include_body: bool,
+) -> Response {
+    let canonical_uri = format!("/{}/{}", uri_encode_segment(bucket), uri_encode_path(key));
+    let method = if include_body { "GET" } else { "HEAD" };
+    if let Err(e) = state.authenticate(method, &canonical_uri, "", &headers, &[]) {
+        return s3_error(StatusCode::FORBIDDEN, "SignatureDoesNotMatch", &e.to_string());
+    }
+
+    let path = match state.staging_path(bucket, key) {
+        Ok(path) => path,
+        Err(e) => return s3_error(StatusCode::BAD_REQUEST, "InvalidArgument", &e.to_string()),
+    };
+    let content = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(_) => return s3_error(StatusCode::NOT_FOUND, "NoSuchKey", "The specified key does not exist"),
+    };
+
+    let mime_type = mime_guess::from_path(&path).first_or_octet_stream().to_string();
+    let etag = hex::encode(hash(&content));
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, mime_type)
+        .header(header::CONTENT_LENGTH, content.len())
+        .header(header::ETAG, format!("\"{etag}\""));
+
+    if !include_body {
+        return builder.body(axum::body::Body::empty()).unwrap().into_response();
+    }
+
+    builder.body(axum::body::Body::from(content)).unwrap().into_response()
+}
+
+async fn delete_object(
+    Path((bucket, key)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let canonical_uri = format!("/{}/{}", uri_encode_segment(&bucket), uri_encode_path(&key));
+    if let Err(e) = state.authenticate("DELETE", &canonical_uri, "", &headers, &[]) {
+        return s3_error(StatusCode::FORBIDDEN, "SignatureDoesNotMatch", &e.to_string());
+    }
+
+    let path = match state.staging_path(&bucket, &key) {
+        Ok(path) => path,
+        Err(e) => return s3_error(StatusCode::BAD_REQUEST, "InvalidArgument", &e.to_string()),
+    };
+
+    // DeleteObject is idempotent: a missing key is still a successful delete.
+    let _ = std::fs::remove_file(path);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    #[serde(rename = "list-type")]
+    list_type: Option<String>,
+    prefix: Option<String>,
+    delimiter: Option<String>,
+    #[serde(rename = "max-keys")]
+    max_keys: Option<u32>,
+}
+
+async fn list_objects(
+    Path(bucket): Path<String>,
+    Query(query): Query<ListQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let mut query_pairs = Vec::new();
+    if let Some(v) = &query.delimiter {
+        query_pairs.push(("delimiter".to_string(), v.clone()));
+    }
+    if let Some(v) = &query.list_type {
+        query_pairs.push(("list-type".to_string(), v.clone()));
+    }
+    if let Some(v) = &query.max_keys {
+        query_pairs.push(("max-keys".to_string(), v.to_string()));
+    }
+    if let Some(v) = &query.prefix {
+        query_pairs.push(("prefix".to_string(), v.clone()));
+    }
+    query_pairs.sort();
+
+    let canonical_uri = format!("/{}", uri_encode_segment(&bucket));
+    let canonical_query = canonical_query_string(&query_pairs);
+    if let Err(e) = state.authenticate("GET", &canonical_uri, &canonical_query, &headers, &[]) {
+        return s3_error(StatusCode::FORBIDDEN, "SignatureDoesNotMatch", &e.to_string());
+    }
+
+    let prefix = query.prefix.clone().unwrap_or_default();
+    let files = state
+        .bundle_store
+        .resolve_site_id(&bucket)
+        .ok()
+        .flatten()
+        .and_then(|site_id| state.bundle_store.get_manifest(&site_id).ok().flatten())
+        .map(|manifest| manifest.files)
+        .unwrap_or_default();
+
+    let mut contents = Vec::new();
+    let mut common_prefixes = BTreeSet::new();
+
+    for file in &files {
+        let Some(rest) = file.path.strip_prefix(&prefix) else { continue };
+
+        if let Some(delimiter) = &query.delimiter {
+            if let Some(idx) = rest.find(delimiter.as_str()) {
+                common_prefixes.insert(format!("{prefix}{}{delimiter}", &rest[..idx]));
+                continue;
+            }
+        }
+
+        contents.push(file);
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<ListBucketResult xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\">\n");
+    xml.push_str(&format!("<Name>{}</Name>\n", xml_escape(&bucket)));
+    xml.push_str(&format!("<Prefix>{}</Prefix>\n", xml_escape(&prefix)));
+    xml.push_str(&format!("<KeyCount>{}</KeyCount>\n", contents.len() + common_prefixes.len()));
+    xml.push_str("<MaxKeys>1000</MaxKeys>\n");
+    xml.push_str("<IsTruncated>false</IsTruncated>\n");
+    for file in &contents {
+        xml.push_str("<Contents>\n");
+        xml.push_str(&format!("<Key>{}</Key>\n", xml_escape(&file.path)));
+        xml.push_str(&format!("<Size>{}</Size>\n", file.size));
+        xml.push_str(&format!("<ETag>\"{}\"</ETag>\n", hex::encode(file.hash)));
+        xml.push_str("</Contents>\n");
+    }
+    for common_prefix in &common_prefixes {
+        xml.push_str(&format!(
+            "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>\n",
+            xml_escape(common_prefix)
+        ));
+    }
+    xml.push_str("</ListBucketResult>");
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/xml")
+        .body(axum::body::Body::from(xml))
+        .unwrap()
+        .into_response()
+}
+
+fn s3_error(status: StatusCode, code: &str, message: &str) -> Response {
+    let xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error><Code>{}</Code><Message>{}</Message></Error>",
+        xml_escape(code),
+        xml_escape(message)
+    );
+    (status, [(header::CONTENT_TYPE, "application/xml")], xml).into_response()
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// URI-encode a single path segment per the SigV4 canonical request rules:
+/// unreserved characters pass through, everything else becomes `%XX`.
+fn uri_encode_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// URI-encode a `/`-separated object key, one segment at a time, leaving
+/// the separators themselves alone.
+fn uri_encode_path(path: &str) -> String {
+    path.split('/').map(uri_encode_segment).collect::<Vec<_>>().join("/")
+}
+
+/// Build a SigV4 canonical query string from already-sorted `(key, value)`
+/// pairs.
+fn canonical_query_string(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", uri_encode_segment(k), uri_encode_segment(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn test_state(data_dir: &std::path::Path) -> Result<AppState> {
+        let chunk_store = Arc::new(ChunkStore::new(data_dir)?);
+        let bundle_store = Arc::new(BundleStore::new(data_dir)?);
+        let key_store = Arc::new(KeyStore::new(data_dir)?);
+        let publisher = Arc::new(Publisher::new(chunk_store, bundle_store.clone(), key_store.clone()));
+        Ok(AppState {
+            data_dir: data_dir.to_path_buf(),
+            bundle_store,
+            key_store,
+            publisher,
+        })
+    }
+
+    #[test]
+    fn test_staging_path_resolves_under_bucket_dir() -> Result<()> {
+        let data_dir = tempdir()?;
+        let state = test_state(data_dir.path())?;
+        let path = state.staging_path("mysite", "images/logo.png")?;
+        assert_eq!(path, state.staging_dir("mysite")?.join("images/logo.png"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_staging_dir_rejects_parent_traversal_in_bucket() -> Result<()> {
+        let data_dir = tempdir()?;
+        let state = test_state(data_dir.path())?;
+        assert!(state.staging_dir("..").is_err());
+        assert!(state.staging_path("..", "bootstrap.json").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_staging_path_rejects_parent_traversal() -> Result<()> {
+        let data_dir = tempdir()?;
+        let state = test_state(data_dir.path())?;
+        assert!(state.staging_path("mysite", "../../../../etc/passwd").is_err());
+        assert!(state.staging_path("mysite", "images/../../escape").is_err());
+        Ok(())
+    }
+}
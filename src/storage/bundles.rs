@@ -1,12 +1,98 @@
 //! Bundle and site metadata storage using sled
 
+use std::collections::HashSet;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 use anyhow::{Result, anyhow};
 
-use crate::types::{SiteId, WebBundle, PublishedSite, HostedSite, SiteManifest};
+use crate::types::{SiteId, WebBundle, PublishedSite, HostedSite, SiteManifest, VersionVector, ChunkId};
 use crate::crypto::{encode_base58, SiteIdExt};
 
+/// Number of past revisions `save_bundle` retains per site before
+/// pruning the oldest. Bounds how far back `rollback` can reach.
+const MAX_REVISION_HISTORY: usize = 10;
+
+/// Key a stored revision by `site_id || revision` (big-endian) so that
+/// `scan_prefix(site_id)` yields every retained revision for a site in
+/// ascending order.
+fn revision_key(site_id: &SiteId, revision: u64) -> [u8; 40] {
+    let mut key = [0u8; 40];
+    key[..32].copy_from_slice(site_id);
+    key[32..].copy_from_slice(&revision.to_be_bytes());
+    key
+}
+
+/// Codec tag bytes for the header `encode_blob` prepends to whatever it
+/// stores in the `bundles`/`revisions` trees.
+const CODEC_TAG_NONE: u8 = 0;
+const CODEC_TAG_ZSTD: u8 = 1;
+
+/// How bundle blobs (the `bundles` and `revisions` trees) are compressed
+/// at rest. Manifests stay uncompressed regardless of this setting: they're
+/// small and read on the hot path, so there's nothing to gain and a decode
+/// step to lose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleCodec {
+    /// Store the serialized bytes as-is.
+    None,
+    /// Compress with zstd at the given level (1-22; higher trades CPU for
+    /// a smaller footprint).
+    Zstd(i32),
+}
+
+impl Default for BundleCodec {
+    fn default() -> Self {
+        BundleCodec::Zstd(3)
+    }
+}
+
+/// Compress `raw` per `codec` and prepend a header recording the codec
+/// tag and the uncompressed length, so `decode_blob` can report both sizes
+/// and decompress without guessing.
+fn encode_blob(codec: BundleCodec, raw: &[u8]) -> Result<Vec<u8>> {
+    let (tag, payload) = match codec {
+        BundleCodec::None => (CODEC_TAG_NONE, raw.to_vec()),
+        BundleCodec::Zstd(level) => (CODEC_TAG_ZSTD, zstd::stream::encode_all(raw, level)?),
+    };
+
+    tracing::debug!(
+        "Encoded blob: {} bytes -> {} bytes (codec {})",
+        raw.len(),
+        payload.len(),
+        tag,
+    );
+
+    let mut out = Vec::with_capacity(payload.len() + 5);
+    out.push(tag);
+    out.extend_from_slice(&(raw.len() as u32).to_le_bytes());
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Undo `encode_blob`, dispatching on its header byte.
+fn decode_blob(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 5 {
+        return Err(anyhow!("corrupt bundle blob: header truncated"));
+    }
+    let tag = data[0];
+    let original_len = u32::from_le_bytes(data[1..5].try_into().unwrap());
+    let payload = &data[5..];
+
+    let raw = match tag {
+        CODEC_TAG_NONE => payload.to_vec(),
+        CODEC_TAG_ZSTD => zstd::stream::decode_all(payload)?,
+        other => return Err(anyhow!("unknown bundle codec tag {}", other)),
+    };
+
+    tracing::debug!(
+        "Decoded blob: {} bytes -> {} bytes (codec {})",
+        payload.len(),
+        original_len,
+        tag,
+    );
+    Ok(raw)
+}
+
 /// Site metadata store backed by sled
 pub struct BundleStore {
     /// Published sites (owned by us)
@@ -15,26 +101,57 @@ pub struct BundleStore {
     hosted: sled::Tree,
     /// Full bundle data
     bundles: sled::Tree,
+    /// Concurrent sibling revisions, keyed by site ID, for sites whose
+    /// causal contexts have forked across devices
+    siblings: sled::Tree,
     /// Site manifests
     manifests: sled::Tree,
     /// Site name -> site ID mapping
     names: sled::Tree,
+    /// Serialized peer reputation records, keyed by peer ID. The encoding
+    /// is owned by the caller (see `network::health::HealthMonitor`); this
+    /// store just persists whatever bytes it's given.
+    peer_scores: sled::Tree,
+    /// Peer IDs worth proactively reconnecting to after a restart
+    reliable_peers: sled::Tree,
+    /// Past bundle revisions, keyed by `site_id || revision`, bounded to
+    /// the last `MAX_REVISION_HISTORY` per site. Lets a publisher roll
+    /// back a bad deploy, and lets a host keep serving a prior revision
+    /// while a new one is still propagating.
+    revisions: sled::Tree,
+    /// Codec applied to blobs in `bundles` and `revisions` before they're
+    /// written to sled.
+    codec: BundleCodec,
     /// Database handle
     _db: sled::Db,
 }
 
 impl BundleStore {
-    /// Create a new bundle store
+    /// Create a new bundle store, compressing bundle blobs with the
+    /// default codec (`BundleCodec::default()`). Equivalent to
+    /// `new_with_codec(data_dir, BundleCodec::default())`.
     pub fn new(data_dir: &Path) -> Result<Self> {
+        Self::new_with_codec(data_dir, BundleCodec::default())
+    }
+
+    /// Create a new bundle store, compressing bundle blobs in the
+    /// `bundles` and `revisions` trees with `codec`. Manifests are always
+    /// stored uncompressed.
+    pub fn new_with_codec(data_dir: &Path, codec: BundleCodec) -> Result<Self> {
         let db_path = data_dir.join("sites.db");
         let db = sled::open(&db_path)?;
-        
+
         Ok(Self {
             published: db.open_tree("published")?,
             hosted: db.open_tree("hosted")?,
             bundles: db.open_tree("bundles")?,
+            siblings: db.open_tree("siblings")?,
             manifests: db.open_tree("manifests")?,
             names: db.open_tree("names")?,
+            peer_scores: db.open_tree("peer_scores")?,
+            reliable_peers: db.open_tree("reliable_peers")?,
+            revisions: db.open_tree("revisions")?,
+            codec,
             _db: db,
         })
     }
@@ -167,15 +284,26 @@ impl BundleStore {
 
     /// Save a bundle
     pub fn save_bundle(&self, bundle: &WebBundle) -> Result<()> {
-        let value = bincode::serialize(bundle)?;
-        tracing::debug!("Saving bundle: {} bytes", value.len());
-        self.bundles.insert(&bundle.site_id, value)?;
-        
-        // Save manifest separately for quick access
+        let raw = bincode::serialize(bundle)?;
+        let encoded = encode_blob(self.codec, &raw)?;
+        tracing::debug!(
+            "Saving bundle: {} bytes uncompressed, {} bytes on disk",
+            raw.len(),
+            encoded.len(),
+        );
+        self.bundles.insert(&bundle.site_id, encoded.clone())?;
+
+        // Save manifest separately for quick access. Small and read on
+        // the hot path, so it stays uncompressed.
         let manifest = bincode::serialize(&bundle.manifest)?;
         tracing::debug!("Saving manifest: {} bytes", manifest.len());
         self.manifests.insert(&bundle.site_id, manifest)?;
-        
+
+        // Retain this revision for rollback, pruning anything beyond
+        // MAX_REVISION_HISTORY.
+        self.revisions.insert(&revision_key(&bundle.site_id, bundle.revision)[..], encoded)?;
+        self.prune_revisions(&bundle.site_id)?;
+
         // Ensure data is flushed to disk
         self.flush()?;
         
@@ -192,11 +320,145 @@ impl BundleStore {
     /// Get a bundle by site ID
     pub fn get_bundle(&self, site_id: &SiteId) -> Result<Option<WebBundle>> {
         match self.bundles.get(site_id)? {
-            Some(data) => Ok(Some(bincode::deserialize(&data)?)),
+            Some(data) => Ok(Some(bincode::deserialize(&decode_blob(&data)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Concurrent sibling revisions kept because their causal context
+    /// neither dominates, nor is dominated by, the current bundle.
+    pub fn get_siblings(&self, site_id: &SiteId) -> Result<Vec<WebBundle>> {
+        match self.siblings.get(site_id)? {
+            Some(data) => Ok(bincode::deserialize(&data)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn save_siblings(&self, site_id: &SiteId, siblings: &[WebBundle]) -> Result<()> {
+        if siblings.is_empty() {
+            self.siblings.remove(site_id)?;
+        } else {
+            self.siblings.insert(site_id, bincode::serialize(siblings)?)?;
+        }
+        Ok(())
+    }
+
+    /// Save a new bundle revision, comparing its causal context against the
+    /// currently stored bundle instead of blindly overwriting it. Returns
+    /// `true` if the new bundle became the current head, `false` if it was
+    /// stale (dominated by the existing bundle) or stored as a concurrent
+    /// sibling instead.
+    pub fn save_bundle_revision(&self, bundle: &WebBundle) -> Result<bool> {
+        let Some(current) = self.get_bundle(&bundle.site_id)? else {
+            self.save_bundle(bundle)?;
+            return Ok(true);
+        };
+
+        if bundle.causal_context.dominates(&current.causal_context) {
+            // Clean successor: promote it, and drop any siblings it also supersedes.
+            let remaining: Vec<WebBundle> = self
+                .get_siblings(&bundle.site_id)?
+                .into_iter()
+                .filter(|sibling| !bundle.causal_context.dominates(&sibling.causal_context))
+                .collect();
+            self.save_siblings(&bundle.site_id, &remaining)?;
+            self.save_bundle(bundle)?;
+            Ok(true)
+        } else if current.causal_context.dominates(&bundle.causal_context) {
+            // Stale write; the stored bundle already supersedes it.
+            Ok(false)
+        } else {
+            // Concurrent fork: keep both.
+            let mut siblings = self.get_siblings(&bundle.site_id)?;
+            siblings.push(bundle.clone());
+            self.save_siblings(&bundle.site_id, &siblings)?;
+            Ok(false)
+        }
+    }
+
+    /// Collapse sibling revisions of a site by writing a new bundle that
+    /// keeps `keep_revision`'s content but whose causal context is the join
+    /// of every sibling's context, so the fork is resolved without losing
+    /// the happened-before relationship of any of them.
+    pub fn resolve_siblings(&self, site_id: &SiteId, keep_revision: u64) -> Result<WebBundle> {
+        let current = self
+            .get_bundle(site_id)?
+            .ok_or_else(|| anyhow!("Unknown site"))?;
+        let siblings = self.get_siblings(site_id)?;
+
+        let mut candidates = siblings.clone();
+        candidates.push(current.clone());
+
+        let chosen = candidates
+            .iter()
+            .find(|b| b.revision == keep_revision)
+            .ok_or_else(|| anyhow!("No sibling revision {}", keep_revision))?
+            .clone();
+
+        let joined_context = candidates
+            .iter()
+            .fold(VersionVector::default(), |acc, b| acc.merge(&b.causal_context));
+
+        let mut resolved = chosen;
+        resolved.causal_context = joined_context;
+        resolved.revision = resolved.causal_context.total();
+
+        self.save_bundle(&resolved)?;
+        self.save_siblings(site_id, &[])?;
+        Ok(resolved)
+    }
+
+    /// Get a previously-retained revision of a site's bundle, if it's
+    /// still within the last `MAX_REVISION_HISTORY` revisions saved.
+    pub fn get_bundle_revision(&self, site_id: &SiteId, revision: u64) -> Result<Option<WebBundle>> {
+        match self.revisions.get(&revision_key(site_id, revision)[..])? {
+            Some(data) => Ok(Some(bincode::deserialize(&decode_blob(&data)?)?)),
             None => Ok(None),
         }
     }
 
+    /// Revisions currently retained for a site, oldest first.
+    pub fn list_revisions(&self, site_id: &SiteId) -> Result<Vec<u64>> {
+        let mut out = Vec::new();
+        for result in self.revisions.scan_prefix(site_id) {
+            let (key, _) = result?;
+            let mut rev_bytes = [0u8; 8];
+            rev_bytes.copy_from_slice(&key[32..40]);
+            out.push(u64::from_be_bytes(rev_bytes));
+        }
+        out.sort_unstable();
+        Ok(out)
+    }
+
+    /// Re-point the current bundle for `site_id` at an already-retained
+    /// revision, without re-fetching anything. Fails if that revision
+    /// has already been pruned from history.
+    pub fn rollback(&self, site_id: &SiteId, revision: u64) -> Result<WebBundle> {
+        let bundle = self
+            .get_bundle_revision(site_id, revision)?
+            .ok_or_else(|| anyhow!("Revision {} is not retained for this site", revision))?;
+
+        let encoded = encode_blob(self.codec, &bincode::serialize(&bundle)?)?;
+        self.bundles.insert(site_id, encoded)?;
+        let manifest = bincode::serialize(&bundle.manifest)?;
+        self.manifests.insert(site_id, manifest)?;
+        self.flush()?;
+
+        Ok(bundle)
+    }
+
+    /// Drop the oldest retained revisions for a site beyond
+    /// `MAX_REVISION_HISTORY`.
+    fn prune_revisions(&self, site_id: &SiteId) -> Result<()> {
+        let revisions = self.list_revisions(site_id)?;
+        if revisions.len() > MAX_REVISION_HISTORY {
+            for old in &revisions[..revisions.len() - MAX_REVISION_HISTORY] {
+                self.revisions.remove(&revision_key(site_id, *old)[..])?;
+            }
+        }
+        Ok(())
+    }
+
     /// Get just the manifest (faster than full bundle)
     pub fn get_manifest(&self, site_id: &SiteId) -> Result<Option<SiteManifest>> {
         match self.manifests.get(site_id)? {
@@ -229,17 +491,109 @@ impl BundleStore {
         Ok(None)
     }
 
+    // =========================================================================
+    // Garbage Collection
+    // =========================================================================
+
+    /// Every `ChunkId` a `ChunkStore::vacuum` pass must not delete: each
+    /// published or hosted site's current bundle, any concurrent sibling
+    /// revisions awaiting `resolve_siblings`, and its `keep_revisions`
+    /// most recently retained revisions (so `rollback` keeps working for
+    /// whatever it still covers).
+    ///
+    /// `keep_revisions` is clamped to what's actually retained —
+    /// `MAX_REVISION_HISTORY` already bounds how far back a site's history
+    /// goes, so a caller asking to keep more than that just keeps
+    /// everything `list_revisions` returns.
+    pub fn live_chunk_ids(&self, keep_revisions: usize) -> Result<HashSet<ChunkId>> {
+        let mut live = HashSet::new();
+
+        let mut site_ids: HashSet<SiteId> = HashSet::new();
+        site_ids.extend(self.get_all_published_sites()?.into_iter().map(|s| s.site_id));
+        site_ids.extend(self.get_all_hosted_sites()?.into_iter().map(|s| s.site_id));
+
+        for site_id in site_ids {
+            if let Some(bundle) = self.get_bundle(&site_id)? {
+                collect_chunks(&bundle, &mut live);
+            }
+            for sibling in self.get_siblings(&site_id)? {
+                collect_chunks(&sibling, &mut live);
+            }
+
+            let mut revisions = self.list_revisions(&site_id)?;
+            revisions.sort_unstable_by(|a, b| b.cmp(a));
+            for revision in revisions.into_iter().take(keep_revisions) {
+                if let Some(bundle) = self.get_bundle_revision(&site_id, revision)? {
+                    collect_chunks(&bundle, &mut live);
+                }
+            }
+        }
+
+        Ok(live)
+    }
+
+    // =========================================================================
+    // Peer Reputation
+    // =========================================================================
+
+    /// Store a peer's serialized reputation record, overwriting any
+    /// previous one for the same peer.
+    pub fn save_peer_score(&self, peer_id: &str, data: &[u8]) -> Result<()> {
+        self.peer_scores.insert(peer_id.as_bytes(), data)?;
+        Ok(())
+    }
+
+    /// Load every stored peer reputation record, keyed by peer ID.
+    pub fn load_peer_scores(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut out = Vec::new();
+        for result in self.peer_scores.iter() {
+            let (key, value) = result?;
+            out.push((String::from_utf8_lossy(&key).to_string(), value.to_vec()));
+        }
+        Ok(out)
+    }
+
+    /// Remember a peer as worth proactively reconnecting to after a
+    /// restart.
+    pub fn mark_reliable_peer(&self, peer_id: &str) -> Result<()> {
+        self.reliable_peers.insert(peer_id.as_bytes(), &[])?;
+        Ok(())
+    }
+
+    /// Peer IDs previously marked reliable via `mark_reliable_peer`.
+    pub fn get_reliable_peers(&self) -> Result<Vec<String>> {
+        let mut out = Vec::new();
+        for result in self.reliable_peers.iter() {
+            let (key, _) = result?;
+            out.push(String::from_utf8_lossy(&key).to_string());
+        }
+        Ok(out)
+    }
+
     /// Flush all data to disk
     pub fn flush(&self) -> Result<()> {
         self.published.flush()?;
         self.hosted.flush()?;
         self.bundles.flush()?;
+        self.siblings.flush()?;
         self.manifests.flush()?;
         self.names.flush()?;
+        self.peer_scores.flush()?;
+        self.reliable_peers.flush()?;
+        self.revisions.flush()?;
         Ok(())
     }
 }
 
+/// Add every chunk referenced by `bundle`'s manifest (per-file chunks plus
+/// each file's whole-content hash) to `live`.
+fn collect_chunks(bundle: &WebBundle, live: &mut HashSet<ChunkId>) {
+    for file in &bundle.manifest.files {
+        live.insert(file.hash);
+        live.extend(file.chunks.iter().copied());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,6 +605,7 @@ mod tests {
             site_id: [1u8; 32],
             name: "test-site".to_string(),
             revision: 1,
+            causal_context: VersionVector::default(),
             root_hash: [2u8; 32],
             publisher: [3u8; 32],
             signature: [4u8; 64].to_vec(),
@@ -259,6 +614,7 @@ mod tests {
                 entry: "index.html".to_string(),
                 routes: None,
                 headers: None,
+                hash_method: Default::default(),
             },
             created_at: 1234567890,
         }
@@ -307,7 +663,140 @@ mod tests {
         let hosted = store.get_hosted_site(&bundle.site_id)?.unwrap();
         assert_eq!(hosted.name, "test-site");
         assert_eq!(hosted.revision, 1);
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_bundle_revision_keeps_concurrent_forks_as_siblings() -> Result<()> {
+        let dir = tempdir()?;
+        let store = BundleStore::new(dir.path())?;
+
+        let mut base = create_test_bundle();
+        base.causal_context.increment("device-a");
+        base.revision = base.causal_context.total();
+        assert!(store.save_bundle_revision(&base)?);
+
+        // Two devices both write a successor to `base` without seeing each
+        // other's write: neither context dominates the other.
+        let mut from_a = base.clone();
+        from_a.causal_context.increment("device-a");
+        from_a.revision = from_a.causal_context.total();
+        assert!(store.save_bundle_revision(&from_a)?);
+
+        let mut from_b = base.clone();
+        from_b.causal_context.increment("device-b");
+        from_b.revision = from_b.causal_context.total();
+        assert!(!store.save_bundle_revision(&from_b)?);
+
+        assert_eq!(store.get_siblings(&base.site_id)?.len(), 1);
+
+        let resolved = store.resolve_siblings(&base.site_id, from_b.revision)?;
+        assert_eq!(resolved.manifest.entry, from_b.manifest.entry);
+        assert!(resolved.causal_context.dominates(&from_a.causal_context));
+        assert!(resolved.causal_context.dominates(&from_b.causal_context));
+        assert!(store.get_siblings(&base.site_id)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_peer_scores_and_reliable_peers_persist() -> Result<()> {
+        let dir = tempdir()?;
+        let store = BundleStore::new(dir.path())?;
+
+        store.save_peer_score("peer1", b"fake-bincode-bytes")?;
+        let loaded = store.load_peer_scores()?;
+        assert_eq!(loaded, vec![("peer1".to_string(), b"fake-bincode-bytes".to_vec())]);
+
+        assert!(store.get_reliable_peers()?.is_empty());
+        store.mark_reliable_peer("peer1")?;
+        assert_eq!(store.get_reliable_peers()?, vec!["peer1".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_revision_history_and_rollback() -> Result<()> {
+        let dir = tempdir()?;
+        let store = BundleStore::new(dir.path())?;
+
+        let mut bundle = create_test_bundle();
+        for revision in 1..=3u64 {
+            bundle.revision = revision;
+            bundle.manifest.entry = format!("index-{revision}.html");
+            store.save_bundle(&bundle)?;
+        }
+
+        assert_eq!(store.list_revisions(&bundle.site_id)?, vec![1, 2, 3]);
+
+        let rev1 = store.get_bundle_revision(&bundle.site_id, 1)?.unwrap();
+        assert_eq!(rev1.manifest.entry, "index-1.html");
+
+        let rolled_back = store.rollback(&bundle.site_id, 2)?;
+        assert_eq!(rolled_back.manifest.entry, "index-2.html");
+        assert_eq!(store.get_bundle(&bundle.site_id)?.unwrap().manifest.entry, "index-2.html");
+
+        assert!(store.rollback(&bundle.site_id, 99).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_revision_history_is_pruned_beyond_max() -> Result<()> {
+        let dir = tempdir()?;
+        let store = BundleStore::new(dir.path())?;
+
+        let mut bundle = create_test_bundle();
+        for revision in 1..=(MAX_REVISION_HISTORY as u64 + 5) {
+            bundle.revision = revision;
+            store.save_bundle(&bundle)?;
+        }
+
+        let revisions = store.list_revisions(&bundle.site_id)?;
+        assert_eq!(revisions.len(), MAX_REVISION_HISTORY);
+        assert_eq!(revisions.first(), Some(&6));
+        assert_eq!(revisions.last(), Some(&(MAX_REVISION_HISTORY as u64 + 5)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zstd_compression_shrinks_compressible_bundles_and_roundtrips() -> Result<()> {
+        let dir = tempdir()?;
+        let store = BundleStore::new_with_codec(dir.path(), BundleCodec::Zstd(3))?;
+
+        let mut bundle = create_test_bundle();
+        bundle.manifest.entry = "a".repeat(4096);
+        store.save_bundle(&bundle)?;
+
+        let raw_len = bincode::serialize(&bundle)?.len();
+        let on_disk_len = store.bundles.get(&bundle.site_id)?.unwrap().len();
+        assert!(on_disk_len < raw_len, "compressed blob should be smaller than raw");
+
+        let restored = store.get_bundle(&bundle.site_id)?.unwrap();
+        assert_eq!(restored.manifest.entry, bundle.manifest.entry);
+
+        let from_revision = store.get_bundle_revision(&bundle.site_id, bundle.revision)?.unwrap();
+        assert_eq!(from_revision.manifest.entry, bundle.manifest.entry);
+
+        let rolled_back = store.rollback(&bundle.site_id, bundle.revision)?;
+        assert_eq!(rolled_back.manifest.entry, bundle.manifest.entry);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_none_codec_roundtrips_uncompressed() -> Result<()> {
+        let dir = tempdir()?;
+        let store = BundleStore::new_with_codec(dir.path(), BundleCodec::None)?;
+
+        let bundle = create_test_bundle();
+        store.save_bundle(&bundle)?;
+
+        let restored = store.get_bundle(&bundle.site_id)?.unwrap();
+        assert_eq!(restored.manifest.entry, bundle.manifest.entry);
+
         Ok(())
     }
 }
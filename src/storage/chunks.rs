@@ -1,82 +1,241 @@
 //! Content-addressed chunk storage using sled
+//!
+//! Chunks are staged in memory as they're written and periodically packed
+//! into append-only files on disk (see `chunk_pack`), rather than each
+//! chunk becoming its own sled record. Sled only holds a small
+//! `ChunkId -> ChunkLocator` entry per chunk, which keeps the per-chunk
+//! overhead low for sites with many small (e.g. FastCDC) chunks.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 use crate::types::ChunkId;
-use crate::crypto::hash;
+use crate::crypto::{hash, HashMethod};
+use super::chunk_pack::{self, PackCompression};
+
+/// Key, in the `meta` tree, under which a store's hashing algorithm is
+/// recorded once and never changed — its existing `ChunkId`s only make
+/// sense under the method that produced them.
+const META_HASH_METHOD_KEY: &[u8] = b"default_hash_method";
+
+/// Flush the staging buffer into a new pack once it holds this many
+/// chunks...
+const PACK_FLUSH_CHUNK_COUNT: usize = 256;
+/// ...or this many bytes, whichever comes first.
+const PACK_FLUSH_BYTES: usize = 8 * 1024 * 1024;
+
+/// Default byte budget for the in-memory chunk cache, used unless a
+/// caller opts into `with_cache_bytes`.
+const DEFAULT_CACHE_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Compression applied to chunks as they're packed. Chunk data is
+/// frequently already gzip-compressed upstream by the publisher, so this
+/// defaults to `None` rather than spending cycles re-compressing bytes
+/// that won't shrink further.
+const PACK_COMPRESSION: PackCompression = PackCompression::None;
+
+/// Where a chunk's bytes live: which pack file, and where within it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ChunkLocator {
+    pack_id: u64,
+    offset: u64,
+    raw_size: u32,
+    stored_size: u32,
+    compression: PackCompression,
+}
+
+/// Result of a `ChunkStore::vacuum` pass.
+#[derive(Debug, Default)]
+pub struct VacuumStats {
+    pub chunks_removed: usize,
+    pub bytes_reclaimed: u64,
+}
+
+/// A cached chunk plus the clock tick it was last read or inserted at, so
+/// `evict_lru` can tell recency apart from insertion order.
+struct CacheEntry {
+    data: Vec<u8>,
+    last_used: u64,
+}
 
 /// Content-addressed chunk store backed by sled
 pub struct ChunkStore {
     db: sled::Db,
-    /// In-memory LRU cache
-    cache: RwLock<HashMap<ChunkId, Vec<u8>>>,
-    cache_max_size: usize,
+    packs_dir: PathBuf,
+    next_pack_id: AtomicU64,
+    /// Hashing algorithm used for `put()`'s `ChunkId`s and recorded in
+    /// packed chunks' headers. Fixed for the lifetime of the store — see
+    /// `META_HASH_METHOD_KEY`.
+    default_hash_method: HashMethod,
+    /// Chunks written since the last pack flush, not yet durable in a
+    /// pack file or locatable through `db`.
+    staging: RwLock<Vec<(ChunkId, Vec<u8>)>>,
+    /// In-memory cache of recently read/written chunk bytes, bounded by
+    /// `cache_max_bytes` rather than entry count so a handful of large
+    /// chunks can't blow past the intended memory budget the way a
+    /// count-based limit would.
+    cache: RwLock<HashMap<ChunkId, CacheEntry>>,
+    cache_max_bytes: usize,
+    cache_bytes: AtomicUsize,
+    /// Monotonic tick, bumped on every cache hit or insert, used as
+    /// `CacheEntry::last_used` so eviction can find genuinely
+    /// least-recently-used entries instead of an arbitrary subset.
+    cache_clock: AtomicU64,
     /// Statistics
     chunk_count: AtomicUsize,
     total_size: AtomicU64,
 }
 
 impl ChunkStore {
-    /// Create a new chunk store
+    /// Create a new chunk store, hashing new content with BLAKE3.
     pub fn new(data_dir: &Path) -> Result<Self> {
+        Self::with_hash_method(data_dir, HashMethod::default())
+    }
+
+    /// Create (or reopen) a chunk store, hashing new content with
+    /// `hash_method`. A store's hash method is fixed the first time it's
+    /// created — reopening an existing store keeps whatever method it
+    /// already recorded, regardless of what's requested here, since its
+    /// existing `ChunkId`s depend on it.
+    pub fn with_hash_method(data_dir: &Path, hash_method: HashMethod) -> Result<Self> {
         let db_path = data_dir.join("chunks");
         let db = sled::open(&db_path)?;
-        
-        // Count existing chunks
+        let meta = db.open_tree("meta")?;
+
+        let default_hash_method = match meta.get(META_HASH_METHOD_KEY)? {
+            Some(bytes) => {
+                let recorded: HashMethod = bincode::deserialize(&bytes)?;
+                if recorded != hash_method {
+                    tracing::warn!(
+                        "chunk store at {} was created with {:?}; ignoring requested {:?}",
+                        data_dir.display(),
+                        recorded,
+                        hash_method
+                    );
+                }
+                recorded
+            }
+            None => {
+                meta.insert(META_HASH_METHOD_KEY, bincode::serialize(&hash_method)?)?;
+                hash_method
+            }
+        };
+
+        let packs_dir = data_dir.join("chunk_packs");
+        std::fs::create_dir_all(&packs_dir)?;
+        let next_pack_id = next_pack_id_after_existing(&packs_dir)?;
+
+        // Count existing chunks and recover their total size from the
+        // locators sled holds (it no longer stores raw chunk bytes).
         let chunk_count = db.len();
         let mut total_size = 0u64;
         for result in db.iter() {
             if let Ok((_, value)) = result {
-                total_size += value.len() as u64;
+                if let Ok(locator) = bincode::deserialize::<ChunkLocator>(&value) {
+                    total_size += locator.raw_size as u64;
+                }
             }
         }
-        
+
         Ok(Self {
             db,
+            packs_dir,
+            next_pack_id: AtomicU64::new(next_pack_id),
+            default_hash_method,
+            staging: RwLock::new(Vec::new()),
             cache: RwLock::new(HashMap::new()),
-            cache_max_size: 1000, // Max cached chunks
+            cache_max_bytes: DEFAULT_CACHE_MAX_BYTES,
+            cache_bytes: AtomicUsize::new(0),
+            cache_clock: AtomicU64::new(0),
             chunk_count: AtomicUsize::new(chunk_count),
             total_size: AtomicU64::new(total_size),
         })
     }
 
-    /// Store a chunk, returns its content-addressed ID
+    /// Override the in-memory cache's byte budget (default
+    /// `DEFAULT_CACHE_MAX_BYTES`). Takes effect immediately: if the cache
+    /// already holds more than `max_bytes`, the next insert evicts down
+    /// to the new budget.
+    pub fn with_cache_bytes(mut self, max_bytes: usize) -> Self {
+        self.cache_max_bytes = max_bytes;
+        self
+    }
+
+    /// The hashing algorithm this store's `ChunkId`s are computed with.
+    pub fn hash_method(&self) -> HashMethod {
+        self.default_hash_method
+    }
+
+    /// Store a chunk using the store's default hash method, returns its
+    /// content-addressed ID.
     pub fn put(&self, data: &[u8]) -> Result<ChunkId> {
-        let chunk_id = hash(data);
-        
-        // Check if already exists
-        if self.db.contains_key(&chunk_id)? {
+        self.put_with_method(data, self.default_hash_method)
+    }
+
+    /// Store a chunk, computing its `ChunkId` with `hash_method` instead
+    /// of the store's default. Callers that do this are responsible for
+    /// recording which method they used (e.g. in a published manifest) —
+    /// the store itself keeps hashing everything else with its default.
+    pub fn put_with_method(&self, data: &[u8], hash_method: HashMethod) -> Result<ChunkId> {
+        let chunk_id = hash_method.hash(data);
+
+        if self.contains(&chunk_id)? {
             return Ok(chunk_id);
         }
-        
-        // Store in database
-        self.db.insert(&chunk_id, data)?;
-        
-        // Update stats
+
+        {
+            let mut staging = self.staging.write();
+            // Re-check under the lock: another thread may have staged or
+            // flushed this same content-addressed chunk since the check
+            // above.
+            if staging.iter().any(|(id, _)| id == &chunk_id) || self.db.contains_key(chunk_id)? {
+                return Ok(chunk_id);
+            }
+            staging.push((chunk_id, data.to_vec()));
+        }
+
         self.chunk_count.fetch_add(1, Ordering::Relaxed);
         self.total_size.fetch_add(data.len() as u64, Ordering::Relaxed);
-        
-        // Add to cache
         self.cache_put(chunk_id, data.to_vec());
-        
+
+        let should_flush = {
+            let staging = self.staging.read();
+            staging.len() >= PACK_FLUSH_CHUNK_COUNT
+                || staging.iter().map(|(_, d)| d.len()).sum::<usize>() >= PACK_FLUSH_BYTES
+        };
+        if should_flush {
+            self.flush_pack()?;
+        }
+
         Ok(chunk_id)
     }
 
     /// Get a chunk by ID
     pub fn get(&self, chunk_id: &ChunkId) -> Result<Option<Vec<u8>>> {
-        // Check cache first
-        if let Some(data) = self.cache.read().get(chunk_id) {
+        // Check cache first, bumping its recency so it survives eviction
+        // a little longer than chunks nobody's re-read.
+        if let Some(data) = self.cache_touch(chunk_id) {
+            return Ok(Some(data));
+        }
+
+        // Not yet packed?
+        if let Some((_, data)) = self.staging.read().iter().find(|(id, _)| id == chunk_id) {
             return Ok(Some(data.clone()));
         }
-        
-        // Load from database
+
+        // Load from its pack, via the locator sled holds for it.
         match self.db.get(chunk_id)? {
-            Some(data) => {
-                let data = data.to_vec();
+            Some(bytes) => {
+                let locator: ChunkLocator = bincode::deserialize(&bytes)?;
+                let path = self.pack_path(locator.pack_id);
+                let data = chunk_pack::read_chunk_at(&path, locator.offset, locator.stored_size, locator.compression)?;
+                if data.len() != locator.raw_size as usize {
+                    return Err(anyhow!("chunk pack size mismatch for {:?}", chunk_id));
+                }
                 self.cache_put(*chunk_id, data.clone());
                 Ok(Some(data))
             }
@@ -89,6 +248,9 @@ impl ChunkStore {
         if self.cache.read().contains_key(chunk_id) {
             return Ok(true);
         }
+        if self.staging.read().iter().any(|(id, _)| id == chunk_id) {
+            return Ok(true);
+        }
         Ok(self.db.contains_key(chunk_id)?)
     }
 
@@ -96,14 +258,14 @@ impl ChunkStore {
     pub fn get_many(&self, chunk_ids: &[ChunkId]) -> Result<(Vec<(ChunkId, Vec<u8>)>, Vec<ChunkId>)> {
         let mut found = Vec::new();
         let mut missing = Vec::new();
-        
+
         for chunk_id in chunk_ids {
             match self.get(chunk_id)? {
                 Some(data) => found.push((*chunk_id, data)),
                 None => missing.push(*chunk_id),
             }
         }
-        
+
         Ok((found, missing))
     }
 
@@ -118,15 +280,32 @@ impl ChunkStore {
         Ok(missing)
     }
 
-    /// Delete a chunk
+    /// Delete a chunk. Packs are append-only, so this only removes the
+    /// chunk's locator (or staged bytes) — it does not reclaim space
+    /// within a pack file that's already been written.
     pub fn delete(&self, chunk_id: &ChunkId) -> Result<bool> {
-        if let Some(data) = self.db.remove(chunk_id)? {
-            self.chunk_count.fetch_sub(1, Ordering::Relaxed);
-            self.total_size.fetch_sub(data.len() as u64, Ordering::Relaxed);
-            self.cache.write().remove(chunk_id);
-            Ok(true)
-        } else {
-            Ok(false)
+        if let Some(entry) = self.cache.write().remove(chunk_id) {
+            self.cache_bytes.fetch_sub(entry.data.len(), Ordering::Relaxed);
+        }
+
+        {
+            let mut staging = self.staging.write();
+            if let Some(pos) = staging.iter().position(|(id, _)| id == chunk_id) {
+                let (_, data) = staging.remove(pos);
+                self.chunk_count.fetch_sub(1, Ordering::Relaxed);
+                self.total_size.fetch_sub(data.len() as u64, Ordering::Relaxed);
+                return Ok(true);
+            }
+        }
+
+        match self.db.remove(chunk_id)? {
+            Some(bytes) => {
+                let locator: ChunkLocator = bincode::deserialize(&bytes)?;
+                self.chunk_count.fetch_sub(1, Ordering::Relaxed);
+                self.total_size.fetch_sub(locator.raw_size as u64, Ordering::Relaxed);
+                Ok(true)
+            }
+            None => Ok(false),
         }
     }
 
@@ -140,26 +319,151 @@ impl ChunkStore {
         self.total_size.load(Ordering::Relaxed)
     }
 
-    /// Flush to disk
+    /// Flush to disk: pack any staged chunks, then flush sled.
     pub fn flush(&self) -> Result<()> {
+        self.flush_pack()?;
         self.db.flush()?;
         Ok(())
     }
 
-    /// Add to cache with simple eviction
+    /// Pack whatever's currently staged into a new append-only pack file
+    /// and record each chunk's locator in sled.
+    fn flush_pack(&self) -> Result<()> {
+        let pending: Vec<(ChunkId, Vec<u8>)> = {
+            let mut staging = self.staging.write();
+            std::mem::take(&mut *staging)
+        };
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let pack_id = self.next_pack_id.fetch_add(1, Ordering::Relaxed);
+        let (file_bytes, entries) = chunk_pack::encode_pack(&pending, PACK_COMPRESSION, self.default_hash_method)?;
+
+        let path = self.pack_path(pack_id);
+        let tmp_path = path.with_extension("pack.tmp");
+        std::fs::write(&tmp_path, &file_bytes)?;
+        std::fs::rename(&tmp_path, &path)?;
+
+        let mut batch = sled::Batch::default();
+        for entry in entries {
+            let locator = ChunkLocator {
+                pack_id,
+                offset: entry.offset,
+                raw_size: entry.raw_size,
+                stored_size: entry.stored_size,
+                compression: PACK_COMPRESSION,
+            };
+            batch.insert(&entry.chunk_id, bincode::serialize(&locator)?);
+        }
+        self.db.apply_batch(batch)?;
+
+        Ok(())
+    }
+
+    /// Delete every stored chunk whose ID is not in `live`, reclaiming
+    /// `chunk_count`/`total_size` as it goes. Callers are expected to
+    /// compute `live` from whatever currently references chunks (e.g.
+    /// `BundleStore::live_chunk_ids`) — `ChunkStore` has no notion of who
+    /// references a chunk on its own. Like `delete`, this cannot reclaim
+    /// space within an already-written pack file; a pack only shrinks on
+    /// disk once every chunk it holds has been vacuumed and, eventually,
+    /// the file itself is removed by out-of-band tooling.
+    pub fn vacuum(&self, live: &HashSet<ChunkId>) -> Result<VacuumStats> {
+        let mut orphaned: Vec<ChunkId> = self
+            .staging
+            .read()
+            .iter()
+            .filter(|(id, _)| !live.contains(id))
+            .map(|(id, _)| *id)
+            .collect();
+
+        for result in self.db.iter() {
+            let (key, _) = result?;
+            let mut chunk_id = [0u8; 32];
+            chunk_id.copy_from_slice(&key);
+            if !live.contains(&chunk_id) {
+                orphaned.push(chunk_id);
+            }
+        }
+
+        let mut stats = VacuumStats::default();
+        for chunk_id in orphaned {
+            let size_before = self.total_size();
+            if self.delete(&chunk_id)? {
+                stats.chunks_removed += 1;
+                stats.bytes_reclaimed += size_before.saturating_sub(self.total_size());
+            }
+        }
+
+        Ok(stats)
+    }
+
+    fn pack_path(&self, pack_id: u64) -> PathBuf {
+        self.packs_dir.join(format!("{:016x}.pack", pack_id))
+    }
+
+    /// Look up `chunk_id` in the cache, bumping its recency on a hit.
+    fn cache_touch(&self, chunk_id: &ChunkId) -> Option<Vec<u8>> {
+        let mut cache = self.cache.write();
+        let tick = self.cache_clock.fetch_add(1, Ordering::Relaxed);
+        let entry = cache.get_mut(chunk_id)?;
+        entry.last_used = tick;
+        Some(entry.data.clone())
+    }
+
+    /// Add to cache, evicting least-recently-used entries first if this
+    /// insert would push it over `cache_max_bytes`.
     fn cache_put(&self, chunk_id: ChunkId, data: Vec<u8>) {
+        // A single chunk larger than the whole budget isn't worth
+        // caching at all — it would just evict everything else for no
+        // benefit the next time it's read.
+        if data.len() > self.cache_max_bytes {
+            return;
+        }
+
         let mut cache = self.cache.write();
-        
-        // Simple eviction: clear half when full
-        if cache.len() >= self.cache_max_size {
-            let to_remove: Vec<_> = cache.keys().take(self.cache_max_size / 2).cloned().collect();
-            for key in to_remove {
-                cache.remove(&key);
+        let tick = self.cache_clock.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(old) = cache.insert(chunk_id, CacheEntry { data: data.clone(), last_used: tick }) {
+            self.cache_bytes.fetch_sub(old.data.len(), Ordering::Relaxed);
+        }
+        let new_total = self.cache_bytes.fetch_add(data.len(), Ordering::Relaxed) + data.len();
+
+        if new_total > self.cache_max_bytes {
+            Self::evict_lru(&mut cache, &self.cache_bytes, self.cache_max_bytes);
+        }
+    }
+
+    /// Evict entries in least-recently-used order until `cache_bytes` is
+    /// back under `max_bytes`.
+    fn evict_lru(cache: &mut HashMap<ChunkId, CacheEntry>, cache_bytes: &AtomicUsize, max_bytes: usize) {
+        let mut by_recency: Vec<ChunkId> = cache.keys().copied().collect();
+        by_recency.sort_unstable_by_key(|id| cache[id].last_used);
+
+        for chunk_id in by_recency {
+            if cache_bytes.load(Ordering::Relaxed) <= max_bytes {
+                break;
+            }
+            if let Some(entry) = cache.remove(&chunk_id) {
+                cache_bytes.fetch_sub(entry.data.len(), Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Scan existing pack files to resume pack IDs after reopening a store.
+fn next_pack_id_after_existing(packs_dir: &Path) -> Result<u64> {
+    let mut max_id: Option<u64> = None;
+    for entry in std::fs::read_dir(packs_dir)? {
+        let entry = entry?;
+        if let Some(stem) = entry.path().file_stem().and_then(|s| s.to_str().map(str::to_string)) {
+            if let Ok(id) = u64::from_str_radix(&stem, 16) {
+                max_id = Some(max_id.map_or(id, |m| m.max(id)));
             }
         }
-        
-        cache.insert(chunk_id, data);
     }
+    Ok(max_id.map_or(0, |m| m + 1))
 }
 
 #[cfg(test)]
@@ -171,25 +475,50 @@ mod tests {
     fn test_chunk_store() -> Result<()> {
         let dir = tempdir()?;
         let store = ChunkStore::new(dir.path())?;
-        
+
         let data = b"hello grabnet";
         let chunk_id = store.put(data)?;
-        
+
         // Verify content addressing
         assert_eq!(chunk_id, hash(data));
-        
+
         // Retrieve
         let retrieved = store.get(&chunk_id)?.unwrap();
         assert_eq!(retrieved, data);
-        
+
         // Contains
         assert!(store.contains(&chunk_id)?);
         assert!(!store.contains(&[0u8; 32])?);
-        
+
         // Stats
         assert_eq!(store.count(), 1);
         assert_eq!(store.total_size(), data.len() as u64);
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used_first() -> Result<()> {
+        let dir = tempdir()?;
+        // Budget only large enough for two ~10-byte chunks at a time.
+        let store = ChunkStore::new(dir.path())?.with_cache_bytes(20);
+
+        let a = store.put(b"aaaaaaaaaa")?;
+        let b = store.put(b"bbbbbbbbbb")?;
+        // Touch `a` so `b` is the least recently used entry.
+        store.get(&a)?;
+
+        // A third insert must evict something; `b` should go, not `a`.
+        let c = store.put(b"cccccccccc")?;
+
+        assert!(store.cache.read().contains_key(&a));
+        assert!(!store.cache.read().contains_key(&b));
+        assert!(store.cache.read().contains_key(&c));
+        assert!(store.cache_bytes.load(Ordering::Relaxed) <= 20);
+
+        // Eviction only drops it from the cache; it's still stored.
+        assert_eq!(store.get(&b)?.unwrap(), b"bbbbbbbbbb");
+
         Ok(())
     }
 
@@ -197,17 +526,129 @@ mod tests {
     fn test_deduplication() -> Result<()> {
         let dir = tempdir()?;
         let store = ChunkStore::new(dir.path())?;
-        
+
         let data = b"duplicate content";
         let id1 = store.put(data)?;
         let id2 = store.put(data)?;
-        
+
         // Same content = same ID
         assert_eq!(id1, id2);
-        
+
         // Only stored once
         assert_eq!(store.count(), 1);
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_survives_pack_flush() -> Result<()> {
+        let dir = tempdir()?;
+        let store = ChunkStore::new(dir.path())?;
+
+        let data = b"chunk that gets packed to disk";
+        let chunk_id = store.put(data)?;
+        store.flush()?;
+
+        // Cache still has it; drop it to force a read from the pack file.
+        store.cache.write().clear();
+        store.cache_bytes.store(0, Ordering::Relaxed);
+
+        let retrieved = store.get(&chunk_id)?.unwrap();
+        assert_eq!(retrieved, data);
+        assert!(store.contains(&chunk_id)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reopen_resumes_pack_ids() -> Result<()> {
+        let dir = tempdir()?;
+        {
+            let store = ChunkStore::new(dir.path())?;
+            store.put(b"first chunk")?;
+            store.flush()?;
+        }
+
+        let store = ChunkStore::new(dir.path())?;
+        assert_eq!(store.count(), 1);
+        assert_eq!(store.total_size(), "first chunk".len() as u64);
+
+        let chunk_id = hash(b"first chunk");
+        assert_eq!(store.get(&chunk_id)?.unwrap(), b"first chunk");
+
+        // A newly packed chunk must not collide with the existing pack file.
+        store.put(b"second chunk")?;
+        store.flush()?;
+        assert_eq!(store.count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_put_with_method_uses_requested_hash() -> Result<()> {
+        let dir = tempdir()?;
+        let store = ChunkStore::new(dir.path())?;
+
+        let data = b"hashed a different way";
+        let chunk_id = store.put_with_method(data, HashMethod::Sha256)?;
+        assert_eq!(chunk_id, HashMethod::Sha256.hash(data));
+
+        assert_eq!(store.get(&chunk_id)?.unwrap(), data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vacuum_removes_chunks_not_in_live_set() -> Result<()> {
+        let dir = tempdir()?;
+        let store = ChunkStore::new(dir.path())?;
+
+        let kept_id = store.put(b"kept chunk")?;
+        let orphaned_id = store.put(b"orphaned chunk")?;
+        store.flush()?;
+
+        let mut live = HashSet::new();
+        live.insert(kept_id);
+
+        let stats = store.vacuum(&live)?;
+        assert_eq!(stats.chunks_removed, 1);
+        assert_eq!(stats.bytes_reclaimed, "orphaned chunk".len() as u64);
+
+        assert!(store.contains(&kept_id)?);
+        assert!(!store.contains(&orphaned_id)?);
+        assert_eq!(store.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vacuum_also_sweeps_staged_chunks() -> Result<()> {
+        let dir = tempdir()?;
+        let store = ChunkStore::new(dir.path())?;
+
+        let orphaned_id = store.put(b"never packed")?;
+        // Not flushed: this chunk only exists in the staging buffer.
+
+        let stats = store.vacuum(&HashSet::new())?;
+        assert_eq!(stats.chunks_removed, 1);
+        assert!(!store.contains(&orphaned_id)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reopen_keeps_original_hash_method() -> Result<()> {
+        let dir = tempdir()?;
+        {
+            let store = ChunkStore::with_hash_method(dir.path(), HashMethod::Sha256)?;
+            assert_eq!(store.hash_method(), HashMethod::Sha256);
+        }
+
+        // Reopening with a different requested method doesn't change what
+        // the store was actually created with.
+        let store = ChunkStore::with_hash_method(dir.path(), HashMethod::Blake3)?;
+        assert_eq!(store.hash_method(), HashMethod::Sha256);
+
         Ok(())
     }
 }
@@ -1,55 +1,186 @@
 //! Ed25519 key management
+//!
+//! Private keys are never written to disk or handed back to callers in the
+//! clear. Each one is sealed with XChaCha20-Poly1305 before it hits the
+//! `private` sled tree, using a key Argon2id-stretches from an operator
+//! passphrase (the salt lives in the `meta` tree so it survives restarts).
+//! The `public` tree stays plaintext, since public keys aren't secret and
+//! need to be readable without unlocking anything. Every private key that
+//! leaves this module comes back wrapped in `Zeroizing` so the backing
+//! buffer is wiped on drop instead of lingering on the heap or stack.
 
 use std::path::Path;
-use anyhow::{Result, anyhow};
+use anyhow::{Result, anyhow, bail};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    Key as AeadKey, XChaCha20Poly1305, XNonce,
+};
+use parking_lot::RwLock;
+use rand::{rngs::OsRng, RngCore};
+use zeroize::Zeroizing;
 
 use crate::types::PublicKey;
 use crate::crypto::{generate_keypair, encode_base58, SiteIdExt};
 
+/// Meta-tree key the Argon2id salt is stored under.
+const SALT_KEY: &[u8] = b"kdf_salt";
+
+/// Salt length for Argon2id. 16 bytes is the recommended minimum.
+const SALT_LEN: usize = 16;
+
+/// Nonce length for XChaCha20-Poly1305.
+const NONCE_LEN: usize = 24;
+
 /// Key store for Ed25519 keypairs
 pub struct KeyStore {
     db: sled::Db,
-    /// Private keys tree
+    /// Private keys tree: `nonce || ciphertext`, sealed with the derived cipher
     private_keys: sled::Tree,
-    /// Public keys tree (for quick lookup)
+    /// Public keys tree (for quick lookup), kept in the clear
     public_keys: sled::Tree,
+    /// Holds the Argon2id-derived salt
+    meta: sled::Tree,
+    /// `None` while locked; private-key reads/writes fail until unlocked
+    cipher: RwLock<Option<XChaCha20Poly1305>>,
 }
 
 impl KeyStore {
-    /// Create a new key store
+    /// Create a new key store with no passphrase. Equivalent to
+    /// `new_with_passphrase(data_dir, None)`: private keys are still
+    /// encrypted at rest, just with a key derived from an empty passphrase,
+    /// so existing single-operator workflows keep working without prompting.
+    /// Call [`KeyStore::lock`] and [`KeyStore::unlock`] to require a real
+    /// passphrase for a session.
     pub fn new(data_dir: &Path) -> Result<Self> {
+        Self::new_with_passphrase(data_dir, None)
+    }
+
+    /// Create a new key store, immediately unlocking it by deriving the
+    /// encryption key from `passphrase` (or from an empty passphrase if
+    /// `None`).
+    pub fn new_with_passphrase(data_dir: &Path, passphrase: Option<&str>) -> Result<Self> {
         let db_path = data_dir.join("keys.db");
         let db = sled::open(&db_path)?;
-        
-        Ok(Self {
+
+        let store = Self {
             private_keys: db.open_tree("private")?,
             public_keys: db.open_tree("public")?,
+            meta: db.open_tree("meta")?,
+            cipher: RwLock::new(None),
             db,
-        })
+        };
+
+        store.unlock(passphrase.unwrap_or(""))?;
+        Ok(store)
+    }
+
+    /// The Argon2id salt, generating and persisting one on first use.
+    fn salt(&self) -> Result<[u8; SALT_LEN]> {
+        if let Some(existing) = self.meta.get(SALT_KEY)? {
+            if existing.len() != SALT_LEN {
+                bail!("corrupted key store: bad salt length");
+            }
+            let mut salt = [0u8; SALT_LEN];
+            salt.copy_from_slice(&existing);
+            return Ok(salt);
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        self.meta.insert(SALT_KEY, &salt)?;
+        Ok(salt)
+    }
+
+    /// Derive the encryption key from `passphrase` via Argon2id and unlock
+    /// the store for private-key reads/writes. Safe to call again (e.g.
+    /// after [`KeyStore::lock`]) to re-derive the same key.
+    pub fn unlock(&self, passphrase: &str) -> Result<()> {
+        let salt = self.salt()?;
+
+        let mut derived = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut *derived)
+            .map_err(|e| anyhow!("key derivation failed: {e}"))?;
+
+        let cipher = XChaCha20Poly1305::new(AeadKey::from_slice(&*derived));
+        *self.cipher.write() = Some(cipher);
+        Ok(())
+    }
+
+    /// Drop the derived cipher from memory. Public key lookups still work;
+    /// private-key access errors until [`KeyStore::unlock`] is called again.
+    pub fn lock(&self) {
+        *self.cipher.write() = None;
+    }
+
+    /// `true` if the store currently has no derived cipher in memory.
+    pub fn is_locked(&self) -> bool {
+        self.cipher.read().is_none()
+    }
+
+    fn encrypt(&self, plaintext: &[u8; 32]) -> Result<Vec<u8>> {
+        let guard = self.cipher.read();
+        let cipher = guard.as_ref().ok_or_else(|| anyhow!("key store is locked"))?;
+
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce_bytes);
+
+        let ciphertext = cipher
+            .encrypt(XNonce::from_slice(&nonce_bytes), plaintext.as_slice())
+            .map_err(|_| anyhow!("encryption failed"))?;
+
+        let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce_bytes);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    fn decrypt(&self, sealed: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        let guard = self.cipher.read();
+        let cipher = guard.as_ref().ok_or_else(|| anyhow!("key store is locked"))?;
+
+        if sealed.len() <= NONCE_LEN {
+            bail!("corrupted key store entry");
+        }
+        let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(XNonce::from_slice(nonce_bytes), ciphertext)
+                .map_err(|_| anyhow!("decryption failed (wrong passphrase?)"))?,
+        );
+        if plaintext.len() != 32 {
+            bail!("corrupted key store entry");
+        }
+
+        let mut private_key = Zeroizing::new([0u8; 32]);
+        private_key.copy_from_slice(&plaintext);
+        Ok(private_key)
     }
 
     /// Get or create a keypair by name
-    pub fn get_or_create(&self, name: &str) -> Result<(PublicKey, [u8; 32])> {
+    pub fn get_or_create(&self, name: &str) -> Result<(PublicKey, Zeroizing<[u8; 32]>)> {
         // Check if exists
-        if let Some(private_key) = self.private_keys.get(name.as_bytes())? {
+        if let Some(sealed) = self.private_keys.get(name.as_bytes())? {
             let public_key = self.public_keys.get(name.as_bytes())?
                 .ok_or_else(|| anyhow!("Corrupted key store: missing public key"))?;
-            
-            let mut priv_arr = [0u8; 32];
+
             let mut pub_arr = [0u8; 32];
-            priv_arr.copy_from_slice(&private_key);
             pub_arr.copy_from_slice(&public_key);
-            
-            return Ok((pub_arr, priv_arr));
+
+            return Ok((pub_arr, self.decrypt(&sealed)?));
         }
-        
+
         // Generate new keypair
         let (public_key, private_key) = generate_keypair();
-        
+        let private_key = Zeroizing::new(private_key);
+
         // Store
-        self.private_keys.insert(name.as_bytes(), &private_key)?;
+        let sealed = self.encrypt(&private_key)?;
+        self.private_keys.insert(name.as_bytes(), sealed)?;
         self.public_keys.insert(name.as_bytes(), &public_key)?;
-        
+
         Ok((public_key, private_key))
     }
 
@@ -65,24 +196,27 @@ impl KeyStore {
         }
     }
 
-    /// Get private key by name (use with caution)
-    pub fn get_private_key(&self, name: &str) -> Result<Option<[u8; 32]>> {
+    /// Get private key by name (use with caution). Returns an error if the
+    /// store is locked.
+    pub fn get_private_key(&self, name: &str) -> Result<Option<Zeroizing<[u8; 32]>>> {
         match self.private_keys.get(name.as_bytes())? {
-            Some(data) => {
-                let mut arr = [0u8; 32];
-                arr.copy_from_slice(&data);
-                Ok(Some(arr))
-            }
+            Some(sealed) => Ok(Some(self.decrypt(&sealed)?)),
             None => Ok(None),
         }
     }
 
-    /// List all key names
+    /// List all key names, excluding internal keys (e.g. the per-device
+    /// identity used for causal revision tracking) that aren't meant to be
+    /// managed as publishing identities. Works even while locked, since
+    /// names and public keys are never encrypted.
     pub fn list_keys(&self) -> Result<Vec<String>> {
         let mut names = Vec::new();
         for result in self.public_keys.iter() {
             let (key, _) = result?;
-            names.push(String::from_utf8_lossy(&key).to_string());
+            let name = String::from_utf8_lossy(&key).to_string();
+            if !name.starts_with("__") {
+                names.push(name);
+            }
         }
         Ok(names)
     }
@@ -92,18 +226,21 @@ impl KeyStore {
         // Derive public key
         let signing_key = ed25519_dalek::SigningKey::from_bytes(private_key);
         let public_key = signing_key.verifying_key().to_bytes();
-        
+
         // Store
-        self.private_keys.insert(name.as_bytes(), private_key)?;
+        let sealed = self.encrypt(private_key)?;
+        self.private_keys.insert(name.as_bytes(), sealed)?;
         self.public_keys.insert(name.as_bytes(), &public_key)?;
-        
+
         Ok(public_key)
     }
 
-    /// Export a private key (returns base58 encoded)
+    /// Export a private key (returns base58 encoded). The intermediate
+    /// `Zeroizing` buffer from [`KeyStore::get_private_key`] is wiped as
+    /// soon as it's encoded.
     pub fn export(&self, name: &str) -> Result<Option<String>> {
         match self.get_private_key(name)? {
-            Some(key) => Ok(Some(encode_base58(&key))),
+            Some(key) => Ok(Some(encode_base58(&*key))),
             None => Ok(None),
         }
     }
@@ -131,19 +268,19 @@ mod tests {
     fn test_key_creation() -> Result<()> {
         let dir = tempdir()?;
         let store = KeyStore::new(dir.path())?;
-        
+
         // Get or create
         let (pub1, priv1) = store.get_or_create("default")?;
-        
+
         // Should return same key
         let (pub2, priv2) = store.get_or_create("default")?;
         assert_eq!(pub1, pub2);
-        assert_eq!(priv1, priv2);
-        
+        assert_eq!(*priv1, *priv2);
+
         // Different name = different key
         let (pub3, _) = store.get_or_create("other")?;
         assert_ne!(pub1, pub3);
-        
+
         Ok(())
     }
 
@@ -151,26 +288,26 @@ mod tests {
     fn test_key_import_export() -> Result<()> {
         let dir = tempdir()?;
         let store = KeyStore::new(dir.path())?;
-        
+
         // Create a key
         let (_, original_private) = store.get_or_create("test")?;
-        
+
         // Export
         let exported = store.export("test")?.unwrap();
-        
+
         // Import to different name
         let dir2 = tempdir()?;
         let store2 = KeyStore::new(dir2.path())?;
-        
-        let mut private_bytes = [0u8; 32];
+
+        let mut private_bytes = Zeroizing::new([0u8; 32]);
         private_bytes.copy_from_slice(&bs58::decode(&exported).into_vec()?);
-        
+
         store2.import("imported", &private_bytes)?;
-        
+
         // Verify
         let retrieved = store2.get_private_key("imported")?.unwrap();
-        assert_eq!(retrieved, original_private);
-        
+        assert_eq!(*retrieved, *original_private);
+
         Ok(())
     }
 
@@ -178,17 +315,42 @@ mod tests {
     fn test_list_keys() -> Result<()> {
         let dir = tempdir()?;
         let store = KeyStore::new(dir.path())?;
-        
+
         store.get_or_create("key1")?;
         store.get_or_create("key2")?;
         store.get_or_create("key3")?;
-        
+
         let keys = store.list_keys()?;
         assert_eq!(keys.len(), 3);
         assert!(keys.contains(&"key1".to_string()));
         assert!(keys.contains(&"key2".to_string()));
         assert!(keys.contains(&"key3".to_string()));
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_passphrase_lock_unlock() -> Result<()> {
+        let dir = tempdir()?;
+        let store = KeyStore::new_with_passphrase(dir.path(), Some("correct horse"))?;
+
+        let (public_key, _) = store.get_or_create("default")?;
+
+        // Locking hides private material but not public lookups
+        store.lock();
+        assert!(store.is_locked());
+        assert_eq!(store.get_public_key("default")?, Some(public_key));
+        assert!(store.get_private_key("default").is_err());
+
+        // Wrong passphrase fails to decrypt the sealed key
+        store.unlock("wrong passphrase")?;
+        assert!(store.get_private_key("default").is_err());
+
+        // Right passphrase unlocks it again
+        store.unlock("correct horse")?;
+        assert!(!store.is_locked());
+        assert!(store.get_private_key("default").is_ok());
+
         Ok(())
     }
 }
@@ -1,9 +1,14 @@
 //! Storage layer for GrabNet
 
 mod chunks;
+mod chunk_pack;
 mod bundles;
 mod keys;
+mod names;
+mod name_chain;
 
-pub use chunks::ChunkStore;
-pub use bundles::BundleStore;
+pub use chunks::{ChunkStore, VacuumStats};
+pub use bundles::{BundleStore, BundleCodec};
 pub use keys::KeyStore;
+pub use names::NameStore;
+pub use name_chain::{NameChain, NameBlock, NameClaim, DEFAULT_DIFFICULTY_BITS};
@@ -0,0 +1,498 @@
+//! Append-only, proof-of-work-gated name-claim chain (inspired by Alfis)
+//!
+//! [`super::NameStore`] already maps names to sites with a
+//! simple first-seen-publisher-wins rule, exchanged directly between two
+//! peers via [`crate::types::GrabRequest::ResolveName`]. That works well
+//! for a single claim reaching a single peer, but gives squatting no cost
+//! beyond winning a race, and has no way for a node that's been offline to
+//! tell whether the claim history it's missing changed the outcome.
+//!
+//! `NameChain` is a separate, gossiped alternative: name claims are signed
+//! transactions batched into blocks, and a block's hash must satisfy a
+//! proof-of-work target before the chain will accept it. Minting a block
+//! costs real CPU, so flooding the chain with squatting claims costs real
+//! CPU too. Blocks are linked by `prev_hash`.
+//!
+//! We gossip whole blocks one at a time rather than whole chains, which
+//! bounds what `ingest_block` can safely accept: without the intervening
+//! blocks there is no way to tell a genuinely taller fork from one that
+//! simply lies about its `height`, so `ingest_block` only ever accepts a
+//! block that directly extends our own verified tip (`prev_hash == our
+//! tip hash`). A node that falls behind a real fork needs its missing
+//! ancestors backfilled from peers to catch back up; that isn't
+//! implemented here.
+
+use std::path::Path;
+
+use anyhow::Result;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{hash, sign_name_claim, verify_name_claim};
+use crate::types::{PublicKey, SiteId, Signature};
+
+/// Default number of leading zero bits a block hash must have. Low enough
+/// that a single node can mine a block in well under a second, which is
+/// all the rate-limiting a reference implementation needs -- operators who
+/// want claims to cost more can raise it with [`NameChain::with_difficulty_bits`].
+pub const DEFAULT_DIFFICULTY_BITS: u32 = 16;
+
+/// A signed name -> site binding, not yet (or recently) batched into a
+/// block. `prev_hash` records the tip the claim was built against and is
+/// covered by the signature; it is not compared with the chain when the
+/// claim is submitted or ingested, so a claim is accepted on top of any
+/// tip. `renewal` marks a claim as a renewal by the name's existing owner
+/// rather than a first claim.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NameClaim {
+    pub name: String,
+    pub site_id: SiteId,
+    pub owner: PublicKey,
+    pub prev_hash: [u8; 32],
+    pub nonce: u64,
+    pub renewal: bool,
+    pub signature: Signature,
+}
+
+impl NameClaim {
+    /// Sign a new claim for `name` against `prev_hash`, the chain tip the
+    /// claimant built it on.
+    pub fn new(
+        name: String,
+        site_id: SiteId,
+        owner: PublicKey,
+        prev_hash: [u8; 32],
+        nonce: u64,
+        renewal: bool,
+        private_key: &[u8; 32],
+    ) -> Self {
+        let signature = sign_name_claim(&name, &site_id, &prev_hash, nonce, renewal, private_key);
+        Self { name, site_id, owner, prev_hash, nonce, renewal, signature }
+    }
+
+    fn verify(&self) -> bool {
+        verify_name_claim(&self.name, &self.site_id, &self.prev_hash, self.nonce, self.renewal, &self.signature, &self.owner)
+    }
+}
+
+/// A batch of claims, mined on top of `prev_hash` at `height`. `block_nonce`
+/// is the proof-of-work nonce, distinct from any individual claim's `nonce`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NameBlock {
+    pub claims: Vec<NameClaim>,
+    pub prev_hash: [u8; 32],
+    pub height: u64,
+    pub block_nonce: u64,
+}
+
+impl NameBlock {
+    /// Content hash of this block, including its proof-of-work nonce.
+    pub fn hash(&self) -> [u8; 32] {
+        let bytes = bincode::serialize(self).expect("NameBlock always serializes");
+        hash(&bytes)
+    }
+
+    /// Whether this block's hash has at least `difficulty_bits` leading
+    /// zero bits.
+    fn meets_difficulty(&self, difficulty_bits: u32) -> bool {
+        leading_zero_bits(&self.hash()) >= difficulty_bits
+    }
+}
+
+fn leading_zero_bits(digest: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in digest {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Where a name currently resolves to, and how it got there -- stored in
+/// the `index` tree so `resolve`/`confirmed_depth` don't have to replay
+/// the chain on every lookup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    site_id: SiteId,
+    owner: PublicKey,
+    height: u64,
+}
+
+/// Persisted claim chain plus an in-memory mempool of claims waiting to be
+/// batched into a block. Blocks are keyed by big-endian height in the
+/// `blocks` tree so the tip is always the max key; `index` holds the
+/// current name -> site_id resolution, replayed incrementally as blocks
+/// are appended rather than recomputed from scratch.
+pub struct NameChain {
+    blocks: sled::Tree,
+    index: sled::Tree,
+    pending: RwLock<Vec<NameClaim>>,
+    difficulty_bits: u32,
+}
+
+impl NameChain {
+    /// Open (or create) the name-claim chain under `data_dir`.
+    pub fn new(data_dir: &Path) -> Result<Self> {
+        let db_path = data_dir.join("name_chain.db");
+        let db = sled::open(&db_path)?;
+
+        Ok(Self {
+            blocks: db.open_tree("blocks")?,
+            index: db.open_tree("index")?,
+            pending: RwLock::new(Vec::new()),
+            difficulty_bits: DEFAULT_DIFFICULTY_BITS,
+        })
+    }
+
+    /// Override the proof-of-work difficulty. Tests use a low value so
+    /// `mine_pending` doesn't burn real wall-clock time.
+    pub fn with_difficulty_bits(mut self, bits: u32) -> Self {
+        self.difficulty_bits = bits;
+        self
+    }
+
+    /// Current chain tip: `(hash, height)`, or the genesis values
+    /// (`[0; 32]`, 0) if no block has been mined yet.
+    pub fn tip(&self) -> Result<([u8; 32], u64)> {
+        match self.blocks.iter().next_back() {
+            Some(entry) => {
+                let (_, value) = entry?;
+                let block: NameBlock = bincode::deserialize(&value)?;
+                Ok((block.hash(), block.height))
+            }
+            None => Ok(([0u8; 32], 0)),
+        }
+    }
+
+    /// Submit a signed claim to the mempool. Verifies the signature and
+    /// the same first-claimer/renewal rule as [`super::NameStore::offer`]
+    /// before accepting it; returns `false` (not an error) for anything
+    /// that fails validation or is squatting on an already-claimed name.
+    pub fn submit_claim(&self, claim: NameClaim) -> Result<bool> {
+        let existing = self.resolve_entry(&claim.name)?;
+        if !Self::claim_is_admissible(&claim, existing.as_ref()) {
+            return Ok(false);
+        }
+
+        self.pending.write().push(claim);
+        Ok(true)
+    }
+
+    /// The first-claimer/renewal ownership rule, shared by `submit_claim`
+    /// (the mempool-admission path) and `ingest_block` (claims arriving
+    /// already batched into a gossiped block) so a block built by hand
+    /// instead of via `submit_claim` can't bypass it.
+    fn claim_is_admissible(claim: &NameClaim, existing: Option<&IndexEntry>) -> bool {
+        if !claim.verify() {
+            return false;
+        }
+        match existing {
+            Some(existing) => claim.owner == existing.owner && claim.renewal,
+            None => !claim.renewal,
+        }
+    }
+
+    /// Claims waiting to be mined into a block, for the dashboard's
+    /// "Name Registrations" card.
+    pub fn pending_claims(&self) -> Vec<NameClaim> {
+        self.pending.read().clone()
+    }
+
+    /// Mine every pending claim into a new block on top of the current
+    /// tip and append it locally. Returns `None` if there are no pending
+    /// claims. The caller is expected to gossip the returned block to
+    /// peers (see `grabnet::network`'s `NAME_CHAIN_TOPIC` handling).
+    pub fn mine_pending(&self) -> Result<Option<NameBlock>> {
+        let claims = {
+            let mut pending = self.pending.write();
+            if pending.is_empty() {
+                return Ok(None);
+            }
+            std::mem::take(&mut *pending)
+        };
+
+        let (prev_hash, prev_height) = self.tip()?;
+        let mut block = NameBlock { claims, prev_hash, height: prev_height + 1, block_nonce: 0 };
+        while !block.meets_difficulty(self.difficulty_bits) {
+            block.block_nonce += 1;
+        }
+
+        self.append_block(&block)?;
+        Ok(Some(block))
+    }
+
+    /// Ingest a block received from a peer. Validates its proof-of-work,
+    /// every claim's signature and first-claimer/renewal rule against our
+    /// current index (exactly as `submit_claim` would for each claim
+    /// individually -- a block built by hand can't skip that check just
+    /// by going around the mempool), and that it directly extends our
+    /// own tip. Returns whether it was accepted.
+    ///
+    /// A block that doesn't extend our tip is always rejected, even if it
+    /// self-reports a greater `height`: without the intervening blocks we
+    /// have no way to confirm that height was actually mined rather than
+    /// made up (see the module doc).
+    pub fn ingest_block(&self, block: NameBlock) -> Result<bool> {
+        if !block.meets_difficulty(self.difficulty_bits) {
+            return Ok(false);
+        }
+        if block.claims.is_empty() {
+            return Ok(false);
+        }
+
+        let (tip_hash, tip_height) = self.tip()?;
+        if block.prev_hash != tip_hash || block.height != tip_height + 1 {
+            return Ok(false);
+        }
+
+        // Claims within the same block are checked in order against a
+        // local overlay of the index, so a legitimate same-block
+        // claim-then-renewal sequence sees its own earlier claims.
+        let mut overlay: std::collections::HashMap<String, IndexEntry> = std::collections::HashMap::new();
+        for claim in &block.claims {
+            let existing = match overlay.get(&claim.name) {
+                Some(entry) => Some(entry.clone()),
+                None => self.resolve_entry(&claim.name)?,
+            };
+            if !Self::claim_is_admissible(claim, existing.as_ref()) {
+                return Ok(false);
+            }
+            overlay.insert(
+                claim.name.clone(),
+                IndexEntry { site_id: claim.site_id, owner: claim.owner, height: block.height },
+            );
+        }
+
+        self.append_block(&block)?;
+        Ok(true)
+    }
+
+    fn append_block(&self, block: &NameBlock) -> Result<()> {
+        for claim in &block.claims {
+            let entry = IndexEntry { site_id: claim.site_id, owner: claim.owner, height: block.height };
+            self.index.insert(claim.name.as_bytes(), bincode::serialize(&entry)?)?;
+            self.pending.write().retain(|p| p.name != claim.name);
+        }
+        self.blocks.insert(block.height.to_be_bytes(), bincode::serialize(block)?)?;
+        Ok(())
+    }
+
+    /// Resolve `name` to its current `SiteId` per the confirmed chain.
+    pub fn resolve(&self, name: &str) -> Result<Option<SiteId>> {
+        Ok(self.resolve_entry(name)?.map(|entry| entry.site_id))
+    }
+
+    fn resolve_entry(&self, name: &str) -> Result<Option<IndexEntry>> {
+        match self.index.get(name.as_bytes())? {
+            Some(data) => Ok(Some(bincode::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// How many blocks deep `name`'s confirming claim is, i.e. the chain
+    /// tip's height minus the height it was confirmed at. `None` if the
+    /// name hasn't been claimed.
+    pub fn confirmed_depth(&self, name: &str) -> Result<Option<u64>> {
+        let Some(entry) = self.resolve_entry(name)? else { return Ok(None) };
+        let (_, tip_height) = self.tip()?;
+        Ok(Some(tip_height - entry.height))
+    }
+
+    /// Height of the chain tip (0 if no blocks have been mined yet).
+    pub fn height(&self) -> Result<u64> {
+        Ok(self.tip()?.1)
+    }
+
+    /// Every confirmed name -> site_id binding, for the dashboard's
+    /// reverse lookup (site_id -> name) and "Name Registrations" card.
+    /// The index has no reverse key, so this is a linear scan; fine for
+    /// occasional dashboard rendering, not meant for the hot path.
+    pub fn all_resolved(&self) -> Result<Vec<(String, SiteId)>> {
+        let mut records = Vec::new();
+        for entry in self.index.iter() {
+            let (name, value) = entry?;
+            let entry: IndexEntry = bincode::deserialize(&value)?;
+            records.push((String::from_utf8_lossy(&name).into_owned(), entry.site_id));
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_keypair;
+    use tempfile::tempdir;
+
+    fn chain() -> NameChain {
+        NameChain::new(tempdir().unwrap().path()).unwrap().with_difficulty_bits(4)
+    }
+
+    fn claim_for(chain: &NameChain, name: &str, site_id: SiteId, owner: PublicKey, private: &[u8; 32], renewal: bool) -> NameClaim {
+        let (prev_hash, _) = chain.tip().unwrap();
+        NameClaim::new(name.to_string(), site_id, owner, prev_hash, 0, renewal, private)
+    }
+
+    #[test]
+    fn test_claim_mines_and_resolves() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        let site_id = [1u8; 32];
+
+        assert!(chain.submit_claim(claim_for(&chain, "example", site_id, public, &private, false)).unwrap());
+        let block = chain.mine_pending().unwrap().unwrap();
+        assert!(block.meets_difficulty(4));
+
+        assert_eq!(chain.resolve("example").unwrap(), Some(site_id));
+        assert_eq!(chain.confirmed_depth("example").unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_squatter_claim_is_rejected() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        let (squatter_public, squatter_private) = generate_keypair();
+        let site_id = [1u8; 32];
+        let squatter_site = [2u8; 32];
+
+        assert!(chain.submit_claim(claim_for(&chain, "example", site_id, public, &private, false)).unwrap());
+        chain.mine_pending().unwrap();
+
+        assert!(!chain.submit_claim(claim_for(&chain, "example", squatter_site, squatter_public, &squatter_private, false)).unwrap());
+        assert_eq!(chain.resolve("example").unwrap(), Some(site_id));
+    }
+
+    #[test]
+    fn test_owner_can_renew() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        let site_id = [1u8; 32];
+        let new_site = [3u8; 32];
+
+        chain.submit_claim(claim_for(&chain, "example", site_id, public, &private, false)).unwrap();
+        chain.mine_pending().unwrap();
+
+        assert!(chain.submit_claim(claim_for(&chain, "example", new_site, public, &private, true)).unwrap());
+        chain.mine_pending().unwrap();
+
+        assert_eq!(chain.resolve("example").unwrap(), Some(new_site));
+    }
+
+    #[test]
+    fn test_renewal_without_prior_claim_is_rejected() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+
+        assert!(!chain.submit_claim(claim_for(&chain, "example", [1u8; 32], public, &private, true)).unwrap());
+    }
+
+    #[test]
+    fn test_tampered_claim_is_rejected() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        let mut claim = claim_for(&chain, "example", [1u8; 32], public, &private, false);
+        claim.site_id = [9u8; 32];
+
+        assert!(!chain.submit_claim(claim).unwrap());
+    }
+
+    #[test]
+    fn test_ingest_rejects_block_below_difficulty() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        let claim = claim_for(&chain, "example", [1u8; 32], public, &private, false);
+        let block = NameBlock { claims: vec![claim], prev_hash: [0u8; 32], height: 1, block_nonce: 0 };
+
+        assert!(!chain.ingest_block(block).unwrap());
+    }
+
+    #[test]
+    fn test_ingest_accepts_block_that_extends_our_tip() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        let site_id = [1u8; 32];
+
+        // Mined on another chain instance from the same genesis, so it
+        // extends `chain`'s tip; the second block is then mined locally on
+        // top of the ingested one.
+        let fork = self::chain();
+        let claim = claim_for(&fork, "example", site_id, public, &private, false);
+        fork.submit_claim(claim).unwrap();
+        let block1 = fork.mine_pending().unwrap().unwrap();
+
+        assert!(chain.ingest_block(block1.clone()).unwrap());
+
+        let other_site = [2u8; 32];
+        let claim2 = claim_for(&chain, "other", other_site, public, &private, false);
+        chain.submit_claim(claim2).unwrap();
+        let block2 = chain.mine_pending().unwrap().unwrap();
+
+        assert_eq!(chain.resolve("example").unwrap(), Some(site_id));
+        assert_eq!(chain.resolve("other").unwrap(), Some(other_site));
+        assert_eq!(chain.height().unwrap(), 2);
+        let _ = block2;
+    }
+
+    #[test]
+    fn test_ingest_rejects_block_that_does_not_extend_tip_even_if_taller() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        chain.submit_claim(claim_for(&chain, "example", [1u8; 32], public, &private, false)).unwrap();
+        chain.mine_pending().unwrap();
+
+        // A block that self-reports a far greater height but doesn't chain
+        // from our actual tip must not be accepted as the new tip, even
+        // though a naive height comparison would call it "taller".
+        let (attacker_public, attacker_private) = generate_keypair();
+        let claim = NameClaim::new(
+            "other".to_string(),
+            [9u8; 32],
+            attacker_public,
+            [0u8; 32],
+            0,
+            false,
+            &attacker_private,
+        );
+        let mut forged = NameBlock { claims: vec![claim], prev_hash: [0u8; 32], height: 999, block_nonce: 0 };
+        while !forged.meets_difficulty(4) {
+            forged.block_nonce += 1;
+        }
+
+        assert!(!chain.ingest_block(forged).unwrap());
+        assert_eq!(chain.height().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_ingest_rejects_squatter_claim_hand_built_into_a_block() {
+        let chain = chain();
+        let (public, private) = generate_keypair();
+        let site_id = [1u8; 32];
+        chain.submit_claim(claim_for(&chain, "example", site_id, public, &private, false)).unwrap();
+        chain.mine_pending().unwrap();
+
+        // Bypass `submit_claim` entirely: sign a claim for an already-owned
+        // name with a different key and mine it directly into a block.
+        let (squatter_public, squatter_private) = generate_keypair();
+        let (tip_hash, tip_height) = chain.tip().unwrap();
+        let claim = NameClaim::new(
+            "example".to_string(),
+            [2u8; 32],
+            squatter_public,
+            tip_hash,
+            0,
+            false,
+            &squatter_private,
+        );
+        let mut block = NameBlock { claims: vec![claim], prev_hash: tip_hash, height: tip_height + 1, block_nonce: 0 };
+        while !block.meets_difficulty(4) {
+            block.block_nonce += 1;
+        }
+
+        assert!(!chain.ingest_block(block).unwrap());
+        assert_eq!(chain.resolve("example").unwrap(), Some(site_id));
+    }
+}
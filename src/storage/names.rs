@@ -0,0 +1,166 @@
+//! Naming registry: persists signed human-readable name -> `SiteId` bindings
+
+use std::path::Path;
+use anyhow::Result;
+
+use crate::crypto::verify_name_record;
+use crate::types::NameRecord;
+
+/// Persisted store of known `NameRecord`s, keyed by name.
+///
+/// Resolution prefers the record with the highest `revision`. A name is
+/// bound to whichever publisher first successfully claimed it: once a
+/// verified record for a name is stored, a later record for the same name
+/// from a *different* publisher is ignored outright (not overwritten),
+/// which keeps a late-arriving squatter from hijacking an already-claimed
+/// name. The original publisher can still renew their own name with a
+/// higher-revision record.
+pub struct NameStore {
+    names: sled::Tree,
+}
+
+impl NameStore {
+    /// Create a new naming registry
+    pub fn new(data_dir: &Path) -> Result<Self> {
+        let db_path = data_dir.join("names.db");
+        let db = sled::open(&db_path)?;
+
+        Ok(Self {
+            names: db.open_tree("names")?,
+        })
+    }
+
+    /// Look up the current record for a name
+    pub fn resolve(&self, name: &str) -> Result<Option<NameRecord>> {
+        match self.names.get(name.as_bytes())? {
+            Some(data) => Ok(Some(bincode::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Offer a record to the registry. Verifies the signature before
+    /// storing anything. Returns `true` if the record was accepted (either
+    /// claiming the name for the first time or renewing it with a higher
+    /// revision from the same publisher), `false` if it was rejected as
+    /// unverified, stale, or squatting on someone else's name.
+    pub fn offer(&self, record: &NameRecord) -> Result<bool> {
+        if !verify_name_record(
+            &record.name,
+            &record.site_id,
+            record.revision,
+            record.updated_at,
+            &record.signature,
+            &record.publisher,
+        ) {
+            return Ok(false);
+        }
+
+        if let Some(existing) = self.resolve(&record.name)? {
+            if existing.publisher != record.publisher {
+                // First-seen-publisher-wins: a different publisher can't
+                // take over a name that's already claimed.
+                return Ok(false);
+            }
+            if record.revision <= existing.revision {
+                return Ok(false);
+            }
+        }
+
+        let value = bincode::serialize(record)?;
+        self.names.insert(record.name.as_bytes(), value)?;
+        Ok(true)
+    }
+
+    /// All known names, for diagnostics and the gateway's listing page
+    pub fn list(&self) -> Result<Vec<NameRecord>> {
+        let mut records = Vec::new();
+        for entry in self.names.iter() {
+            let (_, value) = entry?;
+            records.push(bincode::deserialize(&value)?);
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_keypair, sign_name_record};
+    use tempfile::tempdir;
+
+    fn make_record(name: &str, site_id: [u8; 32], revision: u64, public: [u8; 32], private: &[u8; 32]) -> NameRecord {
+        let updated_at = 1_000 + revision;
+        let signature = sign_name_record(name, &site_id, revision, updated_at, private);
+        NameRecord {
+            name: name.to_string(),
+            site_id,
+            revision,
+            publisher: public,
+            signature,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_claim_and_resolve() {
+        let dir = tempdir().unwrap();
+        let store = NameStore::new(dir.path()).unwrap();
+        let (public, private) = generate_keypair();
+
+        let record = make_record("example", [1u8; 32], 1, public, &private);
+        assert!(store.offer(&record).unwrap());
+
+        let resolved = store.resolve("example").unwrap().unwrap();
+        assert_eq!(resolved.site_id, [1u8; 32]);
+    }
+
+    #[test]
+    fn test_squatter_is_rejected() {
+        let dir = tempdir().unwrap();
+        let store = NameStore::new(dir.path()).unwrap();
+        let (owner_public, owner_private) = generate_keypair();
+        let (squatter_public, squatter_private) = generate_keypair();
+
+        let original = make_record("example", [1u8; 32], 1, owner_public, &owner_private);
+        assert!(store.offer(&original).unwrap());
+
+        let takeover = make_record("example", [2u8; 32], 2, squatter_public, &squatter_private);
+        assert!(!store.offer(&takeover).unwrap());
+
+        let resolved = store.resolve("example").unwrap().unwrap();
+        assert_eq!(resolved.site_id, [1u8; 32]);
+        assert_eq!(resolved.publisher, owner_public);
+    }
+
+    #[test]
+    fn test_owner_can_renew_with_higher_revision() {
+        let dir = tempdir().unwrap();
+        let store = NameStore::new(dir.path()).unwrap();
+        let (public, private) = generate_keypair();
+
+        let first = make_record("example", [1u8; 32], 1, public, &private);
+        assert!(store.offer(&first).unwrap());
+
+        let renewal = make_record("example", [5u8; 32], 2, public, &private);
+        assert!(store.offer(&renewal).unwrap());
+
+        let resolved = store.resolve("example").unwrap().unwrap();
+        assert_eq!(resolved.site_id, [5u8; 32]);
+
+        // A stale (lower-revision) replay from the same owner is rejected
+        assert!(!store.offer(&first).unwrap());
+    }
+
+    #[test]
+    fn test_tampered_record_is_rejected() {
+        let dir = tempdir().unwrap();
+        let store = NameStore::new(dir.path()).unwrap();
+        let (public, private) = generate_keypair();
+
+        let mut record = make_record("example", [1u8; 32], 1, public, &private);
+        record.site_id = [9u8; 32]; // doesn't match what was signed
+
+        assert!(!store.offer(&record).unwrap());
+        assert!(store.resolve("example").unwrap().is_none());
+    }
+}
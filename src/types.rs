@@ -1,6 +1,7 @@
 //! Core types for GrabNet
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// 32-byte site identifier: blake3(publisher_key || site_name)
@@ -22,8 +23,15 @@ pub struct WebBundle {
     pub site_id: SiteId,
     /// Human-readable name
     pub name: String,
-    /// Revision number (auto-increments)
+    /// Revision number, derived from `causal_context` (sum of its dots) for
+    /// display and wire compatibility with code that just wants an
+    /// ever-increasing counter
     pub revision: u64,
+    /// Causal write history, used to tell whether this bundle is a clean
+    /// successor of another revision or a concurrent (forked) write from a
+    /// different device
+    #[serde(default)]
+    pub causal_context: VersionVector,
     /// Merkle root of all content
     pub root_hash: [u8; 32],
     /// Publisher's public key
@@ -37,6 +45,59 @@ pub struct WebBundle {
     pub created_at: u64,
 }
 
+/// A dotted version vector: one write counter per publishing device
+/// (`node_id`), used to detect causality between revisions of the same
+/// site. If publishing happens from two machines sharing a signing key,
+/// comparing this instead of a bare integer tells clean successors
+/// ("every counter in the new context is >= the old one, with at least one
+/// strictly greater") apart from concurrent, conflicting writes neither of
+/// which causally preceded the other.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionVector(pub BTreeMap<String, u64>);
+
+impl VersionVector {
+    /// Record a new write from `node_id`, returning its counter (the "dot").
+    pub fn increment(&mut self, node_id: &str) -> u64 {
+        let counter = self.0.entry(node_id.to_string()).or_insert(0);
+        *counter += 1;
+        *counter
+    }
+
+    /// `true` if `self` causally descends from `other`: every counter in
+    /// `other` is matched or exceeded in `self`, and at least one is
+    /// strictly greater (so an identical context does not "dominate" itself).
+    pub fn dominates(&self, other: &Self) -> bool {
+        if self == other {
+            return false;
+        }
+        other.0.iter().all(|(node_id, count)| self.0.get(node_id).copied().unwrap_or(0) >= *count)
+    }
+
+    /// `true` if neither context dominates the other, i.e. they represent
+    /// conflicting concurrent writes that should be kept as siblings.
+    pub fn concurrent_with(&self, other: &Self) -> bool {
+        self != other && !self.dominates(other) && !other.dominates(self)
+    }
+
+    /// The join of two contexts: the per-node maximum of each, used to
+    /// collapse a set of sibling revisions back into one history.
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut merged = self.0.clone();
+        for (node_id, count) in &other.0 {
+            let entry = merged.entry(node_id.clone()).or_insert(0);
+            if *count > *entry {
+                *entry = *count;
+            }
+        }
+        Self(merged)
+    }
+
+    /// Sum of all per-node counters, used as the human-facing `revision` number.
+    pub fn total(&self) -> u64 {
+        self.0.values().sum()
+    }
+}
+
 /// Site manifest containing file structure and routing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SiteManifest {
@@ -48,6 +109,12 @@ pub struct SiteManifest {
     pub routes: Option<RouteConfig>,
     /// Custom headers
     pub headers: Option<Vec<HeaderRule>>,
+    /// Algorithm used for `FileEntry::hash` and the `ChunkId`s in
+    /// `FileEntry::chunks`, so a reader can verify this manifest without
+    /// assuming BLAKE3. Defaults to BLAKE3 for manifests published before
+    /// this field existed.
+    #[serde(default)]
+    pub hash_method: crate::crypto::HashMethod,
 }
 
 /// A single file in the site
@@ -74,6 +141,7 @@ pub enum Compression {
     None,
     Gzip,
     Brotli,
+    Zstd,
 }
 
 /// Routing configuration for SPAs and clean URLs
@@ -161,15 +229,116 @@ pub struct NetworkConfig {
     /// Bootstrap peers
     #[serde(default)]
     pub bootstrap_peers: Vec<String>,
+    /// Trusted peers, given as full multiaddrs including a `/p2p/<peer_id>`
+    /// suffix, always worth dialing and keeping connected. Seeded into the
+    /// Kademlia routing table and dialed alongside `bootstrap_peers` on
+    /// startup; see [`crate::network::access::PeerAccessControl`].
+    #[serde(default)]
+    pub reserved_peers: Vec<String>,
     /// Maximum connections
     #[serde(default = "default_max_connections")]
     pub max_connections: usize,
+    /// Idle-connection timeout, in seconds, before we consider a peer dead.
+    /// Negotiated down if the remote peer advertises a stricter value.
+    #[serde(default = "default_peer_timeout_secs")]
+    pub peer_timeout_secs: u64,
+    /// Keepalive ping interval, in seconds.
+    #[serde(default = "default_keepalive_secs")]
+    pub keepalive_secs: u64,
+    /// A stable hostname to resolve at startup for initial membership
+    /// seeding, so operators can point at a DNS name instead of
+    /// hardcoding addresses that may move. Superseded by `discovery` for
+    /// anything needing periodic re-resolution or a Kubernetes backend,
+    /// but kept for simple one-shot DNS seeding.
+    #[serde(default)]
+    pub dns_seed: Option<String>,
+    /// A pluggable bootstrap discovery backend (DNS, or Kubernetes behind
+    /// the `kubernetes` feature), consulted at startup and re-resolved on
+    /// a timer so self-hosted clusters and cloud deployments can form
+    /// without a hand-maintained peer list.
+    #[serde(default)]
+    pub discovery: Option<crate::network::DiscoveryConfig>,
+    /// Wrap `GrabCodec` traffic in an authenticated, rekeying session layer
+    /// (see [`crate::network::session`]). `None` keeps the current
+    /// plaintext framing.
+    #[serde(default)]
+    pub secure_transport: Option<SecureTransportConfig>,
+    /// Accept and dial QUIC in addition to TCP. QUIC's single-round-trip
+    /// handshake and built-in multiplexing often traverse NATs better than
+    /// TCP, at the cost of needing UDP reachability instead.
+    #[serde(default = "default_true")]
+    pub enable_quic: bool,
+    /// Accept and dial WebSocket in addition to TCP/QUIC, for peers (e.g.
+    /// browser-based clients) that can't open raw TCP/UDP sockets.
+    #[serde(default)]
+    pub enable_websocket: bool,
+    /// Human-readable name this node presents during
+    /// [`crate::network::pairing`], e.g. "alice's laptop". Falls back to
+    /// the peer ID when unset.
+    #[serde(default)]
+    pub node_name: Option<String>,
+    /// Geographic region/availability-zone tag this node declares when
+    /// announcing a hosted site (see [`crate::network::layout::ReplicaLayout`]),
+    /// so replica placement can spread mirrors of a site across zones
+    /// instead of concentrating them in one.
+    #[serde(default)]
+    pub zone: Option<String>,
+    /// How tranquil (as opposed to urgent) background resync should be:
+    /// [`crate::network::resync::ResyncService`] sleeps
+    /// `tranquility * last_op_duration` between fetches, so `0.0` resyncs
+    /// flat-out and `1.0` spends as long resting between fetches as the
+    /// last one took, leaving more bandwidth for foreground gateway
+    /// traffic at the cost of slower convergence on `ReplicaLayout`'s
+    /// targets.
+    #[serde(default = "default_tranquility")]
+    pub tranquility: f64,
+}
+
+/// Configuration for the optional encrypted session layer over `GrabCodec`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecureTransportConfig {
+    /// Base58-encoded Ed25519 public keys this node accepts as peers.
+    /// Ignored when `shared_secret` is set, since shared-secret mode
+    /// trusts exactly the one identity every holder of the secret derives.
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
+    /// A pre-shared secret for closed networks: every node that knows it
+    /// derives the same Ed25519 identity (and so implicitly trusts every
+    /// other holder), skipping `trusted_keys` management entirely.
+    #[serde(default)]
+    pub shared_secret: Option<String>,
+    /// Rekey the session after this many sealed messages in one direction.
+    #[serde(default = "default_rekey_after_messages")]
+    pub rekey_after_messages: u64,
+    /// Rekey the session after this many seconds since the last rekey.
+    #[serde(default = "default_rekey_after_secs")]
+    pub rekey_after_secs: u64,
+}
+
+fn default_rekey_after_messages() -> u64 {
+    10_000
+}
+
+fn default_rekey_after_secs() -> u64 {
+    600
 }
 
 fn default_network_port() -> u16 {
     4001
 }
 
+fn default_peer_timeout_secs() -> u64 {
+    120
+}
+
+fn default_keepalive_secs() -> u64 {
+    60
+}
+
+fn default_tranquility() -> f64 {
+    1.0
+}
+
 fn default_listen_addresses() -> Vec<String> {
     vec![
         "/ip4/0.0.0.0/tcp/4001".to_string(),
@@ -192,6 +361,23 @@ pub struct GatewayConfig {
     /// Enable CORS
     #[serde(default = "default_true")]
     pub cors: bool,
+    /// Trust `X-Forwarded-For` to recover the real client IP. Only enable
+    /// this when the gateway sits behind a reverse proxy you control —
+    /// otherwise clients can spoof their own IP for access control.
+    #[serde(default)]
+    pub trusted_proxy: bool,
+    /// IPs/CIDR ranges allowed to reach the gateway. Empty means "allow
+    /// everyone not explicitly denied".
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// IPs/CIDR ranges denied access, checked before `allow`.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Security and cache-control headers applied to served responses (see
+    /// [`HeaderPolicyConfig`]). Skipped entirely for WebSocket upgrade
+    /// requests so a reverse-proxied upgrade handshake isn't disturbed.
+    #[serde(default)]
+    pub headers: HeaderPolicyConfig,
 }
 
 fn default_gateway_port() -> u16 {
@@ -206,6 +392,54 @@ fn default_true() -> bool {
     true
 }
 
+/// Response header policy for the HTTP gateway. Site content is
+/// content-addressed and immutable per revision, so non-entry assets are
+/// safe to cache aggressively; the entry file shares a path across
+/// revisions and must instead be revalidated on every request (via the
+/// `ETag`/`If-None-Match` handling already in `serve_file`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderPolicyConfig {
+    /// Send `X-Content-Type-Options: nosniff` to stop browsers from
+    /// MIME-sniffing past the declared `Content-Type`.
+    #[serde(default = "default_true")]
+    pub nosniff: bool,
+    /// `X-Frame-Options` value, e.g. `"DENY"` or `"SAMEORIGIN"`. `None`
+    /// omits the header.
+    #[serde(default = "default_frame_options")]
+    pub frame_options: Option<String>,
+    /// `Content-Security-Policy` value. `None` omits the header, since a
+    /// safe default depends on what a hosted site actually loads.
+    #[serde(default)]
+    pub content_security_policy: Option<String>,
+    /// `Permissions-Policy` value. `None` omits the header.
+    #[serde(default)]
+    pub permissions_policy: Option<String>,
+    /// `max-age`, in seconds, for the `Cache-Control` sent on
+    /// content-addressed assets other than the entry file.
+    #[serde(default = "default_asset_max_age_secs")]
+    pub asset_max_age_secs: u64,
+}
+
+impl Default for HeaderPolicyConfig {
+    fn default() -> Self {
+        Self {
+            nosniff: default_true(),
+            frame_options: default_frame_options(),
+            content_security_policy: None,
+            permissions_policy: None,
+            asset_max_age_secs: default_asset_max_age_secs(),
+        }
+    }
+}
+
+fn default_frame_options() -> Option<String> {
+    Some("DENY".to_string())
+}
+
+fn default_asset_max_age_secs() -> u64 {
+    31_536_000 // 1 year
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     /// Chunk cache size in MB
@@ -228,6 +462,14 @@ pub struct PublisherConfig {
     /// Enable compression
     #[serde(default = "default_true")]
     pub compress: bool,
+    /// Zstd compression level (1-22, higher is smaller but slower), used
+    /// whenever a publish doesn't override it via `PublishOptions::zstd_level`
+    #[serde(default = "default_zstd_level")]
+    pub zstd_level: i32,
+}
+
+fn default_zstd_level() -> i32 {
+    3
 }
 
 fn default_chunk_size() -> usize {
@@ -241,12 +483,27 @@ impl Default for Config {
                 port: default_network_port(),
                 listen_addresses: default_listen_addresses(),
                 bootstrap_peers: vec![],
+                reserved_peers: vec![],
                 max_connections: default_max_connections(),
+                peer_timeout_secs: default_peer_timeout_secs(),
+                keepalive_secs: default_keepalive_secs(),
+                dns_seed: None,
+                discovery: None,
+                zone: None,
+                tranquility: default_tranquility(),
+                secure_transport: None,
+                enable_quic: true,
+                enable_websocket: false,
+                node_name: None,
             },
             gateway: GatewayConfig {
                 port: default_gateway_port(),
                 host: default_gateway_host(),
                 cors: true,
+                trusted_proxy: false,
+                allow: vec![],
+                deny: vec![],
+                headers: HeaderPolicyConfig::default(),
             },
             storage: StorageConfig {
                 cache_size_mb: default_cache_size(),
@@ -255,6 +512,7 @@ impl Default for Config {
             publisher: PublisherConfig {
                 chunk_size: default_chunk_size(),
                 compress: true,
+                zstd_level: default_zstd_level(),
             },
         }
     }
@@ -285,6 +543,23 @@ impl Config {
     }
 }
 
+/// Out-of-band metadata for a [`GrabRequest::PushRevisionDelta`], kept
+/// separate from the (potentially large, compressed) chunk payload so a
+/// receiver knows what to expect -- and can size a progress bar -- before
+/// it starts decompressing anything.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DeltaSyncPreamble {
+    pub site_id: SiteId,
+    /// Revision the receiver reported having (0 if it doesn't host the
+    /// site at all yet)
+    pub from_revision: u64,
+    /// Revision this delta brings the receiver up to
+    pub to_revision: u64,
+    pub chunk_count: usize,
+    /// Total bytes across all chunks, uncompressed
+    pub byte_count: u64,
+}
+
 /// Protocol message types for P2P communication
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GrabRequest {
@@ -292,12 +567,66 @@ pub enum GrabRequest {
     FindSite { site_id: SiteId },
     /// Get site manifest
     GetManifest { site_id: SiteId },
-    /// Get chunks by ID
+    /// Get chunks by ID. This doubles as bitswap's `WANT-BLOCK`: once a
+    /// peer has confirmed it holds a chunk via `WantHave`, this is the
+    /// message that actually pulls the bytes.
     GetChunks { chunk_ids: Vec<ChunkId> },
+    /// Ask whether a peer has a set of chunks before requesting the data,
+    /// so we don't spend bandwidth on a `GetChunks` that comes back empty
+    WantHave { chunk_ids: Vec<ChunkId> },
     /// Announce we're hosting a site
     Announce { site_id: SiteId, revision: u64 },
     /// Push an update to hosts
     PushUpdate { bundle: Box<WebBundle> },
+    /// Failure-detection probe; a peer that answers is still alive.
+    /// Piggybacks a batch of the sender's own recent membership updates so
+    /// routine liveness traffic also carries gossip, rather than waiting
+    /// for the next `Gossip` round. Also piggybacks the sender's own
+    /// BEP-42-style secure ID (see `crate::network::secure_id`), if it's
+    /// minted one, so the responder has something to actually check
+    /// against the IP it observed the sender connect from.
+    Ping { delta: crate::network::MembershipDelta, secure_id: Option<[u8; 20]> },
+    /// SWIM-style indirect probe: asked by a peer whose own direct `Ping`
+    /// to `target` went unanswered, to rule out a one-sided network
+    /// partition before declaring `target` suspect.
+    IndirectPing { target: String },
+    /// Exchange membership deltas with a gossip target
+    Gossip { delta: crate::network::MembershipDelta },
+    /// Resolve a human-readable name to its current `NameRecord`
+    ResolveName { name: String },
+    /// Announce (or update) a name -> site binding
+    AnnounceName { record: NameRecord },
+    /// Ask a peer for its cached Merkle tree hashes at these positions, as
+    /// one round of an anti-entropy diff against `site_id`'s content tree
+    MerkleDiffQuery { site_id: SiteId, nodes: Vec<NodeId> },
+    /// Pull replication records the sender doesn't already have, one
+    /// Bloom filter per partition built by `ReplicationManager::build_pull_filter`
+    PullReplication { filters: Vec<crate::network::CrdsFilter> },
+    /// Active repair: fetch one chunk's bytes plus a Merkle proof against
+    /// `site_id`'s signed `root_hash`, so the requester can verify it
+    /// before accepting it into a `missing_chunks` repair
+    GetChunkWithProof { site_id: SiteId, chunk_id: ChunkId },
+    /// First round of a [`crate::network::ReplicationSession`]: ask what
+    /// revision (and which of its chunks) a peer already has for
+    /// `site_id`, so a push only has to send the diff
+    GetRevisionState { site_id: SiteId },
+    /// Second round of a [`crate::network::ReplicationSession`]: the
+    /// manifest for a newer revision, plus the chunks the peer reported
+    /// it was missing as a single zstd-compressed, bincode-serialized
+    /// `Vec<(ChunkId, Vec<u8>)>`. `preamble` carries the sync metadata
+    /// (site, revisions, counts) outside that payload so a receiver can
+    /// size progress reporting and buffers before touching it.
+    PushRevisionDelta { bundle: Box<WebBundle>, preamble: DeltaSyncPreamble, chunk_payload: Vec<u8> },
+    /// First message of a [`crate::network::pairing`] handshake: the
+    /// sender's identity plus a fresh ephemeral key
+    PairingOffer { offer: crate::network::HandshakeMessage },
+    /// Once a human has confirmed both sides' out-of-band codes match,
+    /// the sender's signed `NodeInformation`, sealed under the pairing
+    /// session
+    PairingConfirm { sealed: crate::network::SealedFrame },
+    /// An application payload carried over an already-paired peer's
+    /// encrypted control tunnel
+    ControlMessage { sealed: crate::network::SealedFrame },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -308,10 +637,87 @@ pub enum GrabResponse {
     Manifest { bundle: Box<WebBundle> },
     /// Requested chunks
     Chunks { chunks: Vec<(ChunkId, Vec<u8>)> },
+    /// Which of the asked-about chunks a peer has (and doesn't have)
+    Have { have: Vec<ChunkId>, dont_have: Vec<ChunkId> },
     /// Acknowledgment
     Ack,
     /// Error
     Error { message: String },
+    /// Reply to [`GrabRequest::Ping`], piggybacking the responder's own
+    /// recent membership updates and (if it's minted one) its own secure ID
+    Pong { delta: crate::network::MembershipDelta, secure_id: Option<[u8; 20]> },
+    /// Reply to [`GrabRequest::IndirectPing`]: whether `target` answered
+    /// the prober's own direct ping
+    IndirectPingResult { alive: bool },
+    /// Reply to [`GrabRequest::Gossip`], carrying the responder's own delta
+    Gossip { delta: crate::network::MembershipDelta },
+    /// Reply to [`GrabRequest::ResolveName`]
+    NameResolved { record: Option<NameRecord> },
+    /// Reply to [`GrabRequest::MerkleDiffQuery`]: the peer's hash at each
+    /// requested node, in the same order (`None` where that position isn't
+    /// materialized, i.e. it has fewer leaves there)
+    MerkleDiffReply { hashes: Vec<Option<[u8; 32]>> },
+    /// Reply to [`GrabRequest::PullReplication`]: records in the filters'
+    /// partitions that none of them matched
+    PullReplicationReply { records: Vec<crate::network::VersionedRecord> },
+    /// Reply to [`GrabRequest::GetChunkWithProof`]
+    ChunkWithProof { data: Vec<u8>, proof: MerkleProof },
+    /// Reply to [`GrabRequest::GetRevisionState`]: `have_revision` is 0 if
+    /// the peer doesn't host the site at all, in which case `have_chunks`
+    /// is empty
+    RevisionState { have_revision: u64, have_chunks: Vec<ChunkId> },
+    /// Reply to [`GrabRequest::PairingOffer`]: the responder's own
+    /// handshake message, completing the key exchange
+    PairingResponse { response: crate::network::HandshakeMessage },
+    /// Reply to [`GrabRequest::PairingConfirm`]: the responder's own
+    /// signed `NodeInformation`, sealed under the same pairing session
+    PairingConfirmed { sealed: crate::network::SealedFrame },
+    /// Reply to [`GrabRequest::ControlMessage`]
+    ControlMessage { sealed: crate::network::SealedFrame },
+}
+
+/// A signed binding from a human-readable name to a `SiteId`, so sites can
+/// be addressed as `<name>` instead of a base58 `SiteId`. Signed by the
+/// claiming publisher; see [`crate::storage::NameStore`] for how records are
+/// verified and resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameRecord {
+    /// The claimed human-readable name
+    pub name: String,
+    /// The site this name resolves to
+    pub site_id: SiteId,
+    /// Monotonically increasing revision; resolution prefers the highest
+    /// among records whose signature verifies for the claimed publisher
+    pub revision: u64,
+    /// Ed25519 public key of the publisher claiming this name
+    pub publisher: PublicKey,
+    /// Signature over `(name, site_id, revision, updated_at)` by `publisher`
+    pub signature: Signature,
+    /// Unix millisecond timestamp the record was created or last renewed
+    pub updated_at: u64,
+}
+
+/// Gossiped over `SITES_TOPIC` in place of a raw `(SiteId, u64)` tuple, so a
+/// receiving node can verify the announcement actually came from the site's
+/// owning publisher before relaying or acting on it. The signature is the
+/// bundle's own `signature` field (see [`crate::crypto::signing::sign_bundle`]),
+/// over `(site_id, revision, root_hash)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteAnnouncement {
+    pub site_id: SiteId,
+    pub revision: u64,
+    pub root_hash: [u8; 32],
+    /// Ed25519 public key of the publisher claiming this site
+    pub publisher: PublicKey,
+    /// Signature over `(site_id, revision, root_hash)` by `publisher`
+    pub signature: Signature,
+    /// The announcing host's declared zone/region tag, if any (see
+    /// `NetworkConfig::zone`). A placement hint for
+    /// [`crate::network::layout::ReplicaLayout`], not covered by
+    /// `signature` -- unlike the rest of this struct, it isn't
+    /// authenticated, so it's only ever used to steer spread, never trust.
+    #[serde(default)]
+    pub zone: Option<String>,
 }
 
 /// Information about a peer hosting a site
@@ -322,6 +728,22 @@ pub struct PeerRecord {
     pub revision: u64,
 }
 
+/// A node's self-reported identity, exchanged once [`crate::network::pairing`]'s
+/// out-of-band code has been confirmed so each side learns who the other
+/// is and what it publishes. Signed by `pubkey` so a relaying third party
+/// can't tamper with it in transit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeInformation {
+    pub peer_id: String,
+    pub name: String,
+    pub site_ids: Vec<SiteId>,
+    /// Ed25519 public key this record is signed by; must match the
+    /// identity the pairing session actually negotiated with.
+    pub pubkey: PublicKey,
+    /// Signature over `(peer_id, name, site_ids, pubkey)` by `pubkey`
+    pub signature: Signature,
+}
+
 /// Merkle proof for content verification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MerkleProof {
@@ -329,4 +751,21 @@ pub struct MerkleProof {
     pub leaf_hash: [u8; 32],
     pub siblings: Vec<[u8; 32]>,
     pub root: [u8; 32],
+    /// Number of leaves in the accumulator the proof was taken from. Zero
+    /// for proofs produced by the flat, single-tree `MerkleTree` (which
+    /// doesn't need it); set by `MerkleMountainRange::proof` so a verifier
+    /// can recover the peak layout and tell where the within-peak
+    /// authentication path ends and the cross-peak bagging siblings begin.
+    #[serde(default)]
+    pub mmr_size: usize,
+}
+
+/// Position of a node in an [`crate::crypto::AppendMerkleTree`]: `height` 0
+/// is the leaf layer, `index` is the node's position within that layer.
+/// Used by the Merkle anti-entropy diff protocol to say exactly which
+/// cached hash is being compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NodeId {
+    pub height: u32,
+    pub index: usize,
 }